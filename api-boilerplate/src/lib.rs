@@ -98,7 +98,7 @@ impl App {
             .with_dimensions((window_width, window_height).into());
 
         let (instance, window) = create_instance_and_window(&cfg, &events_loop, window_builder);
-        let renderer = Api::new(instance);
+        let renderer = Api::new(instance).expect("failed to create renderer");
 
         App {
             events_loop: RefCell::new(events_loop),
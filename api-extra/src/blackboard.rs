@@ -2,6 +2,7 @@ use crate::commandext::CommandBufferExt;
 use autograph_api::{
     buffer::{Buffer, BufferData, BufferTypeless, StructuredBufferData, TypedConstantBufferView},
     command::{CommandBuffer, DrawIndexedParams, DrawParams},
+    error::Result,
     format::Format,
     image::{
         DepthStencilView, Image1d, Image2d, Image2dBuilder, Image3d, ImageCreateInfo,
@@ -65,21 +66,23 @@ pub struct Blackboard<'a, B: Backend> {
 }
 
 impl<'a, B: Backend> Blackboard<'a, B> {
-    pub fn new(r: &'a Api<B>) -> Blackboard<'a, B> {
-        Blackboard {
+    pub fn new(r: &'a Api<B>) -> Result<Blackboard<'a, B>> {
+        Ok(Blackboard {
             lookup: RefCell::new(HashMap::new()),
-            arena: r.create_arena(),
+            arena: r.create_arena()?,
             parent: None,
-        }
+        })
     }
 
     pub fn arena(&self) -> &Arena<'a, B> {
         &self.arena
     }
 
-    pub fn buffer<T: Copy + 'static>(&self, name: &str, data: &T) -> Buffer<B, T> {
-        self.buffer_by_name(name).unwrap_or_else(|| {
-            let buf = self.arena.upload(data);
+    pub fn buffer<T: Copy + 'static>(&self, name: &str, data: &T) -> Result<Buffer<B, T>> {
+        if let Some(buf) = self.buffer_by_name(name) {
+            Ok(buf)
+        } else {
+            let buf = self.arena.upload(data)?;
             self.lookup.borrow_mut().insert(
                 name.to_string(),
                 BlackboardResource::Buffer {
@@ -88,13 +91,19 @@ impl<'a, B: Backend> Blackboard<'a, B> {
                     buf: buf.inner() as *const _,
                 },
             );
-            buf
-        })
+            Ok(buf)
+        }
     }
 
-    pub fn buffer_slice<T: Copy + 'static>(&self, name: &str, data: &[T]) -> Buffer<B, [T]> {
-        self.buffer_by_name(name).unwrap_or_else(|| {
-            let buf = self.arena.upload_slice(data);
+    pub fn buffer_slice<T: Copy + 'static>(
+        &self,
+        name: &str,
+        data: &[T],
+    ) -> Result<Buffer<B, [T]>> {
+        if let Some(buf) = self.buffer_by_name(name) {
+            Ok(buf)
+        } else {
+            let buf = self.arena.upload_slice(data)?;
             self.lookup.borrow_mut().insert(
                 name.to_string(),
                 BlackboardResource::Buffer {
@@ -103,8 +112,8 @@ impl<'a, B: Backend> Blackboard<'a, B> {
                     buf: buf.inner() as *const _,
                 },
             );
-            buf
-        })
+            Ok(buf)
+        }
     }
 
     pub fn buffer_by_name<T: BufferData + ?Sized>(&self, name: &str) -> Option<Buffer<B, T>> {
@@ -123,7 +132,10 @@ impl<'a, B: Backend> Blackboard<'a, B> {
         format: Format,
         width: u32,
         height: u32,
-    ) -> Image2dBuilder<Image2d<'b, B>, impl Fn(&ImageCreateInfo) -> Image2d<'b, B> + 'n> {
+    ) -> Image2dBuilder<
+        Result<Image2d<'b, B>>,
+        impl Fn(&ImageCreateInfo) -> Result<Image2d<'b, B>> + 'n,
+    > {
         Image2dBuilder::new(format, (width, height), move |c| {
             let desc = ImageDesc2d {
                 format,
@@ -136,7 +148,7 @@ impl<'a, B: Backend> Blackboard<'a, B> {
                 assert_eq!(d2, desc);
                 // reborrow to 'self lifetime: OK because inside own arena (and stable addresses), or
                 // any parent, which lives longer
-                unsafe { Image2d::from_raw(&*img) }
+                unsafe { Ok(Image2d::from_raw(&*img)) }
             } else {
                 let img = self.arena.create_image(
                     c.scope,
@@ -146,7 +158,7 @@ impl<'a, B: Backend> Blackboard<'a, B> {
                     c.samples,
                     c.usage,
                     c.data,
-                );
+                )?;
                 self.lookup.borrow_mut().insert(
                     name.to_string(),
                     BlackboardResource::Image2d {
@@ -154,7 +166,7 @@ impl<'a, B: Backend> Blackboard<'a, B> {
                         img: img.inner() as *const _,
                     },
                 );
-                unsafe { Image2d::from_raw(img.inner()) }
+                unsafe { Ok(Image2d::from_raw(img.inner())) }
             }
         })
     }
@@ -0,0 +1,383 @@
+use autograph_api::{
+    error::{Error, Result},
+    pipeline::{
+        reflect::reflect_shader, validate::validate_shader_interface, Arguments, GraphicsPipeline,
+        GraphicsPipelineCreateInfo, GraphicsShaderStages, ShaderStageFlags, Signature,
+        TypedSignature,
+    },
+    Api, Arena, Backend,
+};
+use autograph_spirv::DroplessArena;
+use log::{error, info};
+use shaderc::{IncludeType, ResolvedInclude};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+fn stage_from_extension(path: &Path) -> Option<(ShaderStageFlags, shaderc::ShaderKind)> {
+    match path.extension() {
+        Some(ext) if ext == "vert" => Some((ShaderStageFlags::VERTEX, shaderc::ShaderKind::Vertex)),
+        Some(ext) if ext == "frag" => {
+            Some((ShaderStageFlags::FRAGMENT, shaderc::ShaderKind::Fragment))
+        }
+        Some(ext) if ext == "geom" => {
+            Some((ShaderStageFlags::GEOMETRY, shaderc::ShaderKind::Geometry))
+        }
+        Some(ext) if ext == "tese" => Some((
+            ShaderStageFlags::TESS_EVAL,
+            shaderc::ShaderKind::TessEvaluation,
+        )),
+        Some(ext) if ext == "tesc" => Some((
+            ShaderStageFlags::TESS_CONTROL,
+            shaderc::ShaderKind::TessControl,
+        )),
+        _ => None,
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Compiles a GLSL file to SPIR-V. Also returns the files included by the shader.
+fn compile_glsl(
+    compiler: &mut shaderc::Compiler,
+    path: &Path,
+    stage: ShaderStageFlags,
+    kind: shaderc::ShaderKind,
+) -> (Result<Vec<u8>>, Vec<PathBuf>) {
+    let compilation_error = |message: String| Error::ShaderCompilation { stage, message };
+
+    // shaderc identifies source files by name
+    let file_name = match path.to_str() {
+        Some(file_name) => file_name,
+        None => {
+            let message = format!("path is not valid UTF-8: {}", path.display());
+            return (Err(compilation_error(message)), Vec::new());
+        }
+    };
+    let src = match fs::read_to_string(path) {
+        Ok(src) => src,
+        Err(e) => {
+            let message = format!("failed to read {}: {}", path.display(), e);
+            return (Err(compilation_error(message)), Vec::new());
+        }
+    };
+
+    // RefCell because include_callback is not FnMut
+    let includes = RefCell::new(Vec::new());
+    let result = {
+        let mut opt = shaderc::CompileOptions::new().unwrap();
+        opt.set_target_env(shaderc::TargetEnv::Vulkan, 0);
+        opt.set_optimization_level(shaderc::OptimizationLevel::Zero);
+        opt.set_include_callback(|name, include_type, source_name, _depth| {
+            // relative includes only, like `include_glsl!`
+            if include_type != IncludeType::Relative {
+                return Err(
+                    "`#include <...>` is not supported: use relative include directives \
+                            (`#include \"...\"`)"
+                        .to_string(),
+                );
+            }
+            let include_path = Path::new(source_name).with_file_name(name);
+            includes.borrow_mut().push(include_path.clone());
+            let resolved_name = include_path
+                .to_str()
+                .ok_or_else(|| format!("path is not valid UTF-8: {}", include_path.display()))?
+                .to_owned();
+            match fs::read_to_string(&include_path) {
+                Ok(content) => Ok(ResolvedInclude {
+                    resolved_name,
+                    content,
+                }),
+                Err(e) => Err(format!("error reading include file: {}", e)),
+            }
+        });
+        compiler.compile_into_spirv(&src, kind, file_name, "main", Some(&opt))
+    };
+
+    let result = result
+        .map(|artifact| artifact.as_binary_u8().to_vec())
+        .map_err(|e| compilation_error(format!("{}: {}", path.display(), e)));
+    (result, includes.into_inner())
+}
+
+/// A shader file, and the result of its last compilation.
+struct WatchedShader {
+    stage: ShaderStageFlags,
+    kind: shaderc::ShaderKind,
+    /// Bytecode of the last successful compilation.
+    bytecode: Option<Vec<u8>>,
+    /// Error of the last compilation, if it failed.
+    error: Option<Error>,
+    /// The shader file and its includes, with their modification times at the last compilation.
+    dependencies: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl WatchedShader {
+    fn compile(&mut self, compiler: &mut shaderc::Compiler, path: &Path) {
+        let (result, includes) = compile_glsl(compiler, path, self.stage, self.kind);
+        self.dependencies = Some(path.to_owned())
+            .into_iter()
+            .chain(includes)
+            .map(|path| {
+                let time = modified(&path);
+                (path, time)
+            })
+            .collect();
+        match result {
+            Ok(bytecode) => {
+                self.bytecode = Some(bytecode);
+                self.error = None;
+            }
+            Err(e) => {
+                error!("{}", e);
+                self.error = Some(e);
+            }
+        }
+    }
+
+    fn is_outdated(&self) -> bool {
+        self.dependencies
+            .iter()
+            .any(|(path, time)| modified(path) != *time)
+    }
+}
+
+struct ReloadablePipeline<B: Backend> {
+    signature: *const B::Signature,
+    /// The pipeline in the current arena, or `None` if it must be created again.
+    pipeline: Option<*const B::GraphicsPipeline>,
+    /// The bytecode of the stages of the last pipeline that was successfully created.
+    last_good: Option<Vec<(ShaderStageFlags, Vec<u8>)>>,
+}
+
+/// Graphics pipelines created from GLSL files, that are rebuilt when the files change.
+///
+/// Pipelines are declared by name and by the paths to their shaders (the stage is deduced from the
+/// file extension, like `include_glsl!`), and created on first use in an arena owned by this
+/// object. Shaders are compiled at runtime, and their interface is checked against the signature
+/// of the pipeline (see [validate_shader_interface]).
+///
+/// [update](ShaderReloader::update) polls the modification times of the shaders and of the files
+/// that they include, and compiles the shaders that have changed. If any shader was recompiled,
+/// the arena is dropped, and the pipelines are created again the next time they are requested.
+///
+/// Errors do not interrupt the application: they are logged and returned by
+/// [errors](ShaderReloader::errors). A shader that fails to compile keeps its last successfully
+/// compiled version, and a pipeline that fails to be created from the new shaders is created
+/// again from the shaders of its last good version.
+///
+/// ```ignore
+/// let mut shaders = ShaderReloader::new(&api)?;
+/// loop {
+///     shaders.update()?;
+///     let pipeline = shaders.graphics_pipeline::<BlitArguments>(
+///         "blit",
+///         &["shaders/blit.vert".as_ref(), "shaders/blit.frag".as_ref()],
+///         |shader_stages| blit_pipeline_create_info(shader_stages),
+///     )?;
+///     // ...
+/// }
+/// ```
+pub struct ShaderReloader<'a, B: Backend> {
+    api: &'a Api<B>,
+    arena: Arena<'a, B>,
+    compiler: RefCell<shaderc::Compiler>,
+    shaders: RefCell<HashMap<PathBuf, WatchedShader>>,
+    pipelines: RefCell<HashMap<String, ReloadablePipeline<B>>>,
+}
+
+impl<'a, B: Backend> ShaderReloader<'a, B> {
+    pub fn new(api: &'a Api<B>) -> Result<ShaderReloader<'a, B>> {
+        let compiler = shaderc::Compiler::new().ok_or_else(|| Error::Validation {
+            message: "failed to create the shader compiler".to_string(),
+        })?;
+        Ok(ShaderReloader {
+            api,
+            arena: api.create_arena()?,
+            compiler: RefCell::new(compiler),
+            shaders: RefCell::new(HashMap::new()),
+            pipelines: RefCell::new(HashMap::new()),
+        })
+    }
+
+    /// The arena in which pipelines are created.
+    pub fn arena(&self) -> &Arena<'a, B> {
+        &self.arena
+    }
+
+    /// Compiles the shaders whose source files have changed since the last call.
+    ///
+    /// Returns whether the pipelines were dropped.
+    pub fn update(&mut self) -> Result<bool> {
+        let compiler = self.compiler.get_mut();
+        let mut recompiled = false;
+        for (path, shader) in self.shaders.get_mut().iter_mut() {
+            if shader.is_outdated() {
+                info!("recompiling {}", path.display());
+                shader.compile(compiler, path);
+                recompiled |= shader.error.is_none();
+            }
+        }
+
+        if !recompiled {
+            return Ok(false);
+        }
+
+        for pipeline in self.pipelines.get_mut().values_mut() {
+            pipeline.pipeline = None;
+        }
+        self.arena = self.api.create_arena()?;
+        Ok(true)
+    }
+
+    /// Returns the errors of the last compilation of the shaders that failed to compile.
+    pub fn errors(&self) -> Vec<(PathBuf, Error)> {
+        self.shaders
+            .borrow()
+            .iter()
+            .filter_map(|(path, shader)| shader.error.clone().map(|e| (path.clone(), e)))
+            .collect()
+    }
+
+    /// Returns the graphics pipeline with the specified name, creating it if necessary.
+    ///
+    /// `stages` are the paths to the GLSL sources of the shaders of the pipeline, and
+    /// `create_info` returns the states of the pipeline given the shader stages.
+    /// Panics if a pipeline with the same name but another signature was already created.
+    pub fn graphics_pipeline<'s, P: Arguments<'s, B>>(
+        &'s self,
+        name: &str,
+        stages: &[&Path],
+        create_info: impl for<'r> Fn(
+            GraphicsShaderStages<'s, 'r, B>,
+        ) -> GraphicsPipelineCreateInfo<'s, 'r, B>,
+    ) -> Result<GraphicsPipeline<'s, B, TypedSignature<'s, B, P>>> {
+        let signature = self.api.get_cached_signature::<P>()?;
+
+        let last_good = match self.pipelines.borrow().get(name) {
+            Some(p) => {
+                assert_eq!(p.signature, signature.inner() as *const _);
+                if let Some(pipeline) = p.pipeline {
+                    // reborrow to 'self lifetime: OK because inside own arena
+                    return unsafe { Ok(GraphicsPipeline::from_raw(&*pipeline, signature)) };
+                }
+                p.last_good.clone()
+            }
+            None => None,
+        };
+
+        let bytecode = stages
+            .iter()
+            .map(|path| self.shader_bytecode(path))
+            .collect::<Result<Vec<_>>>();
+        let result = bytecode.and_then(|bytecode| {
+            self.create_graphics_pipeline::<P>(&bytecode, &create_info)
+                .map(|pipeline| (pipeline, bytecode))
+        });
+
+        let (pipeline, last_good) = match (result, last_good) {
+            (Ok((pipeline, bytecode)), _) => (pipeline, bytecode),
+            (Err(e), Some(last_good)) => {
+                error!("{}: {} (using the last good version)", name, e);
+                let pipeline = self.create_graphics_pipeline::<P>(&last_good, &create_info)?;
+                (pipeline, last_good)
+            }
+            (Err(e), None) => return Err(e),
+        };
+
+        self.pipelines.borrow_mut().insert(
+            name.to_string(),
+            ReloadablePipeline {
+                signature: signature.inner() as *const _,
+                pipeline: Some(pipeline.inner() as *const _),
+                last_good: Some(last_good),
+            },
+        );
+        Ok(pipeline)
+    }
+
+    /// Returns the bytecode of the last successful compilation of a shader, compiling it if it
+    /// is not watched yet.
+    fn shader_bytecode(&self, path: &Path) -> Result<(ShaderStageFlags, Vec<u8>)> {
+        let mut shaders = self.shaders.borrow_mut();
+        if !shaders.contains_key(path) {
+            let (stage, kind) = stage_from_extension(path).ok_or_else(|| Error::Validation {
+                message: format!(
+                    "cannot deduce shader stage from extension: {}",
+                    path.display()
+                ),
+            })?;
+            let mut shader = WatchedShader {
+                stage,
+                kind,
+                bytecode: None,
+                error: None,
+                dependencies: Vec::new(),
+            };
+            shader.compile(&mut self.compiler.borrow_mut(), path);
+            shaders.insert(path.to_owned(), shader);
+        }
+        let shader = &shaders[path];
+
+        match (&shader.bytecode, &shader.error) {
+            (Some(bytecode), _) => Ok((shader.stage, bytecode.clone())),
+            (None, Some(e)) => Err(e.clone()),
+            (None, None) => unreachable!(),
+        }
+    }
+
+    fn create_graphics_pipeline<'s, P: Arguments<'s, B>>(
+        &'s self,
+        bytecode: &[(ShaderStageFlags, Vec<u8>)],
+        create_info: &impl for<'r> Fn(
+            GraphicsShaderStages<'s, 'r, B>,
+        ) -> GraphicsPipelineCreateInfo<'s, 'r, B>,
+    ) -> Result<GraphicsPipeline<'s, B, TypedSignature<'s, B, P>>> {
+        let a = DroplessArena::new();
+        let mut vertex = None;
+        let mut geometry = None;
+        let mut fragment = None;
+        let mut tess_eval = None;
+        let mut tess_control = None;
+
+        for (stage, bytecode) in bytecode {
+            let shader = reflect_shader(&a, bytecode, *stage)?;
+            validate_shader_interface(P::SIGNATURE, shader.reflection).map_err(|errors| {
+                Error::Validation {
+                    message: errors
+                        .iter()
+                        .map(|e| e.to_string())
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                }
+            })?;
+            let module = Some(self.arena.create_shader_module(shader)?);
+            match *stage {
+                ShaderStageFlags::VERTEX => vertex = module,
+                ShaderStageFlags::GEOMETRY => geometry = module,
+                ShaderStageFlags::FRAGMENT => fragment = module,
+                ShaderStageFlags::TESS_EVAL => tess_eval = module,
+                ShaderStageFlags::TESS_CONTROL => tess_control = module,
+                _ => unreachable!(),
+            }
+        }
+
+        let shader_stages = GraphicsShaderStages {
+            vertex: vertex.ok_or_else(|| Error::Validation {
+                message: "graphics pipelines must have a vertex shader".to_string(),
+            })?,
+            geometry,
+            fragment,
+            tess_eval,
+            tess_control,
+        };
+        self.arena
+            .create_graphics_pipeline::<P>(&create_info(shader_stages))
+    }
+}
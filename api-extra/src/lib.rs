@@ -1,3 +1,5 @@
 pub mod blackboard;
 pub mod commandext;
+pub mod hot_reload;
 pub mod quad;
+pub mod swapchain;
@@ -1,4 +1,5 @@
 use autograph_api::{
+    error::Result,
     format::Format,
     pipeline::{
         ArgumentBlock, Arguments, Signature, SignatureDescription, TypedSignature,
@@ -67,7 +68,7 @@ impl<'a, B: Backend> Arguments<'a, B> for QuadVertices<'a, B> {
         self,
         signature: TypedSignature<'a, B, Self::IntoInterface>,
         arena: &'a Arena<B>,
-    ) -> ArgumentBlock<'a, B, TypedSignature<'a, B, Self::IntoInterface>> {
+    ) -> Result<ArgumentBlock<'a, B, TypedSignature<'a, B, Self::IntoInterface>>> {
         let (left, top, right, bottom) = (-1.0, -1.0, 1.0, 1.0);
 
         let verts = arena.upload_slice(&[
@@ -77,7 +78,7 @@ impl<'a, B: Backend> Arguments<'a, B> for QuadVertices<'a, B> {
             QuadVertex::new([left, bottom], [0.0, 1.0]),
             QuadVertex::new([right, top], [1.0, 0.0]),
             QuadVertex::new([right, bottom], [1.0, 1.0]),
-        ]);
+        ])?;
 
         arena.create_argument_block(
             signature,
@@ -119,26 +120,25 @@ impl<'a, B: Backend, A: Arguments<'a, B> + 'a> Arguments<'a, B> for Quad<'a, B,
     type UniqueType = QuadUniqueType<A::UniqueType>;
     type IntoInterface = Self;
 
-    fn get_inherited_signatures(renderer: &'a Api<B>) -> Vec<&B::Signature> {
-        vec![
+    fn get_inherited_signatures(renderer: &'a Api<B>) -> Result<Vec<&B::Signature>> {
+        Ok(vec![
             renderer
-                .get_cached_signature::<QuadVertices<'a, B>>()
+                .get_cached_signature::<QuadVertices<'a, B>>()?
                 .inner(),
-            renderer.get_cached_signature::<A>().inner(),
-        ]
+            renderer.get_cached_signature::<A>()?.inner(),
+        ])
     }
 
     fn into_block(
         self,
         signature: TypedSignature<'a, B, Self::IntoInterface>,
         arena: &'a Arena<B>,
-    ) -> ArgumentBlock<'a, B, TypedSignature<'a, B, Self::IntoInterface>> {
-        let vtx = arena.create_typed_argument_block(QuadVertices(PhantomData));
+    ) -> Result<ArgumentBlock<'a, B, TypedSignature<'a, B, Self::IntoInterface>>> {
+        let vtx = arena.create_typed_argument_block(QuadVertices(PhantomData))?;
+        let arguments = arena.create_typed_argument_block(self.arguments)?;
         arena.create_argument_block(
             signature,
-            iter::once(vtx.into()).chain(iter::once(
-                arena.create_typed_argument_block(self.arguments).into(),
-            )),
+            iter::once(vtx.into()).chain(iter::once(arguments.into())),
             iter::empty(),
             iter::empty(),
             None,
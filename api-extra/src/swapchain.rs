@@ -0,0 +1,160 @@
+use autograph_api::{
+    error::Result,
+    format::Format,
+    image::RenderTargetImage2d,
+    pipeline::{ArgumentBlock, BareArgumentBlock, Signature},
+    swapchain::Swapchain,
+    Api, Arena, Backend,
+};
+use std::{cell::RefCell, collections::HashMap, mem};
+
+#[derive(derivative::Derivative)]
+#[derivative(Copy(bound = ""), Clone(bound = ""), Debug(bound = ""))]
+enum SwapchainResource<B: Backend> {
+    RenderTarget {
+        format: Format,
+        img: *const B::Image,
+    },
+    ArgumentBlock {
+        signature: *const B::Signature,
+        block: BareArgumentBlock<'static, B>,
+    },
+}
+
+/// Resources that depend on the size of a swapchain.
+///
+/// Owns an arena for swapchain-related resources. Render targets and argument blocks are
+/// declared by name and created on first use, with the size of the swapchain. When the swapchain
+/// is resized, [update](SwapchainResources::update) drops the arena, and resources are created
+/// again, with the new size, the next time they are requested.
+///
+/// ```ignore
+/// let mut resources = SwapchainResources::new(&api, swapchain)?;
+/// loop {
+///     resources.update()?;
+///     let color = resources.render_target("color", Format::R16G16B16A16_SFLOAT)?;
+///     let args = resources.argument_block("blit", blit_signature, |arena| {
+///         BlitArguments { target: color.into() }.into_block(blit_signature, arena)
+///     })?;
+///     // ...
+/// }
+/// ```
+pub struct SwapchainResources<'a, B: Backend> {
+    api: &'a Api<B>,
+    swapchain: Swapchain<'a, B>,
+    size: (u32, u32),
+    generation: u64,
+    arena: Arena<'a, B>,
+    lookup: RefCell<HashMap<String, SwapchainResource<B>>>,
+}
+
+impl<'a, B: Backend> SwapchainResources<'a, B> {
+    pub fn new(api: &'a Api<B>, swapchain: Swapchain<'a, B>) -> Result<SwapchainResources<'a, B>> {
+        Ok(SwapchainResources {
+            api,
+            swapchain,
+            size: swapchain.size(),
+            generation: swapchain.generation(),
+            arena: api.create_arena()?,
+            lookup: RefCell::new(HashMap::new()),
+        })
+    }
+
+    pub fn swapchain(&self) -> Swapchain<'a, B> {
+        self.swapchain
+    }
+
+    /// Size of the resources: the size of the swapchain when the arena was last recreated.
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    pub fn arena(&self) -> &Arena<'a, B> {
+        &self.arena
+    }
+
+    /// Drops all resources if the swapchain was resized since the last call.
+    ///
+    /// Returns whether the resources were dropped. A swapchain with an empty size (e.g. the
+    /// swapchain of a minimized window) is not considered resized, and the resources are kept
+    /// until the swapchain has a non-empty size again.
+    pub fn update(&mut self) -> Result<bool> {
+        let generation = self.swapchain.generation();
+        if generation == self.generation {
+            return Ok(false);
+        }
+        let (width, height) = self.swapchain.size();
+        if width == 0 || height == 0 {
+            return Ok(false);
+        }
+
+        self.lookup.get_mut().clear();
+        self.arena = self.api.create_arena()?;
+        self.size = (width, height);
+        self.generation = generation;
+        Ok(true)
+    }
+
+    /// Returns the render target with the specified name, with the size of the swapchain.
+    ///
+    /// Panics if a render target with the same name but another format was already created.
+    pub fn render_target(&self, name: &str, format: Format) -> Result<RenderTargetImage2d<B>> {
+        if let Some(SwapchainResource::RenderTarget { format: f, img }) = self.get_resource(name) {
+            assert_eq!(f, format);
+            // reborrow to 'self lifetime: OK because inside own arena (and stable addresses)
+            return unsafe { Ok(RenderTargetImage2d::from_raw(&*img)) };
+        }
+
+        let (width, height) = self.size;
+        let img = self
+            .arena
+            .render_target(format, width, height)
+            .debug_name(name)
+            .build()?;
+        self.lookup.borrow_mut().insert(
+            name.to_string(),
+            SwapchainResource::RenderTarget {
+                format,
+                img: img.inner() as *const _,
+            },
+        );
+        Ok(img)
+    }
+
+    /// Returns the argument block with the specified name, creating it with `f` if necessary.
+    ///
+    /// Argument blocks that reference render targets of this object must be created here, so that
+    /// they are recreated after a resize.
+    /// Panics if a block with the same name but another signature was already created.
+    pub fn argument_block<'s, S: Signature<'s, B>>(
+        &'s self,
+        name: &str,
+        signature: S,
+        f: impl FnOnce(&'s Arena<'a, B>) -> Result<ArgumentBlock<'s, B, S>>,
+    ) -> Result<ArgumentBlock<'s, B, S>> {
+        if let Some(SwapchainResource::ArgumentBlock {
+            signature: sig,
+            block,
+        }) = self.get_resource(name)
+        {
+            assert_eq!(sig, signature.inner() as *const _);
+            return unsafe { Ok(ArgumentBlock::from_bare(block, signature)) };
+        }
+
+        let block = f(&self.arena)?;
+        let bare: BareArgumentBlock<B> = block.into();
+        self.lookup.borrow_mut().insert(
+            name.to_string(),
+            SwapchainResource::ArgumentBlock {
+                signature: signature.inner() as *const _,
+                // removed from the lookup when the arena is dropped
+                block: unsafe { mem::transmute(bare) },
+            },
+        );
+        Ok(block)
+    }
+
+    fn get_resource(&self, name: &str) -> Option<SwapchainResource<B>> {
+        self.lookup.borrow().get(name).cloned()
+    }
+}
@@ -0,0 +1,141 @@
+//! shader hot-reloading tests
+use autograph_api::{
+    error::{Error, Result},
+    mock::{MockBackend, MockInstance, MockObject},
+    pipeline::{
+        ColorBlendState, DepthStencilState, GraphicsPipelineCreateInfo, InputAssemblyState,
+        MultisampleState, PrimitiveTopology, RasterisationState, ViewportState,
+    },
+    Api,
+};
+use autograph_api_extra::hot_reload::ShaderReloader;
+use autograph_api_test::NoArguments;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+const VERTEX: &str = "#version 450\nvoid main() { gl_Position = vec4(0.0); }\n";
+const FRAGMENT: &str = "#version 450\nvoid main() {}\n";
+const FRAGMENT_DISCARD: &str = "#version 450\nvoid main() { discard; }\n";
+// compiles, but has an output that the (empty) signature doesn't have
+const FRAGMENT_OUTPUT: &str =
+    "#version 450\nlayout(location = 0) out vec4 color;\nvoid main() { color = vec4(1.0); }\n";
+const FRAGMENT_SYNTAX_ERROR: &str = "#version 450\nvoid main() {\n";
+
+/// Creates an empty directory for the shaders of a test.
+fn shader_dir(test: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("autograph-{}-{}", test, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Writes a file, making sure that its modification time changes.
+fn write(path: &Path, contents: &str) {
+    let before = fs::metadata(path).and_then(|m| m.modified()).ok();
+    loop {
+        fs::write(path, contents).unwrap();
+        if fs::metadata(path).and_then(|m| m.modified()).ok() != before {
+            break;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// Returns the address of the pipeline, so that the reloader can be updated afterwards.
+fn pipeline(
+    shaders: &ShaderReloader<MockBackend>,
+    vert: &Path,
+    frag: &Path,
+) -> Result<*const MockObject> {
+    shaders
+        .graphics_pipeline::<NoArguments>("test", &[vert, frag], |shader_stages| {
+            GraphicsPipelineCreateInfo {
+                shader_stages,
+                viewport_state: ViewportState::default(),
+                rasterization_state: RasterisationState::default(),
+                multisample_state: MultisampleState::default(),
+                depth_stencil_state: DepthStencilState::default(),
+                input_assembly_state: InputAssemblyState {
+                    topology: PrimitiveTopology::TriangleList,
+                    primitive_restart_enable: false,
+                },
+                color_blend_state: ColorBlendState::DISABLED,
+            }
+        })
+        .map(|pipeline| pipeline.inner() as *const _)
+}
+
+#[test]
+fn test_hot_reload_recompile() {
+    let dir = shader_dir("recompile");
+    let vert = dir.join("test.vert");
+    let frag = dir.join("test.frag");
+    write(&vert, VERTEX);
+    write(&frag, FRAGMENT);
+
+    let api = Api::<MockBackend>::new(MockInstance::new()).unwrap();
+    let mut shaders = ShaderReloader::new(&api).unwrap();
+    let a = pipeline(&shaders, &vert, &frag).unwrap();
+    assert_eq!(pipeline(&shaders, &vert, &frag).unwrap(), a);
+    assert!(!shaders.update().unwrap());
+
+    write(&frag, FRAGMENT_DISCARD);
+    assert!(shaders.update().unwrap());
+    assert!(shaders.errors().is_empty());
+    let b = pipeline(&shaders, &vert, &frag).unwrap();
+    assert_ne!(a, b);
+    assert_eq!(pipeline(&shaders, &vert, &frag).unwrap(), b);
+}
+
+#[test]
+fn test_hot_reload_last_good() {
+    let dir = shader_dir("last_good");
+    let vert = dir.join("test.vert");
+    let frag = dir.join("test.frag");
+    write(&vert, VERTEX);
+    write(&frag, FRAGMENT);
+
+    let api = Api::<MockBackend>::new(MockInstance::new()).unwrap();
+    let mut shaders = ShaderReloader::new(&api).unwrap();
+    let a = pipeline(&shaders, &vert, &frag).unwrap();
+
+    // compilation error: the last successfully compiled shader is kept
+    write(&frag, FRAGMENT_SYNTAX_ERROR);
+    assert!(!shaders.update().unwrap());
+    assert_eq!(shaders.errors().len(), 1);
+    assert_eq!(pipeline(&shaders, &vert, &frag).unwrap(), a);
+
+    // the new shader compiles but doesn't match the signature: the pipeline is created again
+    // from the shaders of the last good version
+    write(&frag, FRAGMENT_OUTPUT);
+    assert!(shaders.update().unwrap());
+    assert!(shaders.errors().is_empty());
+    assert!(pipeline(&shaders, &vert, &frag).is_ok());
+
+    // without a last good version, the error is returned
+    let fresh = ShaderReloader::new(&api).unwrap();
+    match pipeline(&fresh, &vert, &frag) {
+        Err(Error::Validation { .. }) => {}
+        other => panic!("expected a validation error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_hot_reload_unknown_extension() {
+    let dir = shader_dir("unknown_extension");
+    let vert = dir.join("test.vert");
+    let frag = dir.join("test.glsl");
+    write(&vert, VERTEX);
+    write(&frag, FRAGMENT);
+
+    let api = Api::<MockBackend>::new(MockInstance::new()).unwrap();
+    let shaders = ShaderReloader::new(&api).unwrap();
+    match pipeline(&shaders, &vert, &frag) {
+        Err(Error::Validation { .. }) => {}
+        other => panic!("expected a validation error, got {:?}", other),
+    }
+}
@@ -2,14 +2,21 @@ use crate::{
     aliaspool::AliasPool,
     api as gl,
     api::{types::*, Gl},
-    buffer::{create_buffer, GlBuffer, MappedBuffer, RawBuffer, UploadBuffer},
+    buffer::{
+        create_buffer, BufferMapping, GlBuffer, GlHostReference, MappedBuffer, RawBuffer,
+        UploadBuffer,
+    },
     command::{StateCache, SubmissionContext},
+    format::GlFormatInfo,
     framebuffer::GlFramebuffer,
     image::{upload_image_region, GlImage, ImageAliasKey, ImageDescription, RawImage},
     pipeline::{
-        create_graphics_pipeline_internal, GlArgumentBlock, GlGraphicsPipeline, GlShaderModule,
-        GlSignature,
+        create_compute_pipeline_internal, create_graphics_pipeline_internal, GlArgumentBlock,
+        GlComputePipeline, GlGraphicsPipeline, GlShaderModule, GlSignature,
     },
+    profile::PendingProfile,
+    query::GlQueryPool,
+    readback::PendingReadback,
     sampler::SamplerCache,
     swapchain::GlSwapchain,
     sync::{GpuSyncObject, Timeline},
@@ -17,15 +24,20 @@ use crate::{
 };
 use autograph_api::{
     command::Command,
+    debug::DebugObject,
     descriptor::Descriptor,
+    error::Error,
     format::Format,
     image::{DepthStencilView, Dimensions, ImageUsageFlags, MipmapsOption, RenderTargetView},
     pipeline::{
-        BareArgumentBlock, GraphicsPipelineCreateInfo, Scissor, ShaderStageFlags,
-        SignatureDescription, Viewport,
+        BareArgumentBlock, ComputePipelineCreateInfo, GraphicsPipelineCreateInfo, Scissor,
+        ShaderStageFlags, SignatureDescription, Viewport,
     },
+    profile::FrameProfile,
+    query::QueryType,
+    schedule::QueueBatch,
     vertex::{IndexBufferView, VertexBufferView},
-    AliasScope, Backend, Instance,
+    AliasScope, Backend, Instance, MemoryType,
 };
 use dropless_arena::DroplessArena;
 use glutin::{GlContext, GlWindow};
@@ -36,7 +48,7 @@ use std::{
     mem,
     os::raw::c_char,
     ptr, slice, str,
-    sync::Arc,
+    sync::{Arc, Weak},
     time::Duration,
 };
 use typed_arena::Arena;
@@ -47,13 +59,17 @@ use winit::Window;
 //--------------------------------------------------------------------------------------------------
 extern "system" fn debug_callback(
     _source: GLenum,
-    _ty: GLenum,
+    ty: GLenum,
     _id: GLuint,
     severity: GLenum,
     length: GLsizei,
     msg: *const GLchar,
     _data: *mut GLvoid,
 ) {
+    // debug groups are for graphics debuggers
+    if ty == gl::DEBUG_TYPE_PUSH_GROUP || ty == gl::DEBUG_TYPE_POP_GROUP {
+        return;
+    }
     let str = unsafe {
         str::from_utf8(slice::from_raw_parts(msg as *const u8, length as usize)).unwrap()
     };
@@ -73,25 +89,31 @@ pub struct OpenGlBackend;
 impl Backend for OpenGlBackend {
     type Instance = OpenGlInstance;
     type Arena = GlArena;
+    type Window = Arc<GlWindow>;
     type Swapchain = GlSwapchain;
     type Image = GlImage;
     type Buffer = GlBuffer;
     type ShaderModule = GlShaderModule;
     type GraphicsPipeline = GlGraphicsPipeline;
+    type ComputePipeline = GlComputePipeline;
     type Signature = GlSignature;
     type ArgumentBlock = GlArgumentBlock;
-    type HostReference = ();
+    type HostReference = GlHostReference;
+    type QueryPool = GlQueryPool;
 }
 
 //--------------------------------------------------------------------------------------------------
 pub struct GlArena {
-    pub(crate) _swapchains: Arena<GlSwapchain>,
+    pub(crate) swapchains: Arena<GlSwapchain>,
     pub(crate) buffers: Arena<GlBuffer>,
     pub(crate) images: Arena<GlImage>,
     pub(crate) shader_modules: Arena<GlShaderModule>,
     pub(crate) signatures: Arena<GlSignature>,
     pub(crate) graphics_pipelines: Arena<GlGraphicsPipeline>,
+    pub(crate) compute_pipelines: Arena<GlComputePipeline>,
     pub(crate) framebuffers: Arena<GlFramebuffer>,
+    pub(crate) host_references: Arena<GlHostReference>,
+    pub(crate) query_pools: Arena<GlQueryPool>,
     pub(crate) upload_buffer: UploadBuffer,
     pub(crate) other: DroplessArena,
 }
@@ -99,13 +121,16 @@ pub struct GlArena {
 impl GlArena {
     pub(crate) fn new(upload_buffer: UploadBuffer) -> GlArena {
         GlArena {
-            _swapchains: Arena::new(),
+            swapchains: Arena::new(),
             buffers: Arena::new(),
             images: Arena::new(),
             shader_modules: Arena::new(),
             signatures: Arena::new(),
             graphics_pipelines: Arena::new(),
+            compute_pipelines: Arena::new(),
             framebuffers: Arena::new(),
+            host_references: Arena::new(),
+            query_pools: Arena::new(),
             upload_buffer,
             other: DroplessArena::new(),
         }
@@ -122,6 +147,8 @@ struct Resources {
     upload_buffer_size: usize,
     upload_buffers: Vec<MappedBuffer>,
     upload_buffers_in_use: VecDeque<GpuSyncObject<Vec<MappedBuffer>>>,
+    /// Dropped arenas, with the last frame that may use their objects.
+    retired_arenas: VecDeque<(u64, Box<GlArena>)>,
 }
 
 impl Resources {
@@ -132,6 +159,7 @@ impl Resources {
             upload_buffer_size,
             upload_buffers: Vec::new(),
             upload_buffers_in_use: VecDeque::new(),
+            retired_arenas: VecDeque::new(),
         }
     }
 
@@ -161,8 +189,24 @@ impl Resources {
         Box::new(GlArena::new(self.alloc_upload_buffer(gl)))
     }
 
+    /// Retires an arena: its objects are destroyed once `last_frame` has finished executing.
+    fn retire_arena(&mut self, last_frame: u64, arena: Box<GlArena>) {
+        self.retired_arenas.push_back((last_frame, arena));
+    }
+
+    /// Destroys the retired arenas that are not used by frames in flight anymore.
+    fn destroy_retired_arenas(&mut self, gl: &Gl, timeline: &mut Timeline) {
+        while let Some(&(frame, _)) = self.retired_arenas.front() {
+            if !timeline.client_sync(gl, frame, Duration::from_secs(0)) {
+                break;
+            }
+            let arena = self.retired_arenas.pop_front().unwrap().1;
+            self.destroy_arena(gl, arena);
+        }
+    }
+
     // arena can't drop before commands that refer to the objects inside are submitted
-    fn drop_arena(&mut self, gl: &Gl, arena: Box<GlArena>)
+    fn destroy_arena(&mut self, gl: &Gl, arena: Box<GlArena>)
     where
         Self: Sized,
     {
@@ -192,6 +236,10 @@ impl Resources {
             fb.destroy(gl);
         });
 
+        arena.query_pools.into_vec().into_iter().for_each(|pool| {
+            pool.destroy(gl);
+        });
+
         self.upload_buffers_in_use.push_back(GpuSyncObject::new(
             gl,
             vec![arena.upload_buffer.into_inner()],
@@ -223,10 +271,18 @@ pub struct OpenGlInstance {
     rsrc: RefCell<Resources>,
     timeline: RefCell<Timeline>,
     frame_num: Cell<u64>, // replace with AtomicU64 once stabilized
+    /// Readbacks waiting for the end of their frame, with the frame number.
+    readbacks: RefCell<VecDeque<(u64, PendingReadback)>>,
+    /// Profile scopes of the frames in flight.
+    profiles: RefCell<VecDeque<(u64, PendingProfile)>>,
+    /// Profile of the last completed frame with profile scopes.
+    last_profile: RefCell<Option<FrameProfile>>,
     state_cache: RefCell<StateCache>,
     sampler_cache: RefCell<SamplerCache>,
     limits: ImplementationParameters,
     window: Option<Arc<GlWindow>>,
+    /// Windows created with [create_shared_window](crate::create_shared_window).
+    pub(crate) shared_windows: RefCell<Vec<Weak<GlWindow>>>,
     def_swapchain: Option<GlSwapchain>,
     cfg: InstanceConfig,
     gl: gl::Gl,
@@ -379,6 +435,20 @@ impl OpenGlInstance {
         true
     }
 
+    /// Reads the timestamps of the profile scopes of the frames that have finished executing,
+    /// without blocking.
+    fn resolve_profiles(&self) {
+        let mut timeline = self.timeline.borrow_mut();
+        let mut profiles = self.profiles.borrow_mut();
+        while let Some(&(frame, _)) = profiles.front() {
+            if !timeline.client_sync(&self.gl, frame, Duration::from_secs(0)) {
+                break;
+            }
+            let profile = profiles.pop_front().unwrap().1;
+            *self.last_profile.borrow_mut() = Some(unsafe { profile.resolve(&self.gl, frame) });
+        }
+    }
+
     fn init(&mut self, cfg: &InstanceConfig) {
         let (major_version, minor_version) = get_gl_version(&self.gl);
 
@@ -409,10 +479,12 @@ impl OpenGlInstance {
             rsrc: RefCell::new(Resources::new(cfg.upload_buffer_size)),
             timeline: RefCell::new(timeline),
             frame_num: Cell::new(1),
+            readbacks: RefCell::new(VecDeque::new()),
+            profiles: RefCell::new(VecDeque::new()),
+            last_profile: RefCell::new(None),
             window: window.clone(),
-            def_swapchain: window.clone().map(|window| GlSwapchain {
-                window
-            }),
+            shared_windows: RefCell::new(Vec::new()),
+            def_swapchain: window.clone().map(|window| GlSwapchain::new(window, None)),
             gl,
             cfg: *cfg,
             limits,
@@ -424,23 +496,73 @@ impl OpenGlInstance {
     }
 }
 
+impl Drop for OpenGlInstance {
+    fn drop(&mut self) {
+        // destroy the arenas retired while their frames were in flight; if the frames do not
+        // finish in time, leak them rather than destroying objects that may still be in use
+        let last_frame = self.frame_num.get() - 1;
+        let timeline = self.timeline.get_mut();
+        if timeline.client_sync(&self.gl, last_frame, FRAME_WAIT_TIMEOUT) {
+            self.rsrc
+                .get_mut()
+                .destroy_retired_arenas(&self.gl, timeline);
+        }
+    }
+}
+
 // TODO move this into a function in the spirv module
 const SPIRV_MAGIC: u32 = 0x0723_0203;
 const UPLOAD_DEDICATED_THRESHOLD: usize = 65536;
 const FRAME_WAIT_TIMEOUT: Duration = Duration::from_millis(500);
 
 impl Instance<OpenGlBackend> for OpenGlInstance {
-    unsafe fn create_arena(&self) -> Box<GlArena> {
-        self.rsrc.borrow_mut().create_arena(&self.gl)
+    unsafe fn create_arena(&self) -> Result<Box<GlArena>, Error> {
+        Ok(self.rsrc.borrow_mut().create_arena(&self.gl))
     }
 
     unsafe fn drop_arena(&self, arena: Box<GlArena>) {
-        self.rsrc.borrow_mut().drop_arena(&self.gl, arena)
+        // the frames submitted so far may still use the objects of the arena
+        let last_frame = self.frame_num.get() - 1;
+        let mut rsrc = self.rsrc.borrow_mut();
+        rsrc.retire_arena(last_frame, arena);
+        rsrc.destroy_retired_arenas(&self.gl, &mut self.timeline.borrow_mut());
     }
 
     //----------------------------------------------------------------------------------------------
-    unsafe fn create_swapchain<'a>(&self, _arena: &'a GlArena) -> &'a GlSwapchain {
-        unimplemented!()
+    unsafe fn create_swapchain<'a>(
+        &self,
+        arena: &'a GlArena,
+        window: &Arc<GlWindow>,
+    ) -> Result<&'a GlSwapchain, Error> {
+        // presenting to another window switches to its context, and back
+        let main_window = match self.window {
+            Some(ref main_window) if Arc::ptr_eq(main_window, window) => None,
+            Some(ref main_window) => {
+                // the context of the window must share objects with the context of the instance
+                let mut shared_windows = self.shared_windows.borrow_mut();
+                shared_windows.retain(|w| w.upgrade().is_some());
+                let is_shared = shared_windows
+                    .iter()
+                    .any(|w| w.upgrade().map_or(false, |w| Arc::ptr_eq(&w, window)));
+                if !is_shared {
+                    return Err(Error::Validation {
+                        message: "additional swapchains require a window created with \
+                                  `create_shared_window`"
+                            .into(),
+                    });
+                }
+                Some(main_window.clone())
+            }
+            None => {
+                return Err(Error::Validation {
+                    message: "additional swapchains require an instance created with a window"
+                        .into(),
+                })
+            }
+        };
+        Ok(arena
+            .swapchains
+            .alloc(GlSwapchain::new(window.clone(), main_window)))
     }
 
     unsafe fn default_swapchain<'rcx>(&'rcx self) -> Option<&'rcx GlSwapchain> {
@@ -458,15 +580,32 @@ impl Instance<OpenGlBackend> for OpenGlInstance {
         samples: u32,
         usage: ImageUsageFlags,
         initial_data: Option<&[u8]>,
-    ) -> &'a GlImage {
+    ) -> Result<&'a GlImage, Error> {
+        if GlFormatInfo::try_from_format(format).is_none() {
+            return Err(Error::UnsupportedFormat {
+                format,
+                message: String::new(),
+            });
+        }
+        if samples > 1 && dimensions.array_layers() > 1 {
+            return Err(Error::Validation {
+                message: "multisampled array images are not supported".into(),
+            });
+        }
+
         let d = ImageDescription::new(format, dimensions, mipmaps, samples, usage);
 
         if scope != AliasScope::no_alias() {
             // cannot specify initial data for aliasable image
-            assert!(initial_data.is_none());
-            self.rsrc
+            if initial_data.is_some() {
+                return Err(Error::Validation {
+                    message: "cannot specify initial data for an aliasable image".into(),
+                });
+            }
+            Ok(self
+                .rsrc
                 .borrow_mut()
-                .alloc_aliased_image(&self.gl, arena, scope, &d)
+                .alloc_aliased_image(&self.gl, arena, scope, &d))
         } else {
             // not aliasable, dedicated allocation
             let raw = RawImage::new(&self.gl, &d);
@@ -484,11 +623,11 @@ impl Instance<OpenGlBackend> for OpenGlInstance {
                 );
             }
 
-            arena.images.alloc(GlImage {
+            Ok(arena.images.alloc(GlImage {
                 should_destroy: true,
                 raw,
                 alias_info: None,
-            })
+            }))
         }
     }
 
@@ -498,14 +637,16 @@ impl Instance<OpenGlBackend> for OpenGlInstance {
         arena: &'a GlArena,
         size: u64,
         data: &[u8],
-    ) -> &'a GlBuffer {
+    ) -> Result<&'a GlBuffer, Error> {
         if size < UPLOAD_DEDICATED_THRESHOLD as u64 {
             // if the buffer is small enough, allocate through the upload buffer
             let (obj, offset) = arena
                 .upload_buffer
                 .write(data, self.limits.uniform_buffer_alignment)
-                .unwrap();
-            arena.buffers.alloc(GlBuffer {
+                .ok_or_else(|| Error::OutOfMemory {
+                    message: "upload buffer is full".into(),
+                })?;
+            Ok(arena.buffers.alloc(GlBuffer {
                 raw: RawBuffer {
                     obj,
                     size: size as usize,
@@ -513,10 +654,11 @@ impl Instance<OpenGlBackend> for OpenGlInstance {
                 offset,
                 alias_info: None,
                 should_destroy: false,
-            })
+                mapping: None,
+            }))
         } else {
             // otherwise, allocate a dedicated buffer
-            arena.buffers.alloc(GlBuffer {
+            Ok(arena.buffers.alloc(GlBuffer {
                 raw: RawBuffer {
                     obj: create_buffer(&self.gl, size as usize, 0, Some(data)),
                     size: size as usize,
@@ -524,13 +666,70 @@ impl Instance<OpenGlBackend> for OpenGlInstance {
                 offset: 0,
                 should_destroy: true,
                 alias_info: None,
-            })
+                mapping: None,
+            }))
         }
     }
 
     //----------------------------------------------------------------------------------------------
-    unsafe fn create_buffer<'a>(&self, _arena: &'a GlArena, _size: u64) -> &'a GlBuffer {
-        unimplemented!()
+    unsafe fn create_buffer<'a>(
+        &self,
+        arena: &'a GlArena,
+        size: u64,
+        memory: MemoryType,
+    ) -> Result<&'a GlBuffer, Error> {
+        let persistent_flags = gl::MAP_WRITE_BIT | gl::MAP_PERSISTENT_BIT | gl::MAP_COHERENT_BIT;
+        let flags = match memory {
+            MemoryType::DeviceLocal => gl::DYNAMIC_STORAGE_BIT,
+            MemoryType::HostUpload => persistent_flags,
+            MemoryType::HostReadback => gl::DYNAMIC_STORAGE_BIT | gl::CLIENT_STORAGE_BIT,
+        };
+        let obj = create_buffer(&self.gl, size as usize, flags, None);
+
+        let mapping = if memory == MemoryType::HostUpload {
+            let ptr = self
+                .gl
+                .MapNamedBufferRange(obj, 0, size as isize, persistent_flags);
+            if ptr.is_null() {
+                self.gl.DeleteBuffers(1, &obj);
+                return Err(Error::OutOfMemory {
+                    message: "could not map host-visible buffer".into(),
+                });
+            }
+            Some(BufferMapping(ptr as *mut u8))
+        } else {
+            None
+        };
+
+        Ok(arena.buffers.alloc(GlBuffer {
+            raw: RawBuffer {
+                obj,
+                size: size as usize,
+            },
+            offset: 0,
+            should_destroy: true,
+            alias_info: None,
+            mapping,
+        }))
+    }
+
+    unsafe fn write_buffer(&self, buffer: &GlBuffer, offset: u64, data: &[u8]) {
+        assert!(
+            offset as usize + data.len() <= buffer.raw.size,
+            "write out of bounds of buffer"
+        );
+        let offset = buffer.offset + offset as usize;
+        if let Some(mapping) = buffer.mapping {
+            // the mapping is coherent: no need to flush
+            ptr::copy_nonoverlapping(data.as_ptr(), mapping.0.add(offset), data.len());
+        } else {
+            self.gl.NamedBufferSubData(
+                buffer.raw.obj,
+                offset as GLintptr,
+                data.len() as GLsizeiptr,
+                data.as_ptr() as *const GLvoid,
+            );
+        }
     }
 
     //----------------------------------------------------------------------------------------------
@@ -539,7 +738,7 @@ impl Instance<OpenGlBackend> for OpenGlInstance {
         arena: &'a GlArena,
         data: &[u8],
         stage: ShaderStageFlags,
-    ) -> &'a GlShaderModule {
+    ) -> Result<&'a GlShaderModule, Error> {
         // detect SPIR-V or GLSL
         // TODO big-endian is also possible!
         // FIXME clippy warning: data may be misaligned
@@ -556,11 +755,15 @@ impl Instance<OpenGlBackend> for OpenGlInstance {
                 stage,
             }
         } else {
-            GlShaderModule::from_glsl(&self.gl, stage, data)
-                .expect("failed to compile shader from GLSL source")
+            GlShaderModule::from_glsl(&self.gl, stage, data).map_err(|e| {
+                Error::ShaderCompilation {
+                    stage,
+                    message: e.0,
+                }
+            })?
         };
 
-        arena.shader_modules.alloc(module)
+        Ok(arena.shader_modules.alloc(module))
     }
 
     //----------------------------------------------------------------------------------------------
@@ -570,7 +773,7 @@ impl Instance<OpenGlBackend> for OpenGlInstance {
         root_signature: &'a GlSignature,
         root_signature_description: &SignatureDescription,
         create_info: &GraphicsPipelineCreateInfo<'a, 'b, OpenGlBackend>,
-    ) -> &'a GlGraphicsPipeline {
+    ) -> Result<&'a GlGraphicsPipeline, Error> {
         create_graphics_pipeline_internal(
             &self.gl,
             arena,
@@ -580,6 +783,23 @@ impl Instance<OpenGlBackend> for OpenGlInstance {
         )
     }
 
+    //----------------------------------------------------------------------------------------------
+    unsafe fn create_compute_pipeline<'a, 'b>(
+        &self,
+        arena: &'a GlArena,
+        root_signature: &'a GlSignature,
+        root_signature_description: &SignatureDescription,
+        create_info: &ComputePipelineCreateInfo<'a, 'b, OpenGlBackend>,
+    ) -> Result<&'a GlComputePipeline, Error> {
+        create_compute_pipeline_internal(
+            &self.gl,
+            arena,
+            root_signature,
+            root_signature_description,
+            create_info,
+        )
+    }
+
     //----------------------------------------------------------------------------------------------
     unsafe fn create_argument_block<'a>(
         &self,
@@ -593,7 +813,7 @@ impl Instance<OpenGlBackend> for OpenGlInstance {
         depth_stencil_render_target: Option<DepthStencilView<'a, OpenGlBackend>>,
         viewports: impl IntoIterator<Item = Viewport>,
         scissors: impl IntoIterator<Item = Scissor>,
-    ) -> &'a GlArgumentBlock {
+    ) -> Result<&'a GlArgumentBlock, Error> {
         let mut sampler_cache = self.sampler_cache.borrow_mut();
         GlArgumentBlock::new(
             arena,
@@ -616,19 +836,69 @@ impl Instance<OpenGlBackend> for OpenGlInstance {
         arena: &'a GlArena,
         inherited: &[&'a GlSignature],
         description: &SignatureDescription,
-    ) -> &'a GlSignature {
+    ) -> Result<&'a GlSignature, Error> {
         let sig = GlSignature::new(arena, inherited, description);
 
-        sig
+        Ok(sig)
     }
 
     //----------------------------------------------------------------------------------------------
-    unsafe fn create_host_reference<'a>(&self, _arena: &'a GlArena, _data: &'a [u8]) -> &'a () {
-        unimplemented!()
+    unsafe fn create_host_reference<'a>(
+        &self,
+        arena: &'a GlArena,
+        data: &'a [u8],
+    ) -> Result<&'a GlHostReference, Error> {
+        // the data is copied into an upload buffer when the frame is submitted
+        Ok(arena.host_references.alloc(GlHostReference {
+            data: data.as_ptr(),
+            len: data.len(),
+        }))
+    }
+
+    //----------------------------------------------------------------------------------------------
+    unsafe fn set_debug_name(&self, object: DebugObject<OpenGlBackend>, name: &str) {
+        if !self.limits.khr_debug {
+            return;
+        }
+        let (identifier, obj) = match object {
+            DebugObject::Image(image) if image.raw.target == gl::RENDERBUFFER => {
+                (gl::RENDERBUFFER, image.raw.obj)
+            }
+            DebugObject::Image(image) => (gl::TEXTURE, image.raw.obj),
+            // don't label buffers suballocated from a shared buffer
+            DebugObject::Buffer(buffer) if buffer.should_destroy => (gl::BUFFER, buffer.raw.obj),
+            DebugObject::GraphicsPipeline(pipeline) => (gl::PROGRAM, pipeline.program),
+            DebugObject::ComputePipeline(pipeline) => (gl::PROGRAM, pipeline.program),
+            // argument blocks have no corresponding GL object
+            DebugObject::Buffer(_) | DebugObject::ArgumentBlock(_) => return,
+        };
+        self.gl.ObjectLabel(
+            identifier,
+            obj,
+            name.len() as GLsizei,
+            name.as_ptr() as *const GLchar,
+        );
+    }
+
+    //----------------------------------------------------------------------------------------------
+    unsafe fn create_query_pool<'a>(
+        &self,
+        arena: &'a GlArena,
+        ty: QueryType,
+        count: u32,
+    ) -> Result<&'a GlQueryPool, Error> {
+        Ok(arena
+            .query_pools
+            .alloc(GlQueryPool::new(&self.gl, ty, count)))
     }
 
     //----------------------------------------------------------------------------------------------
-    unsafe fn submit_frame<'a>(&self, frame: &[Command<'a, OpenGlBackend>]) {
+    unsafe fn submit_frame<'a>(
+        &self,
+        frame: &[Command<'a, OpenGlBackend>],
+        _batches: &[QueueBatch],
+    ) {
+        // OpenGL has a single queue: all commands are executed in order, in a single batch
         let mut scache = self.state_cache.borrow_mut();
 
         //self.gl.ClipControl(gl::UPPER_LEFT, gl::NEGATIVE_ONE_TO_ONE);
@@ -637,16 +907,38 @@ impl Instance<OpenGlBackend> for OpenGlInstance {
         scache.invalidate();
 
         // execute commands
-        {
-            let mut subctxt = SubmissionContext::new(&self.gl, &mut scache, &self.limits);
+        let mut rsrc = self.rsrc.borrow_mut();
+        rsrc.reclaim_upload_buffers(&self.gl);
+        let (upload_buffers, readbacks, profile) = {
+            let rsrc = &mut *rsrc;
+            let mut subctxt = SubmissionContext::new(
+                &self.gl,
+                &mut scache,
+                &self.limits,
+                &mut rsrc.upload_buffers,
+                rsrc.upload_buffer_size,
+            );
             for cmd in frame.iter() {
                 subctxt.submit_command(cmd);
             }
+            subctxt.finish()
+        };
+        // host data uploaded for the frame: the buffers can be reused once the frame is finished
+        if !upload_buffers.is_empty() {
+            rsrc.upload_buffers_in_use
+                .push_back(GpuSyncObject::new(&self.gl, upload_buffers));
         }
+        drop(rsrc);
 
         let fnum = self.frame_num.get();
         let mut timeline = self.timeline.borrow_mut();
         timeline.signal(&self.gl, fnum);
+        self.readbacks
+            .borrow_mut()
+            .extend(readbacks.into_iter().map(|r| (fnum, r)));
+        if !profile.is_empty() {
+            self.profiles.borrow_mut().push_back((fnum, profile));
+        }
 
         // wait for previous frames before starting a new one
         // if max_frames_in_flight is zero, then will wait on the previously signalled point.
@@ -665,6 +957,60 @@ impl Instance<OpenGlBackend> for OpenGlInstance {
         }
 
         self.frame_num.set(fnum + 1);
+        self.rsrc
+            .borrow_mut()
+            .destroy_retired_arenas(&self.gl, &mut timeline);
+        drop(timeline);
+        drop(scache);
+        self.poll_readbacks(Duration::from_secs(0));
+    }
+
+    unsafe fn poll_readbacks(&self, timeout: Duration) -> bool {
+        let mut completed = Vec::new();
+        {
+            let mut timeline = self.timeline.borrow_mut();
+            let mut readbacks = self.readbacks.borrow_mut();
+            while let Some(&(frame, _)) = readbacks.front() {
+                // only wait if nothing could be completed
+                let timeout = if completed.is_empty() {
+                    timeout
+                } else {
+                    Duration::from_secs(0)
+                };
+                if !timeline.client_sync(&self.gl, frame, timeout) {
+                    break;
+                }
+                completed.push(readbacks.pop_front().unwrap().1);
+            }
+        }
+        // release the borrows before running the callbacks
+        let progress = !completed.is_empty();
+        for readback in completed {
+            readback.complete(&self.gl);
+        }
+        self.resolve_profiles();
+        progress
+    }
+
+    fn frame_profile(&self) -> Option<FrameProfile> {
+        self.last_profile.borrow().clone()
+    }
+
+    fn uniform_buffer_offset_alignment(&self) -> usize {
+        self.limits.uniform_buffer_alignment
+    }
+
+    fn current_frame(&self) -> u64 {
+        self.frame_num.get()
+    }
+
+    fn is_frame_finished(&self, frame: u64) -> bool {
+        // the timeline has no sync point for frames that are not submitted yet
+        frame < self.frame_num.get()
+            && self
+                .timeline
+                .borrow_mut()
+                .client_sync(&self.gl, frame, Duration::from_secs(0))
     }
 
     unsafe fn update_image(
@@ -60,10 +60,36 @@ new_key_type! {
     pub(crate) struct BufferAliasKey;
 }
 
+/// Persistent mapping of a host-visible buffer.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct BufferMapping(pub(crate) *mut u8);
+
+// the mapping is only written through `Instance::write_buffer`, whose caller must synchronize
+unsafe impl Send for BufferMapping {}
+unsafe impl Sync for BufferMapping {}
+
+/// Host data bound as a constant buffer. Uploaded during `submit_frame`.
+#[derive(Debug)]
+pub struct GlHostReference {
+    pub(crate) data: *const u8,
+    pub(crate) len: usize,
+}
+
+// the referenced data is borrowed immutably for the lifetime of the arena
+unsafe impl Sync for GlHostReference {}
+
+impl GlHostReference {
+    pub(crate) unsafe fn data(&self) -> &[u8] {
+        std::slice::from_raw_parts(self.data, self.len)
+    }
+}
+
 #[derive(Debug)]
 pub struct GlBuffer {
     pub(crate) raw: RawBuffer,
     pub(crate) should_destroy: bool,
     pub(crate) alias_info: Option<AliasInfo<BufferAliasKey>>,
     pub(crate) offset: usize,
+    /// Set for host-visible buffers.
+    pub(crate) mapping: Option<BufferMapping>,
 }
@@ -1,7 +1,14 @@
 use crate::api as gl;
-//use crate::api::types::*;
+use crate::api::types::*;
 use crate::{
-    api::Gl, image::GlImage, pipeline::GlGraphicsPipeline, swapchain::GlSwapchain,
+    api::Gl,
+    buffer::{GlBuffer, GlHostReference, MappedBuffer, UploadBuffer},
+    image::{copy_buffer_to_image_region, copy_image_region_to_buffer, region_texels, GlImage},
+    pipeline::{GlComputePipeline, GlGraphicsPipeline},
+    profile::PendingProfile,
+    query::GlQueryPool,
+    readback::PendingReadback,
+    swapchain::GlSwapchain,
     ImplementationParameters,
 };
 use autograph_api::command::{Command, CommandInner};
@@ -12,14 +19,33 @@ use crate::{
     backend::OpenGlBackend,
     pipeline::{GlArgumentBlock, StateBlock},
 };
-use autograph_api::{pipeline::Scissor, traits::Swapchain};
-use std::slice;
+use autograph_api::{
+    barrier::AccessFlags,
+    image::{Filter, ImageRegion},
+    pipeline::Scissor,
+    readback::Readback,
+    traits::Swapchain,
+};
+use glutin::GlContext;
+use std::{collections::HashMap, slice};
 
 pub struct SubmissionContext<'a, 'rcx> {
     state_cache: &'a mut StateCache,
     gl: &'a Gl,
-    _impl_params: &'a ImplementationParameters,
+    impl_params: &'a ImplementationParameters,
     current_pipeline: Option<&'rcx GlGraphicsPipeline>,
+    current_compute_pipeline: Option<&'rcx GlComputePipeline>,
+    readbacks: Vec<PendingReadback>,
+    profile: PendingProfile,
+    /// Free upload buffers, used for the host data referenced in the frame.
+    upload_pool: &'a mut Vec<MappedBuffer>,
+    upload_buffer_size: usize,
+    /// Upload buffers in use by the frame, the last one is the current one.
+    upload_buffers: Vec<UploadBuffer>,
+    /// Location of the host data already uploaded during this frame.
+    uploaded_host_refs: HashMap<*const GlHostReference, (GLuint, GLintptr)>,
+    /// Number of open debug groups.
+    debug_group_depth: usize,
 }
 
 #[derive(Default)]
@@ -35,17 +61,83 @@ struct BaseSlots {
 }
 
 impl<'a, 'rcx> SubmissionContext<'a, 'rcx> {
-    pub fn new(
+    pub(crate) fn new(
         gl: &'a Gl,
         state_cache: &'a mut StateCache,
         impl_params: &'a ImplementationParameters,
+        upload_pool: &'a mut Vec<MappedBuffer>,
+        upload_buffer_size: usize,
     ) -> SubmissionContext<'a, 'rcx> {
         SubmissionContext {
             state_cache,
             gl,
-            _impl_params: impl_params,
+            impl_params,
             current_pipeline: None,
+            current_compute_pipeline: None,
+            readbacks: Vec::new(),
+            profile: PendingProfile::new(),
+            upload_pool,
+            upload_buffer_size,
+            upload_buffers: Vec::new(),
+            uploaded_host_refs: HashMap::new(),
+            debug_group_depth: 0,
+        }
+    }
+
+    /// Returns the upload buffers used by the frame, which must not be reused until the frame
+    /// has finished executing, the readbacks issued by the submitted commands, and the timestamp
+    /// queries of the profile scopes.
+    pub(crate) fn finish(self) -> (Vec<MappedBuffer>, Vec<PendingReadback>, PendingProfile) {
+        // close the debug groups left open, so that they don't leak into the next frame
+        for _ in 0..self.debug_group_depth {
+            unsafe {
+                self.gl.PopDebugGroup();
+            }
         }
+        let upload_buffers = self
+            .upload_buffers
+            .into_iter()
+            .map(|b| b.into_inner())
+            .collect();
+        (upload_buffers, self.readbacks, self.profile)
+    }
+
+    /// Copies host data into the upload buffers of the frame, and returns its location.
+    ///
+    /// Data referenced more than once in the frame is only uploaded once.
+    fn upload_host_reference(&mut self, host_ref: &GlHostReference) -> (GLuint, GLintptr) {
+        if let Some(&location) = self.uploaded_host_refs.get(&(host_ref as *const _)) {
+            return location;
+        }
+        let data = unsafe { host_ref.data() };
+        let align = self.impl_params.uniform_buffer_alignment;
+        let location = match self
+            .upload_buffers
+            .last()
+            .and_then(|b| b.write(data, align))
+        {
+            Some(location) => location,
+            None => {
+                // current upload buffer full: start a new one
+                assert!(
+                    data.len() <= self.upload_buffer_size,
+                    "host data does not fit in an upload buffer ({} bytes)",
+                    data.len()
+                );
+                let buffer = self
+                    .upload_pool
+                    .pop()
+                    .unwrap_or_else(|| MappedBuffer::new(self.gl, self.upload_buffer_size));
+                let buffer = UploadBuffer::new(buffer);
+                let location = buffer.write(data, align).unwrap();
+                self.upload_buffers.push(buffer);
+                location
+            }
+        };
+        let location = (location.0, location.1 as GLintptr);
+        self.uploaded_host_refs
+            .insert(host_ref as *const _, location);
+        location
     }
 
     /// Disable scissor test on the first viewport
@@ -125,6 +217,359 @@ impl<'a, 'rcx> SubmissionContext<'a, 'rcx> {
         }
     }
 
+    fn cmd_pipeline_barrier(&mut self, src_access_mask: AccessFlags, dst_access_mask: AccessFlags) {
+        // On GL, the source writes need the following:
+        // - transfer writes (copies, fills, clears, blits) are performed by GL commands, whose
+        //   results are visible to all subsequent commands: nothing to do.
+        // - host writes go through persistent coherent mappings (see `buffer::upload`), which are
+        //   visible to commands issued after the write: nothing to do.
+        // - attachment writes are visible to subsequent draws, unless the image is sampled while
+        //   still attached to the current framebuffer: issue a texture barrier for sampled reads.
+        // - shader writes (image stores and SSBO writes) are incoherent: issue a memory barrier
+        //   for the destination accesses.
+        if src_access_mask.intersects(
+            AccessFlags::COLOR_ATTACHMENT_WRITE | AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+        ) && dst_access_mask.contains(AccessFlags::SAMPLED_READ)
+        {
+            unsafe {
+                self.gl.TextureBarrier();
+            }
+        }
+
+        if !src_access_mask.intersects(AccessFlags::ALL_SHADER_WRITES) {
+            return;
+        }
+
+        let mut barriers: GLbitfield = 0;
+        if dst_access_mask.contains(AccessFlags::INDIRECT_COMMAND_READ) {
+            barriers |= gl::COMMAND_BARRIER_BIT;
+        }
+        if dst_access_mask.contains(AccessFlags::INDEX_READ) {
+            barriers |= gl::ELEMENT_ARRAY_BARRIER_BIT;
+        }
+        if dst_access_mask.contains(AccessFlags::VERTEX_ATTRIBUTE_READ) {
+            barriers |= gl::VERTEX_ATTRIB_ARRAY_BARRIER_BIT;
+        }
+        if dst_access_mask.contains(AccessFlags::UNIFORM_READ) {
+            barriers |= gl::UNIFORM_BARRIER_BIT;
+        }
+        if dst_access_mask.contains(AccessFlags::SAMPLED_READ) {
+            barriers |= gl::TEXTURE_FETCH_BARRIER_BIT;
+        }
+        if dst_access_mask
+            .intersects(AccessFlags::STORAGE_IMAGE_READ | AccessFlags::STORAGE_IMAGE_WRITE)
+        {
+            barriers |= gl::SHADER_IMAGE_ACCESS_BARRIER_BIT;
+        }
+        if dst_access_mask
+            .intersects(AccessFlags::STORAGE_BUFFER_READ | AccessFlags::STORAGE_BUFFER_WRITE)
+        {
+            barriers |= gl::SHADER_STORAGE_BARRIER_BIT;
+        }
+        if dst_access_mask.intersects(
+            AccessFlags::COLOR_ATTACHMENT_READ
+                | AccessFlags::COLOR_ATTACHMENT_WRITE
+                | AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ
+                | AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+        ) {
+            barriers |= gl::FRAMEBUFFER_BARRIER_BIT;
+        }
+        if dst_access_mask.intersects(AccessFlags::TRANSFER_READ | AccessFlags::TRANSFER_WRITE) {
+            barriers |= gl::BUFFER_UPDATE_BARRIER_BIT
+                | gl::TEXTURE_UPDATE_BARRIER_BIT
+                | gl::PIXEL_BUFFER_BARRIER_BIT
+                | gl::FRAMEBUFFER_BARRIER_BIT;
+        }
+        if dst_access_mask.intersects(AccessFlags::HOST_READ | AccessFlags::HOST_WRITE) {
+            barriers |= gl::CLIENT_MAPPED_BUFFER_BARRIER_BIT;
+        }
+
+        if barriers != 0 {
+            unsafe {
+                self.gl.MemoryBarrier(barriers);
+            }
+        }
+    }
+
+    fn cmd_copy_buffer(
+        &mut self,
+        src: &GlBuffer,
+        src_offset: usize,
+        dst: &GlBuffer,
+        dst_offset: usize,
+        size: usize,
+    ) {
+        unsafe {
+            self.gl.CopyNamedBufferSubData(
+                src.raw.obj,
+                dst.raw.obj,
+                (src.offset + src_offset) as GLintptr,
+                (dst.offset + dst_offset) as GLintptr,
+                size as GLsizeiptr,
+            );
+        }
+    }
+
+    fn cmd_copy_buffer_to_image(
+        &mut self,
+        src: &GlBuffer,
+        src_offset: usize,
+        dst: &GlImage,
+        dst_region: &ImageRegion,
+    ) {
+        let (offset, extent) = region_texels(dst.raw.target, dst_region);
+        unsafe {
+            copy_buffer_to_image_region(
+                self.gl,
+                src.raw.obj,
+                src.offset + src_offset,
+                &dst.raw,
+                dst_region.mip_level as i32,
+                offset,
+                extent,
+            );
+        }
+    }
+
+    fn cmd_copy_image_to_buffer(
+        &mut self,
+        src: &GlImage,
+        src_region: &ImageRegion,
+        dst: &GlBuffer,
+        dst_offset: usize,
+    ) {
+        let (offset, extent) = region_texels(src.raw.target, src_region);
+        unsafe {
+            copy_image_region_to_buffer(
+                self.gl,
+                &src.raw,
+                src_region.mip_level as i32,
+                offset,
+                extent,
+                dst.raw.obj,
+                dst.offset + dst_offset,
+                dst.offset + dst.raw.size,
+            );
+        }
+    }
+
+    fn cmd_copy_image(
+        &mut self,
+        src: &GlImage,
+        src_region: &ImageRegion,
+        dst: &GlImage,
+        dst_region: &ImageRegion,
+    ) {
+        let (src_offset, (w, h, d)) = region_texels(src.raw.target, src_region);
+        let (dst_offset, _) = region_texels(dst.raw.target, dst_region);
+        unsafe {
+            self.gl.CopyImageSubData(
+                src.raw.obj,
+                src.raw.target,
+                src_region.mip_level as i32,
+                src_offset.0 as i32,
+                src_offset.1 as i32,
+                src_offset.2 as i32,
+                dst.raw.obj,
+                dst.raw.target,
+                dst_region.mip_level as i32,
+                dst_offset.0 as i32,
+                dst_offset.1 as i32,
+                dst_offset.2 as i32,
+                w as i32,
+                h as i32,
+                d as i32,
+            );
+        }
+    }
+
+    fn cmd_fill_buffer(&mut self, dst: &GlBuffer, offset: usize, size: usize, value: u32) {
+        unsafe {
+            self.gl.ClearNamedBufferSubData(
+                dst.raw.obj,
+                gl::R32UI,
+                (dst.offset + offset) as GLintptr,
+                size as GLsizeiptr,
+                gl::RED_INTEGER,
+                gl::UNSIGNED_INT,
+                &value as *const u32 as *const GLvoid,
+            );
+        }
+    }
+
+    fn cmd_readback_image(&mut self, src: &GlImage, src_region: &ImageRegion, readback: &Readback) {
+        let (offset, extent) = region_texels(src.raw.target, src_region);
+        let (w, h, d) = extent;
+        let size = (w * h * d) as usize * src.raw.format.get_format_info().byte_size();
+        let pending = PendingReadback::new(self.gl, size, readback.clone());
+        if size != 0 {
+            unsafe {
+                copy_image_region_to_buffer(
+                    self.gl,
+                    &src.raw,
+                    src_region.mip_level as i32,
+                    offset,
+                    extent,
+                    pending.buffer(),
+                    0,
+                    size,
+                );
+            }
+        }
+        self.readbacks.push(pending);
+    }
+
+    fn cmd_readback_buffer(
+        &mut self,
+        src: &GlBuffer,
+        offset: usize,
+        size: usize,
+        readback: &Readback,
+    ) {
+        let pending = PendingReadback::new(self.gl, size, readback.clone());
+        if size != 0 {
+            unsafe {
+                self.gl.CopyNamedBufferSubData(
+                    src.raw.obj,
+                    pending.buffer(),
+                    (src.offset + offset) as GLintptr,
+                    0,
+                    pending.size() as GLsizeiptr,
+                );
+            }
+        }
+        self.readbacks.push(pending);
+    }
+
+    fn cmd_push_debug_group(&mut self, name: &str) {
+        if self.impl_params.khr_debug {
+            unsafe {
+                self.gl.PushDebugGroup(
+                    gl::DEBUG_SOURCE_APPLICATION,
+                    0,
+                    name.len() as GLsizei,
+                    name.as_ptr() as *const GLchar,
+                );
+            }
+            self.debug_group_depth += 1;
+        }
+    }
+
+    fn cmd_pop_debug_group(&mut self) {
+        // groups are balanced in the sorted stream only if the frontend was careful:
+        // ignore unmatched pops instead of underflowing the debug group stack
+        if self.debug_group_depth > 0 {
+            unsafe {
+                self.gl.PopDebugGroup();
+            }
+            self.debug_group_depth -= 1;
+        }
+    }
+
+    fn cmd_insert_debug_marker(&mut self, name: &str) {
+        if self.impl_params.khr_debug {
+            unsafe {
+                self.gl.DebugMessageInsert(
+                    gl::DEBUG_SOURCE_APPLICATION,
+                    gl::DEBUG_TYPE_MARKER,
+                    0,
+                    gl::DEBUG_SEVERITY_NOTIFICATION,
+                    name.len() as GLsizei,
+                    name.as_ptr() as *const GLchar,
+                );
+            }
+        }
+    }
+
+    fn cmd_readback_queries(
+        &mut self,
+        pool: &GlQueryPool,
+        first: u32,
+        count: u32,
+        readback: &Readback,
+    ) {
+        let values = (first..first + count).flat_map(|index| pool.query(index));
+        let size = count as usize * pool.targets.len() * 8;
+        let pending = PendingReadback::new(self.gl, size, readback.clone());
+        for (i, (_, query)) in values.enumerate() {
+            // the result is written once the query is available, without stalling
+            unsafe {
+                self.gl.GetQueryBufferObjectui64v(
+                    query,
+                    pending.buffer(),
+                    gl::QUERY_RESULT,
+                    (i * 8) as GLintptr,
+                );
+            }
+        }
+        self.readbacks.push(pending);
+    }
+
+    /// Creates a temporary framebuffer with the specified image level as the first color attachment.
+    fn create_temporary_framebuffer(&mut self, image: &GlImage, mip_level: u32) -> GLuint {
+        let mut tmpfb = 0;
+        unsafe {
+            self.gl.CreateFramebuffers(1, &mut tmpfb);
+            if image.raw.target == gl::RENDERBUFFER {
+                assert_eq!(mip_level, 0);
+                self.gl.NamedFramebufferRenderbuffer(
+                    tmpfb,
+                    gl::COLOR_ATTACHMENT0,
+                    gl::RENDERBUFFER,
+                    image.raw.obj,
+                );
+            } else {
+                self.gl.NamedFramebufferTexture(
+                    tmpfb,
+                    gl::COLOR_ATTACHMENT0,
+                    image.raw.obj,
+                    mip_level as i32,
+                );
+            }
+        }
+        tmpfb
+    }
+
+    fn cmd_blit_image(
+        &mut self,
+        src: &GlImage,
+        src_mip_level: u32,
+        src_offsets: &[(u32, u32); 2],
+        dst: &GlImage,
+        dst_mip_level: u32,
+        dst_offsets: &[(u32, u32); 2],
+        filter: Filter,
+    ) {
+        let src_fb = self.create_temporary_framebuffer(src, src_mip_level);
+        let dst_fb = self.create_temporary_framebuffer(dst, dst_mip_level);
+        self.disable_scissor_test();
+        unsafe {
+            self.gl
+                .NamedFramebufferReadBuffer(src_fb, gl::COLOR_ATTACHMENT0);
+            self.gl
+                .NamedFramebufferDrawBuffers(dst_fb, 1, (&[gl::COLOR_ATTACHMENT0]).as_ptr());
+            self.gl.BlitNamedFramebuffer(
+                src_fb,
+                dst_fb,
+                src_offsets[0].0 as i32,
+                src_offsets[0].1 as i32,
+                src_offsets[1].0 as i32,
+                src_offsets[1].1 as i32,
+                dst_offsets[0].0 as i32,
+                dst_offsets[0].1 as i32,
+                dst_offsets[1].0 as i32,
+                dst_offsets[1].1 as i32,
+                gl::COLOR_BUFFER_BIT,
+                match filter {
+                    Filter::Nearest => gl::NEAREST,
+                    Filter::Linear => gl::LINEAR,
+                },
+            );
+            self.gl.DeleteFramebuffers(1, &src_fb);
+            self.gl.DeleteFramebuffers(1, &dst_fb);
+        }
+    }
+
     //pub fn cmd_set_attachments(&mut self, color_attachments: &[R::])
 
     //unsafe fn cmd_set_pipeline_arguments_rec(&mut self, args: &GlPipelineArguments) {}
@@ -135,8 +580,13 @@ impl<'a, 'rcx> SubmissionContext<'a, 'rcx> {
         base_slots: &mut BaseSlots,
         is_root: bool,
     ) {
-        let pipeline = self.current_pipeline.unwrap();
-        let _descriptor_map = pipeline.descriptor_map();
+        let _descriptor_map = if let Some(pipeline) = self.current_compute_pipeline {
+            pipeline.descriptor_map()
+        } else {
+            self.current_pipeline
+                .expect("cmd_set_pipeline_arguments called with no pipeline bound")
+                .descriptor_map()
+        };
 
         // could also fetch the signature from the pipeline
         let sig = unsafe { &*args.signature };
@@ -154,18 +604,41 @@ impl<'a, 'rcx> SubmissionContext<'a, 'rcx> {
                     buffers,
                     offsets,
                     sizes,
+                    host_refs,
                 } => {
                     let n = sig.num_uniform_buffers;
                     let buffers = unsafe { slice::from_raw_parts(buffers, n) };
                     let offsets = unsafe { slice::from_raw_parts(offsets, n) };
                     let sizes = unsafe { slice::from_raw_parts(sizes, n) };
-                    self.state_cache.set_uniform_buffers(
-                        self.gl,
-                        base_slots.ubo,
-                        buffers,
-                        offsets,
-                        sizes,
-                    );
+                    let host_refs = unsafe { slice::from_raw_parts(host_refs, n) };
+                    if host_refs.iter().all(|r| r.is_null()) {
+                        self.state_cache.set_uniform_buffers(
+                            self.gl,
+                            base_slots.ubo,
+                            buffers,
+                            offsets,
+                            sizes,
+                        );
+                    } else {
+                        // bind the uploaded host data instead
+                        let mut buffers: smallvec::SmallVec<[GLuint; 8]> = buffers.into();
+                        let mut offsets: smallvec::SmallVec<[GLintptr; 8]> = offsets.into();
+                        for (i, &host_ref) in host_refs.iter().enumerate() {
+                            if !host_ref.is_null() {
+                                let (buffer, offset) =
+                                    self.upload_host_reference(unsafe { &*host_ref });
+                                buffers[i] = buffer;
+                                offsets[i] = offset;
+                            }
+                        }
+                        self.state_cache.set_uniform_buffers(
+                            self.gl,
+                            base_slots.ubo,
+                            &buffers,
+                            &offsets,
+                            sizes,
+                        );
+                    }
                     base_slots.ubo += n;
                 }
                 &StateBlock::ShaderStorageBuffers {
@@ -258,59 +731,53 @@ impl<'a, 'rcx> SubmissionContext<'a, 'rcx> {
     }
 
     fn cmd_present(&mut self, image: &GlImage, swapchain: &GlSwapchain) {
-        // only handle default swapchain for now
-        //assert_eq!(swapchain, 0, "invalid swapchain handle");
-        // make a framebuffer and bind the image to it
-        unsafe {
-            let mut tmpfb = 0;
-            self.gl.CreateFramebuffers(1, &mut tmpfb);
-            // bind image to it
-            if image.raw.target == gl::RENDERBUFFER {
-                self.gl.NamedFramebufferRenderbuffer(
-                    tmpfb,
-                    gl::COLOR_ATTACHMENT0,
-                    gl::RENDERBUFFER,
-                    image.raw.obj,
-                );
-            } else {
-                // TODO other levels / layers?
-                self.gl
-                    .NamedFramebufferTexture(tmpfb, gl::COLOR_ATTACHMENT0, image.raw.obj, 0);
+        let main_window = match swapchain.main_window {
+            None => {
+                self.disable_scissor_test();
+                blit_to_default_framebuffer(self.gl, image, swapchain.size());
+                swapchain
+                    .window
+                    .swap_buffers()
+                    .expect("failed to swap buffers");
+                return;
             }
-            // blit to default framebuffer
-            let (w, h): (u32, u32) = swapchain.size();
-
-            self.disable_scissor_test();
-
-            self.gl.BlitNamedFramebuffer(
-                tmpfb,
-                0,
-                0,        // srcX0
-                0,        // srcY0
-                w as i32, // srcX1,
-                h as i32, // srcY1,
-                0,        // dstX0
-                h as i32, // dstY0
-                w as i32, // dstX1,
-                0,        // dstY1,
-                gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT,
-                gl::NEAREST,
-            );
+            Some(ref main_window) => main_window,
+        };
 
-            // destroy temp framebuffer
-            self.gl.DeleteFramebuffers(1, &tmpfb);
+        // Additional window: the image is shared between the contexts, but framebuffers are not,
+        // so the blit happens in the context of the window, once the commands that render
+        // to the image are complete.
+        unsafe {
+            let fence = self.gl.FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0);
+            self.gl.Flush();
+            swapchain
+                .window
+                .make_current()
+                .expect("failed to make the window context current");
+            self.gl.WaitSync(fence, 0, gl::TIMEOUT_IGNORED);
+            self.gl.DeleteSync(fence);
+            // the scissor test is never enabled in the contexts of additional windows
+            blit_to_default_framebuffer(self.gl, image, swapchain.size());
+            swapchain
+                .window
+                .swap_buffers()
+                .expect("failed to swap buffers");
+            main_window
+                .make_current()
+                .expect("failed to make the main context current");
         }
-
-        // swap buffers
-        swapchain
-            .window
-            .swap_buffers()
-            .expect("failed to swap buffers")
     }
 
     fn cmd_set_graphics_pipeline(&mut self, pipeline: &'rcx GlGraphicsPipeline) {
         // switching pipelines
         self.current_pipeline = Some(pipeline);
+        self.current_compute_pipeline = None;
+        pipeline.bind(self.gl, self.state_cache);
+    }
+
+    fn cmd_set_compute_pipeline(&mut self, pipeline: &'rcx GlComputePipeline) {
+        self.current_compute_pipeline = Some(pipeline);
+        self.current_pipeline = None;
         pipeline.bind(self.gl, self.state_cache);
     }
 
@@ -396,10 +863,155 @@ impl<'a, 'rcx> SubmissionContext<'a, 'rcx> {
         );
     }
 
+    fn cmd_draw_indirect(
+        &mut self,
+        indirect_buffer: &GlBuffer,
+        offset: usize,
+        draw_count: u32,
+        count_buffer: Option<&GlBuffer>,
+    ) {
+        let pipeline = self
+            .current_pipeline
+            .expect("cmd_draw_indirect called with no pipeline bound");
+        self.state_cache
+            .set_draw_indirect_buffer(self.gl, indirect_buffer.raw.obj);
+        let count_offset = count_buffer.map(|count_buffer| {
+            self.state_cache
+                .set_parameter_buffer(self.gl, count_buffer.raw.obj);
+            count_buffer.offset
+        });
+        self.state_cache.draw_indirect(
+            self.gl,
+            pipeline.input_assembly_state.topology,
+            indirect_buffer.offset + offset,
+            draw_count,
+            count_offset,
+        );
+    }
+
+    fn cmd_draw_indexed_indirect(
+        &mut self,
+        indirect_buffer: &GlBuffer,
+        offset: usize,
+        draw_count: u32,
+        count_buffer: Option<&GlBuffer>,
+    ) {
+        let pipeline = self
+            .current_pipeline
+            .expect("cmd_draw_indexed_indirect called with no pipeline bound");
+        self.state_cache
+            .set_draw_indirect_buffer(self.gl, indirect_buffer.raw.obj);
+        let count_offset = count_buffer.map(|count_buffer| {
+            self.state_cache
+                .set_parameter_buffer(self.gl, count_buffer.raw.obj);
+            count_buffer.offset
+        });
+        self.state_cache.draw_indexed_indirect(
+            self.gl,
+            pipeline.input_assembly_state.topology,
+            indirect_buffer.offset + offset,
+            draw_count,
+            count_offset,
+        );
+    }
+
+    fn cmd_dispatch(&mut self, x: u32, y: u32, z: u32) {
+        self.current_compute_pipeline
+            .expect("cmd_dispatch called with no compute pipeline bound");
+        self.state_cache.dispatch(self.gl, x, y, z);
+    }
+
+    fn cmd_dispatch_indirect(&mut self, indirect_buffer: &GlBuffer, offset: usize) {
+        self.current_compute_pipeline
+            .expect("cmd_dispatch_indirect called with no compute pipeline bound");
+        self.state_cache
+            .set_dispatch_indirect_buffer(self.gl, indirect_buffer.raw.obj);
+        self.state_cache
+            .dispatch_indirect(self.gl, indirect_buffer.offset + offset);
+    }
+
     pub unsafe fn submit_command(&mut self, command: &Command<'rcx, OpenGlBackend>) {
         match command.cmd {
-            CommandInner::PipelineBarrier {} => {
-                // no-op on GL
+            CommandInner::PipelineBarrier {
+                src_access_mask,
+                dst_access_mask,
+            } => {
+                self.cmd_pipeline_barrier(src_access_mask, dst_access_mask);
+            }
+            CommandInner::CopyBuffer {
+                src,
+                src_offset,
+                dst,
+                dst_offset,
+                size,
+            } => {
+                self.cmd_copy_buffer(src, src_offset, dst, dst_offset, size);
+            }
+            CommandInner::CopyBufferToImage {
+                src,
+                src_offset,
+                dst,
+                ref dst_region,
+            } => {
+                self.cmd_copy_buffer_to_image(src, src_offset, dst, dst_region);
+            }
+            CommandInner::CopyImageToBuffer {
+                src,
+                ref src_region,
+                dst,
+                dst_offset,
+            } => {
+                self.cmd_copy_image_to_buffer(src, src_region, dst, dst_offset);
+            }
+            CommandInner::CopyImage {
+                src,
+                ref src_region,
+                dst,
+                ref dst_region,
+            } => {
+                self.cmd_copy_image(src, src_region, dst, dst_region);
+            }
+            CommandInner::FillBuffer {
+                dst,
+                offset,
+                size,
+                value,
+            } => {
+                self.cmd_fill_buffer(dst, offset, size, value);
+            }
+            CommandInner::ReadbackImage {
+                src,
+                ref src_region,
+                ref readback,
+            } => {
+                self.cmd_readback_image(src, src_region, readback);
+            }
+            CommandInner::ReadbackBuffer {
+                src,
+                offset,
+                size,
+                ref readback,
+            } => {
+                self.cmd_readback_buffer(src, offset, size, readback);
+            }
+            CommandInner::BlitImage {
+                src,
+                src_mip_level,
+                ref src_offsets,
+                dst,
+                dst_mip_level,
+                ref dst_offsets,
+                filter,
+            } => {
+                self.cmd_blit_image(
+                    src,
+                    src_mip_level,
+                    src_offsets,
+                    dst,
+                    dst_mip_level,
+                    dst_offsets,
+                    filter,
+                );
             }
             CommandInner::ClearImageFloat { image, color } => {
                 self.cmd_clear_image_float(image, &color);
@@ -411,7 +1023,7 @@ impl<'a, 'rcx> SubmissionContext<'a, 'rcx> {
             } => {
                 self.cmd_clear_depth_stencil_image(image, depth, stencil);
             }
-            CommandInner::SetPipelineArguments { arguments } => {
+            CommandInner::SetPipelineArguments { arguments, .. } => {
                 self.cmd_set_pipeline_arguments(arguments, &mut BaseSlots::default(), true);
             }
             /*CommandInner::SetDescriptorSets {
@@ -432,6 +1044,9 @@ impl<'a, 'rcx> SubmissionContext<'a, 'rcx> {
             CommandInner::DrawHeader { pipeline } => {
                 self.cmd_set_graphics_pipeline(pipeline);
             }
+            CommandInner::DispatchHeader { pipeline } => {
+                self.cmd_set_compute_pipeline(pipeline);
+            }
             /*CommandInner::SetScissors { .. } => {}
             //CommandInner::SetAllScissors { scissor } => {}
             CommandInner::SetViewports { ref viewports } => {
@@ -460,9 +1075,91 @@ impl<'a, 'rcx> SubmissionContext<'a, 'rcx> {
                 vertex_offset,
                 first_instance,
             ),
+            CommandInner::DrawIndirect {
+                indirect_buffer,
+                offset,
+                draw_count,
+                count_buffer,
+            } => self.cmd_draw_indirect(indirect_buffer, offset, draw_count, count_buffer),
+            CommandInner::DrawIndexedIndirect {
+                indirect_buffer,
+                offset,
+                draw_count,
+                count_buffer,
+            } => self.cmd_draw_indexed_indirect(indirect_buffer, offset, draw_count, count_buffer),
+            CommandInner::Dispatch { x, y, z } => self.cmd_dispatch(x, y, z),
+            CommandInner::DispatchIndirect {
+                indirect_buffer,
+                offset,
+            } => self.cmd_dispatch_indirect(indirect_buffer, offset),
             CommandInner::Present { image, swapchain } => {
                 self.cmd_present(image, swapchain);
             }
+            CommandInner::BeginProfileScope { name } => self.profile.begin_scope(self.gl, name),
+            CommandInner::EndProfileScope => self.profile.end_scope(self.gl),
+            CommandInner::PushDebugGroup { name } => self.cmd_push_debug_group(name),
+            CommandInner::PopDebugGroup => self.cmd_pop_debug_group(),
+            CommandInner::InsertDebugMarker { name } => self.cmd_insert_debug_marker(name),
+            CommandInner::BeginQuery { pool, index } => {
+                for (target, query) in pool.query(index) {
+                    self.gl.BeginQuery(target, query);
+                }
+            }
+            CommandInner::EndQuery { pool, index } => {
+                for (target, _) in pool.query(index) {
+                    self.gl.EndQuery(target);
+                }
+            }
+            CommandInner::ReadbackQueries {
+                pool,
+                first,
+                count,
+                ref readback,
+            } => self.cmd_readback_queries(pool, first, count, readback),
+            CommandInner::BeginConditionalRendering { pool, index } => {
+                // occlusion queries have a single value
+                let (_, query) = pool.query(index).next().unwrap();
+                self.gl.BeginConditionalRender(query, gl::QUERY_WAIT);
+            }
+            CommandInner::EndConditionalRendering => self.gl.EndConditionalRender(),
+        }
+    }
+}
+
+/// Blits an image to the default framebuffer of the current context, flipping it vertically.
+fn blit_to_default_framebuffer(gl: &Gl, image: &GlImage, (w, h): (u32, u32)) {
+    unsafe {
+        // make a framebuffer and bind the image to it
+        let mut tmpfb = 0;
+        gl.CreateFramebuffers(1, &mut tmpfb);
+        if image.raw.target == gl::RENDERBUFFER {
+            gl.NamedFramebufferRenderbuffer(
+                tmpfb,
+                gl::COLOR_ATTACHMENT0,
+                gl::RENDERBUFFER,
+                image.raw.obj,
+            );
+        } else {
+            // TODO other levels / layers?
+            gl.NamedFramebufferTexture(tmpfb, gl::COLOR_ATTACHMENT0, image.raw.obj, 0);
         }
+
+        gl.BlitNamedFramebuffer(
+            tmpfb,
+            0,
+            0,        // srcX0
+            0,        // srcY0
+            w as i32, // srcX1,
+            h as i32, // srcY1,
+            0,        // dstX0
+            h as i32, // dstY0
+            w as i32, // dstX1,
+            0,        // dstY1,
+            gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT,
+            gl::NEAREST,
+        );
+
+        // destroy temp framebuffer
+        gl.DeleteFramebuffers(1, &tmpfb);
     }
 }
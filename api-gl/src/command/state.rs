@@ -45,6 +45,9 @@ pub struct StateCache {
     index_buffer: Option<GLuint>,
     index_buffer_offset: Option<usize>,
     index_buffer_type: Option<GLenum>,
+    draw_indirect_buffer: Option<GLuint>,
+    dispatch_indirect_buffer: Option<GLuint>,
+    parameter_buffer: Option<GLuint>,
     /*textures: Option<Vec<GLuint>>,
     samplers: Option<Vec<GLuint>>,
     images: Option<Vec<GLuint>>,
@@ -205,6 +208,9 @@ impl StateCache {
             index_buffer: None,
             index_buffer_offset: None,
             index_buffer_type: None,
+            draw_indirect_buffer: None,
+            dispatch_indirect_buffer: None,
+            parameter_buffer: None,
             /*textures: None,
             samplers: None,
             images: None,
@@ -242,6 +248,9 @@ impl StateCache {
             index_buffer: None,
             index_buffer_offset: None,
             index_buffer_type: None,
+            draw_indirect_buffer: None,
+            dispatch_indirect_buffer: None,
+            parameter_buffer: None,
             /*textures: None,
             samplers: None,
             images: None,
@@ -713,6 +722,25 @@ impl StateCache {
         });
     }
 
+    pub fn set_draw_indirect_buffer(&mut self, gl: &Gl, buffer: GLuint) {
+        self.draw_indirect_buffer.update_cached(buffer, || unsafe {
+            gl.BindBuffer(gl::DRAW_INDIRECT_BUFFER, buffer);
+        });
+    }
+
+    pub fn set_dispatch_indirect_buffer(&mut self, gl: &Gl, buffer: GLuint) {
+        self.dispatch_indirect_buffer
+            .update_cached(buffer, || unsafe {
+                gl.BindBuffer(gl::DISPATCH_INDIRECT_BUFFER, buffer);
+            });
+    }
+
+    pub fn set_parameter_buffer(&mut self, gl: &Gl, buffer: GLuint) {
+        self.parameter_buffer.update_cached(buffer, || unsafe {
+            gl.BindBuffer(gl::PARAMETER_BUFFER, buffer);
+        });
+    }
+
     pub fn draw(
         &mut self,
         gl: &Gl,
@@ -767,5 +795,86 @@ impl StateCache {
         }
     }
 
+    /// Draws with parameters read from the bound draw indirect buffer at the specified offset.
+    ///
+    /// If `count_offset` is not `None`, the number of draws is read from the bound
+    /// parameter buffer, and `draw_count` is the maximum number of draws.
+    pub fn draw_indirect(
+        &mut self,
+        gl: &Gl,
+        topo: PrimitiveTopology,
+        offset: usize,
+        draw_count: u32,
+        count_offset: Option<usize>,
+    ) {
+        let mode = topology_to_gl(topo);
+        unsafe {
+            if let Some(count_offset) = count_offset {
+                gl.MultiDrawArraysIndirectCount(
+                    mode,
+                    offset as *const GLvoid,
+                    count_offset as GLintptr,
+                    draw_count as i32,
+                    0,
+                );
+            } else {
+                gl.MultiDrawArraysIndirect(mode, offset as *const GLvoid, draw_count as i32, 0);
+            }
+        }
+    }
+
+    pub fn draw_indexed_indirect(
+        &mut self,
+        gl: &Gl,
+        topo: PrimitiveTopology,
+        offset: usize,
+        draw_count: u32,
+        count_offset: Option<usize>,
+    ) {
+        let mode = topology_to_gl(topo);
+        let idx_offset = self
+            .index_buffer_offset
+            .expect("no index buffer was bound before indexed draw operation");
+        // the first index is read from the indirect buffer: there is no way to add an offset
+        // to the index buffer binding
+        assert_eq!(
+            idx_offset, 0,
+            "indirect indexed draws do not support index buffers with a non-zero offset"
+        );
+        let ty = self.index_buffer_type.unwrap();
+        unsafe {
+            if let Some(count_offset) = count_offset {
+                gl.MultiDrawElementsIndirectCount(
+                    mode,
+                    ty,
+                    offset as *const GLvoid,
+                    count_offset as GLintptr,
+                    draw_count as i32,
+                    0,
+                );
+            } else {
+                gl.MultiDrawElementsIndirect(
+                    mode,
+                    ty,
+                    offset as *const GLvoid,
+                    draw_count as i32,
+                    0,
+                );
+            }
+        }
+    }
+
+    pub fn dispatch_indirect(&mut self, gl: &Gl, offset: usize) {
+        unsafe {
+            gl.DispatchComputeIndirect(offset as GLintptr);
+        }
+    }
+
+    pub fn dispatch(&mut self, gl: &Gl, x: u32, y: u32, z: u32) {
+        unsafe {
+            gl.DispatchCompute(x, y, z);
+        }
+    }
+
     //pub fn set_blend_mode(&mut self)
 }
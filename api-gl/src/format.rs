@@ -161,8 +161,16 @@ static GLF_D32_SFLOAT: GlFormatInfo = GlFormatInfo {
 
 impl GlFormatInfo {
     /// Returns the equivalent OpenGL format information for the specified format.
+    ///
+    /// Panics if the format is not supported.
     pub fn from_format(fmt: Format) -> &'static GlFormatInfo {
-        match fmt {
+        Self::try_from_format(fmt).unwrap_or_else(|| panic!("Unsupported format: {:?}", fmt))
+    }
+
+    /// Returns the equivalent OpenGL format information for the specified format, or `None` if
+    /// the format is not supported.
+    pub fn try_from_format(fmt: Format) -> Option<&'static GlFormatInfo> {
+        Some(match fmt {
             Format::R8_UNORM => &GLF_R8_UNORM,
             Format::R8_SNORM => &GLF_R8_SNORM,
             Format::R8_UINT => &GLF_R8_UINT,
@@ -194,7 +202,7 @@ impl GlFormatInfo {
             Format::R8G8B8_SRGB => &GLF_R8G8B8_SRGB,
             Format::R8G8B8A8_SRGB => &GLF_R8G8B8A8_SRGB,
             Format::D32_SFLOAT => &GLF_D32_SFLOAT,
-            _ => panic!("Unsupported format: {:?}", fmt),
-        }
+            _ => return None,
+        })
     }
 }
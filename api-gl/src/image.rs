@@ -5,7 +5,8 @@ use crate::{
     AliasInfo,
 };
 use autograph_api::{
-    get_texture_mip_map_count, Dimensions, Format, ImageUsageFlags, MipmapsOption,
+    get_texture_mip_map_count, image::ImageRegion, Dimensions, Format, ImageUsageFlags,
+    MipmapsOption,
 };
 use slotmap::new_key_type;
 use std::cmp::max;
@@ -68,7 +69,11 @@ impl ExtentsAndType {
                 width,
                 array_layers,
             } => ExtentsAndType {
-                target: gl::TEXTURE_1D,
+                target: if array_layers > 1 {
+                    gl::TEXTURE_1D_ARRAY
+                } else {
+                    gl::TEXTURE_1D
+                },
                 width,
                 height: 1,
                 depth: 1,
@@ -79,7 +84,11 @@ impl ExtentsAndType {
                 height,
                 array_layers,
             } => ExtentsAndType {
-                target: gl::TEXTURE_2D,
+                target: if array_layers > 1 {
+                    gl::TEXTURE_2D_ARRAY
+                } else {
+                    gl::TEXTURE_2D
+                },
                 width,
                 height,
                 depth: 1,
@@ -108,13 +117,13 @@ impl ExtentsAndType {
 pub struct RawImage {
     pub(crate) obj: GLuint,
     pub(crate) target: GLenum,
-    //pub format: Format,
+    pub(crate) format: Format,
 }
 
 impl RawImage {
     pub fn new(gl: &Gl, d: &ImageDescription) -> RawImage {
-        if d.usage != ImageUsageFlags::COLOR_ATTACHMENT {
-            // will be used as storage or sampled image
+        if d.usage != ImageUsageFlags::COLOR_ATTACHMENT || d.dimensions.array_layers() > 1 {
+            // will be used as storage or sampled image, or has array layers
             RawImage::new_texture(gl, d.format, &d.dimensions, d.mipcount, d.samples)
         } else {
             // only used as color attachments: can use a renderbuffer instead
@@ -132,10 +141,6 @@ impl RawImage {
         let et = ExtentsAndType::from_dimensions(&dimensions);
         let glfmt = GlFormatInfo::from_format(format);
 
-        if et.array_layers > 1 {
-            unimplemented!("array textures")
-        }
-
         let mut obj = 0;
         unsafe {
             gl.CreateTextures(et.target, 1, &mut obj);
@@ -174,6 +179,26 @@ impl RawImage {
                         et.depth as i32,
                     );
                 }
+                gl::TEXTURE_1D_ARRAY => {
+                    gl.TextureStorage2D(
+                        obj,
+                        mipcount as i32,
+                        glfmt.internal_fmt,
+                        et.width as i32,
+                        et.array_layers as i32,
+                    );
+                }
+                gl::TEXTURE_2D_ARRAY => {
+                    // multisampled array images are rejected by `create_image`
+                    gl.TextureStorage3D(
+                        obj,
+                        mipcount as i32,
+                        glfmt.internal_fmt,
+                        et.width as i32,
+                        et.height as i32,
+                        et.array_layers as i32,
+                    );
+                }
                 _ => unimplemented!("texture type"),
             };
 
@@ -187,7 +212,7 @@ impl RawImage {
         RawImage {
             obj,
             target: et.target,
-            //format
+            format,
         }
     }
 
@@ -226,7 +251,7 @@ impl RawImage {
         RawImage {
             obj,
             target: gl::RENDERBUFFER,
-            //format
+            format,
         }
     }
 
@@ -245,6 +270,23 @@ impl RawImage {
     }
 }
 
+/// Returns the offset and size of the texels of an image region, in the coordinates of the
+/// texture functions (`glTextureSubImage*`, `glGetTextureSubImage`, `glCopyImageSubData`).
+///
+/// Array layers are addressed with the Y coordinate in 1D array textures, and with the Z
+/// coordinate otherwise.
+pub fn region_texels(target: GLenum, region: &ImageRegion) -> ((u32, u32, u32), (u32, u32, u32)) {
+    let (x, y, z) = region.offset;
+    let (w, h, d) = region.extent;
+    match target {
+        gl::TEXTURE_1D_ARRAY => ((x, region.base_array_layer, 0), (w, region.layer_count, 1)),
+        _ => (
+            (x, y, z + region.base_array_layer),
+            (w, h, d * region.layer_count),
+        ),
+    }
+}
+
 /// Texture upload
 ///
 /// TODO move in cmd
@@ -266,11 +308,132 @@ pub unsafe fn upload_image_region(
     );
 
     // TODO check size of mip level
-    let glfmt = GlFormatInfo::from_format(fmt);
+    let mut prev_unpack_alignment = 0;
+    gl.GetIntegerv(gl::UNPACK_ALIGNMENT, &mut prev_unpack_alignment);
+    gl.PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+    texture_sub_image(
+        gl,
+        target,
+        img,
+        fmt,
+        mip_level,
+        offset,
+        size,
+        data.as_ptr() as *const GLvoid,
+    );
+    gl.PixelStorei(gl::UNPACK_ALIGNMENT, prev_unpack_alignment);
+}
 
+/// Copies a region of an image from a buffer.
+///
+/// The data in the buffer is assumed to be tightly packed.
+pub unsafe fn copy_buffer_to_image_region(
+    gl: &Gl,
+    buffer: GLuint,
+    buffer_offset: usize,
+    image: &RawImage,
+    mip_level: i32,
+    offset: (u32, u32, u32),
+    size: (u32, u32, u32),
+) {
+    assert_ne!(
+        image.target,
+        gl::RENDERBUFFER,
+        "cannot copy buffer data to a renderbuffer"
+    );
     let mut prev_unpack_alignment = 0;
     gl.GetIntegerv(gl::UNPACK_ALIGNMENT, &mut prev_unpack_alignment);
     gl.PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+    gl.BindBuffer(gl::PIXEL_UNPACK_BUFFER, buffer);
+    texture_sub_image(
+        gl,
+        image.target,
+        image.obj,
+        image.format,
+        mip_level,
+        offset,
+        size,
+        buffer_offset as *const GLvoid,
+    );
+    gl.BindBuffer(gl::PIXEL_UNPACK_BUFFER, 0);
+    gl.PixelStorei(gl::UNPACK_ALIGNMENT, prev_unpack_alignment);
+}
+
+/// Copies a region of an image into a buffer.
+///
+/// The data is tightly packed in the buffer.
+pub unsafe fn copy_image_region_to_buffer(
+    gl: &Gl,
+    image: &RawImage,
+    mip_level: i32,
+    offset: (u32, u32, u32),
+    size: (u32, u32, u32),
+    buffer: GLuint,
+    buffer_offset: usize,
+    buffer_size: usize,
+) {
+    let glfmt = GlFormatInfo::from_format(image.format);
+
+    let mut prev_pack_alignment = 0;
+    gl.GetIntegerv(gl::PACK_ALIGNMENT, &mut prev_pack_alignment);
+    gl.PixelStorei(gl::PACK_ALIGNMENT, 1);
+    gl.BindBuffer(gl::PIXEL_PACK_BUFFER, buffer);
+
+    if image.target == gl::RENDERBUFFER {
+        // renderbuffers can only be read through a framebuffer
+        assert_eq!(mip_level, 0);
+        let mut tmpfb = 0;
+        gl.CreateFramebuffers(1, &mut tmpfb);
+        gl.NamedFramebufferRenderbuffer(tmpfb, gl::COLOR_ATTACHMENT0, gl::RENDERBUFFER, image.obj);
+        gl.NamedFramebufferReadBuffer(tmpfb, gl::COLOR_ATTACHMENT0);
+        gl.BindFramebuffer(gl::READ_FRAMEBUFFER, tmpfb);
+        gl.ReadnPixels(
+            offset.0 as i32,
+            offset.1 as i32,
+            size.0 as i32,
+            size.1 as i32,
+            glfmt.upload_components,
+            glfmt.upload_ty,
+            (buffer_size - buffer_offset) as i32,
+            buffer_offset as *mut GLvoid,
+        );
+        gl.BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+        gl.DeleteFramebuffers(1, &tmpfb);
+    } else {
+        gl.GetTextureSubImage(
+            image.obj,
+            mip_level,
+            offset.0 as i32,
+            offset.1 as i32,
+            offset.2 as i32,
+            size.0 as i32,
+            size.1 as i32,
+            size.2 as i32,
+            glfmt.upload_components,
+            glfmt.upload_ty,
+            (buffer_size - buffer_offset) as i32,
+            buffer_offset as *mut GLvoid,
+        );
+    }
+
+    gl.BindBuffer(gl::PIXEL_PACK_BUFFER, 0);
+    gl.PixelStorei(gl::PACK_ALIGNMENT, prev_pack_alignment);
+}
+
+/// Calls the glTextureSubImage* function appropriate for the target.
+///
+/// `data` is either a pointer to client memory or an offset into the bound pixel unpack buffer.
+unsafe fn texture_sub_image(
+    gl: &Gl,
+    target: GLenum,
+    img: GLuint,
+    fmt: Format,
+    mip_level: i32,
+    offset: (u32, u32, u32),
+    size: (u32, u32, u32),
+    data: *const GLvoid,
+) {
+    let glfmt = GlFormatInfo::from_format(fmt);
 
     match target {
         gl::TEXTURE_1D => {
@@ -281,10 +444,10 @@ pub unsafe fn upload_image_region(
                 size.0 as i32,
                 glfmt.upload_components,
                 glfmt.upload_ty,
-                data.as_ptr() as *const GLvoid,
+                data,
             );
         }
-        gl::TEXTURE_2D => {
+        gl::TEXTURE_2D | gl::TEXTURE_1D_ARRAY => {
             gl.TextureSubImage2D(
                 img,
                 mip_level,
@@ -294,10 +457,10 @@ pub unsafe fn upload_image_region(
                 size.1 as i32,
                 glfmt.upload_components,
                 glfmt.upload_ty,
-                data.as_ptr() as *const GLvoid,
+                data,
             );
         }
-        gl::TEXTURE_3D => {
+        gl::TEXTURE_3D | gl::TEXTURE_2D_ARRAY => {
             gl.TextureSubImage3D(
                 img,
                 mip_level,
@@ -309,13 +472,11 @@ pub unsafe fn upload_image_region(
                 size.2 as i32,
                 glfmt.upload_components,
                 glfmt.upload_ty,
-                data.as_ptr() as *const GLvoid,
+                data,
             );
         }
         _ => unimplemented!(),
     };
-
-    gl.PixelStorei(gl::UNPACK_ALIGNMENT, prev_unpack_alignment);
 }
 
 //--------------------------------------------------------------------------------------------------
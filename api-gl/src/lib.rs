@@ -32,6 +32,48 @@
 //! In order for the images to appear correctly on the screen, image data is flipped vertically
 //! before a "present" operation.
 //!
+//! ### Readbacks
+//!
+//! Readback commands copy the data into a host-readable staging buffer (through a pixel-pack
+//! buffer for images). The buffers are mapped and read once the fence signalled at the end of the
+//! frame is reached, either at the end of a later `submit_frame` or in `poll_readbacks`.
+//! Since image data is stored upside-down (see above), rows are returned top to bottom.
+//!
+//! ### Profile scopes
+//!
+//! The boundaries of profile scopes are recorded with `glQueryCounter(GL_TIMESTAMP)`. The
+//! queries are read once the frame has finished executing, in the same way as readbacks.
+//!
+//! ### Debug labels
+//!
+//! When KHR_debug is available, debug groups and markers are forwarded to `glPushDebugGroup`,
+//! `glPopDebugGroup` and `glDebugMessageInsert`, and debug names to `glObjectLabel`. Unmatched
+//! pops are ignored, and the groups still open at the end of a frame are closed. Argument blocks
+//! and buffers suballocated from a shared buffer are not labelled.
+//!
+//! ### Multiple windows
+//!
+//! Additional windows are created with [create_shared_window]: each window has its own context,
+//! which shares objects (images, buffers, programs) with the context of the instance. All commands
+//! are executed in the context of the instance. To present to an additional window, the backend
+//! switches to the context of the window, waits for the commands of the frame with a fence,
+//! blits the image to the window and swaps its buffers, then switches back to the context of the
+//! instance.
+//!
+//! ### Queries
+//!
+//! A query of a pipeline statistics pool is made of one query object per counter, since OpenGL
+//! queries measure a single value. Query readbacks are written to the readback buffer with
+//! `glGetQueryBufferObjectui64v`. Conditional rendering waits for the result of the query
+//! (`GL_QUERY_WAIT`).
+//!
+//! ### Arena destruction
+//!
+//! Dropping an arena does not destroy its objects immediately: the arena is retired along with
+//! the number of the last submitted frame, and its objects are destroyed once the timeline
+//! reaches that frame, at the end of a later `submit_frame` or `drop_arena`. The arenas still
+//! retired when the instance is dropped are destroyed once the frames in flight have finished.
+//!
 #[macro_use]
 extern crate log;
 
@@ -45,6 +87,9 @@ mod framebuffer;
 mod image;
 mod pipeline;
 pub mod prelude;
+mod profile;
+mod query;
+mod readback;
 mod sampler;
 mod swapchain;
 mod sync;
@@ -53,12 +98,12 @@ mod window;
 
 pub use self::{
     backend::{InstanceConfig, OpenGlBackend, OpenGlInstance},
-    window::create_instance_and_window,
+    window::{create_instance_and_window, create_shared_window},
 };
 
-use crate::api as gl;
+use crate::{api as gl, api::types::*};
 use autograph_api::AliasScope;
-use std::mem;
+use std::{ffi::CStr, mem, os::raw::c_char};
 
 #[derive(Copy, Clone, Debug)]
 struct AliasInfo<K: slotmap::Key> {
@@ -72,6 +117,8 @@ pub struct ImplementationParameters {
     pub max_draw_buffers: u32,
     pub max_color_attachments: u32,
     pub max_viewports: u32,
+    /// Whether object labels and debug groups are supported (KHR_debug, core since OpenGL 4.3).
+    pub khr_debug: bool,
 }
 
 impl ImplementationParameters {
@@ -87,6 +134,19 @@ impl ImplementationParameters {
             max_draw_buffers: getint(gl::MAX_DRAW_BUFFERS) as u32,
             max_color_attachments: getint(gl::MAX_COLOR_ATTACHMENTS) as u32,
             max_viewports: getint(gl::MAX_VIEWPORTS) as u32,
+            khr_debug: (getint(gl::MAJOR_VERSION), getint(gl::MINOR_VERSION)) >= (4, 3)
+                || has_extension(gl, "GL_KHR_debug"),
         }
     }
 }
+
+fn has_extension(gl: &gl::Gl, name: &str) -> bool {
+    unsafe {
+        let mut count = 0;
+        gl.GetIntegerv(gl::NUM_EXTENSIONS, &mut count);
+        (0..count as GLuint).any(|i| {
+            let ext = CStr::from_ptr(gl.GetStringi(gl::EXTENSIONS, i) as *const c_char);
+            ext.to_bytes() == name.as_bytes()
+        })
+    }
+}
@@ -1,6 +1,7 @@
 use crate::{
     api::{types::*, Gl},
     backend::GlArena,
+    buffer::GlHostReference,
     framebuffer::GlFramebuffer,
     image::GlImage,
     sampler::SamplerCache,
@@ -8,11 +9,12 @@ use crate::{
 };
 use autograph_api::{
     descriptor::{Descriptor, ResourceBindingType},
+    error::Error,
     image::{DepthStencilView, RenderTargetView},
     pipeline::{BareArgumentBlock, Scissor, SignatureDescription, Viewport},
     vertex::{IndexBufferView, IndexFormat, VertexBufferView},
 };
-use std::slice;
+use std::{ptr, slice};
 
 /// Proposal: flatten signature?
 /// At least, no need to store inherited (only the length matters)
@@ -154,6 +156,8 @@ pub(crate) enum StateBlock {
         buffers: *const GLuint,
         offsets: *const GLintptr,
         sizes: *const GLintptr,
+        /// Host data to upload in place of the buffer, or null.
+        host_refs: *const *const GlHostReference,
     },
     ShaderStorageBuffers {
         buffers: *const GLuint,
@@ -186,6 +190,7 @@ struct StateBlocks<'a> {
     uniform_buffers: &'a mut [GLuint],
     uniform_buffer_offsets: &'a mut [GLintptr],
     uniform_buffer_sizes: &'a mut [GLintptr],
+    uniform_buffer_host_refs: &'a mut [*const GlHostReference],
     shader_storage_buffers: &'a mut [GLuint],
     shader_storage_buffer_offsets: &'a mut [GLintptr],
     shader_storage_buffer_sizes: &'a mut [GLintptr],
@@ -212,16 +217,21 @@ impl<'a> StateBlocks<'a> {
             &mut [][..]
         };
 
-        let (uniform_buffers, uniform_buffer_offsets, uniform_buffer_sizes) =
-            if sig.num_uniform_buffers != 0 {
-                (
-                    arena.other.alloc_uninitialized(sig.num_uniform_buffers),
-                    arena.other.alloc_uninitialized(sig.num_uniform_buffers),
-                    arena.other.alloc_uninitialized(sig.num_uniform_buffers),
-                )
-            } else {
-                (&mut [][..], &mut [][..], &mut [][..])
-            };
+        let (
+            uniform_buffers,
+            uniform_buffer_offsets,
+            uniform_buffer_sizes,
+            uniform_buffer_host_refs,
+        ) = if sig.num_uniform_buffers != 0 {
+            (
+                arena.other.alloc_uninitialized(sig.num_uniform_buffers),
+                arena.other.alloc_uninitialized(sig.num_uniform_buffers),
+                arena.other.alloc_uninitialized(sig.num_uniform_buffers),
+                arena.other.alloc_uninitialized(sig.num_uniform_buffers),
+            )
+        } else {
+            (&mut [][..], &mut [][..], &mut [][..], &mut [][..])
+        };
 
         let (shader_storage_buffers, shader_storage_buffer_offsets, shader_storage_buffer_sizes) =
             if sig.num_shader_storage_buffers != 0 {
@@ -289,6 +299,7 @@ impl<'a> StateBlocks<'a> {
             uniform_buffers,
             uniform_buffer_offsets,
             uniform_buffer_sizes,
+            uniform_buffer_host_refs,
             shader_storage_buffers,
             shader_storage_buffer_offsets,
             shader_storage_buffer_sizes,
@@ -313,7 +324,7 @@ impl<'a> StateBlocks<'a> {
         arena: &'a GlArena,
         gl: &Gl,
         signature: &GlSignature,
-    ) -> &'a GlArgumentBlock {
+    ) -> Result<&'a GlArgumentBlock, Error> {
         let state_blocks = arena.other.alloc_uninitialized(signature.num_state_blocks);
 
         let mut i = 0;
@@ -327,6 +338,7 @@ impl<'a> StateBlocks<'a> {
                 buffers: self.uniform_buffers.as_ptr(),
                 offsets: self.uniform_buffer_offsets.as_ptr(),
                 sizes: self.uniform_buffer_sizes.as_ptr(),
+                host_refs: self.uniform_buffer_host_refs.as_ptr(),
             };
             i += 1;
         }
@@ -366,10 +378,12 @@ impl<'a> StateBlocks<'a> {
 
                 // build framebuffer
                 // put in arena so that it's deleted at the same time as the argument block
-                let fb = arena.framebuffers.alloc(
-                    GlFramebuffer::new(gl, &tmp_color[..], tmp_depth_stencil)
-                        .expect("failed to create framebuffer"),
-                );
+                let fb = GlFramebuffer::new(gl, &tmp_color[..], tmp_depth_stencil).map_err(
+                    |status| Error::Validation {
+                        message: format!("incomplete framebuffer (status 0x{:X})", status),
+                    },
+                )?;
+                let fb = arena.framebuffers.alloc(fb);
 
                 state_blocks[i] = StateBlock::Framebuffer(fb.obj);
                 i += 1;
@@ -412,10 +426,10 @@ impl<'a> StateBlocks<'a> {
             //i += 1;
         }
 
-        arena.other.alloc(GlArgumentBlock {
+        Ok(arena.other.alloc(GlArgumentBlock {
             signature: signature as *const GlSignature,
             blocks: state_blocks.as_ptr(),
-        })
+        }))
     }
 }
 
@@ -487,7 +501,7 @@ impl GlArgumentBlock {
         depth_stencil_target: Option<DepthStencilView<'a, OpenGlBackend>>,
         viewports: impl IntoIterator<Item = Viewport>,
         scissors: impl IntoIterator<Item = Scissor>,
-    ) -> &'a GlArgumentBlock {
+    ) -> Result<&'a GlArgumentBlock, Error> {
         let mut stb = unsafe { StateBlocks::new(arena, signature) };
 
         let i_inherited = copy_iter(inherited.into_iter().map(|a| a.0), stb.inherited);
@@ -529,6 +543,15 @@ impl GlArgumentBlock {
                         (buffer.offset + offset) as isize;
                     stb.uniform_buffer_sizes[i_uniform_buffers] =
                         size.unwrap_or(buffer.raw.size - offset) as isize;
+                    stb.uniform_buffer_host_refs[i_uniform_buffers] = ptr::null();
+                    i_uniform_buffers += 1;
+                }
+                Descriptor::HostConstantBuffer { reference } => {
+                    // the buffer and offset are determined when the data is uploaded
+                    stb.uniform_buffers[i_uniform_buffers] = 0;
+                    stb.uniform_buffer_offsets[i_uniform_buffers] = 0;
+                    stb.uniform_buffer_sizes[i_uniform_buffers] = reference.len as isize;
+                    stb.uniform_buffer_host_refs[i_uniform_buffers] = reference;
                     i_uniform_buffers += 1;
                 }
                 Descriptor::RwBuffer {
@@ -4,6 +4,7 @@ use crate::{
     command::StateCache,
 };
 use autograph_api::{
+    error::Error,
     image::SamplerDescription,
     pipeline::{
         ColorBlendAttachmentState, ColorBlendAttachments, DepthStencilState, InputAssemblyState,
@@ -17,7 +18,7 @@ mod program;
 mod shader;
 mod vao;
 
-use self::program::create_graphics_program;
+use self::program::{create_compute_program, create_graphics_program};
 
 pub(crate) use self::{
     arguments::{GlArgumentBlock, GlSignature, StateBlock},
@@ -25,8 +26,8 @@ pub(crate) use self::{
 };
 use crate::format::GlFormatInfo;
 use autograph_api::pipeline::{
-    GraphicsPipelineCreateInfo, ScissorsOwned, SignatureDescription, VertexInputBinding,
-    ViewportsOwned,
+    ComputePipelineCreateInfo, GraphicsPipelineCreateInfo, ScissorsOwned, SignatureDescription,
+    VertexInputBinding, ViewportsOwned,
 };
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
@@ -70,6 +71,18 @@ impl GlGraphicsPipeline {
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct GlComputePipeline {
+    pub(crate) descriptor_map: DescriptorMap,
+    pub(crate) program: GLuint,
+}
+
+impl GlComputePipeline {
+    pub(crate) fn descriptor_map(&self) -> &DescriptorMap {
+        &self.descriptor_map
+    }
+}
+
 /// Converts a sequence of VertexInputBinding (one for each vertex buffer) into a VAO.
 ///
 /// This function generates vertex attributes for each element in all layouts,
@@ -126,14 +139,14 @@ pub(crate) unsafe fn create_graphics_pipeline_internal<'a>(
     _root_signature: &'a GlSignature,
     root_signature_description: &SignatureDescription,
     ci: &GraphicsPipelineCreateInfo<'a, '_, OpenGlBackend>,
-) -> &'a GlGraphicsPipeline {
+) -> Result<&'a GlGraphicsPipeline, Error> {
     let (program, descriptor_map) = {
         let vs = ci.shader_stages.vertex.inner();
         let fs = ci.shader_stages.fragment.map(|s| s.inner());
         let gs = ci.shader_stages.geometry.map(|s| s.inner());
         let tcs = ci.shader_stages.tess_control.map(|s| s.inner());
         let tes = ci.shader_stages.tess_eval.map(|s| s.inner());
-        create_graphics_program(gl, vs, fs, gs, tcs, tes).expect("failed to create program")
+        create_graphics_program(gl, vs, fs, gs, tcs, tes)?
     };
 
     // collect vertex bindings
@@ -176,7 +189,22 @@ pub(crate) unsafe fn create_graphics_pipeline_internal<'a>(
         scissors: ci.viewport_state.scissors.into(),
     };
 
-    arena.graphics_pipelines.alloc(g)
+    Ok(arena.graphics_pipelines.alloc(g))
+}
+
+pub(crate) unsafe fn create_compute_pipeline_internal<'a>(
+    gl: &Gl,
+    arena: &'a GlArena,
+    _root_signature: &'a GlSignature,
+    _root_signature_description: &SignatureDescription,
+    ci: &ComputePipelineCreateInfo<'a, '_, OpenGlBackend>,
+) -> Result<&'a GlComputePipeline, Error> {
+    let (program, descriptor_map) = create_compute_program(gl, ci.shader_stage.inner())?;
+
+    Ok(arena.compute_pipelines.alloc(GlComputePipeline {
+        descriptor_map,
+        program,
+    }))
 }
 
 impl GlGraphicsPipeline {
@@ -208,3 +236,9 @@ impl GlGraphicsPipeline {
         }
     }
 }
+
+impl GlComputePipeline {
+    pub(crate) fn bind(&self, gl: &Gl, state_cache: &mut StateCache) {
+        state_cache.set_program(gl, self.program);
+    }
+}
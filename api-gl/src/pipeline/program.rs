@@ -6,7 +6,7 @@ use crate::{
     api as gl,
     api::{types::*, Gl},
 };
-use autograph_api::pipeline::ShaderStageFlags;
+use autograph_api::{error, pipeline::ShaderStageFlags};
 use std::{error::Error, fmt};
 
 //--------------------------------------------------------------------------------------------------
@@ -52,6 +52,19 @@ impl From<ShaderCreationError> for ProgramCreationError {
     }
 }
 
+impl From<ProgramCreationError> for error::Error {
+    fn from(err: ProgramCreationError) -> Self {
+        error::Error::ShaderLink { message: err.0 }
+    }
+}
+
+/// GLSL shaders have no reflection information from which to build the descriptor map.
+fn glsl_not_supported() -> ProgramCreationError {
+    ProgramCreationError(
+        "pipelines with GLSL shaders are not supported: use SPIR-V shaders instead".into(),
+    )
+}
+
 pub(crate) fn create_graphics_program(
     gl: &Gl,
     vert: &GlShaderModule,
@@ -121,7 +134,7 @@ pub(crate) fn create_graphics_program(
         (vs, fs, gs, tcs, tes, dm)
     } else {
         // GLSL path
-        unimplemented!("descriptor map for GLSL compilation path")
+        return Err(glsl_not_supported());
         /*(
             vert.obj,
             frag.map(|s| s.obj),
@@ -192,3 +205,38 @@ pub(crate) fn create_graphics_program(
         Ok((program, dm))
     }
 }
+
+pub(crate) fn create_compute_program(
+    gl: &Gl,
+    comp: &GlShaderModule,
+) -> Result<(GLuint, DescriptorMap), ProgramCreationError> {
+    let (cs, dm) = if let Some(ref comp) = comp.spirv {
+        // SPIR-V path: same as graphics
+        let mut dmb = DescriptorMapBuilder::new();
+        let cs = {
+            let comp = translate_spirv_to_gl_flavor(comp, ShaderStageFlags::COMPUTE, &mut dmb);
+            create_specialized_spirv_shader(gl, ShaderStageFlags::COMPUTE, "main", &comp)?
+        };
+        let dm = dmb.into();
+        debug!("inferred descriptor map: {:#?}", dm);
+        (cs, dm)
+    } else {
+        // GLSL path
+        return Err(glsl_not_supported());
+    };
+
+    unsafe {
+        let program = gl.CreateProgram();
+        gl.AttachShader(program, cs);
+
+        let result = link_program(gl, program);
+        // the SPIR-V path has generated a new shader object: don't leak it
+        gl.DeleteShader(cs);
+        result.map_err(|log| {
+            gl.DeleteProgram(program);
+            ProgramCreationError(format!("program link error: {}", log))
+        })?;
+
+        Ok((program, dm))
+    }
+}
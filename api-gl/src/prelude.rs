@@ -11,5 +11,7 @@ pub type RenderTarget2dView<'a> = autograph_api::image::RenderTarget2dView<'a, B
 pub type TextureSampler2dView<'a> = autograph_api::image::TextureSampler2dView<'a, Backend>;
 pub type TypedGraphicsPipeline<'a, T> =
     autograph_api::pipeline::TypedGraphicsPipeline<'a, Backend, T>;
+pub type TypedComputePipeline<'a, T> =
+    autograph_api::pipeline::TypedComputePipeline<'a, Backend, T>;
 pub type TypedArgumentBlock<'a, T> = autograph_api::pipeline::TypedArgumentBlock<'a, Backend, T>;
 pub type DynamicSignature<'a> = autograph_api::pipeline::DynamicSignature<'a, Backend>;
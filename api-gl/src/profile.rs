@@ -0,0 +1,74 @@
+use crate::{
+    api as gl,
+    api::{types::*, Gl},
+};
+use autograph_api::profile::{FrameProfile, ProfileEvent};
+
+/// Timestamp queries recorded at the boundaries of the profile scopes of a frame.
+///
+/// The results are read once the frame has finished executing.
+#[derive(Debug)]
+pub(crate) struct PendingProfile {
+    /// Scope boundaries, in stream order: the name of the scope for the start of a scope,
+    /// `None` for the end.
+    events: Vec<(Option<String>, GLuint)>,
+}
+
+impl PendingProfile {
+    pub(crate) fn new() -> PendingProfile {
+        PendingProfile { events: Vec::new() }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Records a timestamp for the start of a scope.
+    pub(crate) fn begin_scope(&mut self, gl: &Gl, name: &str) {
+        let query = query_timestamp(gl);
+        self.events.push((Some(name.to_string()), query));
+    }
+
+    /// Records a timestamp for the end of the innermost scope.
+    pub(crate) fn end_scope(&mut self, gl: &Gl) {
+        let query = query_timestamp(gl);
+        self.events.push((None, query));
+    }
+
+    /// Reads the timestamps, deletes the queries and builds the profile of the frame.
+    ///
+    /// The frame must have finished executing.
+    pub(crate) unsafe fn resolve(self, gl: &Gl, frame: u64) -> FrameProfile {
+        let timestamps: Vec<u64> = self
+            .events
+            .iter()
+            .map(|&(_, query)| {
+                let mut timestamp = 0;
+                gl.GetQueryObjectui64v(query, gl::QUERY_RESULT, &mut timestamp);
+                gl.DeleteQueries(1, &query);
+                timestamp
+            })
+            .collect();
+        FrameProfile::from_events(
+            frame,
+            self.events
+                .iter()
+                .zip(timestamps)
+                .map(|(&(ref name, _), timestamp)| match name {
+                    Some(name) => ProfileEvent::Begin { name, timestamp },
+                    None => ProfileEvent::End { timestamp },
+                }),
+        )
+    }
+}
+
+/// Creates a query object and records the GPU timestamp at which all previous commands are
+/// complete.
+fn query_timestamp(gl: &Gl) -> GLuint {
+    let mut query = 0;
+    unsafe {
+        gl.CreateQueries(gl::TIMESTAMP, 1, &mut query);
+        gl.QueryCounter(query, gl::TIMESTAMP);
+    }
+    query
+}
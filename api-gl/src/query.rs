@@ -0,0 +1,88 @@
+use crate::{
+    api as gl,
+    api::{types::*, Gl},
+};
+use autograph_api::query::{PipelineStatisticsFlags, QueryType};
+
+/// Query pool.
+///
+/// OpenGL queries measure a single value: a query of the pool is made of one GL query object
+/// for each value of its result.
+#[derive(Debug)]
+pub struct GlQueryPool {
+    /// Query targets of each value of a result.
+    pub(crate) targets: Vec<GLenum>,
+    /// Query objects, `targets.len()` per query.
+    pub(crate) queries: Vec<GLuint>,
+}
+
+/// Pipeline statistics counters and the corresponding query targets, in the order of the bits.
+const PIPELINE_STATISTICS_TARGETS: &[(PipelineStatisticsFlags, GLenum)] = &[
+    (
+        PipelineStatisticsFlags::INPUT_ASSEMBLY_VERTICES,
+        gl::VERTICES_SUBMITTED,
+    ),
+    (
+        PipelineStatisticsFlags::INPUT_ASSEMBLY_PRIMITIVES,
+        gl::PRIMITIVES_SUBMITTED,
+    ),
+    (
+        PipelineStatisticsFlags::VERTEX_SHADER_INVOCATIONS,
+        gl::VERTEX_SHADER_INVOCATIONS,
+    ),
+    (
+        PipelineStatisticsFlags::CLIPPING_INVOCATIONS,
+        gl::CLIPPING_INPUT_PRIMITIVES,
+    ),
+    (
+        PipelineStatisticsFlags::CLIPPING_PRIMITIVES,
+        gl::CLIPPING_OUTPUT_PRIMITIVES,
+    ),
+    (
+        PipelineStatisticsFlags::FRAGMENT_SHADER_INVOCATIONS,
+        gl::FRAGMENT_SHADER_INVOCATIONS,
+    ),
+    (
+        PipelineStatisticsFlags::COMPUTE_SHADER_INVOCATIONS,
+        gl::COMPUTE_SHADER_INVOCATIONS,
+    ),
+];
+
+impl GlQueryPool {
+    pub(crate) fn new(gl: &Gl, ty: QueryType, count: u32) -> GlQueryPool {
+        let targets: Vec<_> = match ty {
+            QueryType::Occlusion => vec![gl::SAMPLES_PASSED],
+            QueryType::ConservativeOcclusion => vec![gl::ANY_SAMPLES_PASSED_CONSERVATIVE],
+            QueryType::PipelineStatistics(flags) => PIPELINE_STATISTICS_TARGETS
+                .iter()
+                .filter(|(flag, _)| flags.contains(*flag))
+                .map(|&(_, target)| target)
+                .collect(),
+        };
+
+        let mut queries = vec![0; count as usize * targets.len()];
+        for (i, query) in queries.iter_mut().enumerate() {
+            unsafe {
+                gl.CreateQueries(targets[i % targets.len()], 1, query);
+            }
+        }
+
+        GlQueryPool { targets, queries }
+    }
+
+    /// Returns the query objects of query `index`, with their targets.
+    pub(crate) fn query(&self, index: u32) -> impl Iterator<Item = (GLenum, GLuint)> + '_ {
+        let n = self.targets.len();
+        let start = index as usize * n;
+        self.targets
+            .iter()
+            .cloned()
+            .zip(self.queries[start..start + n].iter().cloned())
+    }
+
+    pub(crate) fn destroy(&self, gl: &Gl) {
+        unsafe {
+            gl.DeleteQueries(self.queries.len() as GLsizei, self.queries.as_ptr());
+        }
+    }
+}
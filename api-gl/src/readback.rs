@@ -0,0 +1,58 @@
+use crate::{
+    api as gl,
+    api::{types::*, Gl},
+    buffer::create_buffer,
+};
+use autograph_api::readback::Readback;
+use std::{ptr, slice};
+
+/// A readback whose data is being copied into a staging buffer.
+///
+/// The data is read back once the frame in which the copy was submitted has finished executing.
+#[derive(Debug)]
+pub(crate) struct PendingReadback {
+    /// Staging buffer, or zero if the readback is empty.
+    buffer: GLuint,
+    size: usize,
+    readback: Readback,
+}
+
+impl PendingReadback {
+    /// Allocates a host-readable staging buffer of `size` bytes.
+    pub(crate) fn new(gl: &Gl, size: usize, readback: Readback) -> PendingReadback {
+        let buffer = if size != 0 {
+            create_buffer(gl, size, gl::MAP_READ_BIT | gl::CLIENT_STORAGE_BIT, None)
+        } else {
+            0
+        };
+        PendingReadback {
+            buffer,
+            size,
+            readback,
+        }
+    }
+
+    pub(crate) fn buffer(&self) -> GLuint {
+        self.buffer
+    }
+
+    pub(crate) fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Reads the contents of the staging buffer, resolves the readback and deletes the buffer.
+    ///
+    /// The commands writing to the staging buffer must have finished executing.
+    pub(crate) unsafe fn complete(self, gl: &Gl) {
+        let mut data = vec![0; self.size];
+        if self.buffer != 0 {
+            let ptr =
+                gl.MapNamedBufferRange(self.buffer, 0, self.size as GLsizeiptr, gl::MAP_READ_BIT);
+            assert!(ptr != ptr::null_mut(), "failed to map readback buffer");
+            data.copy_from_slice(slice::from_raw_parts(ptr as *const u8, self.size));
+            gl.UnmapNamedBuffer(self.buffer);
+            gl.DeleteBuffers(1, &self.buffer);
+        }
+        self.readback.complete(data);
+    }
+}
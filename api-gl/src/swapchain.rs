@@ -1,6 +1,9 @@
 use autograph_api::traits;
 use glutin::GlWindow;
-use std::{fmt, sync::Arc};
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
 
 /// Represents an OpenGL "swapchain".
 ///
@@ -8,8 +11,31 @@ use std::{fmt, sync::Arc};
 /// underlying window system. This type wraps around window handles and provides an interface
 /// for getting the size of the swapchain (default framebuffer) and present an image to the screen
 /// (swap buffers).
+///
+/// Window systems do not notify the swapchain of resizes: the generation of the swapchain is
+/// updated lazily, by comparing the current size of the window with the size observed on the
+/// last call to `generation`.
+///
+/// Additional windows have their own context, which shares objects with the context of the
+/// instance (see [create_shared_window](crate::create_shared_window)).
 pub struct GlSwapchain {
     pub(crate) window: Arc<GlWindow>,
+    /// For the swapchains of additional windows: the window of the context in which commands
+    /// are executed, made current again after presenting. `None` for the default swapchain.
+    pub(crate) main_window: Option<Arc<GlWindow>>,
+    /// Last observed size and generation.
+    generation: Mutex<((u32, u32), u64)>,
+}
+
+impl GlSwapchain {
+    pub(crate) fn new(window: Arc<GlWindow>, main_window: Option<Arc<GlWindow>>) -> GlSwapchain {
+        let size = window.get_inner_size().unwrap().into();
+        GlSwapchain {
+            window,
+            main_window,
+            generation: Mutex::new((size, 0)),
+        }
+    }
 }
 
 impl fmt::Debug for GlSwapchain {
@@ -22,4 +48,13 @@ impl traits::Swapchain for GlSwapchain {
     fn size(&self) -> (u32, u32) {
         self.window.get_inner_size().unwrap().into()
     }
+
+    fn generation(&self) -> u64 {
+        let size = self.size();
+        let mut generation = self.generation.lock().unwrap();
+        if generation.0 != size {
+            *generation = (size, generation.1 + 1);
+        }
+        generation.1
+    }
 }
@@ -1,5 +1,6 @@
 use crate::backend::{InstanceConfig, OpenGlInstance};
-use glutin::{self, GlWindow};
+use autograph_api::error::Error;
+use glutin::{self, GlContext, GlWindow};
 use std::sync::Arc;
 use winit::{EventsLoop, WindowBuilder};
 
@@ -24,3 +25,45 @@ pub fn create_instance_and_window(
     let inst = OpenGlInstance::from_gl_window(cfg, window.clone()).expect("failed to create instance");
     (inst, window)
 }
+
+/// Creates an additional window, whose context shares objects with the context of `instance`.
+///
+/// Swapchains for the window can then be created with
+/// [Arena::create_swapchain](autograph_api::Arena::create_swapchain): this is the only way to
+/// create windows for additional swapchains.
+///
+/// Returns an error if the instance was not created with a window, or if the window could not be
+/// created.
+pub fn create_shared_window(
+    instance: &OpenGlInstance,
+    events_loop: &EventsLoop,
+    window_builder: WindowBuilder,
+) -> Result<Arc<GlWindow>, Error> {
+    let main_window = instance.window().ok_or_else(|| Error::Validation {
+        message: "the instance was not created with a window".into(),
+    })?;
+    let context_builder = glutin::ContextBuilder::new()
+        .with_gl_profile(glutin::GlProfile::Core)
+        .with_gl_debug_flag(true)
+        .with_gl(glutin::GlRequest::Specific(glutin::Api::OpenGl, (4, 6)))
+        .with_shared_lists(main_window.context());
+
+    let window =
+        glutin::GlWindow::new(window_builder, context_builder, events_loop).map_err(|e| {
+            Error::Validation {
+                message: format!("failed to create the window: {}", e),
+            }
+        })?;
+    // creating the window makes its context current
+    unsafe {
+        main_window.make_current().map_err(|e| Error::Validation {
+            message: format!("failed to make the instance context current: {:?}", e),
+        })?;
+    }
+    let window = Arc::new(window);
+    instance
+        .shared_windows
+        .borrow_mut()
+        .push(Arc::downgrade(&window));
+    Ok(window)
+}
@@ -1,6 +1,11 @@
 //! Test fixtures for autograph-render and friends.
 //! Boilerplate code for creating a window and an OpenGL context with winit/glutin.
+use autograph_api::{
+    error::Result,
+    pipeline::{self, Arguments, SignatureDescription, TypedSignature},
+};
 use glutin::{Event, EventsLoop, WindowEvent};
+use std::iter;
 
 pub type Backend = autograph_api_gl::OpenGlBackend;
 pub type Renderer = autograph_api::Api<Backend>;
@@ -14,6 +19,33 @@ pub type TypedArgumentBlock<'a, T> = autograph_api::pipeline::TypedArgumentBlock
 pub type TextureImageView<'a> = autograph_api::image::TextureImageView<'a, Backend>;
 pub type RenderTargetView<'a> = autograph_api::image::RenderTargetView<'a, Backend>;
 
+/// Pipeline interface without arguments, for any backend.
+pub struct NoArguments;
+
+impl<'a, B: autograph_api::Backend> Arguments<'a, B> for NoArguments {
+    const SIGNATURE: &'static SignatureDescription<'static> = &SignatureDescription::EMPTY;
+    type UniqueType = NoArguments;
+    type IntoInterface = NoArguments;
+
+    fn into_block(
+        self,
+        signature: TypedSignature<'a, B, NoArguments>,
+        arena: &'a autograph_api::Arena<B>,
+    ) -> Result<pipeline::TypedArgumentBlock<'a, B, NoArguments>> {
+        arena.create_argument_block(
+            signature,
+            iter::empty(),
+            iter::empty(),
+            iter::empty(),
+            None,
+            iter::empty(),
+            None,
+            iter::empty(),
+            iter::empty(),
+        )
+    }
+}
+
 pub struct InnerLoop<'a> {
     _renderer: &'a Renderer,
     _arena: &'a Arena<'a>,
@@ -67,8 +99,8 @@ where
     let cfg = config::Config::new();
     let (instance, _window) =
         autograph_api_gl::create_instance_and_window(&cfg, &events_loop, window_builder);
-    let renderer = Renderer::new(instance);
-    let arena = renderer.create_arena();
+    let renderer = Renderer::new(instance).expect("failed to create renderer");
+    let arena = renderer.create_arena().expect("failed to create arena");
     f(
         &renderer,
         &arena,
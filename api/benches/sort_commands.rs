@@ -0,0 +1,84 @@
+//! Benchmarks for command sorting.
+#![feature(test)]
+extern crate test;
+
+use autograph_api::{
+    command::{
+        merge_recorded_commands, sort_command_buffers, Command, CommandBuffer, RecordedCommands,
+    },
+    mock::{MockBackend, MockInstance},
+    Api,
+};
+use test::Bencher;
+
+const NUM_COMMAND_BUFFERS: usize = 16;
+const COMMANDS_PER_BUFFER: usize = 4096;
+
+/// Generates command buffers with pseudo-random sortkeys.
+fn make_command_buffers<'a>(api: &Api<MockBackend>) -> Vec<CommandBuffer<'a, MockBackend>> {
+    let mut seed = 0x2545_f491_4f6c_dd1du64;
+    (0..NUM_COMMAND_BUFFERS)
+        .map(|_| {
+            let mut cmdbuf = api.create_command_buffer();
+            for _ in 0..COMMANDS_PER_BUFFER {
+                seed ^= seed << 13;
+                seed ^= seed >> 7;
+                seed ^= seed << 17;
+                // few distinct high bits, like typical sortkeys (pass, layer...)
+                cmdbuf.insert_debug_marker(seed & 0xFF00_0000_0000_FFFF, "marker");
+            }
+            cmdbuf
+        })
+        .collect()
+}
+
+/// The previous implementation: clone everything and run a comparison sort.
+fn sort_command_buffers_clone<'a>(
+    cmdbufs: impl IntoIterator<Item = CommandBuffer<'a, MockBackend>>,
+) -> Vec<Command<'a, MockBackend>> {
+    let mut fused = Vec::new();
+    for cmdbuf in cmdbufs.into_iter() {
+        for cmd in cmdbuf.iter() {
+            fused.push(cmd.clone());
+        }
+    }
+    fused.sort_by(|cmd_a, cmd_b| cmd_a.sortkey.cmp(&cmd_b.sortkey));
+    fused
+}
+
+#[bench]
+fn bench_sort_radix(b: &mut Bencher) {
+    let api = Api::<MockBackend>::new(MockInstance::new()).unwrap();
+    b.iter(|| {
+        let cmdbufs = make_command_buffers(&api);
+        sort_command_buffers(cmdbufs)
+    });
+}
+
+#[bench]
+fn bench_sort_clone_comparison(b: &mut Bencher) {
+    let api = Api::<MockBackend>::new(MockInstance::new()).unwrap();
+    b.iter(|| {
+        let cmdbufs = make_command_buffers(&api);
+        sort_command_buffers_clone(cmdbufs)
+    });
+}
+
+/// Resubmitting the same commands, recorded once: compare with `bench_sort_radix` minus
+/// `bench_generate`.
+#[bench]
+fn bench_merge_recorded(b: &mut Bencher) {
+    let api = Api::<MockBackend>::new(MockInstance::new()).unwrap();
+    let recorded: Vec<_> = make_command_buffers(&api)
+        .into_iter()
+        .map(RecordedCommands::new)
+        .collect();
+    b.iter(|| merge_recorded_commands(Vec::new(), recorded.iter().map(|r| (r, 0))).count());
+}
+
+/// Baseline: cost of generating the command buffers alone.
+#[bench]
+fn bench_generate(b: &mut Bencher) {
+    let api = Api::<MockBackend>::new(MockInstance::new()).unwrap();
+    b.iter(|| make_command_buffers(&api));
+}
@@ -2,6 +2,7 @@
 use autograph_api::{
     buffer::StructuredBufferData,
     command::{DrawIndexedParams, DrawParams},
+    error::Result,
     format::Format,
     glm, include_shader,
     pipeline::{
@@ -129,9 +130,10 @@ struct Pipelines<'a> {
     path: TypedGraphicsPipeline<'a, PathRendering<'a>>,
 }
 
-fn create_pipelines<'a>(arena: &'a Arena) -> Pipelines<'a> {
+fn create_pipelines<'a>(arena: &'a Arena) -> Result<Pipelines<'a>> {
     let background = GraphicsPipelineCreateInfo {
-        shader_stages: arena.create_vertex_fragment_shader_stages(BACKGROUND_VERT, BACKGROUND_FRAG),
+        shader_stages: arena
+            .create_vertex_fragment_shader_stages(BACKGROUND_VERT, BACKGROUND_FRAG)?,
         viewport_state: ViewportState::default(),
         rasterization_state: RasterisationState::default(),
         multisample_state: MultisampleState::default(),
@@ -140,10 +142,10 @@ fn create_pipelines<'a>(arena: &'a Arena) -> Pipelines<'a> {
         color_blend_state: ColorBlendState::DISABLED,
     };
 
-    let background = arena.create_graphics_pipeline(&background);
+    let background = arena.create_graphics_pipeline(&background)?;
 
     let path = GraphicsPipelineCreateInfo {
-        shader_stages: arena.create_vertex_fragment_shader_stages(PATH_VERT, PATH_FRAG),
+        shader_stages: arena.create_vertex_fragment_shader_stages(PATH_VERT, PATH_FRAG)?,
         viewport_state: ViewportState::default(),
         rasterization_state: RasterisationState::default(),
         multisample_state: MultisampleState::default(),
@@ -152,9 +154,9 @@ fn create_pipelines<'a>(arena: &'a Arena) -> Pipelines<'a> {
         color_blend_state: ColorBlendState::DISABLED,
     };
 
-    let path = arena.create_graphics_pipeline(&path);
+    let path = arena.create_graphics_pipeline(&path)?;
 
-    Pipelines { background, path }
+    Ok(Pipelines { background, path })
 }
 
 //--------------------------------------------------------------------------------------------------
@@ -166,7 +168,7 @@ struct TessPath<'a> {
     index_buffer: Buffer<'a, [u16]>,
 }
 
-fn tessellate_path<'a>(arena: &'a Arena) -> (TessPath<'a>, TessPath<'a>) {
+fn tessellate_path<'a>(arena: &'a Arena) -> Result<(TessPath<'a>, TessPath<'a>)> {
     let mut builder = SvgPathBuilder::new(Path::builder());
     build_logo_path(&mut builder);
     let path = builder.build();
@@ -204,8 +206,8 @@ fn tessellate_path<'a>(arena: &'a Arena) -> (TessPath<'a>, TessPath<'a>) {
             &mut buffers_builder,
         );
 
-        let vb = arena.upload_slice(&buffers.vertices);
-        let ib = arena.upload_slice(&buffers.indices);
+        let vb = arena.upload_slice(&buffers.vertices)?;
+        let ib = arena.upload_slice(&buffers.indices)?;
 
         TessPath {
             _num_vertices: buffers.vertices.len(),
@@ -227,8 +229,8 @@ fn tessellate_path<'a>(arena: &'a Arena) -> (TessPath<'a>, TessPath<'a>) {
             )
             .unwrap();
 
-        let vb = arena.upload_slice(&buffers.vertices);
-        let ib = arena.upload_slice(&buffers.indices);
+        let vb = arena.upload_slice(&buffers.vertices)?;
+        let ib = arena.upload_slice(&buffers.indices)?;
 
         TessPath {
             _num_vertices: buffers.vertices.len(),
@@ -238,7 +240,7 @@ fn tessellate_path<'a>(arena: &'a Arena) -> (TessPath<'a>, TessPath<'a>) {
         }
     };
 
-    (stroke, fill)
+    Ok((stroke, fill))
 }
 
 fn main() {
@@ -246,40 +248,46 @@ fn main() {
         "lyon tessellation test",
         None,
         |renderer, arena, main_loop| {
-            let pipelines = create_pipelines(arena);
-            let (stroke_path, fill_path) = tessellate_path(arena);
+            let pipelines = create_pipelines(arena).unwrap();
+            let (stroke_path, fill_path) = tessellate_path(arena).unwrap();
             let default_swapchain = renderer.default_swapchain().unwrap();
             let (w, h) = default_swapchain.size();
             let color_buffer =
                 arena.create_unaliasable_render_target(Format::R16G16B16A16_SFLOAT, (w, h), 8);
 
-            let render_targets = arena.create_typed_argument_block(RenderTargets {
-                color_target: color_buffer.into(),
-                viewport: (w, h).into(),
-            });
+            let render_targets = arena
+                .create_typed_argument_block(RenderTargets {
+                    color_target: color_buffer.into(),
+                    viewport: (w, h).into(),
+                })
+                .unwrap();
 
             let (left, top, right, bottom) = (-1.0, 1.0, 1.0, -1.0);
 
-            let vertex_buffer = arena.upload_slice(&[
-                Vertex2D::new([left, top]),
-                Vertex2D::new([right, top]),
-                Vertex2D::new([left, bottom]),
-                Vertex2D::new([left, bottom]),
-                Vertex2D::new([right, top]),
-                Vertex2D::new([right, bottom]),
-            ]);
+            let vertex_buffer = arena
+                .upload_slice(&[
+                    Vertex2D::new([left, top]),
+                    Vertex2D::new([right, top]),
+                    Vertex2D::new([left, bottom]),
+                    Vertex2D::new([left, bottom]),
+                    Vertex2D::new([right, top]),
+                    Vertex2D::new([right, bottom]),
+                ])
+                .unwrap();
 
             main_loop.run(|| {
-                let arena = renderer.create_arena();
+                let arena = renderer.create_arena().unwrap();
                 let mut cmdbuf = renderer.create_command_buffer();
 
                 //----------------------------------------------------------------------------------
                 // Draw background
-                let background_params = arena.upload(&BackgroundParams {
-                    resolution: glm::vec2(w as f32, h as f32),
-                    scroll_offset: glm::vec2(0.0, 0.0),
-                    zoom: 5.0,
-                });
+                let background_params = arena
+                    .upload(&BackgroundParams {
+                        resolution: glm::vec2(w as f32, h as f32),
+                        scroll_offset: glm::vec2(0.0, 0.0),
+                        zoom: 5.0,
+                    })
+                    .unwrap();
 
                 cmdbuf.draw(
                     0x0,
@@ -304,12 +312,14 @@ fn main() {
                     PathRendering {
                         render_targets,
                         params: background_params,
-                        primitives: arena.upload(&Primitive {
-                            color: glm::vec4(1.0, 1.0, 1.0, 1.0),
-                            z_index: 0,
-                            width: 0.0,
-                            translate: glm::vec2(-70.0, -70.0),
-                        }),
+                        primitives: arena
+                            .upload(&Primitive {
+                                color: glm::vec4(1.0, 1.0, 1.0, 1.0),
+                                z_index: 0,
+                                width: 0.0,
+                                translate: glm::vec2(-70.0, -70.0),
+                            })
+                            .unwrap(),
                         vertex_buffer: fill_path.vertex_buffer,
                         index_buffer: fill_path.index_buffer,
                     },
@@ -330,12 +340,14 @@ fn main() {
                     PathRendering {
                         render_targets,
                         params: background_params,
-                        primitives: arena.upload(&Primitive {
-                            color: glm::vec4(0.0, 0.0, 0.0, 1.0),
-                            z_index: 0,
-                            width: 1.0,
-                            translate: glm::vec2(-70.0, -70.0),
-                        }),
+                        primitives: arena
+                            .upload(&Primitive {
+                                color: glm::vec4(0.0, 0.0, 0.0, 1.0),
+                                z_index: 0,
+                                width: 1.0,
+                                translate: glm::vec2(-70.0, -70.0),
+                            })
+                            .unwrap(),
                         vertex_buffer: stroke_path.vertex_buffer,
                         index_buffer: stroke_path.index_buffer,
                     },
@@ -0,0 +1,29 @@
+//! Replays a frame captured with `Api::capture_next_frame`.
+//!
+//! Usage: `cargo run --example replay -- <capture file> [number of frames]`
+use autograph_api::capture::{FrameCapture, Replay};
+use autograph_api_test::*;
+use std::env;
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let path = args
+        .next()
+        .expect("usage: replay <capture file> [number of frames]");
+    let num_frames = args
+        .next()
+        .map(|n| n.parse().expect("invalid number of frames"));
+
+    let capture = FrameCapture::load(&path).expect("failed to load capture");
+    println!(
+        "{}: {} objects, {} commands",
+        path,
+        capture.objects.len(),
+        capture.commands.len()
+    );
+
+    with_test_fixture("replay", num_frames, |_renderer, arena, innerloop| {
+        let replay = Replay::new(arena, &capture).expect("failed to replay capture");
+        innerloop.run(|| replay.submit());
+    });
+}
@@ -48,6 +48,8 @@ struct ArgumentsItem {
     // Shader interfaces -----------------------
     #[darling(default)]
     descriptor: Flag,
+    #[darling(default)]
+    uniform_buffer: Flag,
 }
 
 pub fn generate(ast: &syn::DeriveInput, fields: &syn::Fields) -> TokenStream {
@@ -146,6 +148,9 @@ pub fn generate(ast: &syn::DeriveInput, fields: &syn::Fields) -> TokenStream {
                 if pitem.descriptor.is_some() {
                     num_attrs += 1;
                 }
+                if pitem.uniform_buffer.is_some() {
+                    num_attrs += 1;
+                }
                 if pitem.depth_stencil_render_target.is_some() {
                     num_attrs += 1;
                 }
@@ -200,16 +205,23 @@ pub fn generate(ast: &syn::DeriveInput, fields: &syn::Fields) -> TokenStream {
                     }
                 }
                 // descriptor --------------------------------------------
-                else if pitem.descriptor.is_some() {
+                else if pitem.descriptor.is_some() || pitem.uniform_buffer.is_some() {
                     iter_descriptors.push(quote! {
                        std::iter::once(self.#name.into_descriptor())
                     });
                     let index = i_desc.len() as u32;
+                    // uniform buffers (buffers, or host references bound as inline uniform data)
+                    // are always bound as constant buffers
+                    let binding_ty = if pitem.uniform_buffer.is_some() {
+                        quote!(#G::descriptor::ResourceBindingType::ConstantBuffer)
+                    } else {
+                        quote!(<#ty as #G::descriptor::ResourceInterface<#ty_backend>>::TYPE)
+                    };
                     i_desc.push(quote!{
                         #G::descriptor::ResourceBinding {
                             set: None, // descriptor set is determined by the argument block layout
                             index: #index,
-                            ty: <#ty as #G::descriptor::ResourceInterface<#ty_backend>>::TYPE,
+                            ty: #binding_ty,
                             stage_flags: #G::pipeline::ShaderStageFlags::ALL_GRAPHICS,
                             count: 1,
                             data_ty: <#ty as #G::descriptor::ResourceInterface<#ty_backend>>::DATA_TYPE,
@@ -335,18 +347,18 @@ pub fn generate(ast: &syn::DeriveInput, fields: &syn::Fields) -> TokenStream {
                 num_scissors                      : #n_scissors,
             };
 
-            fn get_inherited_signatures(renderer: &#lt_arena #G::Api<#ty_backend>) -> Vec<&#lt_arena <#ty_backend as #G::Backend>::Signature> {
+            fn get_inherited_signatures(renderer: &#lt_arena #G::Api<#ty_backend>) -> #G::error::Result<Vec<&#lt_arena <#ty_backend as #G::Backend>::Signature>> {
                 use autograph_api::pipeline::Signature;
                 let mut sig = Vec::new();
-                #(sig.push(renderer.get_cached_signature::<#i_inherited_ty>().inner());)*
-                sig
+                #(sig.push(renderer.get_cached_signature::<#i_inherited_ty>()?.inner());)*
+                Ok(sig)
             }
 
             fn into_block(
                     self,
                     signature: #G::pipeline::TypedSignature<#lt_arena, #ty_backend, Self::IntoInterface>,
                     arena: &#lt_arena #G::Arena <#ty_backend>) ->
-                    #G::pipeline::ArgumentBlock<#lt_arena,  #ty_backend, #G::pipeline::TypedSignature<#lt_arena, #ty_backend, Self::IntoInterface>>
+                    #G::error::Result<#G::pipeline::ArgumentBlock<#lt_arena,  #ty_backend, #G::pipeline::TypedSignature<#lt_arena, #ty_backend, Self::IntoInterface>>>
             {
                 use #G::pipeline::Arguments;
                 use #G::descriptor::ResourceInterface;
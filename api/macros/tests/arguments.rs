@@ -1,9 +1,10 @@
 use autograph_api::{
     buffer::{Buffer, BufferTypeless},
     image::{ImageView, RenderTargetView, TextureImageView},
+    mock::MockBackend,
     pipeline::{Arguments, Scissor, TypedArgumentBlock, Viewport},
     vertex::{IndexFormat, VertexData},
-    Backend, DummyBackend,
+    Backend,
 };
 
 #[derive(VertexData, Copy, Clone)]
@@ -245,19 +246,19 @@ const SIGNATURE: &'static autograph_api::pipeline::SignatureDescription<'static>
 #[test]
 fn arguments_check() {
     assert_eq!(
-        <TestInheritedArgs0<DummyBackend> as Arguments<DummyBackend>>::SIGNATURE,
+        <TestInheritedArgs0<MockBackend> as Arguments<MockBackend>>::SIGNATURE,
         ARGS_0_SIGNATURE
     );
     assert_eq!(
-        <TestInheritedArgs1<DummyBackend> as Arguments<DummyBackend>>::SIGNATURE,
+        <TestInheritedArgs1<MockBackend> as Arguments<MockBackend>>::SIGNATURE,
         ARGS_1_SIGNATURE
     );
     assert_eq!(
-        <TestInheritedArgs2<DummyBackend> as Arguments<DummyBackend>>::SIGNATURE,
+        <TestInheritedArgs2<MockBackend> as Arguments<MockBackend>>::SIGNATURE,
         ARGS_2_SIGNATURE
     );
     assert_eq!(
-        <TestArguments<DummyBackend> as Arguments<DummyBackend>>::SIGNATURE,
+        <TestArguments<MockBackend> as Arguments<MockBackend>>::SIGNATURE,
         SIGNATURE
     );
 }
@@ -0,0 +1,453 @@
+//! Hazard tracking and barrier insertion.
+//!
+//! Once the command stream is sorted, [insert_pipeline_barriers] walks it and determines,
+//! for each command, the resources that it reads and writes. When a command depends on the
+//! result of a previous command, an explicit `PipelineBarrier` command is inserted before it.
+//! Backends are free to ignore barriers that are not necessary for their API
+//! (e.g. OpenGL only needs barriers after incoherent shader writes).
+//!
+//! Resources are tracked by object identity: aliased resources are considered distinct
+//! even if they share the same memory, and buffer accesses are tracked for the whole buffer.
+use crate::{
+    command::{Command, CommandInner},
+    descriptor::Descriptor,
+    image::{DepthStencilView, RenderTargetView},
+    vertex::{IndexBufferView, VertexBufferView},
+    Backend, Queue,
+};
+use bitflags::bitflags;
+use std::{collections::HashMap, mem};
+
+bitflags! {
+    /// Types of memory accesses performed by commands.
+    #[derive(Default)]
+    pub struct AccessFlags: u32 {
+        const INDIRECT_COMMAND_READ = (1 << 0);
+        const INDEX_READ = (1 << 1);
+        const VERTEX_ATTRIBUTE_READ = (1 << 2);
+        const UNIFORM_READ = (1 << 3);
+        const SAMPLED_READ = (1 << 4);
+        const STORAGE_IMAGE_READ = (1 << 5);
+        const STORAGE_IMAGE_WRITE = (1 << 6);
+        const STORAGE_BUFFER_READ = (1 << 7);
+        const STORAGE_BUFFER_WRITE = (1 << 8);
+        const COLOR_ATTACHMENT_READ = (1 << 9);
+        const COLOR_ATTACHMENT_WRITE = (1 << 10);
+        const DEPTH_STENCIL_ATTACHMENT_READ = (1 << 11);
+        const DEPTH_STENCIL_ATTACHMENT_WRITE = (1 << 12);
+        const TRANSFER_READ = (1 << 13);
+        const TRANSFER_WRITE = (1 << 14);
+        const HOST_READ = (1 << 15);
+        const HOST_WRITE = (1 << 16);
+
+        const ALL_SHADER_WRITES = Self::STORAGE_IMAGE_WRITE.bits | Self::STORAGE_BUFFER_WRITE.bits;
+        const ALL_WRITES = Self::STORAGE_IMAGE_WRITE.bits | Self::STORAGE_BUFFER_WRITE.bits
+            | Self::COLOR_ATTACHMENT_WRITE.bits | Self::DEPTH_STENCIL_ATTACHMENT_WRITE.bits
+            | Self::TRANSFER_WRITE.bits | Self::HOST_WRITE.bits;
+        const ALL_ATTACHMENTS = Self::COLOR_ATTACHMENT_READ.bits
+            | Self::COLOR_ATTACHMENT_WRITE.bits | Self::DEPTH_STENCIL_ATTACHMENT_READ.bits
+            | Self::DEPTH_STENCIL_ATTACHMENT_WRITE.bits;
+    }
+}
+
+impl AccessFlags {
+    /// Returns true if the access includes a write.
+    pub fn is_write(&self) -> bool {
+        self.intersects(AccessFlags::ALL_WRITES)
+    }
+}
+
+/// A resource referenced by a command.
+#[derive(derivative::Derivative)]
+#[derivative(Copy(bound = ""), Clone(bound = ""), Debug(bound = ""))]
+pub enum ResourceRef<'a, B: Backend> {
+    Image(&'a B::Image),
+    Buffer(&'a B::Buffer),
+}
+
+impl<'a, B: Backend> ResourceRef<'a, B> {
+    /// Key identifying the resource.
+    pub(crate) fn key(&self) -> usize {
+        match *self {
+            ResourceRef::Image(image) => image as *const _ as usize,
+            ResourceRef::Buffer(buffer) => buffer as *const _ as usize,
+        }
+    }
+}
+
+/// A resource and the way it is accessed by a command.
+#[derive(derivative::Derivative)]
+#[derivative(Copy(bound = ""), Clone(bound = ""), Debug(bound = ""))]
+pub struct ResourceAccess<'a, B: Backend> {
+    pub resource: ResourceRef<'a, B>,
+    pub access: AccessFlags,
+}
+
+impl<'a, B: Backend> ResourceAccess<'a, B> {
+    pub fn image(image: &'a B::Image, access: AccessFlags) -> ResourceAccess<'a, B> {
+        ResourceAccess {
+            resource: ResourceRef::Image(image),
+            access,
+        }
+    }
+
+    pub fn buffer(buffer: &'a B::Buffer, access: AccessFlags) -> ResourceAccess<'a, B> {
+        ResourceAccess {
+            resource: ResourceRef::Buffer(buffer),
+            access,
+        }
+    }
+}
+
+/// Returns the resource access corresponding to a descriptor.
+///
+/// Storage images and buffers are conservatively considered to be both read and written.
+pub(crate) fn descriptor_access<'a, B: Backend>(
+    descriptor: &Descriptor<'a, B>,
+) -> Option<ResourceAccess<'a, B>> {
+    match *descriptor {
+        Descriptor::Texture { image, .. } | Descriptor::TextureSampler { image, .. } => {
+            Some(ResourceAccess::image(image, AccessFlags::SAMPLED_READ))
+        }
+        Descriptor::RwImage { image, .. } => Some(ResourceAccess::image(
+            image,
+            AccessFlags::STORAGE_IMAGE_READ | AccessFlags::STORAGE_IMAGE_WRITE,
+        )),
+        Descriptor::ConstantBuffer { buffer, .. } => {
+            Some(ResourceAccess::buffer(buffer, AccessFlags::UNIFORM_READ))
+        }
+        Descriptor::RwBuffer { buffer, .. } => Some(ResourceAccess::buffer(
+            buffer,
+            AccessFlags::STORAGE_BUFFER_READ | AccessFlags::STORAGE_BUFFER_WRITE,
+        )),
+        Descriptor::TexelBuffer { buffer, .. } => {
+            Some(ResourceAccess::buffer(buffer, AccessFlags::SAMPLED_READ))
+        }
+        Descriptor::RwTexelBuffer { buffer, .. } => Some(ResourceAccess::buffer(
+            buffer,
+            AccessFlags::STORAGE_IMAGE_READ | AccessFlags::STORAGE_IMAGE_WRITE,
+        )),
+        // host data is uploaded by the backend
+        Descriptor::HostConstantBuffer { .. } | Descriptor::Sampler { .. } | Descriptor::Empty => {
+            None
+        }
+    }
+}
+
+pub(crate) fn vertex_buffer_access<'a, B: Backend>(
+    vb: &VertexBufferView<'a, B>,
+) -> ResourceAccess<'a, B> {
+    ResourceAccess::buffer(vb.buffer, AccessFlags::VERTEX_ATTRIBUTE_READ)
+}
+
+pub(crate) fn index_buffer_access<'a, B: Backend>(
+    ib: &IndexBufferView<'a, B>,
+) -> ResourceAccess<'a, B> {
+    ResourceAccess::buffer(ib.buffer, AccessFlags::INDEX_READ)
+}
+
+pub(crate) fn render_target_access<'a, B: Backend>(
+    rt: &RenderTargetView<'a, B>,
+) -> ResourceAccess<'a, B> {
+    ResourceAccess::image(
+        rt.image,
+        AccessFlags::COLOR_ATTACHMENT_READ | AccessFlags::COLOR_ATTACHMENT_WRITE,
+    )
+}
+
+pub(crate) fn depth_stencil_target_access<'a, B: Backend>(
+    ds: &DepthStencilView<'a, B>,
+) -> ResourceAccess<'a, B> {
+    ResourceAccess::image(
+        ds.image,
+        AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ | AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+    )
+}
+
+//--------------------------------------------------------------------------------------------------
+
+/// Calls `f` for each resource directly accessed by a command (not including the resources
+/// accessed through argument blocks).
+pub(crate) fn command_accesses<'a, B: Backend>(
+    cmd: &CommandInner<'a, B>,
+    mut f: impl FnMut(ResourceAccess<'a, B>),
+) {
+    match *cmd {
+        CommandInner::CopyBuffer { src, dst, .. } => {
+            f(ResourceAccess::buffer(src, AccessFlags::TRANSFER_READ));
+            f(ResourceAccess::buffer(dst, AccessFlags::TRANSFER_WRITE));
+        }
+        CommandInner::CopyBufferToImage { src, dst, .. } => {
+            f(ResourceAccess::buffer(src, AccessFlags::TRANSFER_READ));
+            f(ResourceAccess::image(dst, AccessFlags::TRANSFER_WRITE));
+        }
+        CommandInner::CopyImageToBuffer { src, dst, .. } => {
+            f(ResourceAccess::image(src, AccessFlags::TRANSFER_READ));
+            f(ResourceAccess::buffer(dst, AccessFlags::TRANSFER_WRITE));
+        }
+        CommandInner::CopyImage { src, dst, .. } | CommandInner::BlitImage { src, dst, .. } => {
+            f(ResourceAccess::image(src, AccessFlags::TRANSFER_READ));
+            f(ResourceAccess::image(dst, AccessFlags::TRANSFER_WRITE));
+        }
+        CommandInner::FillBuffer { dst, .. } => {
+            f(ResourceAccess::buffer(dst, AccessFlags::TRANSFER_WRITE));
+        }
+        CommandInner::ReadbackImage { src, .. } => {
+            f(ResourceAccess::image(src, AccessFlags::TRANSFER_READ));
+        }
+        CommandInner::ReadbackBuffer { src, .. } => {
+            f(ResourceAccess::buffer(src, AccessFlags::TRANSFER_READ));
+        }
+        CommandInner::ClearImageFloat { image, .. }
+        | CommandInner::ClearDepthStencilImage { image, .. } => {
+            f(ResourceAccess::image(image, AccessFlags::TRANSFER_WRITE));
+        }
+        CommandInner::Present { image, .. } => {
+            f(ResourceAccess::image(image, AccessFlags::TRANSFER_READ));
+        }
+        CommandInner::DrawIndirect {
+            indirect_buffer,
+            count_buffer,
+            ..
+        }
+        | CommandInner::DrawIndexedIndirect {
+            indirect_buffer,
+            count_buffer,
+            ..
+        } => {
+            f(ResourceAccess::buffer(
+                indirect_buffer,
+                AccessFlags::INDIRECT_COMMAND_READ,
+            ));
+            if let Some(count_buffer) = count_buffer {
+                f(ResourceAccess::buffer(
+                    count_buffer,
+                    AccessFlags::INDIRECT_COMMAND_READ,
+                ));
+            }
+        }
+        CommandInner::DispatchIndirect {
+            indirect_buffer, ..
+        } => {
+            f(ResourceAccess::buffer(
+                indirect_buffer,
+                AccessFlags::INDIRECT_COMMAND_READ,
+            ));
+        }
+        CommandInner::PipelineBarrier { .. }
+        | CommandInner::BeginProfileScope { .. }
+        | CommandInner::EndProfileScope
+        | CommandInner::PushDebugGroup { .. }
+        | CommandInner::PopDebugGroup
+        | CommandInner::InsertDebugMarker { .. }
+        | CommandInner::BeginQuery { .. }
+        | CommandInner::EndQuery { .. }
+        | CommandInner::ReadbackQueries { .. }
+        | CommandInner::BeginConditionalRendering { .. }
+        | CommandInner::EndConditionalRendering
+        | CommandInner::DrawHeader { .. }
+        | CommandInner::DispatchHeader { .. }
+        | CommandInner::SetPipelineArguments { .. }
+        | CommandInner::Draw { .. }
+        | CommandInner::DrawIndexed { .. }
+        | CommandInner::Dispatch { .. } => {}
+    }
+}
+
+/// State of a resource in the command stream.
+#[derive(Copy, Clone, Debug, Default)]
+struct ResourceState {
+    /// Accesses of the last write that are not yet visible to subsequent commands.
+    last_write: AccessFlags,
+    /// Kinds of accesses to which the last write has been made visible.
+    visible: AccessFlags,
+    /// Accesses performed since the last write.
+    reads: AccessFlags,
+    /// Kinds of accesses that an explicit barrier has ordered after all previous accesses.
+    ordered: AccessFlags,
+}
+
+/// Accumulates the dependencies of a command.
+#[derive(Default)]
+struct HazardTracker {
+    states: HashMap<usize, ResourceState>,
+    /// Accesses of the current command, merged by resource.
+    current: Vec<(usize, AccessFlags)>,
+    src_access_mask: AccessFlags,
+    dst_access_mask: AccessFlags,
+}
+
+impl HazardTracker {
+    /// Adds a resource access to the current command.
+    fn add<B: Backend>(&mut self, a: ResourceAccess<B>) {
+        let key = a.resource.key();
+        if let Some(c) = self.current.iter_mut().find(|c| c.0 == key) {
+            c.1 |= a.access;
+        } else {
+            self.current.push((key, a.access));
+        }
+    }
+
+    /// Returns the barrier needed before the current command, if any.
+    fn finish_command<'a, B: Backend>(
+        &mut self,
+        sortkey: u64,
+        queue: Queue,
+    ) -> Option<Command<'a, B>> {
+        let mut current = mem::replace(&mut self.current, Vec::new());
+        for &(key, access) in current.iter() {
+            self.access(key, access);
+        }
+        current.clear();
+        self.current = current;
+        self.take_barrier(sortkey, queue)
+    }
+
+    fn access(&mut self, key: usize, access: AccessFlags) {
+        let state = self.states.entry(key).or_default();
+
+        if AccessFlags::ALL_ATTACHMENTS.contains(access | state.last_write | state.reads) {
+            // successive attachment accesses are performed in rasterization order
+            if access.is_write() {
+                // previous barriers don't apply to this write
+                state.visible = AccessFlags::empty();
+            }
+            state.last_write |= access & AccessFlags::ALL_WRITES;
+            state.reads |= access - AccessFlags::ALL_WRITES;
+            state.ordered = AccessFlags::empty();
+            return;
+        }
+
+        if access.is_write() {
+            // write-after-write and write-after-read
+            if (!state.last_write.is_empty() || !state.reads.is_empty())
+                && !state.ordered.contains(access)
+            {
+                self.src_access_mask |= state.last_write | state.reads;
+                self.dst_access_mask |= access;
+            }
+            *state = ResourceState {
+                last_write: access & AccessFlags::ALL_WRITES,
+                visible: AccessFlags::empty(),
+                reads: AccessFlags::empty(),
+                ordered: AccessFlags::empty(),
+            };
+        } else {
+            // read-after-write
+            if !state.last_write.is_empty() && !state.visible.contains(access) {
+                self.src_access_mask |= state.last_write;
+                self.dst_access_mask |= access;
+                state.visible |= access;
+            }
+            state.reads |= access;
+            // this read is not ordered before subsequent writes
+            state.ordered = AccessFlags::empty();
+        }
+    }
+
+    /// Accounts for an explicit barrier in the command stream.
+    ///
+    /// Barriers apply to all resources: the last write of a resource is made visible if it is
+    /// covered by `src_access_mask`, and the accesses of a resource are ordered before subsequent
+    /// writes if they are all covered by `src_access_mask`.
+    fn barrier(&mut self, src_access_mask: AccessFlags, dst_access_mask: AccessFlags) {
+        for state in self.states.values_mut() {
+            if src_access_mask.contains(state.last_write) {
+                state.visible |= dst_access_mask;
+            }
+            // reads after a barrier are already ordered after the last write
+            let pending = if state.visible.is_empty() {
+                state.last_write | state.reads
+            } else {
+                state.reads
+            };
+            if src_access_mask.contains(pending) {
+                state.ordered |= dst_access_mask;
+            }
+        }
+    }
+
+    fn take_barrier<'a, B: Backend>(
+        &mut self,
+        sortkey: u64,
+        queue: Queue,
+    ) -> Option<Command<'a, B>> {
+        let barrier = if !self.src_access_mask.is_empty() {
+            Some(Command {
+                sortkey,
+                queue,
+                cmd: CommandInner::PipelineBarrier {
+                    src_access_mask: self.src_access_mask,
+                    dst_access_mask: self.dst_access_mask,
+                },
+            })
+        } else {
+            None
+        };
+        self.src_access_mask = AccessFlags::empty();
+        self.dst_access_mask = AccessFlags::empty();
+        barrier
+    }
+}
+
+/// Inserts pipeline barriers in a sorted command stream.
+///
+/// Barriers are inserted before the lead-in command of the draw or dispatch operation that
+/// needs them, or before the command itself for other commands (copies, clears...), and are
+/// executed on the same queue. Dependencies between queues are handled by [crate::schedule].
+pub fn insert_pipeline_barriers<'a, B: Backend>(
+    commands: impl IntoIterator<Item = Command<'a, B>>,
+) -> Vec<Command<'a, B>> {
+    let commands = commands.into_iter();
+    let mut tracker = HazardTracker::default();
+    let mut out = Vec::with_capacity(commands.size_hint().0);
+    // lead-in and state change commands waiting for their lead-out command
+    let mut pending: Vec<Command<'a, B>> = Vec::new();
+    let mut arguments: &[ResourceAccess<'a, B>] = &[];
+
+    for cmd in commands {
+        match cmd.cmd {
+            CommandInner::DrawHeader { .. } | CommandInner::DispatchHeader { .. } => {
+                out.extend(pending.drain(..));
+                arguments = &[];
+                pending.push(cmd);
+            }
+            CommandInner::SetPipelineArguments { accesses, .. } => {
+                arguments = accesses;
+                pending.push(cmd);
+            }
+            CommandInner::Draw { .. }
+            | CommandInner::DrawIndexed { .. }
+            | CommandInner::DrawIndirect { .. }
+            | CommandInner::DrawIndexedIndirect { .. }
+            | CommandInner::Dispatch { .. }
+            | CommandInner::DispatchIndirect { .. } => {
+                for &a in arguments {
+                    tracker.add(a);
+                }
+                command_accesses(&cmd.cmd, |a| tracker.add(a));
+                out.extend(tracker.finish_command(cmd.sortkey, cmd.queue));
+                out.extend(pending.drain(..));
+                out.push(cmd);
+            }
+            CommandInner::PipelineBarrier {
+                src_access_mask,
+                dst_access_mask,
+            } => {
+                // explicit barrier: keep it, and don't insert the same barrier after it
+                out.extend(pending.drain(..));
+                tracker.barrier(src_access_mask, dst_access_mask);
+                out.push(cmd);
+            }
+            _ => {
+                out.extend(pending.drain(..));
+                command_accesses(&cmd.cmd, |a| tracker.add(a));
+                out.extend(tracker.finish_command(cmd.sortkey, cmd.queue));
+                out.push(cmd);
+            }
+        }
+    }
+
+    out.extend(pending.drain(..));
+    out
+}
@@ -0,0 +1,991 @@
+//! Binary file format for frame captures.
+//!
+//! All values are little-endian. `usize` values are stored as `u64`, sequences are prefixed
+//! by their length, and enums by the index of the variant (`u8`).
+use super::*;
+use crate::{
+    descriptor::ResourceShape,
+    image::{SamplerAddressMode, SamplerMipmapMode},
+    pipeline::{
+        BlendFactor, BlendOp, ColorComponentFlags, CompareOp, CullModeFlags, DepthBias,
+        DepthBoundTest, FrontFace, PolygonMode, PrimitiveTopology, SampleShading, ScissorRect,
+        StencilOp, StencilOpState, StencilTest,
+    },
+};
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    mem,
+    path::Path,
+};
+
+const MAGIC: &[u8; 8] = b"AGCAPTUR";
+/// Incremented on every change to the encoding of objects or commands:
+/// 2 added readbacks, 3 host references, 4 command queues, 5 profile scopes,
+/// 6 queries and conditional rendering, 7 debug groups and markers.
+const VERSION: u32 = 7;
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+impl FrameCapture {
+    /// Writes the capture to the specified stream.
+    pub fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(MAGIC)?;
+        VERSION.encode(w)?;
+        self.objects.encode(w)?;
+        self.commands.encode(w)
+    }
+
+    /// Reads a capture from the specified stream.
+    pub fn read_from<R: Read>(r: &mut R) -> io::Result<FrameCapture> {
+        let mut magic = [0u8; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not a frame capture file"));
+        }
+        if u32::decode(r)? != VERSION {
+            return Err(invalid_data("unsupported frame capture version"));
+        }
+        let objects: Vec<CapturedObject> = Decode::decode(r)?;
+        let commands: Vec<CapturedCommand> = Decode::decode(r)?;
+
+        // check reference indices; replay checks that they designate objects of the right kind
+        let check = |n: usize, r: &mut ObjectRef, err: &mut bool| {
+            if r.0 as usize >= n {
+                *err = true;
+            }
+        };
+        let mut err = false;
+        for (i, o) in objects.iter().enumerate() {
+            o.clone().for_each_ref(|r| check(i, r, &mut err));
+        }
+        for c in commands.iter() {
+            c.cmd
+                .clone()
+                .for_each_ref(|r| check(objects.len(), r, &mut err));
+        }
+        if err {
+            return Err(invalid_data("invalid object reference"));
+        }
+
+        Ok(FrameCapture { objects, commands })
+    }
+
+    /// Writes the capture to a file.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        self.write_to(&mut w)?;
+        w.flush()
+    }
+
+    /// Reads a capture from a file.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<FrameCapture> {
+        let mut r = BufReader::new(File::open(path)?);
+        FrameCapture::read_from(&mut r)
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+
+pub(super) trait Encode {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()>;
+}
+
+pub(super) trait Decode: Sized {
+    fn decode<R: Read>(r: &mut R) -> io::Result<Self>;
+}
+
+macro_rules! impl_codec_int {
+    ($($t:ty),*) => {
+        $(impl Encode for $t {
+            fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+                w.write_all(&self.to_le_bytes())
+            }
+        }
+        impl Decode for $t {
+            fn decode<R: Read>(r: &mut R) -> io::Result<Self> {
+                let mut bytes = [0u8; mem::size_of::<$t>()];
+                r.read_exact(&mut bytes)?;
+                Ok(<$t>::from_le_bytes(bytes))
+            }
+        })*
+    };
+}
+
+impl_codec_int!(u8, u16, u32, u64, i32);
+
+impl Encode for usize {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        (*self as u64).encode(w)
+    }
+}
+
+impl Decode for usize {
+    fn decode<R: Read>(r: &mut R) -> io::Result<Self> {
+        let v = u64::decode(r)?;
+        if v as usize as u64 != v {
+            return Err(invalid_data("size too large"));
+        }
+        Ok(v as usize)
+    }
+}
+
+impl Encode for bool {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        (*self as u8).encode(w)
+    }
+}
+
+impl Decode for bool {
+    fn decode<R: Read>(r: &mut R) -> io::Result<Self> {
+        match u8::decode(r)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(invalid_data("invalid boolean")),
+        }
+    }
+}
+
+impl Encode for f32 {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.to_bits().encode(w)
+    }
+}
+
+impl Decode for f32 {
+    fn decode<R: Read>(r: &mut R) -> io::Result<Self> {
+        Ok(f32::from_bits(u32::decode(r)?))
+    }
+}
+
+impl Encode for NotNan<f32> {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.into_inner().encode(w)
+    }
+}
+
+impl Decode for NotNan<f32> {
+    fn decode<R: Read>(r: &mut R) -> io::Result<Self> {
+        NotNan::new(f32::decode(r)?).map_err(|_| invalid_data("unexpected NaN"))
+    }
+}
+
+impl Encode for String {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        encode_bytes(w, self.as_bytes())
+    }
+}
+
+impl Decode for String {
+    fn decode<R: Read>(r: &mut R) -> io::Result<Self> {
+        String::from_utf8(decode_bytes(r)?).map_err(|_| invalid_data("invalid UTF-8 string"))
+    }
+}
+
+impl<T: Encode> Encode for Option<T> {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        match self {
+            None => 0u8.encode(w),
+            Some(v) => {
+                1u8.encode(w)?;
+                v.encode(w)
+            }
+        }
+    }
+}
+
+impl<T: Decode> Decode for Option<T> {
+    fn decode<R: Read>(r: &mut R) -> io::Result<Self> {
+        match u8::decode(r)? {
+            0 => Ok(None),
+            1 => Ok(Some(T::decode(r)?)),
+            _ => Err(invalid_data("invalid option")),
+        }
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.len().encode(w)?;
+        for v in self.iter() {
+            v.encode(w)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Decode> Decode for Vec<T> {
+    fn decode<R: Read>(r: &mut R) -> io::Result<Self> {
+        let len = usize::decode(r)?;
+        // don't trust the length for the allocation
+        let mut v = Vec::with_capacity(len.min(4096));
+        for _ in 0..len {
+            v.push(T::decode(r)?);
+        }
+        Ok(v)
+    }
+}
+
+impl<A: Encode, B: Encode> Encode for (A, B) {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.0.encode(w)?;
+        self.1.encode(w)
+    }
+}
+
+impl<A: Decode, B: Decode> Decode for (A, B) {
+    fn decode<R: Read>(r: &mut R) -> io::Result<Self> {
+        Ok((A::decode(r)?, B::decode(r)?))
+    }
+}
+
+impl<A: Encode, B: Encode, C: Encode> Encode for (A, B, C) {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.0.encode(w)?;
+        self.1.encode(w)?;
+        self.2.encode(w)
+    }
+}
+
+impl<A: Decode, B: Decode, C: Decode> Decode for (A, B, C) {
+    fn decode<R: Read>(r: &mut R) -> io::Result<Self> {
+        Ok((A::decode(r)?, B::decode(r)?, C::decode(r)?))
+    }
+}
+
+macro_rules! impl_codec_array {
+    ($($n:expr => [$($i:expr),*]),*) => {
+        $(impl<T: Encode> Encode for [T; $n] {
+            fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+                for v in self.iter() {
+                    v.encode(w)?;
+                }
+                Ok(())
+            }
+        }
+        impl<T: Decode> Decode for [T; $n] {
+            fn decode<R: Read>(r: &mut R) -> io::Result<Self> {
+                Ok([$({ let _ = $i; T::decode(r)? }),*])
+            }
+        })*
+    };
+}
+
+impl_codec_array!(2 => [0, 1], 4 => [0, 1, 2, 3]);
+
+fn encode_bytes<W: Write>(w: &mut W, bytes: &[u8]) -> io::Result<()> {
+    bytes.len().encode(w)?;
+    w.write_all(bytes)
+}
+
+fn decode_bytes<R: Read>(r: &mut R) -> io::Result<Vec<u8>> {
+    let len = usize::decode(r)?;
+    let mut bytes = Vec::new();
+    r.take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(bytes)
+}
+
+/// Byte buffers, encoded in bulk.
+struct Bytes<'a>(&'a [u8]);
+
+impl<'a> Encode for Bytes<'a> {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        encode_bytes(w, self.0)
+    }
+}
+
+impl Encode for Format {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        (*self as u16).encode(w)
+    }
+}
+
+impl Decode for Format {
+    fn decode<R: Read>(r: &mut R) -> io::Result<Self> {
+        let v = u16::decode(r)?;
+        // formats are numbered contiguously from UNDEFINED
+        if v > Format::ASTC_12x12_SRGB_BLOCK as u16 {
+            return Err(invalid_data("invalid format"));
+        }
+        Ok(unsafe { mem::transmute::<u16, Format>(v) })
+    }
+}
+
+macro_rules! impl_codec_bitflags {
+    ($($t:ty),*) => {
+        $(impl Encode for $t {
+            fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+                self.bits().encode(w)
+            }
+        }
+        impl Decode for $t {
+            fn decode<R: Read>(r: &mut R) -> io::Result<Self> {
+                <$t>::from_bits(Decode::decode(r)?)
+                    .ok_or_else(|| invalid_data(concat!("invalid ", stringify!($t))))
+            }
+        })*
+    };
+}
+
+impl_codec_bitflags!(
+    AccessFlags,
+    ImageUsageFlags,
+    ShaderStageFlags,
+    CullModeFlags,
+    ColorComponentFlags,
+    PipelineStatisticsFlags
+);
+
+macro_rules! impl_codec_struct {
+    ($t:ident { $($f:ident),* }) => {
+        impl Encode for $t {
+            fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+                $(self.$f.encode(w)?;)*
+                Ok(())
+            }
+        }
+        impl Decode for $t {
+            fn decode<R: Read>(r: &mut R) -> io::Result<Self> {
+                Ok($t {
+                    $($f: Decode::decode(r)?,)*
+                })
+            }
+        }
+    };
+}
+
+/// Enums are encoded as the index of the variant followed by the fields.
+macro_rules! impl_codec_enum {
+    ($t:ident { $($v:ident $({ $($f:ident),* })? $(( $($tf:ident),* ))?),* }) => {
+        impl Encode for $t {
+            #[allow(unused_assignments)]
+            fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+                let mut tag = 0u8;
+                $(
+                    if let $t::$v $({ $($f),* })? $(( $($tf),* ))? = self {
+                        tag.encode(w)?;
+                        $($($f.encode(w)?;)*)?
+                        $($($tf.encode(w)?;)*)?
+                        return Ok(());
+                    }
+                    tag += 1;
+                )*
+                unreachable!()
+            }
+        }
+        impl Decode for $t {
+            #[allow(unused_assignments)]
+            fn decode<R: Read>(r: &mut R) -> io::Result<Self> {
+                let tag = u8::decode(r)?;
+                let mut i = 0u8;
+                $(
+                    if tag == i {
+                        return Ok($t::$v
+                            $({ $($f: Decode::decode(r)?),* })?
+                            $(( $({ let $tf = Decode::decode(r)?; $tf }),* ))?
+                        );
+                    }
+                    i += 1;
+                )*
+                Err(invalid_data(concat!("invalid ", stringify!($t))))
+            }
+        }
+    };
+}
+
+impl Encode for ObjectRef {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.0.encode(w)
+    }
+}
+
+impl Decode for ObjectRef {
+    fn decode<R: Read>(r: &mut R) -> io::Result<Self> {
+        Ok(ObjectRef(Decode::decode(r)?))
+    }
+}
+
+impl_codec_struct!(AliasScope { value, mask });
+impl_codec_struct!(SubresourceRange {
+    base_mip_level,
+    level_count,
+    base_array_layer,
+    layer_count
+});
+impl_codec_struct!(SamplerDescription {
+    addr_u,
+    addr_v,
+    addr_w,
+    min_filter,
+    mag_filter,
+    mipmap_mode
+});
+impl_codec_struct!(ImageRegion {
+    mip_level,
+    base_array_layer,
+    layer_count,
+    offset,
+    extent
+});
+impl_codec_struct!(Viewport {
+    x,
+    y,
+    width,
+    height,
+    min_depth,
+    max_depth
+});
+impl_codec_struct!(ScissorRect {
+    x,
+    y,
+    width,
+    height
+});
+impl_codec_struct!(RasterisationState {
+    depth_clamp_enable,
+    rasterizer_discard_enable,
+    polygon_mode,
+    cull_mode,
+    depth_bias,
+    front_face,
+    line_width
+});
+impl_codec_struct!(MultisampleState {
+    rasterization_samples,
+    sample_shading,
+    alpha_to_coverage_enable,
+    alpha_to_one_enable
+});
+impl_codec_struct!(StencilOpState {
+    fail_op,
+    pass_op,
+    depth_fail_op,
+    compare_op,
+    compare_mask,
+    write_mask,
+    reference
+});
+impl_codec_struct!(DepthStencilState {
+    depth_test_enable,
+    depth_write_enable,
+    depth_compare_op,
+    depth_bounds_test,
+    stencil_test
+});
+impl_codec_struct!(InputAssemblyState {
+    topology,
+    primitive_restart_enable
+});
+impl_codec_struct!(CapturedSignatureDescription {
+    descriptors,
+    vertex_inputs,
+    num_fragment_outputs,
+    depth_stencil_fragment_output,
+    index_format,
+    num_viewports,
+    num_scissors,
+    is_root_fragment_output_signature,
+    is_root_vertex_input_signature
+});
+impl_codec_struct!(CapturedResourceBinding {
+    set,
+    index,
+    ty,
+    stage_flags,
+    count,
+    data_format
+});
+impl_codec_struct!(CapturedVertexInputBinding {
+    elements,
+    stride,
+    rate,
+    base_location
+});
+impl_codec_struct!(CapturedVertexLayoutElement {
+    semantic,
+    format,
+    offset
+});
+impl_codec_struct!(CapturedGraphicsPipeline {
+    signature,
+    vertex_shader,
+    geometry_shader,
+    fragment_shader,
+    tess_eval_shader,
+    tess_control_shader,
+    viewports,
+    scissors,
+    rasterization_state,
+    multisample_state,
+    depth_stencil_state,
+    input_assembly_state,
+    logic_op,
+    color_blend_attachments,
+    blend_constants
+});
+impl_codec_struct!(CapturedArgumentBlock {
+    signature,
+    inherited,
+    descriptors,
+    vertex_buffers,
+    index_buffer,
+    render_targets,
+    depth_stencil_target,
+    viewports,
+    scissors
+});
+impl_codec_struct!(CapturedVertexBuffer {
+    buffer,
+    stride,
+    offset
+});
+impl_codec_struct!(CapturedIndexBuffer {
+    buffer,
+    format,
+    offset
+});
+impl_codec_struct!(CapturedImageView { image, subresource });
+impl_codec_struct!(CapturedCommand {
+    sortkey,
+    queue,
+    cmd
+});
+
+impl_codec_enum!(Dimensions {
+    Dim1d { width, array_layers },
+    Dim2d { width, height, array_layers },
+    Dim3d { width, height, depth },
+    Cubemap { size, array_layers }
+});
+impl_codec_enum!(MipmapsOption {
+    NoMipmap,
+    Allocate,
+    AllocateCount(n),
+    Generate,
+    GenerateCount(n)
+});
+impl_codec_enum!(SamplerAddressMode {
+    Clamp,
+    Mirror,
+    Wrap
+});
+impl_codec_enum!(Filter { Nearest, Linear });
+impl_codec_enum!(QueryType {
+    Occlusion,
+    ConservativeOcclusion,
+    PipelineStatistics(flags)
+});
+impl_codec_enum!(Queue {
+    Graphics,
+    Compute,
+    Transfer
+});
+impl_codec_enum!(SamplerMipmapMode { Nearest, Linear });
+impl_codec_enum!(Scissor {
+    Enabled(rect),
+    Disabled
+});
+impl_codec_enum!(ViewportsOwned {
+    Static(viewports),
+    Dynamic
+});
+impl_codec_enum!(ScissorsOwned {
+    Static(scissors),
+    Dynamic
+});
+impl_codec_enum!(PolygonMode { Line, Fill });
+impl_codec_enum!(FrontFace {
+    Clockwise,
+    CounterClockwise
+});
+impl_codec_enum!(DepthBias {
+    Disabled,
+    Enabled {
+        constant_factor,
+        clamp,
+        slope_factor
+    }
+});
+impl_codec_enum!(SampleShading {
+    Disabled,
+    Enabled { min_sample_shading }
+});
+impl_codec_enum!(CompareOp {
+    Never,
+    Less,
+    Equal,
+    LessOrEqual,
+    Greater,
+    NotEqual,
+    GreaterOrEqual,
+    Always
+});
+impl_codec_enum!(StencilOp {
+    Keep,
+    Zero,
+    Replace,
+    IncrementAndClamp,
+    DecrementAndClamp,
+    Invert,
+    IncrementAndWrap,
+    DecrementAndWrap
+});
+impl_codec_enum!(DepthBoundTest {
+    Disabled,
+    Enabled {
+        min_depth_bounds,
+        max_depth_bounds
+    }
+});
+impl_codec_enum!(StencilTest {
+    Disabled,
+    Enabled { front, back }
+});
+impl_codec_enum!(PrimitiveTopology {
+    PointList,
+    LineList,
+    TriangleList
+});
+impl_codec_enum!(LogicOp {
+    Clear,
+    And,
+    AndReverse,
+    Copy,
+    AndInverted,
+    NoOp,
+    Xor,
+    Or,
+    Nor,
+    Equivalent,
+    Invert,
+    OrReverse,
+    CopyInverted,
+    OrInverted,
+    Nand,
+    Set
+});
+impl_codec_enum!(BlendFactor {
+    Zero,
+    One,
+    SrcColor,
+    OneMinusSrcColor,
+    DstColor,
+    OneMinusDstColor,
+    SrcAlpha,
+    OneMinusSrcAlpha,
+    DstAlpha,
+    OneMinusDstAlpha,
+    ConstantColor,
+    OneMinusConstantColor,
+    ConstantAlpha,
+    OneMinusConstantAlpha,
+    SrcAlphaSaturate,
+    Src1Color,
+    OneMinusSrc1Color,
+    Src1Alpha,
+    OneMinusSrc1Alpha
+});
+impl_codec_enum!(BlendOp {
+    Add,
+    Subtract,
+    ReverseSubtract,
+    Min,
+    Max
+});
+impl_codec_enum!(ColorBlendAttachmentState {
+    Disabled,
+    Enabled {
+        src_color_blend_factor,
+        dst_color_blend_factor,
+        color_blend_op,
+        src_alpha_blend_factor,
+        dst_alpha_blend_factor,
+        alpha_blend_op,
+        color_write_mask
+    }
+});
+impl_codec_enum!(IndexFormat { U16, U32 });
+impl_codec_enum!(VertexInputRate { Vertex, Instance });
+impl_codec_enum!(ResourceShape {
+    R1d,
+    R1dArray,
+    R2d,
+    R2dArray,
+    R2dMultisample,
+    R2dMultisampleArray,
+    R3d,
+    RCube
+});
+impl_codec_enum!(ResourceBindingType {
+    Sampler,
+    Texture(shape),
+    TextureSampler(shape),
+    RwImage(shape),
+    ConstantBuffer,
+    RwBuffer,
+    TexelBuffer,
+    RwTexelBuffer
+});
+impl_codec_enum!(CapturedColorBlendAttachments {
+    All(attachment),
+    Separate(attachments)
+});
+impl_codec_enum!(CapturedDescriptor {
+    Sampler { desc },
+    Texture { image, subresource },
+    TextureSampler {
+        image,
+        subresource,
+        sampler
+    },
+    RwImage { image, subresource },
+    ConstantBuffer {
+        buffer,
+        offset,
+        size
+    },
+    RwBuffer {
+        buffer,
+        offset,
+        size
+    },
+    TexelBuffer {
+        buffer,
+        offset,
+        size
+    },
+    RwTexelBuffer {
+        buffer,
+        offset,
+        size
+    },
+    Empty,
+    HostConstantBuffer { reference }
+});
+impl_codec_enum!(CapturedCommandInner {
+    PipelineBarrier {
+        src_access_mask,
+        dst_access_mask
+    },
+    CopyBuffer {
+        src,
+        src_offset,
+        dst,
+        dst_offset,
+        size
+    },
+    CopyBufferToImage {
+        src,
+        src_offset,
+        dst,
+        dst_region
+    },
+    CopyImageToBuffer {
+        src,
+        src_region,
+        dst,
+        dst_offset
+    },
+    CopyImage {
+        src,
+        src_region,
+        dst,
+        dst_region
+    },
+    FillBuffer {
+        dst,
+        offset,
+        size,
+        value
+    },
+    BlitImage {
+        src,
+        src_mip_level,
+        src_offsets,
+        dst,
+        dst_mip_level,
+        dst_offsets,
+        filter
+    },
+    ClearImageFloat { image, color },
+    ClearDepthStencilImage {
+        image,
+        depth,
+        stencil
+    },
+    Present { image, swapchain },
+    DrawHeader { pipeline },
+    DispatchHeader { pipeline },
+    SetPipelineArguments { arguments },
+    Draw {
+        vertex_count,
+        instance_count,
+        first_vertex,
+        first_instance
+    },
+    DrawIndexed {
+        index_count,
+        instance_count,
+        first_index,
+        vertex_offset,
+        first_instance
+    },
+    DrawIndirect {
+        indirect_buffer,
+        offset,
+        draw_count,
+        count_buffer
+    },
+    DrawIndexedIndirect {
+        indirect_buffer,
+        offset,
+        draw_count,
+        count_buffer
+    },
+    Dispatch { x, y, z },
+    DispatchIndirect {
+        indirect_buffer,
+        offset
+    },
+    ReadbackImage { src, src_region },
+    ReadbackBuffer { src, offset, size },
+    BeginProfileScope { name },
+    EndProfileScope,
+    BeginQuery { pool, index },
+    EndQuery { pool, index },
+    ReadbackQueries { pool, first, count },
+    BeginConditionalRendering { pool, index },
+    EndConditionalRendering,
+    PushDebugGroup { name },
+    PopDebugGroup,
+    InsertDebugMarker { name }
+});
+
+// objects contain large byte buffers: encode them in bulk
+impl Encode for CapturedObject {
+    fn encode<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        match self {
+            CapturedObject::Swapchain => 0u8.encode(w),
+            CapturedObject::Image {
+                scope,
+                format,
+                dimensions,
+                mipmaps,
+                samples,
+                usage,
+                initial_data,
+            } => {
+                1u8.encode(w)?;
+                scope.encode(w)?;
+                format.encode(w)?;
+                dimensions.encode(w)?;
+                mipmaps.encode(w)?;
+                samples.encode(w)?;
+                usage.encode(w)?;
+                initial_data.as_ref().map(|d| Bytes(d)).encode(w)
+            }
+            CapturedObject::Buffer { size, initial_data } => {
+                2u8.encode(w)?;
+                size.encode(w)?;
+                initial_data.as_ref().map(|d| Bytes(d)).encode(w)
+            }
+            CapturedObject::ShaderModule {
+                stage,
+                bytecode,
+                num_fragment_outputs,
+            } => {
+                3u8.encode(w)?;
+                stage.encode(w)?;
+                Bytes(bytecode).encode(w)?;
+                num_fragment_outputs.encode(w)
+            }
+            CapturedObject::Signature {
+                inherited,
+                description,
+            } => {
+                4u8.encode(w)?;
+                inherited.encode(w)?;
+                description.encode(w)
+            }
+            CapturedObject::GraphicsPipeline(p) => {
+                5u8.encode(w)?;
+                p.encode(w)
+            }
+            CapturedObject::ComputePipeline { signature, shader } => {
+                6u8.encode(w)?;
+                signature.encode(w)?;
+                shader.encode(w)
+            }
+            CapturedObject::ArgumentBlock(b) => {
+                7u8.encode(w)?;
+                b.encode(w)
+            }
+            CapturedObject::HostReference { data } => {
+                8u8.encode(w)?;
+                Bytes(data).encode(w)
+            }
+            CapturedObject::QueryPool { ty, count } => {
+                9u8.encode(w)?;
+                ty.encode(w)?;
+                count.encode(w)
+            }
+        }
+    }
+}
+
+impl Decode for CapturedObject {
+    fn decode<R: Read>(r: &mut R) -> io::Result<Self> {
+        let decode_opt_bytes = |r: &mut R| -> io::Result<Option<Vec<u8>>> {
+            match u8::decode(r)? {
+                0 => Ok(None),
+                1 => Ok(Some(decode_bytes(r)?)),
+                _ => Err(invalid_data("invalid option")),
+            }
+        };
+
+        match u8::decode(r)? {
+            0 => Ok(CapturedObject::Swapchain),
+            1 => Ok(CapturedObject::Image {
+                scope: Decode::decode(r)?,
+                format: Decode::decode(r)?,
+                dimensions: Decode::decode(r)?,
+                mipmaps: Decode::decode(r)?,
+                samples: Decode::decode(r)?,
+                usage: Decode::decode(r)?,
+                initial_data: decode_opt_bytes(r)?,
+            }),
+            2 => Ok(CapturedObject::Buffer {
+                size: Decode::decode(r)?,
+                initial_data: decode_opt_bytes(r)?,
+            }),
+            3 => Ok(CapturedObject::ShaderModule {
+                stage: Decode::decode(r)?,
+                bytecode: decode_bytes(r)?,
+                num_fragment_outputs: Decode::decode(r)?,
+            }),
+            4 => Ok(CapturedObject::Signature {
+                inherited: Decode::decode(r)?,
+                description: Decode::decode(r)?,
+            }),
+            5 => Ok(CapturedObject::GraphicsPipeline(Decode::decode(r)?)),
+            6 => Ok(CapturedObject::ComputePipeline {
+                signature: Decode::decode(r)?,
+                shader: Decode::decode(r)?,
+            }),
+            7 => Ok(CapturedObject::ArgumentBlock(Decode::decode(r)?)),
+            8 => Ok(CapturedObject::HostReference {
+                data: decode_bytes(r)?,
+            }),
+            9 => Ok(CapturedObject::QueryPool {
+                ty: Decode::decode(r)?,
+                count: Decode::decode(r)?,
+            }),
+            _ => Err(invalid_data("invalid CapturedObject")),
+        }
+    }
+}
@@ -0,0 +1,1202 @@
+//! Frame capture and replay.
+//!
+//! When capture is enabled (see [Api::enable_capture](crate::Api::enable_capture)), the
+//! creation parameters of every object created through the API are recorded
+//! (image and buffer descriptions and initial data, shader bytecode, pipeline states,
+//! signatures, argument blocks and host data).
+//! After [Api::capture_next_frame](crate::Api::capture_next_frame), the next call to
+//! `submit_frame` collects the sorted command stream and all the objects that it references
+//! into a self-contained [FrameCapture], and writes it to a file.
+//!
+//! A capture can be loaded back and replayed against any backend with [replay].
+//!
+//! Limitations:
+//! * objects created before capture was enabled cannot be captured;
+//! * the contents of buffers and images written by the GPU (or updated after creation)
+//!   are not captured, only their initial data; mutable buffers are replayed in device-local
+//!   memory;
+//! * shader and binding type information (`TypeDesc`, layouts) is not captured:
+//!   it is only used for validation;
+//! * all swapchains are replayed on the default swapchain of the instance.
+use crate::{
+    barrier::AccessFlags,
+    command::{Command, CommandInner},
+    descriptor::{Descriptor, ResourceBindingType, SubresourceRange},
+    format::Format,
+    image::{
+        DepthStencilView, Dimensions, Filter, ImageRegion, ImageUsageFlags, MipmapsOption,
+        RenderTargetView, SamplerDescription,
+    },
+    pipeline::{
+        BareArgumentBlock, ColorBlendAttachmentState, ColorBlendAttachments,
+        ComputePipelineCreateInfo, DepthStencilState, GraphicsPipelineCreateInfo,
+        InputAssemblyState, LogicOp, MultisampleState, RasterisationState, Scissor, ScissorsOwned,
+        ShaderStageFlags, SignatureDescription, Viewport, ViewportsOwned,
+    },
+    query::{PipelineStatisticsFlags, QueryType},
+    vertex::{IndexBufferView, IndexFormat, VertexBufferView, VertexInputRate},
+    AliasScope, Backend, Queue,
+};
+use ordered_float::NotNan;
+use std::collections::HashMap;
+
+mod io;
+mod replay;
+
+pub use self::replay::{replay, Replay};
+
+/// Reference to an object in a capture.
+///
+/// In a [FrameCapture], this is the index of the object in [FrameCapture::objects].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ObjectRef(pub u64);
+
+impl ObjectRef {
+    /// Returns the reference used while recording, i.e. the address of the backend object.
+    pub fn address_of<T>(obj: &T) -> ObjectRef {
+        addr(obj)
+    }
+}
+
+/// A captured frame: the sorted command stream and all the objects it references.
+#[derive(Clone, Debug)]
+pub struct FrameCapture {
+    /// Objects, in creation order: an object only references objects that come before it.
+    pub objects: Vec<CapturedObject>,
+    /// Sorted commands, including pipeline barriers.
+    pub commands: Vec<CapturedCommand>,
+}
+
+/// Creation parameters of an object.
+#[derive(Clone, Debug)]
+pub enum CapturedObject {
+    /// A swapchain. The default swapchain of the instance is used on replay.
+    Swapchain,
+    Image {
+        scope: AliasScope,
+        format: Format,
+        dimensions: Dimensions,
+        mipmaps: MipmapsOption,
+        samples: u32,
+        usage: ImageUsageFlags,
+        initial_data: Option<Vec<u8>>,
+    },
+    Buffer {
+        size: u64,
+        initial_data: Option<Vec<u8>>,
+    },
+    ShaderModule {
+        stage: ShaderStageFlags,
+        bytecode: Vec<u8>,
+        num_fragment_outputs: u32,
+    },
+    Signature {
+        inherited: Vec<ObjectRef>,
+        description: CapturedSignatureDescription,
+    },
+    GraphicsPipeline(CapturedGraphicsPipeline),
+    ComputePipeline {
+        signature: ObjectRef,
+        shader: ObjectRef,
+    },
+    ArgumentBlock(CapturedArgumentBlock),
+    HostReference {
+        data: Vec<u8>,
+    },
+    QueryPool {
+        ty: QueryType,
+        count: u32,
+    },
+}
+
+/// See [SignatureDescription]. Inherited descriptions are those of the inherited signatures.
+#[derive(Clone, Debug)]
+pub struct CapturedSignatureDescription {
+    pub descriptors: Vec<CapturedResourceBinding>,
+    pub vertex_inputs: Vec<CapturedVertexInputBinding>,
+    pub num_fragment_outputs: u32,
+    pub depth_stencil_fragment_output: bool,
+    pub index_format: Option<IndexFormat>,
+    pub num_viewports: usize,
+    pub num_scissors: usize,
+    pub is_root_fragment_output_signature: bool,
+    pub is_root_vertex_input_signature: bool,
+}
+
+/// See [ResourceBinding](crate::descriptor::ResourceBinding).
+#[derive(Copy, Clone, Debug)]
+pub struct CapturedResourceBinding {
+    pub set: Option<u32>,
+    pub index: u32,
+    pub ty: ResourceBindingType,
+    pub stage_flags: ShaderStageFlags,
+    pub count: u32,
+    pub data_format: Format,
+}
+
+/// See [VertexInputBinding](crate::pipeline::VertexInputBinding).
+#[derive(Clone, Debug)]
+pub struct CapturedVertexInputBinding {
+    pub elements: Vec<CapturedVertexLayoutElement>,
+    pub stride: usize,
+    pub rate: VertexInputRate,
+    pub base_location: Option<u32>,
+}
+
+/// See [VertexLayoutElement](crate::vertex::VertexLayoutElement).
+#[derive(Clone, Debug)]
+pub struct CapturedVertexLayoutElement {
+    /// Semantic name and index.
+    pub semantic: Option<(String, u32)>,
+    pub format: Format,
+    pub offset: u32,
+}
+
+/// See [GraphicsPipelineCreateInfo].
+#[derive(Clone, Debug)]
+pub struct CapturedGraphicsPipeline {
+    pub signature: ObjectRef,
+    pub vertex_shader: ObjectRef,
+    pub geometry_shader: Option<ObjectRef>,
+    pub fragment_shader: Option<ObjectRef>,
+    pub tess_eval_shader: Option<ObjectRef>,
+    pub tess_control_shader: Option<ObjectRef>,
+    pub viewports: ViewportsOwned,
+    pub scissors: ScissorsOwned,
+    pub rasterization_state: RasterisationState,
+    pub multisample_state: MultisampleState,
+    pub depth_stencil_state: DepthStencilState,
+    pub input_assembly_state: InputAssemblyState,
+    pub logic_op: Option<LogicOp>,
+    pub color_blend_attachments: CapturedColorBlendAttachments,
+    pub blend_constants: [NotNan<f32>; 4],
+}
+
+/// See [ColorBlendAttachments].
+#[derive(Clone, Debug)]
+pub enum CapturedColorBlendAttachments {
+    All(ColorBlendAttachmentState),
+    Separate(Vec<ColorBlendAttachmentState>),
+}
+
+/// Arguments of an argument block.
+#[derive(Clone, Debug)]
+pub struct CapturedArgumentBlock {
+    pub signature: ObjectRef,
+    pub inherited: Vec<ObjectRef>,
+    pub descriptors: Vec<CapturedDescriptor>,
+    pub vertex_buffers: Vec<CapturedVertexBuffer>,
+    pub index_buffer: Option<CapturedIndexBuffer>,
+    pub render_targets: Vec<CapturedImageView>,
+    pub depth_stencil_target: Option<CapturedImageView>,
+    pub viewports: Vec<Viewport>,
+    pub scissors: Vec<Scissor>,
+}
+
+/// See [Descriptor].
+#[derive(Copy, Clone, Debug)]
+pub enum CapturedDescriptor {
+    Sampler {
+        desc: SamplerDescription,
+    },
+    Texture {
+        image: ObjectRef,
+        subresource: SubresourceRange,
+    },
+    TextureSampler {
+        image: ObjectRef,
+        subresource: SubresourceRange,
+        sampler: SamplerDescription,
+    },
+    RwImage {
+        image: ObjectRef,
+        subresource: SubresourceRange,
+    },
+    ConstantBuffer {
+        buffer: ObjectRef,
+        offset: usize,
+        size: Option<usize>,
+    },
+    RwBuffer {
+        buffer: ObjectRef,
+        offset: usize,
+        size: Option<usize>,
+    },
+    TexelBuffer {
+        buffer: ObjectRef,
+        offset: usize,
+        size: Option<usize>,
+    },
+    RwTexelBuffer {
+        buffer: ObjectRef,
+        offset: usize,
+        size: Option<usize>,
+    },
+    Empty,
+    HostConstantBuffer {
+        reference: ObjectRef,
+    },
+}
+
+/// See [VertexBufferView].
+#[derive(Copy, Clone, Debug)]
+pub struct CapturedVertexBuffer {
+    pub buffer: ObjectRef,
+    pub stride: usize,
+    pub offset: usize,
+}
+
+/// See [IndexBufferView].
+#[derive(Copy, Clone, Debug)]
+pub struct CapturedIndexBuffer {
+    pub buffer: ObjectRef,
+    pub format: IndexFormat,
+    pub offset: usize,
+}
+
+/// A render target or depth-stencil view.
+#[derive(Copy, Clone, Debug)]
+pub struct CapturedImageView {
+    pub image: ObjectRef,
+    pub subresource: SubresourceRange,
+}
+
+/// A command in a captured frame.
+#[derive(Clone, Debug)]
+pub struct CapturedCommand {
+    pub sortkey: u64,
+    pub queue: Queue,
+    pub cmd: CapturedCommandInner,
+}
+
+/// See [CommandInner].
+#[derive(Clone, Debug)]
+pub enum CapturedCommandInner {
+    PipelineBarrier {
+        src_access_mask: AccessFlags,
+        dst_access_mask: AccessFlags,
+    },
+    CopyBuffer {
+        src: ObjectRef,
+        src_offset: usize,
+        dst: ObjectRef,
+        dst_offset: usize,
+        size: usize,
+    },
+    CopyBufferToImage {
+        src: ObjectRef,
+        src_offset: usize,
+        dst: ObjectRef,
+        dst_region: ImageRegion,
+    },
+    CopyImageToBuffer {
+        src: ObjectRef,
+        src_region: ImageRegion,
+        dst: ObjectRef,
+        dst_offset: usize,
+    },
+    CopyImage {
+        src: ObjectRef,
+        src_region: ImageRegion,
+        dst: ObjectRef,
+        dst_region: ImageRegion,
+    },
+    FillBuffer {
+        dst: ObjectRef,
+        offset: usize,
+        size: usize,
+        value: u32,
+    },
+    ReadbackImage {
+        src: ObjectRef,
+        src_region: ImageRegion,
+    },
+    ReadbackBuffer {
+        src: ObjectRef,
+        offset: usize,
+        size: usize,
+    },
+    BeginProfileScope {
+        name: String,
+    },
+    EndProfileScope,
+    PushDebugGroup {
+        name: String,
+    },
+    PopDebugGroup,
+    InsertDebugMarker {
+        name: String,
+    },
+    BeginQuery {
+        pool: ObjectRef,
+        index: u32,
+    },
+    EndQuery {
+        pool: ObjectRef,
+        index: u32,
+    },
+    ReadbackQueries {
+        pool: ObjectRef,
+        first: u32,
+        count: u32,
+    },
+    BeginConditionalRendering {
+        pool: ObjectRef,
+        index: u32,
+    },
+    EndConditionalRendering,
+    BlitImage {
+        src: ObjectRef,
+        src_mip_level: u32,
+        src_offsets: [(u32, u32); 2],
+        dst: ObjectRef,
+        dst_mip_level: u32,
+        dst_offsets: [(u32, u32); 2],
+        filter: Filter,
+    },
+    ClearImageFloat {
+        image: ObjectRef,
+        color: [f32; 4],
+    },
+    ClearDepthStencilImage {
+        image: ObjectRef,
+        depth: f32,
+        stencil: Option<u8>,
+    },
+    Present {
+        image: ObjectRef,
+        swapchain: ObjectRef,
+    },
+    DrawHeader {
+        pipeline: ObjectRef,
+    },
+    DispatchHeader {
+        pipeline: ObjectRef,
+    },
+    SetPipelineArguments {
+        arguments: ObjectRef,
+    },
+    Draw {
+        vertex_count: u32,
+        instance_count: u32,
+        first_vertex: u32,
+        first_instance: u32,
+    },
+    DrawIndexed {
+        index_count: u32,
+        instance_count: u32,
+        first_index: u32,
+        vertex_offset: i32,
+        first_instance: u32,
+    },
+    DrawIndirect {
+        indirect_buffer: ObjectRef,
+        offset: usize,
+        draw_count: u32,
+        count_buffer: Option<ObjectRef>,
+    },
+    DrawIndexedIndirect {
+        indirect_buffer: ObjectRef,
+        offset: usize,
+        draw_count: u32,
+        count_buffer: Option<ObjectRef>,
+    },
+    Dispatch {
+        x: u32,
+        y: u32,
+        z: u32,
+    },
+    DispatchIndirect {
+        indirect_buffer: ObjectRef,
+        offset: usize,
+    },
+}
+
+//--------------------------------------------------------------------------------------------------
+
+/// Returns the reference used while recording: the address of the backend object.
+fn addr<T>(obj: &T) -> ObjectRef {
+    ObjectRef(obj as *const T as usize as u64)
+}
+
+impl CapturedObject {
+    /// Calls `f` on every object referenced by this object.
+    fn for_each_ref(&mut self, mut f: impl FnMut(&mut ObjectRef)) {
+        match self {
+            CapturedObject::Signature { inherited, .. } => inherited.iter_mut().for_each(f),
+            CapturedObject::GraphicsPipeline(p) => {
+                f(&mut p.signature);
+                f(&mut p.vertex_shader);
+                if let Some(s) = &mut p.geometry_shader {
+                    f(s);
+                }
+                if let Some(s) = &mut p.fragment_shader {
+                    f(s);
+                }
+                if let Some(s) = &mut p.tess_eval_shader {
+                    f(s);
+                }
+                if let Some(s) = &mut p.tess_control_shader {
+                    f(s);
+                }
+            }
+            CapturedObject::ComputePipeline { signature, shader } => {
+                f(signature);
+                f(shader);
+            }
+            CapturedObject::ArgumentBlock(b) => {
+                f(&mut b.signature);
+                b.inherited.iter_mut().for_each(&mut f);
+                for d in b.descriptors.iter_mut() {
+                    match d {
+                        CapturedDescriptor::Texture { image, .. }
+                        | CapturedDescriptor::TextureSampler { image, .. }
+                        | CapturedDescriptor::RwImage { image, .. } => f(image),
+                        CapturedDescriptor::ConstantBuffer { buffer, .. }
+                        | CapturedDescriptor::RwBuffer { buffer, .. }
+                        | CapturedDescriptor::TexelBuffer { buffer, .. }
+                        | CapturedDescriptor::RwTexelBuffer { buffer, .. } => f(buffer),
+                        CapturedDescriptor::HostConstantBuffer { reference } => f(reference),
+                        CapturedDescriptor::Sampler { .. } | CapturedDescriptor::Empty => {}
+                    }
+                }
+                b.vertex_buffers.iter_mut().for_each(|vb| f(&mut vb.buffer));
+                if let Some(ib) = &mut b.index_buffer {
+                    f(&mut ib.buffer);
+                }
+                b.render_targets.iter_mut().for_each(|rt| f(&mut rt.image));
+                if let Some(ds) = &mut b.depth_stencil_target {
+                    f(&mut ds.image);
+                }
+            }
+            CapturedObject::Swapchain
+            | CapturedObject::Image { .. }
+            | CapturedObject::Buffer { .. }
+            | CapturedObject::ShaderModule { .. }
+            | CapturedObject::HostReference { .. }
+            | CapturedObject::QueryPool { .. } => {}
+        }
+    }
+}
+
+impl CapturedCommandInner {
+    /// Calls `f` on every object referenced by this command.
+    fn for_each_ref(&mut self, mut f: impl FnMut(&mut ObjectRef)) {
+        match self {
+            CapturedCommandInner::CopyBuffer { src, dst, .. }
+            | CapturedCommandInner::CopyBufferToImage { src, dst, .. }
+            | CapturedCommandInner::CopyImageToBuffer { src, dst, .. }
+            | CapturedCommandInner::CopyImage { src, dst, .. }
+            | CapturedCommandInner::BlitImage { src, dst, .. } => {
+                f(src);
+                f(dst);
+            }
+            CapturedCommandInner::FillBuffer { dst, .. } => f(dst),
+            CapturedCommandInner::ReadbackImage { src, .. }
+            | CapturedCommandInner::ReadbackBuffer { src, .. } => f(src),
+            CapturedCommandInner::BeginQuery { pool, .. }
+            | CapturedCommandInner::EndQuery { pool, .. }
+            | CapturedCommandInner::ReadbackQueries { pool, .. }
+            | CapturedCommandInner::BeginConditionalRendering { pool, .. } => f(pool),
+            CapturedCommandInner::ClearImageFloat { image, .. }
+            | CapturedCommandInner::ClearDepthStencilImage { image, .. } => f(image),
+            CapturedCommandInner::Present { image, swapchain } => {
+                f(image);
+                f(swapchain);
+            }
+            CapturedCommandInner::DrawHeader { pipeline }
+            | CapturedCommandInner::DispatchHeader { pipeline } => f(pipeline),
+            CapturedCommandInner::SetPipelineArguments { arguments } => f(arguments),
+            CapturedCommandInner::DrawIndirect {
+                indirect_buffer,
+                count_buffer,
+                ..
+            }
+            | CapturedCommandInner::DrawIndexedIndirect {
+                indirect_buffer,
+                count_buffer,
+                ..
+            } => {
+                f(indirect_buffer);
+                if let Some(count_buffer) = count_buffer {
+                    f(count_buffer);
+                }
+            }
+            CapturedCommandInner::DispatchIndirect {
+                indirect_buffer, ..
+            } => f(indirect_buffer),
+            CapturedCommandInner::PipelineBarrier { .. }
+            | CapturedCommandInner::BeginProfileScope { .. }
+            | CapturedCommandInner::EndProfileScope
+            | CapturedCommandInner::PushDebugGroup { .. }
+            | CapturedCommandInner::PopDebugGroup
+            | CapturedCommandInner::InsertDebugMarker { .. }
+            | CapturedCommandInner::EndConditionalRendering
+            | CapturedCommandInner::Draw { .. }
+            | CapturedCommandInner::DrawIndexed { .. }
+            | CapturedCommandInner::Dispatch { .. } => {}
+        }
+    }
+
+    /// Converts a command, with references to backend objects replaced by their addresses.
+    fn from_command<B: Backend>(cmd: &CommandInner<B>) -> CapturedCommandInner {
+        match *cmd {
+            CommandInner::PipelineBarrier {
+                src_access_mask,
+                dst_access_mask,
+            } => CapturedCommandInner::PipelineBarrier {
+                src_access_mask,
+                dst_access_mask,
+            },
+            CommandInner::CopyBuffer {
+                src,
+                src_offset,
+                dst,
+                dst_offset,
+                size,
+            } => CapturedCommandInner::CopyBuffer {
+                src: addr(src),
+                src_offset,
+                dst: addr(dst),
+                dst_offset,
+                size,
+            },
+            CommandInner::CopyBufferToImage {
+                src,
+                src_offset,
+                dst,
+                dst_region,
+            } => CapturedCommandInner::CopyBufferToImage {
+                src: addr(src),
+                src_offset,
+                dst: addr(dst),
+                dst_region,
+            },
+            CommandInner::CopyImageToBuffer {
+                src,
+                src_region,
+                dst,
+                dst_offset,
+            } => CapturedCommandInner::CopyImageToBuffer {
+                src: addr(src),
+                src_region,
+                dst: addr(dst),
+                dst_offset,
+            },
+            CommandInner::CopyImage {
+                src,
+                src_region,
+                dst,
+                dst_region,
+            } => CapturedCommandInner::CopyImage {
+                src: addr(src),
+                src_region,
+                dst: addr(dst),
+                dst_region,
+            },
+            CommandInner::FillBuffer {
+                dst,
+                offset,
+                size,
+                value,
+            } => CapturedCommandInner::FillBuffer {
+                dst: addr(dst),
+                offset,
+                size,
+                value,
+            },
+            CommandInner::ReadbackImage {
+                src, src_region, ..
+            } => CapturedCommandInner::ReadbackImage {
+                src: addr(src),
+                src_region,
+            },
+            CommandInner::ReadbackBuffer {
+                src, offset, size, ..
+            } => CapturedCommandInner::ReadbackBuffer {
+                src: addr(src),
+                offset,
+                size,
+            },
+            CommandInner::BeginProfileScope { name } => CapturedCommandInner::BeginProfileScope {
+                name: name.to_string(),
+            },
+            CommandInner::EndProfileScope => CapturedCommandInner::EndProfileScope,
+            CommandInner::PushDebugGroup { name } => CapturedCommandInner::PushDebugGroup {
+                name: name.to_string(),
+            },
+            CommandInner::PopDebugGroup => CapturedCommandInner::PopDebugGroup,
+            CommandInner::InsertDebugMarker { name } => CapturedCommandInner::InsertDebugMarker {
+                name: name.to_string(),
+            },
+            CommandInner::BeginQuery { pool, index } => CapturedCommandInner::BeginQuery {
+                pool: addr(pool),
+                index,
+            },
+            CommandInner::EndQuery { pool, index } => CapturedCommandInner::EndQuery {
+                pool: addr(pool),
+                index,
+            },
+            CommandInner::ReadbackQueries {
+                pool, first, count, ..
+            } => CapturedCommandInner::ReadbackQueries {
+                pool: addr(pool),
+                first,
+                count,
+            },
+            CommandInner::BeginConditionalRendering { pool, index } => {
+                CapturedCommandInner::BeginConditionalRendering {
+                    pool: addr(pool),
+                    index,
+                }
+            }
+            CommandInner::EndConditionalRendering => CapturedCommandInner::EndConditionalRendering,
+            CommandInner::BlitImage {
+                src,
+                src_mip_level,
+                src_offsets,
+                dst,
+                dst_mip_level,
+                dst_offsets,
+                filter,
+            } => CapturedCommandInner::BlitImage {
+                src: addr(src),
+                src_mip_level,
+                src_offsets,
+                dst: addr(dst),
+                dst_mip_level,
+                dst_offsets,
+                filter,
+            },
+            CommandInner::ClearImageFloat { image, color } => {
+                CapturedCommandInner::ClearImageFloat {
+                    image: addr(image),
+                    color,
+                }
+            }
+            CommandInner::ClearDepthStencilImage {
+                image,
+                depth,
+                stencil,
+            } => CapturedCommandInner::ClearDepthStencilImage {
+                image: addr(image),
+                depth,
+                stencil,
+            },
+            CommandInner::Present { image, swapchain } => CapturedCommandInner::Present {
+                image: addr(image),
+                swapchain: addr(swapchain),
+            },
+            CommandInner::DrawHeader { pipeline } => CapturedCommandInner::DrawHeader {
+                pipeline: addr(pipeline),
+            },
+            CommandInner::DispatchHeader { pipeline } => CapturedCommandInner::DispatchHeader {
+                pipeline: addr(pipeline),
+            },
+            CommandInner::SetPipelineArguments { arguments, .. } => {
+                CapturedCommandInner::SetPipelineArguments {
+                    arguments: addr(arguments),
+                }
+            }
+            CommandInner::Draw {
+                vertex_count,
+                instance_count,
+                first_vertex,
+                first_instance,
+            } => CapturedCommandInner::Draw {
+                vertex_count,
+                instance_count,
+                first_vertex,
+                first_instance,
+            },
+            CommandInner::DrawIndexed {
+                index_count,
+                instance_count,
+                first_index,
+                vertex_offset,
+                first_instance,
+            } => CapturedCommandInner::DrawIndexed {
+                index_count,
+                instance_count,
+                first_index,
+                vertex_offset,
+                first_instance,
+            },
+            CommandInner::DrawIndirect {
+                indirect_buffer,
+                offset,
+                draw_count,
+                count_buffer,
+            } => CapturedCommandInner::DrawIndirect {
+                indirect_buffer: addr(indirect_buffer),
+                offset,
+                draw_count,
+                count_buffer: count_buffer.map(addr),
+            },
+            CommandInner::DrawIndexedIndirect {
+                indirect_buffer,
+                offset,
+                draw_count,
+                count_buffer,
+            } => CapturedCommandInner::DrawIndexedIndirect {
+                indirect_buffer: addr(indirect_buffer),
+                offset,
+                draw_count,
+                count_buffer: count_buffer.map(addr),
+            },
+            CommandInner::Dispatch { x, y, z } => CapturedCommandInner::Dispatch { x, y, z },
+            CommandInner::DispatchIndirect {
+                indirect_buffer,
+                offset,
+            } => CapturedCommandInner::DispatchIndirect {
+                indirect_buffer: addr(indirect_buffer),
+                offset,
+            },
+        }
+    }
+}
+
+fn capture_descriptor<B: Backend>(d: &Descriptor<B>) -> CapturedDescriptor {
+    match *d {
+        Descriptor::Sampler { desc } => CapturedDescriptor::Sampler { desc },
+        Descriptor::Texture { image, subresource } => CapturedDescriptor::Texture {
+            image: addr(image),
+            subresource,
+        },
+        Descriptor::TextureSampler {
+            image,
+            subresource,
+            sampler,
+        } => CapturedDescriptor::TextureSampler {
+            image: addr(image),
+            subresource,
+            sampler,
+        },
+        Descriptor::RwImage { image, subresource } => CapturedDescriptor::RwImage {
+            image: addr(image),
+            subresource,
+        },
+        Descriptor::ConstantBuffer {
+            buffer,
+            offset,
+            size,
+        } => CapturedDescriptor::ConstantBuffer {
+            buffer: addr(buffer),
+            offset,
+            size,
+        },
+        Descriptor::HostConstantBuffer { reference } => CapturedDescriptor::HostConstantBuffer {
+            reference: addr(reference),
+        },
+        Descriptor::RwBuffer {
+            buffer,
+            offset,
+            size,
+        } => CapturedDescriptor::RwBuffer {
+            buffer: addr(buffer),
+            offset,
+            size,
+        },
+        Descriptor::TexelBuffer {
+            buffer,
+            offset,
+            size,
+        } => CapturedDescriptor::TexelBuffer {
+            buffer: addr(buffer),
+            offset,
+            size,
+        },
+        Descriptor::RwTexelBuffer {
+            buffer,
+            offset,
+            size,
+        } => CapturedDescriptor::RwTexelBuffer {
+            buffer: addr(buffer),
+            offset,
+            size,
+        },
+        Descriptor::Empty => CapturedDescriptor::Empty,
+    }
+}
+
+fn capture_signature_description(d: &SignatureDescription) -> CapturedSignatureDescription {
+    CapturedSignatureDescription {
+        descriptors: d
+            .descriptors
+            .iter()
+            .map(|b| CapturedResourceBinding {
+                set: b.set,
+                index: b.index,
+                ty: b.ty,
+                stage_flags: b.stage_flags,
+                count: b.count,
+                data_format: b.data_format,
+            })
+            .collect(),
+        vertex_inputs: d
+            .vertex_inputs
+            .iter()
+            .map(|vi| CapturedVertexInputBinding {
+                elements: vi
+                    .layout
+                    .elements
+                    .iter()
+                    .map(|e| CapturedVertexLayoutElement {
+                        semantic: e.semantic.map(|s| (s.name.to_string(), s.index)),
+                        format: e.format,
+                        offset: e.offset,
+                    })
+                    .collect(),
+                stride: vi.layout.stride,
+                rate: vi.rate,
+                base_location: vi.base_location,
+            })
+            .collect(),
+        num_fragment_outputs: d.fragment_outputs.len() as u32,
+        depth_stencil_fragment_output: d.depth_stencil_fragment_output.is_some(),
+        index_format: d.index_format,
+        num_viewports: d.num_viewports,
+        num_scissors: d.num_scissors,
+        is_root_fragment_output_signature: d.is_root_fragment_output_signature,
+        is_root_vertex_input_signature: d.is_root_vertex_input_signature,
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+
+struct RecordedObject {
+    /// Address of the backend arena that owns the object.
+    arena: usize,
+    /// Creation parameters, with references to other objects given by address.
+    object: CapturedObject,
+}
+
+/// Records the creation parameters of objects.
+///
+/// Objects are identified by their address, and forgotten when their arena is dropped.
+pub(crate) struct CaptureRecorder {
+    objects: HashMap<ObjectRef, RecordedObject>,
+    /// Capture the next frame to this file.
+    pub(crate) requested: Option<std::path::PathBuf>,
+}
+
+impl CaptureRecorder {
+    pub(crate) fn new() -> CaptureRecorder {
+        CaptureRecorder {
+            objects: HashMap::new(),
+            requested: None,
+        }
+    }
+
+    fn record<A, T>(&mut self, arena: &A, obj: &T, object: CapturedObject) {
+        self.objects.insert(
+            addr(obj),
+            RecordedObject {
+                arena: arena as *const A as usize,
+                object,
+            },
+        );
+    }
+
+    /// Returns the recorded creation parameters of an object, with references to other objects
+    /// given by address.
+    pub(crate) fn object<T>(&self, obj: &T) -> Option<&CapturedObject> {
+        self.objects.get(&addr(obj)).map(|o| &o.object)
+    }
+
+    /// Forgets all objects allocated in the specified arena.
+    pub(crate) fn forget_arena<A>(&mut self, arena: &A) {
+        let arena = arena as *const A as usize;
+        self.objects.retain(|_, o| o.arena != arena);
+    }
+
+    pub(crate) fn swapchain<A, T>(&mut self, arena: &A, swapchain: &T) {
+        self.record(arena, swapchain, CapturedObject::Swapchain);
+    }
+
+    pub(crate) fn image<B: Backend>(
+        &mut self,
+        arena: &B::Arena,
+        image: &B::Image,
+        scope: AliasScope,
+        format: Format,
+        dimensions: Dimensions,
+        mipmaps: MipmapsOption,
+        samples: u32,
+        usage: ImageUsageFlags,
+        initial_data: Option<&[u8]>,
+    ) {
+        self.record(
+            arena,
+            image,
+            CapturedObject::Image {
+                scope,
+                format,
+                dimensions,
+                mipmaps,
+                samples,
+                usage,
+                initial_data: initial_data.map(|d| d.to_vec()),
+            },
+        );
+    }
+
+    pub(crate) fn buffer<B: Backend>(
+        &mut self,
+        arena: &B::Arena,
+        buffer: &B::Buffer,
+        size: u64,
+        initial_data: Option<&[u8]>,
+    ) {
+        self.record(
+            arena,
+            buffer,
+            CapturedObject::Buffer {
+                size,
+                initial_data: initial_data.map(|d| d.to_vec()),
+            },
+        );
+    }
+
+    pub(crate) fn shader_module<B: Backend>(
+        &mut self,
+        arena: &B::Arena,
+        module: &B::ShaderModule,
+        stage: ShaderStageFlags,
+        bytecode: &[u8],
+        num_fragment_outputs: usize,
+    ) {
+        self.record(
+            arena,
+            module,
+            CapturedObject::ShaderModule {
+                stage,
+                bytecode: bytecode.to_vec(),
+                num_fragment_outputs: num_fragment_outputs as u32,
+            },
+        );
+    }
+
+    pub(crate) fn signature<B: Backend>(
+        &mut self,
+        arena: &B::Arena,
+        signature: &B::Signature,
+        inherited: &[&B::Signature],
+        description: &SignatureDescription,
+    ) {
+        self.record(
+            arena,
+            signature,
+            CapturedObject::Signature {
+                inherited: inherited.iter().map(|&s| addr(s)).collect(),
+                description: capture_signature_description(description),
+            },
+        );
+    }
+
+    pub(crate) fn graphics_pipeline<B: Backend>(
+        &mut self,
+        arena: &B::Arena,
+        pipeline: &B::GraphicsPipeline,
+        signature: &B::Signature,
+        create_info: &GraphicsPipelineCreateInfo<B>,
+    ) {
+        let stages = &create_info.shader_stages;
+        let blend = &create_info.color_blend_state;
+        self.record(
+            arena,
+            pipeline,
+            CapturedObject::GraphicsPipeline(CapturedGraphicsPipeline {
+                signature: addr(signature),
+                vertex_shader: addr(stages.vertex.module),
+                geometry_shader: stages.geometry.map(|s| addr(s.module)),
+                fragment_shader: stages.fragment.map(|s| addr(s.module)),
+                tess_eval_shader: stages.tess_eval.map(|s| addr(s.module)),
+                tess_control_shader: stages.tess_control.map(|s| addr(s.module)),
+                viewports: create_info.viewport_state.viewports.into(),
+                scissors: create_info.viewport_state.scissors.into(),
+                rasterization_state: create_info.rasterization_state,
+                multisample_state: create_info.multisample_state,
+                depth_stencil_state: create_info.depth_stencil_state,
+                input_assembly_state: create_info.input_assembly_state,
+                logic_op: blend.logic_op,
+                color_blend_attachments: match blend.attachments {
+                    ColorBlendAttachments::All(a) => CapturedColorBlendAttachments::All(*a),
+                    ColorBlendAttachments::Separate(a) => {
+                        CapturedColorBlendAttachments::Separate(a.to_vec())
+                    }
+                },
+                blend_constants: blend.blend_constants,
+            }),
+        );
+    }
+
+    pub(crate) fn compute_pipeline<B: Backend>(
+        &mut self,
+        arena: &B::Arena,
+        pipeline: &B::ComputePipeline,
+        signature: &B::Signature,
+        create_info: &ComputePipelineCreateInfo<B>,
+    ) {
+        self.record(
+            arena,
+            pipeline,
+            CapturedObject::ComputePipeline {
+                signature: addr(signature),
+                shader: addr(create_info.shader_stage.module),
+            },
+        );
+    }
+
+    pub(crate) fn argument_block<'a, B: Backend>(
+        &mut self,
+        arena: &B::Arena,
+        block: &B::ArgumentBlock,
+        signature: &B::Signature,
+        inherited: &[BareArgumentBlock<'a, B>],
+        descriptors: &[Descriptor<'a, B>],
+        vertex_buffers: &[VertexBufferView<'a, B>],
+        index_buffer: Option<&IndexBufferView<'a, B>>,
+        render_targets: &[RenderTargetView<'a, B>],
+        depth_stencil_target: Option<&DepthStencilView<'a, B>>,
+        viewports: &[Viewport],
+        scissors: &[Scissor],
+    ) {
+        self.record(
+            arena,
+            block,
+            CapturedObject::ArgumentBlock(CapturedArgumentBlock {
+                signature: addr(signature),
+                inherited: inherited.iter().map(|b| addr(b.0)).collect(),
+                descriptors: descriptors.iter().map(capture_descriptor).collect(),
+                vertex_buffers: vertex_buffers
+                    .iter()
+                    .map(|vb| CapturedVertexBuffer {
+                        buffer: addr(vb.buffer),
+                        stride: vb.stride,
+                        offset: vb.offset,
+                    })
+                    .collect(),
+                index_buffer: index_buffer.map(|ib| CapturedIndexBuffer {
+                    buffer: addr(ib.buffer),
+                    format: ib.format,
+                    offset: ib.offset,
+                }),
+                render_targets: render_targets
+                    .iter()
+                    .map(|rt| CapturedImageView {
+                        image: addr(rt.image),
+                        subresource: rt.subresource,
+                    })
+                    .collect(),
+                depth_stencil_target: depth_stencil_target.map(|ds| CapturedImageView {
+                    image: addr(ds.image),
+                    subresource: ds.subresource,
+                }),
+                viewports: viewports.to_vec(),
+                scissors: scissors.to_vec(),
+            }),
+        );
+    }
+
+    pub(crate) fn host_reference<B: Backend>(
+        &mut self,
+        arena: &B::Arena,
+        host_reference: &B::HostReference,
+        data: &[u8],
+    ) {
+        self.record(
+            arena,
+            host_reference,
+            CapturedObject::HostReference {
+                data: data.to_vec(),
+            },
+        );
+    }
+
+    pub(crate) fn query_pool<B: Backend>(
+        &mut self,
+        arena: &B::Arena,
+        pool: &B::QueryPool,
+        ty: QueryType,
+        count: u32,
+    ) {
+        self.record(arena, pool, CapturedObject::QueryPool { ty, count });
+    }
+
+    /// Builds a self-contained capture from a sorted command stream.
+    ///
+    /// Returns an error if a command references an object that was not recorded.
+    pub(crate) fn capture_frame<B: Backend>(
+        &self,
+        commands: &[Command<B>],
+    ) -> Result<FrameCapture, String> {
+        let mut builder = CaptureBuilder {
+            recorder: self,
+            indices: HashMap::new(),
+            objects: Vec::new(),
+        };
+
+        let mut captured_commands = Vec::with_capacity(commands.len());
+        for cmd in commands.iter() {
+            let mut captured = CapturedCommandInner::from_command(&cmd.cmd);
+            let mut err = None;
+            captured.for_each_ref(|r| match builder.add(*r) {
+                Ok(index) => *r = index,
+                Err(e) => err = Some(e),
+            });
+            if let Some(err) = err {
+                return Err(err);
+            }
+            captured_commands.push(CapturedCommand {
+                sortkey: cmd.sortkey,
+                queue: cmd.queue,
+                cmd: captured,
+            });
+        }
+
+        Ok(FrameCapture {
+            objects: builder.objects,
+            commands: captured_commands,
+        })
+    }
+}
+
+/// Collects the objects referenced by a frame, dependencies first.
+struct CaptureBuilder<'r> {
+    recorder: &'r CaptureRecorder,
+    /// Address to index in `objects`.
+    indices: HashMap<ObjectRef, ObjectRef>,
+    objects: Vec<CapturedObject>,
+}
+
+impl<'r> CaptureBuilder<'r> {
+    /// Adds an object and all its dependencies, and returns its index.
+    fn add(&mut self, r: ObjectRef) -> Result<ObjectRef, String> {
+        if let Some(&index) = self.indices.get(&r) {
+            return Ok(index);
+        }
+
+        let recorded = self.recorder.objects.get(&r).ok_or_else(|| {
+            format!(
+                "object at address {:#x} was not recorded (created before capture was enabled?)",
+                r.0
+            )
+        })?;
+
+        let mut object = recorded.object.clone();
+        let mut err = None;
+        object.for_each_ref(|dep| match self.add(*dep) {
+            Ok(index) => *dep = index,
+            Err(e) => err = Some(e),
+        });
+        if let Some(err) = err {
+            return Err(err);
+        }
+
+        let index = ObjectRef(self.objects.len() as u64);
+        self.objects.push(object);
+        self.indices.insert(r, index);
+        Ok(index)
+    }
+}
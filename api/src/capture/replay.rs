@@ -0,0 +1,681 @@
+//! Replay of captured frames.
+use super::*;
+use crate::{
+    descriptor::ResourceBinding,
+    error::{Error, Result},
+    pipeline::{
+        cache::bytecode_hash, ColorBlendState, FragmentOutputDescription, GraphicsShaderStages,
+        Scissors, ShaderModule, ShaderStageReflection, VertexInputBinding, ViewportState,
+        Viewports,
+    },
+    readback::Readback,
+    schedule::{schedule_batches, QueueBatch},
+    vertex::{Semantic, VertexLayout, VertexLayoutElement},
+    Arena, Instance, MemoryType,
+};
+use std::{iter, str};
+
+/// A backend object recreated from a capture.
+#[derive(derivative::Derivative)]
+#[derivative(Copy(bound = ""), Clone(bound = ""))]
+enum Replayed<'a, B: Backend> {
+    Swapchain(&'a B::Swapchain),
+    Image(&'a B::Image),
+    Buffer(&'a B::Buffer),
+    ShaderModule(ShaderModule<'a, 'a, B>),
+    Signature(&'a B::Signature, &'a SignatureDescription<'a>),
+    GraphicsPipeline(&'a B::GraphicsPipeline),
+    ComputePipeline(&'a B::ComputePipeline),
+    ArgumentBlock(&'a B::ArgumentBlock),
+    HostReference(&'a B::HostReference),
+    QueryPool(&'a B::QueryPool),
+}
+
+/// A captured frame recreated on a backend, ready to be submitted.
+pub struct Replay<'a, B: Backend> {
+    instance: &'a B::Instance,
+    commands: Vec<Command<'a, B>>,
+    batches: Vec<QueueBatch>,
+}
+
+macro_rules! replayed {
+    ($objects:expr, $r:expr, $v:ident) => {
+        match $objects[$r.0 as usize] {
+            Replayed::$v(obj) => obj,
+            _ => {
+                return Err(Error::Validation {
+                    message: format!(
+                        "invalid capture: object {} is not a {}",
+                        $r.0,
+                        stringify!($v)
+                    ),
+                })
+            }
+        }
+    };
+}
+
+impl<'a, B: Backend> Replay<'a, B> {
+    /// Recreates all the objects of a captured frame in the specified arena.
+    ///
+    /// Returns an error if an object could not be created, or if the capture is inconsistent
+    /// (e.g. a reference to an image designates a buffer).
+    pub fn new(arena: &'a Arena<B>, capture: &FrameCapture) -> Result<Replay<'a, B>> {
+        let mut objects: Vec<Replayed<'a, B>> = Vec::with_capacity(capture.objects.len());
+        for object in capture.objects.iter() {
+            let replayed = unsafe { replay_object(arena, &objects, object)? };
+            objects.push(replayed);
+        }
+
+        let commands = capture
+            .commands
+            .iter()
+            .map(|c| {
+                Ok(Command {
+                    sortkey: c.sortkey,
+                    queue: c.queue,
+                    cmd: replay_command(arena, &objects, &c.cmd)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let batches = schedule_batches(&commands, arena.instance.queues());
+
+        Ok(Replay {
+            instance: arena.instance,
+            commands,
+            batches,
+        })
+    }
+
+    /// Submits the commands of the captured frame.
+    pub fn submit(&self) {
+        // commands are already sorted, and contain the necessary barriers
+        unsafe { self.instance.submit_frame(&self.commands, &self.batches) }
+    }
+}
+
+/// Recreates a captured frame in the specified arena and submits it.
+///
+/// The commands are submitted as they were captured (already sorted, with barriers).
+/// See also [Replay] to submit a captured frame multiple times.
+pub fn replay<B: Backend>(arena: &Arena<B>, capture: &FrameCapture) -> Result<()> {
+    Replay::new(arena, capture)?.submit();
+    Ok(())
+}
+
+unsafe fn replay_object<'a, B: Backend>(
+    arena: &'a Arena<B>,
+    objects: &[Replayed<'a, B>],
+    object: &CapturedObject,
+) -> Result<Replayed<'a, B>> {
+    let instance = arena.instance;
+    let misc = &arena.misc;
+
+    Ok(match object {
+        // all swapchains are replayed on the default swapchain
+        CapturedObject::Swapchain => Replayed::Swapchain(match instance.default_swapchain() {
+            Some(swapchain) => swapchain,
+            None => {
+                return Err(Error::Validation {
+                    message: "replaying a swapchain requires a default swapchain".into(),
+                })
+            }
+        }),
+        CapturedObject::Image {
+            scope,
+            format,
+            dimensions,
+            mipmaps,
+            samples,
+            usage,
+            initial_data,
+        } => Replayed::Image(instance.create_image(
+            arena.inner(),
+            *scope,
+            *format,
+            *dimensions,
+            *mipmaps,
+            *samples,
+            *usage,
+            initial_data.as_ref().map(|d| &d[..]),
+        )?),
+        CapturedObject::Buffer { size, initial_data } => Replayed::Buffer(match initial_data {
+            Some(data) => instance.create_immutable_buffer(arena.inner(), *size, data)?,
+            None => instance.create_buffer(arena.inner(), *size, MemoryType::DeviceLocal)?,
+        }),
+        CapturedObject::ShaderModule {
+            stage,
+            bytecode,
+            num_fragment_outputs,
+        } => {
+            let fragment_outputs = misc.alloc_extend(
+                iter::repeat(FragmentOutputDescription {}).take(*num_fragment_outputs as usize),
+            );
+            let reflection = misc.alloc(ShaderStageReflection {
+                stage: *stage,
+                descriptors: &[],
+                vertex_input_attributes: &[],
+                fragment_outputs,
+            });
+            Replayed::ShaderModule(ShaderModule {
+                module: instance.create_shader_module(arena.inner(), bytecode, *stage)?,
+                reflection,
+                bytecode: misc.alloc_extend(bytecode.iter().cloned()),
+                hash: bytecode_hash(bytecode),
+            })
+        }
+        CapturedObject::Signature {
+            inherited,
+            description,
+        } => {
+            let inherited = inherited
+                .iter()
+                .map(|&r| Ok(replayed!(objects, r, Signature)))
+                .collect::<Result<Vec<_>>>()?;
+            let inherited_signatures: Vec<_> = inherited.iter().map(|s| s.0).collect();
+            let description = replay_signature_description(
+                arena,
+                misc.alloc_extend(inherited.iter().map(|s| s.1)),
+                description,
+            );
+            Replayed::Signature(
+                instance.create_signature(arena.inner(), &inherited_signatures, description)?,
+                description,
+            )
+        }
+        CapturedObject::GraphicsPipeline(p) => {
+            let (signature, signature_description) = replayed!(objects, p.signature, Signature);
+            let shader = |r: Option<ObjectRef>| -> Result<_> {
+                Ok(match r {
+                    Some(r) => Some(replayed!(objects, r, ShaderModule)),
+                    None => None,
+                })
+            };
+            let create_info = GraphicsPipelineCreateInfo {
+                shader_stages: GraphicsShaderStages {
+                    vertex: replayed!(objects, p.vertex_shader, ShaderModule),
+                    geometry: shader(p.geometry_shader)?,
+                    fragment: shader(p.fragment_shader)?,
+                    tess_eval: shader(p.tess_eval_shader)?,
+                    tess_control: shader(p.tess_control_shader)?,
+                },
+                viewport_state: ViewportState {
+                    viewports: match p.viewports {
+                        ViewportsOwned::Static(ref v) => Viewports::Static(v),
+                        ViewportsOwned::Dynamic => Viewports::Dynamic,
+                    },
+                    scissors: match p.scissors {
+                        ScissorsOwned::Static(ref s) => Scissors::Static(s),
+                        ScissorsOwned::Dynamic => Scissors::Dynamic,
+                    },
+                },
+                rasterization_state: p.rasterization_state,
+                multisample_state: p.multisample_state,
+                depth_stencil_state: p.depth_stencil_state,
+                input_assembly_state: p.input_assembly_state,
+                color_blend_state: ColorBlendState {
+                    logic_op: p.logic_op,
+                    attachments: match p.color_blend_attachments {
+                        CapturedColorBlendAttachments::All(ref a) => ColorBlendAttachments::All(a),
+                        CapturedColorBlendAttachments::Separate(ref a) => {
+                            ColorBlendAttachments::Separate(a)
+                        }
+                    },
+                    blend_constants: p.blend_constants,
+                },
+            };
+            Replayed::GraphicsPipeline(instance.create_graphics_pipeline(
+                arena.inner(),
+                signature,
+                signature_description,
+                &create_info,
+            )?)
+        }
+        CapturedObject::ComputePipeline { signature, shader } => {
+            let (signature, signature_description) = replayed!(objects, signature, Signature);
+            let create_info = ComputePipelineCreateInfo {
+                shader_stage: replayed!(objects, shader, ShaderModule),
+            };
+            Replayed::ComputePipeline(instance.create_compute_pipeline(
+                arena.inner(),
+                signature,
+                signature_description,
+                &create_info,
+            )?)
+        }
+        CapturedObject::ArgumentBlock(b) => {
+            let (signature, _) = replayed!(objects, b.signature, Signature);
+            let image_view = |v: &CapturedImageView| -> Result<_> {
+                Ok((replayed!(objects, v.image, Image), v.subresource))
+            };
+            let inherited = b
+                .inherited
+                .iter()
+                .map(|&r| Ok(BareArgumentBlock(replayed!(objects, r, ArgumentBlock), &[])))
+                .collect::<Result<Vec<_>>>()?;
+            let descriptors = b
+                .descriptors
+                .iter()
+                .map(|d| replay_descriptor(objects, d))
+                .collect::<Result<Vec<_>>>()?;
+            let vertex_buffers = b
+                .vertex_buffers
+                .iter()
+                .map(|vb| {
+                    Ok(VertexBufferView {
+                        buffer: replayed!(objects, vb.buffer, Buffer),
+                        stride: vb.stride,
+                        offset: vb.offset,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let index_buffer = match b.index_buffer {
+                Some(ref ib) => Some(IndexBufferView {
+                    buffer: replayed!(objects, ib.buffer, Buffer),
+                    format: ib.format,
+                    offset: ib.offset,
+                }),
+                None => None,
+            };
+            let render_targets = b
+                .render_targets
+                .iter()
+                .map(|rt| {
+                    let (image, subresource) = image_view(rt)?;
+                    Ok(RenderTargetView { image, subresource })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let depth_stencil_target = match b.depth_stencil_target {
+                Some(ref ds) => {
+                    let (image, subresource) = image_view(ds)?;
+                    Some(DepthStencilView { image, subresource })
+                }
+                None => None,
+            };
+            Replayed::ArgumentBlock(instance.create_argument_block(
+                arena.inner(),
+                signature,
+                inherited,
+                descriptors,
+                vertex_buffers,
+                index_buffer,
+                render_targets,
+                depth_stencil_target,
+                b.viewports.iter().cloned(),
+                b.scissors.iter().cloned(),
+            )?)
+        }
+        CapturedObject::HostReference { data } => {
+            let data = misc.alloc_extend(data.iter().cloned());
+            Replayed::HostReference(instance.create_host_reference(arena.inner(), data)?)
+        }
+        CapturedObject::QueryPool { ty, count } => {
+            Replayed::QueryPool(instance.create_query_pool(arena.inner(), *ty, *count)?)
+        }
+    })
+}
+
+fn replay_signature_description<'a, B: Backend>(
+    arena: &'a Arena<B>,
+    inherited: &'a [&'a SignatureDescription<'a>],
+    d: &CapturedSignatureDescription,
+) -> &'a SignatureDescription<'a> {
+    let misc = &arena.misc;
+
+    let descriptors = misc.alloc_extend(d.descriptors.iter().map(|b| ResourceBinding {
+        set: b.set,
+        index: b.index,
+        ty: b.ty,
+        stage_flags: b.stage_flags,
+        count: b.count,
+        data_ty: None,
+        data_layout: None,
+        data_format: b.data_format,
+    }));
+
+    // don't allocate in the arena while extending it: collect into vectors first
+    let vertex_inputs: Vec<_> = d
+        .vertex_inputs
+        .iter()
+        .map(|vi| {
+            let elements: Vec<_> = vi
+                .elements
+                .iter()
+                .map(|e| VertexLayoutElement {
+                    semantic: e.semantic.as_ref().map(|(name, index)| Semantic {
+                        // the bytes come from a String
+                        name: str::from_utf8(misc.alloc_extend(name.bytes())).unwrap(),
+                        index: *index,
+                    }),
+                    format: e.format,
+                    offset: e.offset,
+                })
+                .collect();
+            VertexInputBinding {
+                layout: VertexLayout {
+                    elements: misc.alloc_extend(elements.into_iter()),
+                    stride: vi.stride,
+                },
+                rate: vi.rate,
+                base_location: vi.base_location,
+            }
+        })
+        .collect();
+    let vertex_inputs = misc.alloc_extend(vertex_inputs.into_iter());
+
+    let fragment_outputs = misc.alloc_extend(
+        iter::repeat(FragmentOutputDescription {}).take(d.num_fragment_outputs as usize),
+    );
+
+    misc.alloc(SignatureDescription {
+        inherited,
+        descriptors,
+        vertex_inputs,
+        fragment_outputs,
+        depth_stencil_fragment_output: if d.depth_stencil_fragment_output {
+            Some(FragmentOutputDescription {})
+        } else {
+            None
+        },
+        index_format: d.index_format,
+        num_viewports: d.num_viewports,
+        num_scissors: d.num_scissors,
+        is_root_fragment_output_signature: d.is_root_fragment_output_signature,
+        is_root_vertex_input_signature: d.is_root_vertex_input_signature,
+    })
+}
+
+fn replay_descriptor<'a, B: Backend>(
+    objects: &[Replayed<'a, B>],
+    d: &CapturedDescriptor,
+) -> Result<Descriptor<'a, B>> {
+    Ok(match *d {
+        CapturedDescriptor::Sampler { desc } => Descriptor::Sampler { desc },
+        CapturedDescriptor::Texture { image, subresource } => Descriptor::Texture {
+            image: replayed!(objects, image, Image),
+            subresource,
+        },
+        CapturedDescriptor::TextureSampler {
+            image,
+            subresource,
+            sampler,
+        } => Descriptor::TextureSampler {
+            image: replayed!(objects, image, Image),
+            subresource,
+            sampler,
+        },
+        CapturedDescriptor::RwImage { image, subresource } => Descriptor::RwImage {
+            image: replayed!(objects, image, Image),
+            subresource,
+        },
+        CapturedDescriptor::ConstantBuffer {
+            buffer,
+            offset,
+            size,
+        } => Descriptor::ConstantBuffer {
+            buffer: replayed!(objects, buffer, Buffer),
+            offset,
+            size,
+        },
+        CapturedDescriptor::HostConstantBuffer { reference } => Descriptor::HostConstantBuffer {
+            reference: replayed!(objects, reference, HostReference),
+        },
+        CapturedDescriptor::RwBuffer {
+            buffer,
+            offset,
+            size,
+        } => Descriptor::RwBuffer {
+            buffer: replayed!(objects, buffer, Buffer),
+            offset,
+            size,
+        },
+        CapturedDescriptor::TexelBuffer {
+            buffer,
+            offset,
+            size,
+        } => Descriptor::TexelBuffer {
+            buffer: replayed!(objects, buffer, Buffer),
+            offset,
+            size,
+        },
+        CapturedDescriptor::RwTexelBuffer {
+            buffer,
+            offset,
+            size,
+        } => Descriptor::RwTexelBuffer {
+            buffer: replayed!(objects, buffer, Buffer),
+            offset,
+            size,
+        },
+        CapturedDescriptor::Empty => Descriptor::Empty,
+    })
+}
+
+fn replay_command<'a, B: Backend>(
+    arena: &'a Arena<B>,
+    objects: &[Replayed<'a, B>],
+    cmd: &CapturedCommandInner,
+) -> Result<CommandInner<'a, B>> {
+    Ok(match *cmd {
+        CapturedCommandInner::PipelineBarrier {
+            src_access_mask,
+            dst_access_mask,
+        } => CommandInner::PipelineBarrier {
+            src_access_mask,
+            dst_access_mask,
+        },
+        CapturedCommandInner::CopyBuffer {
+            src,
+            src_offset,
+            dst,
+            dst_offset,
+            size,
+        } => CommandInner::CopyBuffer {
+            src: replayed!(objects, src, Buffer),
+            src_offset,
+            dst: replayed!(objects, dst, Buffer),
+            dst_offset,
+            size,
+        },
+        CapturedCommandInner::CopyBufferToImage {
+            src,
+            src_offset,
+            dst,
+            dst_region,
+        } => CommandInner::CopyBufferToImage {
+            src: replayed!(objects, src, Buffer),
+            src_offset,
+            dst: replayed!(objects, dst, Image),
+            dst_region,
+        },
+        CapturedCommandInner::CopyImageToBuffer {
+            src,
+            src_region,
+            dst,
+            dst_offset,
+        } => CommandInner::CopyImageToBuffer {
+            src: replayed!(objects, src, Image),
+            src_region,
+            dst: replayed!(objects, dst, Buffer),
+            dst_offset,
+        },
+        CapturedCommandInner::CopyImage {
+            src,
+            src_region,
+            dst,
+            dst_region,
+        } => CommandInner::CopyImage {
+            src: replayed!(objects, src, Image),
+            src_region,
+            dst: replayed!(objects, dst, Image),
+            dst_region,
+        },
+        CapturedCommandInner::FillBuffer {
+            dst,
+            offset,
+            size,
+            value,
+        } => CommandInner::FillBuffer {
+            dst: replayed!(objects, dst, Buffer),
+            offset,
+            size,
+            value,
+        },
+        // the data read back during replay is discarded
+        CapturedCommandInner::ReadbackImage { src, src_region } => CommandInner::ReadbackImage {
+            src: replayed!(objects, src, Image),
+            src_region,
+            readback: Readback::new(),
+        },
+        CapturedCommandInner::ReadbackBuffer { src, offset, size } => {
+            CommandInner::ReadbackBuffer {
+                src: replayed!(objects, src, Buffer),
+                offset,
+                size,
+                readback: Readback::new(),
+            }
+        }
+        CapturedCommandInner::BeginProfileScope { ref name } => CommandInner::BeginProfileScope {
+            name: str::from_utf8(arena.misc.alloc_extend(name.bytes())).unwrap(),
+        },
+        CapturedCommandInner::EndProfileScope => CommandInner::EndProfileScope,
+        CapturedCommandInner::PushDebugGroup { ref name } => CommandInner::PushDebugGroup {
+            name: str::from_utf8(arena.misc.alloc_extend(name.bytes())).unwrap(),
+        },
+        CapturedCommandInner::PopDebugGroup => CommandInner::PopDebugGroup,
+        CapturedCommandInner::InsertDebugMarker { ref name } => CommandInner::InsertDebugMarker {
+            name: str::from_utf8(arena.misc.alloc_extend(name.bytes())).unwrap(),
+        },
+        CapturedCommandInner::BeginQuery { pool, index } => CommandInner::BeginQuery {
+            pool: replayed!(objects, pool, QueryPool),
+            index,
+        },
+        CapturedCommandInner::EndQuery { pool, index } => CommandInner::EndQuery {
+            pool: replayed!(objects, pool, QueryPool),
+            index,
+        },
+        // the results read back during replay are discarded
+        CapturedCommandInner::ReadbackQueries { pool, first, count } => {
+            CommandInner::ReadbackQueries {
+                pool: replayed!(objects, pool, QueryPool),
+                first,
+                count,
+                readback: Readback::new(),
+            }
+        }
+        CapturedCommandInner::BeginConditionalRendering { pool, index } => {
+            CommandInner::BeginConditionalRendering {
+                pool: replayed!(objects, pool, QueryPool),
+                index,
+            }
+        }
+        CapturedCommandInner::EndConditionalRendering => CommandInner::EndConditionalRendering,
+        CapturedCommandInner::BlitImage {
+            src,
+            src_mip_level,
+            src_offsets,
+            dst,
+            dst_mip_level,
+            dst_offsets,
+            filter,
+        } => CommandInner::BlitImage {
+            src: replayed!(objects, src, Image),
+            src_mip_level,
+            src_offsets,
+            dst: replayed!(objects, dst, Image),
+            dst_mip_level,
+            dst_offsets,
+            filter,
+        },
+        CapturedCommandInner::ClearImageFloat { image, color } => CommandInner::ClearImageFloat {
+            image: replayed!(objects, image, Image),
+            color,
+        },
+        CapturedCommandInner::ClearDepthStencilImage {
+            image,
+            depth,
+            stencil,
+        } => CommandInner::ClearDepthStencilImage {
+            image: replayed!(objects, image, Image),
+            depth,
+            stencil,
+        },
+        CapturedCommandInner::Present { image, swapchain } => CommandInner::Present {
+            image: replayed!(objects, image, Image),
+            swapchain: replayed!(objects, swapchain, Swapchain),
+        },
+        CapturedCommandInner::DrawHeader { pipeline } => CommandInner::DrawHeader {
+            pipeline: replayed!(objects, pipeline, GraphicsPipeline),
+        },
+        CapturedCommandInner::DispatchHeader { pipeline } => CommandInner::DispatchHeader {
+            pipeline: replayed!(objects, pipeline, ComputePipeline),
+        },
+        CapturedCommandInner::SetPipelineArguments { arguments } => {
+            CommandInner::SetPipelineArguments {
+                arguments: replayed!(objects, arguments, ArgumentBlock),
+                // barriers are already in the command stream
+                accesses: &[],
+            }
+        }
+        CapturedCommandInner::Draw {
+            vertex_count,
+            instance_count,
+            first_vertex,
+            first_instance,
+        } => CommandInner::Draw {
+            vertex_count,
+            instance_count,
+            first_vertex,
+            first_instance,
+        },
+        CapturedCommandInner::DrawIndexed {
+            index_count,
+            instance_count,
+            first_index,
+            vertex_offset,
+            first_instance,
+        } => CommandInner::DrawIndexed {
+            index_count,
+            instance_count,
+            first_index,
+            vertex_offset,
+            first_instance,
+        },
+        CapturedCommandInner::DrawIndirect {
+            indirect_buffer,
+            offset,
+            draw_count,
+            count_buffer,
+        } => CommandInner::DrawIndirect {
+            indirect_buffer: replayed!(objects, indirect_buffer, Buffer),
+            offset,
+            draw_count,
+            count_buffer: match count_buffer {
+                Some(r) => Some(replayed!(objects, r, Buffer)),
+                None => None,
+            },
+        },
+        CapturedCommandInner::DrawIndexedIndirect {
+            indirect_buffer,
+            offset,
+            draw_count,
+            count_buffer,
+        } => CommandInner::DrawIndexedIndirect {
+            indirect_buffer: replayed!(objects, indirect_buffer, Buffer),
+            offset,
+            draw_count,
+            count_buffer: match count_buffer {
+                Some(r) => Some(replayed!(objects, r, Buffer)),
+                None => None,
+            },
+        },
+        CapturedCommandInner::Dispatch { x, y, z } => CommandInner::Dispatch { x, y, z },
+        CapturedCommandInner::DispatchIndirect {
+            indirect_buffer,
+            offset,
+        } => CommandInner::DispatchIndirect {
+            indirect_buffer: replayed!(objects, indirect_buffer, Buffer),
+            offset,
+        },
+    })
+}
@@ -1,9 +1,17 @@
 use crate::{
-    image::{DepthStencilView, Image2dView, RenderTargetView},
-    pipeline::{GraphicsPipeline, IntoArgumentBlock, Signature},
+    barrier::{AccessFlags, ResourceAccess},
+    buffer::{Buffer, BufferTypeless},
+    image::{DepthStencilView, Filter, Image2dView, ImageRegion, ImageView, RenderTargetView},
+    pipeline::{
+        ArgumentBlock, BareArgumentBlock, ComputePipeline, GraphicsPipeline, IntoArgumentBlock,
+        Signature,
+    },
+    query::QueryPool,
+    readback::Readback,
     swapchain::Swapchain,
-    Arena, Backend,
+    Arena, Backend, Queue,
 };
+use std::{cmp::Reverse, collections::BinaryHeap, iter::Peekable, mem, vec};
 
 /// Represents a command to be executed by the renderer backend.
 ///
@@ -15,11 +23,16 @@ use crate::{
 #[derive(Clone)]
 pub struct Command<'a, B: Backend> {
     pub sortkey: u64,
+    /// Queue on which the command is executed. See [crate::schedule].
+    pub queue: Queue,
     pub cmd: CommandInner<'a, B>,
 }
 
 /// Parameters for non-indexed draw commands.
+///
+/// The layout matches the one expected by indirect draw commands.
 #[derive(Copy, Clone, Debug)]
+#[repr(C)]
 pub struct DrawParams {
     pub vertex_count: u32,
     pub instance_count: u32,
@@ -40,7 +53,10 @@ impl DrawParams {
 }
 
 /// Parameters for indexed draw commands.
+///
+/// The layout matches the one expected by indirect draw commands.
 #[derive(Copy, Clone, Debug)]
+#[repr(C)]
 pub struct DrawIndexedParams {
     pub index_count: u32,
     pub instance_count: u32,
@@ -49,11 +65,80 @@ pub struct DrawIndexedParams {
     pub first_instance: u32,
 }
 
+/// Parameters for indirect dispatch commands (number of work groups in each dimension).
+#[derive(Copy, Clone, Debug)]
+#[repr(C)]
+pub struct DispatchParams {
+    pub x: u32,
+    pub y: u32,
+    pub z: u32,
+}
+
 #[derive(derivative::Derivative)]
 #[derivative(Clone(bound = ""))]
 pub enum CommandInner<'a, B: Backend> {
     // MAIN (LEAD-IN) COMMANDS ---------------------------------------------------------------------
-    PipelineBarrier {},
+    /// Makes the results of previous accesses (`src_access_mask`) visible to
+    /// subsequent accesses (`dst_access_mask`).
+    ///
+    /// Barriers are normally inserted automatically after sorting (see [crate::barrier]).
+    PipelineBarrier {
+        src_access_mask: AccessFlags,
+        dst_access_mask: AccessFlags,
+    },
+    CopyBuffer {
+        src: &'a B::Buffer,
+        src_offset: usize,
+        dst: &'a B::Buffer,
+        dst_offset: usize,
+        size: usize,
+    },
+    CopyBufferToImage {
+        src: &'a B::Buffer,
+        src_offset: usize,
+        dst: &'a B::Image,
+        dst_region: ImageRegion,
+    },
+    CopyImageToBuffer {
+        src: &'a B::Image,
+        src_region: ImageRegion,
+        dst: &'a B::Buffer,
+        dst_offset: usize,
+    },
+    CopyImage {
+        src: &'a B::Image,
+        src_region: ImageRegion,
+        dst: &'a B::Image,
+        dst_region: ImageRegion,
+    },
+    FillBuffer {
+        dst: &'a B::Buffer,
+        offset: usize,
+        size: usize,
+        value: u32,
+    },
+    /// Reads back a region of an image to host memory. See [crate::readback].
+    ReadbackImage {
+        src: &'a B::Image,
+        src_region: ImageRegion,
+        readback: Readback,
+    },
+    /// Reads back a range of a buffer to host memory. See [crate::readback].
+    ReadbackBuffer {
+        src: &'a B::Buffer,
+        offset: usize,
+        size: usize,
+        readback: Readback,
+    },
+    BlitImage {
+        src: &'a B::Image,
+        src_mip_level: u32,
+        src_offsets: [(u32, u32); 2],
+        dst: &'a B::Image,
+        dst_mip_level: u32,
+        dst_offsets: [(u32, u32); 2],
+        filter: Filter,
+    },
     ClearImageFloat {
         image: &'a B::Image,
         color: [f32; 4],
@@ -67,13 +152,56 @@ pub enum CommandInner<'a, B: Backend> {
         image: &'a B::Image,
         swapchain: &'a B::Swapchain,
     },
+    /// Starts a profile scope. See [crate::profile].
+    BeginProfileScope {
+        name: &'a str,
+    },
+    /// Ends the innermost profile scope.
+    EndProfileScope,
+    /// Opens a debug group. See [crate::debug].
+    PushDebugGroup {
+        name: &'a str,
+    },
+    /// Closes the innermost debug group.
+    PopDebugGroup,
+    InsertDebugMarker {
+        name: &'a str,
+    },
+    /// Starts query `index` of the pool. See [crate::query].
+    BeginQuery {
+        pool: &'a B::QueryPool,
+        index: u32,
+    },
+    EndQuery {
+        pool: &'a B::QueryPool,
+        index: u32,
+    },
+    /// Reads back the results of `count` queries of the pool, starting at `first`, to host memory.
+    ReadbackQueries {
+        pool: &'a B::QueryPool,
+        first: u32,
+        count: u32,
+        readback: Readback,
+    },
+    /// Discards the following draw commands if occlusion query `index` of the pool
+    /// passed no samples.
+    BeginConditionalRendering {
+        pool: &'a B::QueryPool,
+        index: u32,
+    },
+    EndConditionalRendering,
     DrawHeader {
         pipeline: &'a B::GraphicsPipeline,
     },
+    DispatchHeader {
+        pipeline: &'a B::ComputePipeline,
+    },
 
     // STATE CHANGE COMMANDS -----------------------------------------------------------------------
     SetPipelineArguments {
         arguments: &'a B::ArgumentBlock,
+        /// Resources accessed by the pipeline through the arguments.
+        accesses: &'a [ResourceAccess<'a, B>],
     },
 
     // DRAW (LEAD-OUT) COMMANDS --------------------------------------------------------------------
@@ -90,11 +218,67 @@ pub enum CommandInner<'a, B: Backend> {
         vertex_offset: i32,
         first_instance: u32,
     },
+    /// Draws with parameters read from `indirect_buffer` at byte offset `offset`.
+    ///
+    /// If `count_buffer` is not `None`, the actual number of draws is read from it,
+    /// and `draw_count` is the maximum number of draws.
+    DrawIndirect {
+        indirect_buffer: &'a B::Buffer,
+        offset: usize,
+        draw_count: u32,
+        count_buffer: Option<&'a B::Buffer>,
+    },
+    DrawIndexedIndirect {
+        indirect_buffer: &'a B::Buffer,
+        offset: usize,
+        draw_count: u32,
+        count_buffer: Option<&'a B::Buffer>,
+    },
+    Dispatch {
+        x: u32,
+        y: u32,
+        z: u32,
+    },
+    DispatchIndirect {
+        indirect_buffer: &'a B::Buffer,
+        offset: usize,
+    },
+}
+
+impl<'a, B: Backend> CommandInner<'a, B> {
+    /// Returns the queue on which the command executes by default.
+    ///
+    /// Copies, uploads and readbacks go to the transfer queue, and dispatches go to the compute
+    /// queue. Returns `None` for commands that belong to the same operation as the previous
+    /// command (pipeline arguments and explicit barriers).
+    pub fn default_queue(&self) -> Option<Queue> {
+        match *self {
+            CommandInner::CopyBuffer { .. }
+            | CommandInner::CopyBufferToImage { .. }
+            | CommandInner::CopyImageToBuffer { .. }
+            | CommandInner::CopyImage { .. }
+            | CommandInner::FillBuffer { .. }
+            | CommandInner::ReadbackImage { .. }
+            | CommandInner::ReadbackBuffer { .. } => Some(Queue::Transfer),
+            CommandInner::DispatchHeader { .. }
+            | CommandInner::Dispatch { .. }
+            | CommandInner::DispatchIndirect { .. } => Some(Queue::Compute),
+            CommandInner::PipelineBarrier { .. }
+            | CommandInner::SetPipelineArguments { .. }
+            | CommandInner::BeginProfileScope { .. }
+            | CommandInner::EndProfileScope
+            | CommandInner::PushDebugGroup { .. }
+            | CommandInner::PopDebugGroup
+            | CommandInner::InsertDebugMarker { .. } => None,
+            _ => Some(Queue::Graphics),
+        }
+    }
 }
 
 /// Command buffers contain a list of commands.
 pub struct CommandBuffer<'a, B: Backend> {
     commands: Vec<Command<'a, B>>,
+    queue: Option<Queue>,
 }
 
 /// API exposed by command buffers.
@@ -103,11 +287,38 @@ impl<'a, B: Backend> CommandBuffer<'a, B> {
     pub(super) fn new() -> CommandBuffer<'a, B> {
         CommandBuffer {
             commands: Vec::new(),
+            queue: None,
+        }
+    }
+
+    /// Creates a command buffer whose commands all execute on the specified queue.
+    pub fn with_queue(queue: Queue) -> CommandBuffer<'a, B> {
+        CommandBuffer {
+            commands: Vec::new(),
+            queue: Some(queue),
         }
     }
 
+    /// Sets the queue of the commands pushed afterwards.
+    ///
+    /// If `None`, the queue is inferred from the kind of command
+    /// (see [CommandInner::default_queue]).
+    pub fn set_queue(&mut self, queue: Option<Queue>) {
+        self.queue = queue;
+    }
+
+    /// Pushes a raw command into the command buffer.
     fn push_command(&mut self, sortkey: u64, cmd: CommandInner<'a, B>) {
-        self.commands.push(Command { cmd, sortkey })
+        let queue = self
+            .queue
+            .or_else(|| cmd.default_queue())
+            .or_else(|| self.commands.last().map(|c| c.queue))
+            .unwrap_or(Queue::Graphics);
+        self.commands.push(Command {
+            cmd,
+            sortkey,
+            queue,
+        })
     }
 
     pub fn iter(&self) -> impl Iterator<Item = &Command<'a, B>> {
@@ -117,17 +328,199 @@ impl<'a, B: Backend> CommandBuffer<'a, B> {
     //----------------------------------------------------------------------------------------------
     // Copy
 
-    /*/// Copy data between buffers.
+    /// Copies `size` bytes of data between buffers.
     pub fn copy_buffer(
         &mut self,
-        _sort_key: u64,
-        _src: BufferTypeless<'a, B>,
-        _dst: BufferTypeless<'a, B>,
-        _src_range: Range<u64>,
-        _dst_range: Range<u64>,
+        sortkey: u64,
+        src: impl Into<BufferTypeless<'a, B>>,
+        src_offset: usize,
+        dst: impl Into<BufferTypeless<'a, B>>,
+        dst_offset: usize,
+        size: usize,
+    ) {
+        self.push_command(
+            sortkey,
+            CommandInner::CopyBuffer {
+                src: src.into().0,
+                src_offset,
+                dst: dst.into().0,
+                dst_offset,
+                size,
+            },
+        )
+    }
+
+    /// Copies data from a buffer to a region of an image.
+    ///
+    /// The data in the buffer must be tightly packed, and in the same format as the image.
+    pub fn copy_buffer_to_image(
+        &mut self,
+        sortkey: u64,
+        src: impl Into<BufferTypeless<'a, B>>,
+        src_offset: usize,
+        dst: impl Into<ImageView<'a, B>>,
+        dst_region: ImageRegion,
     ) {
-        unimplemented!()
-    }*/
+        self.push_command(
+            sortkey,
+            CommandInner::CopyBufferToImage {
+                src: src.into().0,
+                src_offset,
+                dst: dst.into().image,
+                dst_region,
+            },
+        )
+    }
+
+    /// Copies a region of an image to a buffer.
+    ///
+    /// The data is tightly packed in the buffer, and in the same format as the image.
+    pub fn copy_image_to_buffer(
+        &mut self,
+        sortkey: u64,
+        src: impl Into<ImageView<'a, B>>,
+        src_region: ImageRegion,
+        dst: impl Into<BufferTypeless<'a, B>>,
+        dst_offset: usize,
+    ) {
+        self.push_command(
+            sortkey,
+            CommandInner::CopyImageToBuffer {
+                src: src.into().image,
+                src_region,
+                dst: dst.into().0,
+                dst_offset,
+            },
+        )
+    }
+
+    /// Copies a region of an image to another image.
+    ///
+    /// The regions must have the same extents. No format conversion is performed.
+    pub fn copy_image(
+        &mut self,
+        sortkey: u64,
+        src: impl Into<ImageView<'a, B>>,
+        src_region: ImageRegion,
+        dst: impl Into<ImageView<'a, B>>,
+        dst_region: ImageRegion,
+    ) {
+        assert_eq!(
+            (src_region.extent, src_region.layer_count),
+            (dst_region.extent, dst_region.layer_count),
+            "source and destination regions should have the same size"
+        );
+        self.push_command(
+            sortkey,
+            CommandInner::CopyImage {
+                src: src.into().image,
+                src_region,
+                dst: dst.into().image,
+                dst_region,
+            },
+        )
+    }
+
+    /// Fills a range of a buffer with a 32-bit value.
+    ///
+    /// `offset` and `size` must be multiples of 4.
+    pub fn fill_buffer(
+        &mut self,
+        sortkey: u64,
+        dst: impl Into<BufferTypeless<'a, B>>,
+        offset: usize,
+        size: usize,
+        value: u32,
+    ) {
+        assert!(
+            offset % 4 == 0 && size % 4 == 0,
+            "offset and size should be multiples of 4"
+        );
+        self.push_command(
+            sortkey,
+            CommandInner::FillBuffer {
+                dst: dst.into().0,
+                offset,
+                size,
+                value,
+            },
+        )
+    }
+
+    /// Reads back a region of an image to host memory.
+    ///
+    /// Returns a handle that resolves to the contents of the region once the frame has finished
+    /// executing on the GPU. The data is tightly packed, and in the same format as the image.
+    pub fn readback_image(
+        &mut self,
+        sortkey: u64,
+        src: impl Into<ImageView<'a, B>>,
+        src_region: ImageRegion,
+    ) -> Readback {
+        let readback = Readback::new();
+        self.push_command(
+            sortkey,
+            CommandInner::ReadbackImage {
+                src: src.into().image,
+                src_region,
+                readback: readback.clone(),
+            },
+        );
+        readback
+    }
+
+    /// Reads back a range of a buffer to host memory.
+    ///
+    /// Returns a handle that resolves to the contents of the range once the frame has finished
+    /// executing on the GPU.
+    pub fn readback_buffer(
+        &mut self,
+        sortkey: u64,
+        src: impl Into<BufferTypeless<'a, B>>,
+        offset: usize,
+        size: usize,
+    ) -> Readback {
+        let readback = Readback::new();
+        self.push_command(
+            sortkey,
+            CommandInner::ReadbackBuffer {
+                src: src.into().0,
+                offset,
+                size,
+                readback: readback.clone(),
+            },
+        );
+        readback
+    }
+
+    /// Copies a rectangle of an image to another, scaling and filtering if necessary.
+    ///
+    /// The rectangles are specified by their two corners. If a corner has
+    /// a smaller coordinate than the other, the image is flipped along the corresponding axis.
+    pub fn blit_image(
+        &mut self,
+        sortkey: u64,
+        src: impl Into<Image2dView<'a, B>>,
+        src_offsets: [(u32, u32); 2],
+        dst: impl Into<Image2dView<'a, B>>,
+        dst_offsets: [(u32, u32); 2],
+        filter: Filter,
+    ) {
+        let src = src.into();
+        let dst = dst.into();
+        self.push_command(
+            sortkey,
+            CommandInner::BlitImage {
+                src: src.image,
+                src_mip_level: src.subresource.base_mip_level,
+                src_offsets,
+                dst: dst.image,
+                dst_mip_level: dst.subresource.base_mip_level,
+                dst_offsets,
+                filter,
+            },
+        )
+    }
 
     //----------------------------------------------------------------------------------------------
     // Clear
@@ -174,10 +567,16 @@ impl<'a, B: Backend> CommandBuffer<'a, B> {
         &mut self,
         sortkey: u64,
         pipeline: &'a B::GraphicsPipeline,
-        arguments: &'a B::ArgumentBlock,
+        arguments: BareArgumentBlock<'a, B>,
     ) {
         self.push_command(sortkey, CommandInner::DrawHeader { pipeline });
-        self.push_command(sortkey, CommandInner::SetPipelineArguments { arguments })
+        self.push_command(
+            sortkey,
+            CommandInner::SetPipelineArguments {
+                arguments: arguments.0,
+                accesses: arguments.1,
+            },
+        )
     }
 
     // S: Signature<'a,B> P: Into<ArgumentBlock<'a,B,S>>
@@ -189,8 +588,8 @@ impl<'a, B: Backend> CommandBuffer<'a, B> {
         arguments: P,
         params: DrawParams,
     ) {
-        let arguments = arguments.into_block(pipeline.signature, arena);
-        self.set_pipeline(sortkey, pipeline.inner, arguments.arguments);
+        let arguments = into_block(arguments, pipeline.signature, arena);
+        self.set_pipeline(sortkey, pipeline.inner, arguments.into());
         self.push_command(
             sortkey,
             CommandInner::Draw {
@@ -210,8 +609,8 @@ impl<'a, B: Backend> CommandBuffer<'a, B> {
         arguments: P,
         params: DrawIndexedParams,
     ) {
-        let arguments = arguments.into_block(pipeline.signature, arena);
-        self.set_pipeline(sortkey, pipeline.inner, arguments.arguments);
+        let arguments = into_block(arguments, pipeline.signature, arena);
+        self.set_pipeline(sortkey, pipeline.inner, arguments.into());
         self.push_command(
             sortkey,
             CommandInner::DrawIndexed {
@@ -224,6 +623,279 @@ impl<'a, B: Backend> CommandBuffer<'a, B> {
         );
     }
 
+    /// Draws with the parameters stored at index `index` in the buffer `indirect`.
+    pub fn draw_indirect<S: Signature<'a, B>, P: IntoArgumentBlock<'a, B, S>>(
+        &mut self,
+        sortkey: u64,
+        arena: &'a Arena<B>,
+        pipeline: GraphicsPipeline<'a, B, S>,
+        arguments: P,
+        indirect: Buffer<'a, B, [DrawParams]>,
+        index: usize,
+    ) {
+        self.multi_draw_indirect(
+            sortkey, arena, pipeline, arguments, indirect, index, 1, None,
+        )
+    }
+
+    /// Issues `draw_count` draws with the parameters stored in the buffer `indirect`,
+    /// starting at index `first`. The draws must not read past the end of `indirect`.
+    ///
+    /// If `count` is not `None`, the number of draws is read from the GPU buffer instead, and
+    /// `draw_count` is the maximum number of draws that can be issued.
+    pub fn multi_draw_indirect<S: Signature<'a, B>, P: IntoArgumentBlock<'a, B, S>>(
+        &mut self,
+        sortkey: u64,
+        arena: &'a Arena<B>,
+        pipeline: GraphicsPipeline<'a, B, S>,
+        arguments: P,
+        indirect: Buffer<'a, B, [DrawParams]>,
+        first: usize,
+        draw_count: u32,
+        count: Option<Buffer<'a, B, u32>>,
+    ) {
+        let arguments = into_block(arguments, pipeline.signature, arena);
+        self.set_pipeline(sortkey, pipeline.inner, arguments.into());
+        self.push_command(
+            sortkey,
+            CommandInner::DrawIndirect {
+                indirect_buffer: indirect.0,
+                offset: first * mem::size_of::<DrawParams>(),
+                draw_count,
+                count_buffer: count.map(|c| c.0),
+            },
+        );
+    }
+
+    /// Indexed version of [draw_indirect](CommandBuffer::draw_indirect).
+    pub fn draw_indexed_indirect<S: Signature<'a, B>, P: IntoArgumentBlock<'a, B, S>>(
+        &mut self,
+        sortkey: u64,
+        arena: &'a Arena<B>,
+        pipeline: GraphicsPipeline<'a, B, S>,
+        arguments: P,
+        indirect: Buffer<'a, B, [DrawIndexedParams]>,
+        index: usize,
+    ) {
+        self.multi_draw_indexed_indirect(
+            sortkey, arena, pipeline, arguments, indirect, index, 1, None,
+        )
+    }
+
+    /// Indexed version of [multi_draw_indirect](CommandBuffer::multi_draw_indirect).
+    pub fn multi_draw_indexed_indirect<S: Signature<'a, B>, P: IntoArgumentBlock<'a, B, S>>(
+        &mut self,
+        sortkey: u64,
+        arena: &'a Arena<B>,
+        pipeline: GraphicsPipeline<'a, B, S>,
+        arguments: P,
+        indirect: Buffer<'a, B, [DrawIndexedParams]>,
+        first: usize,
+        draw_count: u32,
+        count: Option<Buffer<'a, B, u32>>,
+    ) {
+        let arguments = into_block(arguments, pipeline.signature, arena);
+        self.set_pipeline(sortkey, pipeline.inner, arguments.into());
+        self.push_command(
+            sortkey,
+            CommandInner::DrawIndexedIndirect {
+                indirect_buffer: indirect.0,
+                offset: first * mem::size_of::<DrawIndexedParams>(),
+                draw_count,
+                count_buffer: count.map(|c| c.0),
+            },
+        );
+    }
+
+    //----------------------------------------------------------------------------------------------
+    // Compute
+
+    fn set_compute_pipeline(
+        &mut self,
+        sortkey: u64,
+        pipeline: &'a B::ComputePipeline,
+        arguments: BareArgumentBlock<'a, B>,
+    ) {
+        self.push_command(sortkey, CommandInner::DispatchHeader { pipeline });
+        self.push_command(
+            sortkey,
+            CommandInner::SetPipelineArguments {
+                arguments: arguments.0,
+                accesses: arguments.1,
+            },
+        )
+    }
+
+    /// Dispatches a compute shader with the specified number of work groups in each dimension.
+    pub fn dispatch<S: Signature<'a, B>, P: IntoArgumentBlock<'a, B, S>>(
+        &mut self,
+        sortkey: u64,
+        arena: &'a Arena<B>,
+        pipeline: ComputePipeline<'a, B, S>,
+        arguments: P,
+        group_count: [u32; 3],
+    ) {
+        let arguments = into_block(arguments, pipeline.signature, arena);
+        self.set_compute_pipeline(sortkey, pipeline.inner, arguments.into());
+        self.push_command(
+            sortkey,
+            CommandInner::Dispatch {
+                x: group_count[0],
+                y: group_count[1],
+                z: group_count[2],
+            },
+        );
+    }
+
+    /// Dispatches a compute shader with the number of work groups stored at index `index` in the
+    /// buffer `indirect`.
+    pub fn dispatch_indirect<S: Signature<'a, B>, P: IntoArgumentBlock<'a, B, S>>(
+        &mut self,
+        sortkey: u64,
+        arena: &'a Arena<B>,
+        pipeline: ComputePipeline<'a, B, S>,
+        arguments: P,
+        indirect: Buffer<'a, B, [DispatchParams]>,
+        index: usize,
+    ) {
+        let arguments = into_block(arguments, pipeline.signature, arena);
+        self.set_compute_pipeline(sortkey, pipeline.inner, arguments.into());
+        self.push_command(
+            sortkey,
+            CommandInner::DispatchIndirect {
+                indirect_buffer: indirect.0,
+                offset: index * mem::size_of::<DispatchParams>(),
+            },
+        );
+    }
+
+    //----------------------------------------------------------------------------------------------
+    // Profiling
+
+    /// Starts a profile scope at the specified position in the command stream.
+    ///
+    /// The scope ends at the next [end_profile_scope](CommandBuffer::end_profile_scope) in the
+    /// sorted command stream. Scopes can be nested. See [crate::profile].
+    pub fn begin_profile_scope(&mut self, sortkey: u64, name: &'a str) {
+        self.push_command(sortkey, CommandInner::BeginProfileScope { name })
+    }
+
+    /// Ends the innermost profile scope open at the specified position in the command stream.
+    pub fn end_profile_scope(&mut self, sortkey: u64) {
+        self.push_command(sortkey, CommandInner::EndProfileScope)
+    }
+
+    //----------------------------------------------------------------------------------------------
+    // Debug labels
+
+    /// Opens a debug group at the specified position in the command stream.
+    ///
+    /// The group is closed by the next [pop_debug_group](CommandBuffer::pop_debug_group) in the
+    /// sorted command stream. Groups can be nested. See [crate::debug].
+    pub fn push_debug_group(&mut self, sortkey: u64, name: &'a str) {
+        self.push_command(sortkey, CommandInner::PushDebugGroup { name })
+    }
+
+    /// Closes the innermost debug group open at the specified position in the command stream.
+    pub fn pop_debug_group(&mut self, sortkey: u64) {
+        self.push_command(sortkey, CommandInner::PopDebugGroup)
+    }
+
+    /// Inserts a debug marker at the specified position in the command stream.
+    pub fn insert_debug_marker(&mut self, sortkey: u64, name: &'a str) {
+        self.push_command(sortkey, CommandInner::InsertDebugMarker { name })
+    }
+
+    //----------------------------------------------------------------------------------------------
+    // Queries
+
+    /// Starts a query. See [crate::query].
+    ///
+    /// The query measures the commands up to the matching [end_query](CommandBuffer::end_query)
+    /// in the sorted command stream.
+    pub fn begin_query(&mut self, sortkey: u64, pool: QueryPool<'a, B>, index: u32) {
+        assert!(index < pool.count, "query index out of range");
+        self.push_command(
+            sortkey,
+            CommandInner::BeginQuery {
+                pool: pool.inner,
+                index,
+            },
+        )
+    }
+
+    /// Ends a query.
+    pub fn end_query(&mut self, sortkey: u64, pool: QueryPool<'a, B>, index: u32) {
+        assert!(index < pool.count, "query index out of range");
+        self.push_command(
+            sortkey,
+            CommandInner::EndQuery {
+                pool: pool.inner,
+                index,
+            },
+        )
+    }
+
+    /// Reads back the results of `count` queries, starting at `first`, to host memory.
+    ///
+    /// Returns a handle that resolves to the results once the frame has finished executing
+    /// on the GPU. See [query_results](crate::query::query_results) to decode them.
+    pub fn readback_queries(
+        &mut self,
+        sortkey: u64,
+        pool: QueryPool<'a, B>,
+        first: u32,
+        count: u32,
+    ) -> Readback {
+        assert!(
+            first
+                .checked_add(count)
+                .map_or(false, |end| end <= pool.count),
+            "query range out of bounds of the pool"
+        );
+        let readback = Readback::new();
+        self.push_command(
+            sortkey,
+            CommandInner::ReadbackQueries {
+                pool: pool.inner,
+                first,
+                count,
+                readback: readback.clone(),
+            },
+        );
+        readback
+    }
+
+    /// Starts a range of conditional rendering: draws between this command and the next
+    /// [end_conditional_rendering](CommandBuffer::end_conditional_rendering) in the sorted
+    /// command stream are discarded if the occlusion query `index` passed no samples.
+    ///
+    /// The query must have ended before this command in the stream, or in a previous frame.
+    pub fn begin_conditional_rendering(
+        &mut self,
+        sortkey: u64,
+        pool: QueryPool<'a, B>,
+        index: u32,
+    ) {
+        assert!(
+            pool.ty.is_occlusion(),
+            "conditional rendering requires an occlusion query"
+        );
+        assert!(index < pool.count, "query index out of range");
+        self.push_command(
+            sortkey,
+            CommandInner::BeginConditionalRendering {
+                pool: pool.inner,
+                index,
+            },
+        )
+    }
+
+    /// Ends a range of conditional rendering.
+    pub fn end_conditional_rendering(&mut self, sortkey: u64) {
+        self.push_command(sortkey, CommandInner::EndConditionalRendering)
+    }
+
     //----------------------------------------------------------------------------------------------
     // Present
 
@@ -247,20 +919,235 @@ impl<'a, B: Backend> CommandBuffer<'a, B> {
     }
 }
 
-/// TODO optimize (radix sort, dense command buffer layout, separate index map)
+/// Creates the argument block of a draw or dispatch command.
+///
+/// Panics if the argument block could not be created: to handle the error, create the block
+/// beforehand with [Arena::create_typed_argument_block] or [Arena::create_argument_block].
+fn into_block<'a, B: Backend, S: Signature<'a, B>, P: IntoArgumentBlock<'a, B, S>>(
+    arguments: P,
+    signature: S,
+    arena: &'a Arena<B>,
+) -> ArgumentBlock<'a, B, S> {
+    arguments
+        .into_block(signature, arena)
+        .unwrap_or_else(|e| panic!("failed to create argument block: {}", e))
+}
+
+/// Merges command buffers into a single command stream sorted by sortkey.
+///
+/// The commands are moved (not cloned) into a dense array, and the (sortkey, index) pairs are
+/// sorted separately with a LSD radix sort. The sort is stable: commands with the same sortkey
+/// keep their submission order (command buffers are merged in iteration order).
 pub fn sort_command_buffers<'a, B: Backend>(
     cmdbufs: impl IntoIterator<Item = CommandBuffer<'a, B>>,
 ) -> Vec<Command<'a, B>> {
     let mut fused = Vec::new();
-    //let mut sortkeys = Vec::new();
-    //let mut i: usize = 0;
-    for cmdbuf in cmdbufs.into_iter() {
-        for cmd in cmdbuf.commands.iter() {
-            fused.push(cmd.clone());
-            //sortkeys.push(cmd.sortkey);
+    for mut cmdbuf in cmdbufs.into_iter() {
+        fused.append(&mut cmdbuf.commands);
+    }
+
+    assert!(
+        fused.len() <= u32::max_value() as usize,
+        "too many commands"
+    );
+    let mut keys: Vec<(u64, u32)> = fused
+        .iter()
+        .enumerate()
+        .map(|(i, cmd)| (cmd.sortkey, i as u32))
+        .collect();
+    radix_sort(&mut keys);
+
+    // reorder the commands in place by following the cycles of the permutation
+    let mut order: Vec<u32> = keys.into_iter().map(|(_, i)| i).collect();
+    for i in 0..order.len() {
+        let mut cur = i;
+        while order[cur] as usize != i {
+            let next = order[cur] as usize;
+            fused.swap(cur, next);
+            order[cur] = cur as u32;
+            cur = next;
         }
+        order[cur] = cur as u32;
     }
 
-    fused.sort_by(|cmd_a, cmd_b| cmd_a.sortkey.cmp(&cmd_b.sortkey));
     fused
 }
+
+/// Commands recorded once and submitted in several frames.
+///
+/// Built from a command buffer with [RecordedCommands::new], which sorts the commands once.
+/// The resources referenced by the commands must outlive all the frames in which the commands are
+/// submitted: typically, they are allocated in a long-lived arena.
+///
+/// Recorded commands are submitted alongside the command buffers of a frame with
+/// [Api::submit_frame_with_recorded](crate::Api::submit_frame_with_recorded), optionally with an
+/// offset added to their sortkeys. Since they are already sorted, they are merged into the sorted
+/// stream of the frame in linear time instead of being sorted again.
+///
+/// Readbacks complete only once, so commands that read back data cannot be recorded.
+pub struct RecordedCommands<'a, B: Backend> {
+    commands: Vec<Command<'a, B>>,
+}
+
+impl<'a, B: Backend> RecordedCommands<'a, B> {
+    /// Sorts the commands of a command buffer for resubmission.
+    ///
+    /// Panics if the command buffer contains readback commands.
+    pub fn new(cmdbuf: CommandBuffer<'a, B>) -> RecordedCommands<'a, B> {
+        let commands = sort_command_buffers(Some(cmdbuf));
+        for cmd in commands.iter() {
+            match cmd.cmd {
+                CommandInner::ReadbackImage { .. }
+                | CommandInner::ReadbackBuffer { .. }
+                | CommandInner::ReadbackQueries { .. } => {
+                    panic!("readback commands cannot be recorded for resubmission")
+                }
+                _ => {}
+            }
+        }
+        RecordedCommands { commands }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Command<'a, B>> {
+        self.commands.iter()
+    }
+
+    /// Returns the number of recorded commands.
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+}
+
+/// Merges sorted runs of recorded commands into a sorted command stream.
+///
+/// The offset of each run is added to the sortkeys of its commands. The merge is stable: commands
+/// with the same sortkey are ordered by run, with `commands` first, then the recorded runs in
+/// iteration order. Panics if adding an offset overflows a sortkey.
+///
+/// The merged stream is not built: the returned iterator walks the runs by index, and yields
+/// each recorded command only when it is consumed. Consuming it (e.g. with
+/// [insert_pipeline_barriers](crate::barrier::insert_pipeline_barriers)) writes each recorded
+/// command once, directly into the command stream submitted to the backend.
+pub fn merge_recorded_commands<'a, 'r, B: Backend>(
+    commands: Vec<Command<'a, B>>,
+    recorded: impl IntoIterator<Item = (&'r RecordedCommands<'a, B>, u64)>,
+) -> MergedCommands<'a, 'r, B>
+where
+    'a: 'r,
+{
+    let recorded: Vec<_> = recorded
+        .into_iter()
+        .filter(|(r, _)| !r.is_empty())
+        .collect();
+    let remaining = commands.len() + recorded.iter().map(|(r, _)| r.len()).sum::<usize>();
+    let heap = recorded
+        .iter()
+        .enumerate()
+        .map(|(run, &(r, offset))| Reverse((offset_sortkey(&r.commands[0], offset), run)))
+        .collect();
+    MergedCommands {
+        transient: commands.into_iter().peekable(),
+        positions: vec![0; recorded.len()],
+        recorded,
+        heap,
+        remaining,
+    }
+}
+
+fn offset_sortkey<B: Backend>(cmd: &Command<B>, offset: u64) -> u64 {
+    cmd.sortkey
+        .checked_add(offset)
+        .expect("sortkey overflow in recorded commands")
+}
+
+/// Iterator over a command stream merged with recorded commands.
+///
+/// See [merge_recorded_commands].
+pub struct MergedCommands<'a, 'r, B: Backend> {
+    transient: Peekable<vec::IntoIter<Command<'a, B>>>,
+    recorded: Vec<(&'r RecordedCommands<'a, B>, u64)>,
+    /// Position in each recorded run.
+    positions: Vec<usize>,
+    /// Min-heap of (sortkey, run) of the next command of each run.
+    heap: BinaryHeap<Reverse<(u64, usize)>>,
+    remaining: usize,
+}
+
+impl<'a, 'r, B: Backend> Iterator for MergedCommands<'a, 'r, B> {
+    type Item = Command<'a, B>;
+
+    fn next(&mut self) -> Option<Command<'a, B>> {
+        let run = match (self.transient.peek(), self.heap.peek()) {
+            // transient commands go first on equal sortkeys
+            (Some(cmd), Some(&Reverse((sortkey, _)))) if cmd.sortkey <= sortkey => None,
+            (_, Some(&Reverse((_, run)))) => Some(run),
+            (_, None) => None,
+        };
+        let cmd = match run {
+            None => self.transient.next()?,
+            Some(run) => {
+                let Reverse((sortkey, _)) = self.heap.pop().unwrap();
+                let (r, offset) = self.recorded[run];
+                let cmd = &r.commands[self.positions[run]];
+                self.positions[run] += 1;
+                if let Some(next) = r.commands.get(self.positions[run]) {
+                    self.heap.push(Reverse((offset_sortkey(next, offset), run)));
+                }
+                // recorded commands only hold references and plain data (no readbacks)
+                Command {
+                    sortkey,
+                    queue: cmd.queue,
+                    cmd: cmd.cmd.clone(),
+                }
+            }
+        };
+        self.remaining -= 1;
+        Some(cmd)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, 'r, B: Backend> ExactSizeIterator for MergedCommands<'a, 'r, B> {}
+
+/// Stable LSD radix sort of (sortkey, index) pairs on the sortkey, 8 bits at a time.
+///
+/// Passes on bytes that are the same for all keys are skipped.
+fn radix_sort(keys: &mut Vec<(u64, u32)>) {
+    if keys.len() <= 1 {
+        return;
+    }
+
+    let mut histograms = [[0usize; 256]; 8];
+    for &(k, _) in keys.iter() {
+        for (pass, h) in histograms.iter_mut().enumerate() {
+            h[((k >> (pass * 8)) & 0xFF) as usize] += 1;
+        }
+    }
+
+    let mut tmp = vec![(0u64, 0u32); keys.len()];
+    for (pass, h) in histograms.iter().enumerate() {
+        if h.iter().any(|&n| n == keys.len()) {
+            // all keys have the same byte
+            continue;
+        }
+        let mut offsets = [0usize; 256];
+        let mut total = 0;
+        for (o, &n) in offsets.iter_mut().zip(h.iter()) {
+            *o = total;
+            total += n;
+        }
+        for &(k, i) in keys.iter() {
+            let b = ((k >> (pass * 8)) & 0xFF) as usize;
+            tmp[offsets[b]] = (k, i);
+            offsets[b] += 1;
+        }
+        mem::swap(keys, &mut tmp);
+    }
+}
@@ -0,0 +1,106 @@
+//! Debug labels for graphics debuggers.
+//!
+//! Objects can be given a name, which shows up in graphics debuggers such as RenderDoc:
+//! images with the `debug_name` method of image builders, and all objects with
+//! [Api::set_debug_name](crate::Api::set_debug_name).
+//!
+//! The command stream can be structured with debug groups and markers, using
+//! [CommandBuffer::push_debug_group](crate::command::CommandBuffer::push_debug_group) and
+//! [CommandBuffer::insert_debug_marker](crate::command::CommandBuffer::insert_debug_marker).
+//! Like profile scopes, debug groups nest according to their position in the sorted stream, not
+//! in the order in which they were recorded: a group pushed at sortkey 0x100 and popped at
+//! sortkey 0x1FF contains all commands in between, whatever command buffer they come from.
+//!
+//! ```ignore
+//! let gbuffer = arena
+//!     .render_target(Format::R16G16B16A16_SFLOAT, w, h)
+//!     .debug_name("gbuffer")
+//!     .build()?;
+//! api.set_debug_name(lighting_pipeline, "deferred lighting");
+//! cmdbuf.push_debug_group(0x100, "geometry");
+//! // ... draws, sortkeys 0x100..0x1FF ...
+//! cmdbuf.pop_debug_group(0x1FF);
+//! ```
+//!
+//! Debug labels have no effect on rendering. Backends without debugging support ignore them.
+use crate::{
+    buffer::{Buffer, BufferData, BufferTypeless},
+    image::{DepthStencilImage2d, Image1d, Image2d, Image3d, RenderTargetImage2d, UnsafeImage},
+    pipeline::{
+        ArgumentBlock, BareArgumentBlock, ComputePipeline, GraphicsPipeline,
+        GraphicsPipelineTypeless, Signature,
+    },
+    Backend,
+};
+
+/// An object that can be given a debug name.
+#[derive(derivative::Derivative)]
+#[derivative(Copy(bound = ""), Clone(bound = ""), Debug(bound = ""))]
+pub enum DebugObject<'a, B: Backend> {
+    Image(&'a B::Image),
+    Buffer(&'a B::Buffer),
+    GraphicsPipeline(&'a B::GraphicsPipeline),
+    ComputePipeline(&'a B::ComputePipeline),
+    ArgumentBlock(&'a B::ArgumentBlock),
+}
+
+macro_rules! impl_from_image {
+    ($($t:ident),*) => {
+        $(impl<'a, B: Backend> From<$t<'a, B>> for DebugObject<'a, B> {
+            fn from(image: $t<'a, B>) -> Self {
+                DebugObject::Image(image.image)
+            }
+        })*
+    };
+}
+
+impl_from_image!(
+    UnsafeImage,
+    Image1d,
+    Image2d,
+    Image3d,
+    RenderTargetImage2d,
+    DepthStencilImage2d
+);
+
+impl<'a, B: Backend> From<BufferTypeless<'a, B>> for DebugObject<'a, B> {
+    fn from(buffer: BufferTypeless<'a, B>) -> Self {
+        DebugObject::Buffer(buffer.0)
+    }
+}
+
+impl<'a, B: Backend, T: BufferData + ?Sized> From<Buffer<'a, B, T>> for DebugObject<'a, B> {
+    fn from(buffer: Buffer<'a, B, T>) -> Self {
+        DebugObject::Buffer(BufferTypeless::from(buffer).0)
+    }
+}
+
+impl<'a, B: Backend, S: Signature<'a, B>> From<GraphicsPipeline<'a, B, S>> for DebugObject<'a, B> {
+    fn from(pipeline: GraphicsPipeline<'a, B, S>) -> Self {
+        DebugObject::GraphicsPipeline(pipeline.inner)
+    }
+}
+
+impl<'a, B: Backend> From<GraphicsPipelineTypeless<'a, B>> for DebugObject<'a, B> {
+    fn from(pipeline: GraphicsPipelineTypeless<'a, B>) -> Self {
+        DebugObject::GraphicsPipeline(pipeline.0)
+    }
+}
+
+impl<'a, B: Backend, S: Signature<'a, B>> From<ComputePipeline<'a, B, S>> for DebugObject<'a, B> {
+    fn from(pipeline: ComputePipeline<'a, B, S>) -> Self {
+        DebugObject::ComputePipeline(pipeline.inner)
+    }
+}
+
+impl<'a, B: Backend, S: Signature<'a, B>> From<ArgumentBlock<'a, B, S>> for DebugObject<'a, B> {
+    fn from(block: ArgumentBlock<'a, B, S>) -> Self {
+        DebugObject::ArgumentBlock(block.arguments)
+    }
+}
+
+impl<'a, B: Backend> From<BareArgumentBlock<'a, B>> for DebugObject<'a, B> {
+    fn from(block: BareArgumentBlock<'a, B>) -> Self {
+        DebugObject::ArgumentBlock(block.0)
+    }
+}
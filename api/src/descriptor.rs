@@ -1,11 +1,19 @@
 //! Descriptors
 use crate::{
-    buffer::BufferData, format::Format, image::SamplerDescription, pipeline::ShaderStageFlags,
-    typedesc::TypeDesc, Backend,
+    buffer::{BufferData, StructuredBufferData},
+    format::Format,
+    image::SamplerDescription,
+    pipeline::ShaderStageFlags,
+    typedesc::TypeDesc,
+    Backend,
 };
 use autograph_spirv::layout::Layout;
 use std::marker::PhantomData;
 
+/// Reference to host data, created with [Arena::host_reference](crate::Arena::host_reference).
+///
+/// Host references can be bound as constant buffers: the data is uploaded to the GPU
+/// when the frame is submitted.
 #[derive(Copy, Clone, Debug)]
 #[repr(transparent)]
 pub struct HostReference<'a, B: Backend, T: BufferData>(
@@ -96,6 +104,10 @@ pub enum Descriptor<'a, B: Backend> {
         offset: usize,
         size: Option<usize>,
     },
+    /// Constant buffer with the contents of host data, uploaded during `submit_frame`.
+    HostConstantBuffer {
+        reference: &'a B::HostReference,
+    },
     RwBuffer {
         buffer: &'a B::Buffer,
         offset: usize,
@@ -126,3 +138,12 @@ pub trait ResourceInterface<'a, B: Backend> {
     const DATA_FORMAT: Format = Format::UNDEFINED;
     fn into_descriptor(self) -> Descriptor<'a, B>;
 }
+
+impl<'a, B: Backend, T: StructuredBufferData> ResourceInterface<'a, B> for HostReference<'a, B, T> {
+    const TYPE: ResourceBindingType = ResourceBindingType::ConstantBuffer;
+    const DATA_TYPE: Option<&'static TypeDesc<'static>> = Some(&T::TYPE);
+    const DATA_LAYOUT: Option<&'static Layout<'static>> = Some(&T::LAYOUT);
+    fn into_descriptor(self) -> Descriptor<'a, B> {
+        Descriptor::HostConstantBuffer { reference: self.0 }
+    }
+}
@@ -0,0 +1,822 @@
+//! Type-erased backend.
+//!
+//! [DynBackend] is a backend whose objects are opaque handles, and whose instance is a boxed
+//! [DynInstance] trait object. Any backend can be wrapped into it with [into_dyn_instance]: an
+//! application can choose a backend at runtime, and code that renders through an `Api<DynBackend>`
+//! (e.g. plugins, or hot-reloaded code) is compiled once for all backends.
+//!
+//! ```ignore
+//! let instance = if use_gl {
+//!     into_dyn_instance::<OpenGlBackend>(gl_instance)
+//! } else {
+//!     into_dyn_instance::<MockBackend>(MockInstance::new())
+//! };
+//! let api = Api::<DynBackend>::new(instance)?;
+//! ```
+//!
+//! Handles are references to the objects of the wrapped backend: they are not checked when
+//! converted back, so objects must not be shared between instances of different backends.
+//! Windows are type-erased with [DynWindow], and checked when a swapchain is created.
+//!
+//! Compared to a generic backend, calls cannot be inlined, and argument blocks are collected into
+//! vectors before being forwarded.
+use crate::{
+    command::Command,
+    debug::DebugObject,
+    descriptor::Descriptor,
+    error::{Error, Result},
+    format::Format,
+    image::{DepthStencilView, Dimensions, ImageUsageFlags, MipmapsOption, RenderTargetView},
+    pipeline::{
+        BareArgumentBlock, ComputePipelineCreateInfo, GraphicsPipelineCreateInfo, Scissor,
+        ShaderStageFlags, SignatureDescription, Viewport,
+    },
+    profile::FrameProfile,
+    query::QueryType,
+    schedule::QueueBatch,
+    traits,
+    unwrap::{self, Unwrap},
+    vertex::{IndexBufferView, VertexBufferView},
+    AliasScope, Backend, Instance, MemoryType, Queue,
+};
+use std::{any::Any, fmt, sync::Mutex, time::Duration};
+use typed_arena::Arena;
+
+/// A backend with opaque objects, that wraps the instance of any other backend.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct DynBackend;
+
+impl Backend for DynBackend {
+    type Instance = Box<dyn DynInstance>;
+    type Arena = DynArena;
+    type Window = DynWindow;
+    type Swapchain = DynSwapchain;
+    type Image = DynImage;
+    type Buffer = DynBuffer;
+    type ShaderModule = DynShaderModule;
+    type GraphicsPipeline = DynGraphicsPipeline;
+    type ComputePipeline = DynComputePipeline;
+    type Signature = DynSignature;
+    type ArgumentBlock = DynArgumentBlock;
+    type HostReference = DynHostReference;
+    type QueryPool = DynQueryPool;
+}
+
+//--------------------------------------------------------------------------------------------------
+
+macro_rules! opaque_handle {
+    ($($(#[$m:meta])* $t:ident),*) => {
+        $(
+        $(#[$m])*
+        ///
+        /// Zero-sized: references to this type point to the object of the wrapped backend.
+        pub struct $t {
+            _private: [u8; 0],
+        }
+
+        impl fmt::Debug for $t {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "{}({:p})", stringify!($t), self)
+            }
+        }
+        )*
+    };
+}
+
+opaque_handle!(
+    /// Image of a [DynBackend].
+    DynImage,
+    /// Buffer of a [DynBackend].
+    DynBuffer,
+    /// Shader module of a [DynBackend].
+    DynShaderModule,
+    /// Graphics pipeline of a [DynBackend].
+    DynGraphicsPipeline,
+    /// Compute pipeline of a [DynBackend].
+    DynComputePipeline,
+    /// Signature of a [DynBackend].
+    DynSignature,
+    /// Argument block of a [DynBackend].
+    DynArgumentBlock,
+    /// Host reference of a [DynBackend].
+    DynHostReference,
+    /// Query pool of a [DynBackend].
+    DynQueryPool
+);
+
+/// Reinterprets a reference to an object of the wrapped backend as a handle, or the reverse.
+unsafe fn cast<T, U>(object: &T) -> &U {
+    &*(object as *const T as *const U)
+}
+
+/// Arena of a [DynBackend].
+pub struct DynArena {
+    /// The arena of the wrapped backend (`Box<B::Arena>`).
+    inner: *mut (),
+    swapchains: Arena<DynSwapchain>,
+}
+
+impl DynArena {
+    unsafe fn inner<B: Backend>(&self) -> &B::Arena {
+        &*(self.inner as *const B::Arena)
+    }
+}
+
+/// Window of a [DynBackend]: wraps the window type of the wrapped backend.
+pub struct DynWindow(Box<dyn Any>);
+
+impl DynWindow {
+    /// Wraps a window of the backend (e.g. an `Arc<GlWindow>` for OpenGL).
+    pub fn new<W: Any>(window: W) -> DynWindow {
+        DynWindow(Box::new(window))
+    }
+}
+
+/// Swapchain of a [DynBackend].
+///
+/// Unlike other objects, swapchains are not opaque, since the size of a swapchain can be queried
+/// without the instance.
+pub struct DynSwapchain {
+    inner: *const (),
+    size: unsafe fn(*const ()) -> (u32, u32),
+    generation: unsafe fn(*const ()) -> u64,
+}
+
+// The wrapped swapchain is Sync.
+unsafe impl Sync for DynSwapchain {}
+
+impl DynSwapchain {
+    fn new<B: Backend>(swapchain: &B::Swapchain) -> DynSwapchain {
+        unsafe fn size<B: Backend>(swapchain: *const ()) -> (u32, u32) {
+            traits::Swapchain::size(&*(swapchain as *const B::Swapchain))
+        }
+        unsafe fn generation<B: Backend>(swapchain: *const ()) -> u64 {
+            traits::Swapchain::generation(&*(swapchain as *const B::Swapchain))
+        }
+        DynSwapchain {
+            inner: swapchain as *const B::Swapchain as *const (),
+            size: size::<B>,
+            generation: generation::<B>,
+        }
+    }
+}
+
+impl fmt::Debug for DynSwapchain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DynSwapchain({:p})", self.inner)
+    }
+}
+
+impl traits::Swapchain for DynSwapchain {
+    fn size(&self) -> (u32, u32) {
+        unsafe { (self.size)(self.inner) }
+    }
+    fn generation(&self) -> u64 {
+        unsafe { (self.generation)(self.inner) }
+    }
+}
+
+impl<B: Backend> Unwrap<B> for DynBackend {
+    unsafe fn swapchain(swapchain: &DynSwapchain) -> &B::Swapchain {
+        &*(swapchain.inner as *const B::Swapchain)
+    }
+    unsafe fn image(image: &DynImage) -> &B::Image {
+        cast(image)
+    }
+    unsafe fn buffer(buffer: &DynBuffer) -> &B::Buffer {
+        cast(buffer)
+    }
+    unsafe fn shader_module(module: &DynShaderModule) -> &B::ShaderModule {
+        cast(module)
+    }
+    unsafe fn graphics_pipeline(pipeline: &DynGraphicsPipeline) -> &B::GraphicsPipeline {
+        cast(pipeline)
+    }
+    unsafe fn compute_pipeline(pipeline: &DynComputePipeline) -> &B::ComputePipeline {
+        cast(pipeline)
+    }
+    unsafe fn signature(signature: &DynSignature) -> &B::Signature {
+        cast(signature)
+    }
+    unsafe fn argument_block(block: &DynArgumentBlock) -> &B::ArgumentBlock {
+        cast(block)
+    }
+    unsafe fn host_reference(reference: &DynHostReference) -> &B::HostReference {
+        cast(reference)
+    }
+    unsafe fn query_pool(pool: &DynQueryPool) -> &B::QueryPool {
+        cast(pool)
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+
+/// Object-safe version of [Instance], implemented by the instances wrapped by [DynBackend].
+///
+/// The methods are the same as [Instance], except for
+/// [create_argument_block](DynInstance::create_argument_block), which takes slices instead of
+/// iterators.
+pub trait DynInstance {
+    unsafe fn create_arena(&self) -> Result<Box<DynArena>>;
+    unsafe fn drop_arena(&self, arena: Box<DynArena>);
+    unsafe fn create_swapchain<'a>(
+        &self,
+        arena: &'a DynArena,
+        window: &DynWindow,
+    ) -> Result<&'a DynSwapchain>;
+    unsafe fn default_swapchain<'a>(&'a self) -> Option<&'a DynSwapchain>;
+    unsafe fn create_image<'a>(
+        &self,
+        arena: &'a DynArena,
+        scope: AliasScope,
+        format: Format,
+        dimensions: Dimensions,
+        mipcount: MipmapsOption,
+        samples: u32,
+        usage: ImageUsageFlags,
+        initial_data: Option<&[u8]>,
+    ) -> Result<&'a DynImage>;
+    unsafe fn update_image(
+        &self,
+        image: &DynImage,
+        min_extent: (u32, u32, u32),
+        max_extent: (u32, u32, u32),
+        data: &[u8],
+    );
+    unsafe fn create_immutable_buffer<'a>(
+        &self,
+        arena: &'a DynArena,
+        size: u64,
+        data: &[u8],
+    ) -> Result<&'a DynBuffer>;
+    unsafe fn create_buffer<'a>(
+        &self,
+        arena: &'a DynArena,
+        size: u64,
+        memory: MemoryType,
+    ) -> Result<&'a DynBuffer>;
+    unsafe fn write_buffer(&self, buffer: &DynBuffer, offset: u64, data: &[u8]);
+    unsafe fn create_shader_module<'a>(
+        &self,
+        arena: &'a DynArena,
+        spirv: &[u8],
+        stage: ShaderStageFlags,
+    ) -> Result<&'a DynShaderModule>;
+    unsafe fn create_graphics_pipeline<'a>(
+        &self,
+        arena: &'a DynArena,
+        root_signature: &'a DynSignature,
+        root_signature_description: &SignatureDescription,
+        create_info: &GraphicsPipelineCreateInfo<'a, '_, DynBackend>,
+    ) -> Result<&'a DynGraphicsPipeline>;
+    unsafe fn create_compute_pipeline<'a>(
+        &self,
+        arena: &'a DynArena,
+        root_signature: &'a DynSignature,
+        root_signature_description: &SignatureDescription,
+        create_info: &ComputePipelineCreateInfo<'a, '_, DynBackend>,
+    ) -> Result<&'a DynComputePipeline>;
+    unsafe fn create_signature<'a>(
+        &'a self,
+        arena: &'a DynArena,
+        inherited: &[&'a DynSignature],
+        description: &SignatureDescription,
+    ) -> Result<&'a DynSignature>;
+    unsafe fn create_argument_block<'a>(
+        &self,
+        arena: &'a DynArena,
+        signature: &'a DynSignature,
+        arguments: &[BareArgumentBlock<'a, DynBackend>],
+        descriptors: &[Descriptor<'a, DynBackend>],
+        vertex_buffers: &[VertexBufferView<'a, DynBackend>],
+        index_buffer: Option<IndexBufferView<'a, DynBackend>>,
+        render_targets: &[RenderTargetView<'a, DynBackend>],
+        depth_stencil_target: Option<DepthStencilView<'a, DynBackend>>,
+        viewports: &[Viewport],
+        scissors: &[Scissor],
+    ) -> Result<&'a DynArgumentBlock>;
+    unsafe fn create_host_reference<'a>(
+        &self,
+        arena: &'a DynArena,
+        data: &'a [u8],
+    ) -> Result<&'a DynHostReference>;
+    unsafe fn create_query_pool<'a>(
+        &self,
+        arena: &'a DynArena,
+        ty: QueryType,
+        count: u32,
+    ) -> Result<&'a DynQueryPool>;
+    unsafe fn set_debug_name(&self, object: DebugObject<DynBackend>, name: &str);
+    fn queues(&self) -> &[Queue];
+    fn uniform_buffer_offset_alignment(&self) -> usize;
+    fn current_frame(&self) -> u64;
+    fn is_frame_finished(&self, frame: u64) -> bool;
+    unsafe fn submit_frame<'a>(&self, commands: &[Command<'a, DynBackend>], batches: &[QueueBatch]);
+    unsafe fn poll_readbacks(&self, timeout: Duration) -> bool;
+    fn frame_profile(&self) -> Option<FrameProfile>;
+}
+
+/// Wraps the instance of a backend into a [DynInstance].
+pub fn into_dyn_instance<B: Backend>(instance: B::Instance) -> Box<dyn DynInstance>
+where
+    B::Instance: 'static,
+    B::Window: 'static,
+{
+    Box::new(ErasedInstance::<B> {
+        inner: instance,
+        default_swapchain: Mutex::new(None),
+    })
+}
+
+/// Instance of a backend, wrapped into a [DynInstance].
+struct ErasedInstance<B: Backend> {
+    inner: B::Instance,
+    /// Handle to the default swapchain of the wrapped instance, created on first use.
+    default_swapchain: Mutex<Option<Box<DynSwapchain>>>,
+}
+
+impl<B: Backend> DynInstance for ErasedInstance<B>
+where
+    B::Window: 'static,
+{
+    unsafe fn create_arena(&self) -> Result<Box<DynArena>> {
+        let inner = self.inner.create_arena()?;
+        Ok(Box::new(DynArena {
+            inner: Box::into_raw(inner) as *mut (),
+            swapchains: Arena::new(),
+        }))
+    }
+
+    unsafe fn drop_arena(&self, arena: Box<DynArena>) {
+        self.inner
+            .drop_arena(Box::from_raw(arena.inner as *mut B::Arena))
+    }
+
+    unsafe fn create_swapchain<'a>(
+        &self,
+        arena: &'a DynArena,
+        window: &DynWindow,
+    ) -> Result<&'a DynSwapchain> {
+        let window = window
+            .0
+            .downcast_ref::<B::Window>()
+            .ok_or_else(|| Error::Validation {
+                message: "the window type does not match the backend".into(),
+            })?;
+        let swapchain = self.inner.create_swapchain(arena.inner::<B>(), window)?;
+        Ok(arena.swapchains.alloc(DynSwapchain::new::<B>(swapchain)))
+    }
+
+    unsafe fn default_swapchain<'a>(&'a self) -> Option<&'a DynSwapchain> {
+        let mut default_swapchain = self.default_swapchain.lock().unwrap();
+        if default_swapchain.is_none() {
+            *default_swapchain = self
+                .inner
+                .default_swapchain()
+                .map(|s| Box::new(DynSwapchain::new::<B>(s)));
+        }
+        // the box is never replaced once created, so it lives as long as the instance
+        default_swapchain
+            .as_ref()
+            .map(|s| &*(&**s as *const DynSwapchain))
+    }
+
+    unsafe fn create_image<'a>(
+        &self,
+        arena: &'a DynArena,
+        scope: AliasScope,
+        format: Format,
+        dimensions: Dimensions,
+        mipcount: MipmapsOption,
+        samples: u32,
+        usage: ImageUsageFlags,
+        initial_data: Option<&[u8]>,
+    ) -> Result<&'a DynImage> {
+        Ok(cast(self.inner.create_image(
+            arena.inner::<B>(),
+            scope,
+            format,
+            dimensions,
+            mipcount,
+            samples,
+            usage,
+            initial_data,
+        )?))
+    }
+
+    unsafe fn update_image(
+        &self,
+        image: &DynImage,
+        min_extent: (u32, u32, u32),
+        max_extent: (u32, u32, u32),
+        data: &[u8],
+    ) {
+        self.inner
+            .update_image(cast(image), min_extent, max_extent, data)
+    }
+
+    unsafe fn create_immutable_buffer<'a>(
+        &self,
+        arena: &'a DynArena,
+        size: u64,
+        data: &[u8],
+    ) -> Result<&'a DynBuffer> {
+        Ok(cast(self.inner.create_immutable_buffer(
+            arena.inner::<B>(),
+            size,
+            data,
+        )?))
+    }
+
+    unsafe fn create_buffer<'a>(
+        &self,
+        arena: &'a DynArena,
+        size: u64,
+        memory: MemoryType,
+    ) -> Result<&'a DynBuffer> {
+        Ok(cast(self.inner.create_buffer(
+            arena.inner::<B>(),
+            size,
+            memory,
+        )?))
+    }
+
+    unsafe fn write_buffer(&self, buffer: &DynBuffer, offset: u64, data: &[u8]) {
+        self.inner.write_buffer(cast(buffer), offset, data)
+    }
+
+    unsafe fn create_shader_module<'a>(
+        &self,
+        arena: &'a DynArena,
+        spirv: &[u8],
+        stage: ShaderStageFlags,
+    ) -> Result<&'a DynShaderModule> {
+        Ok(cast(self.inner.create_shader_module(
+            arena.inner::<B>(),
+            spirv,
+            stage,
+        )?))
+    }
+
+    unsafe fn create_graphics_pipeline<'a>(
+        &self,
+        arena: &'a DynArena,
+        root_signature: &'a DynSignature,
+        root_signature_description: &SignatureDescription,
+        create_info: &GraphicsPipelineCreateInfo<'a, '_, DynBackend>,
+    ) -> Result<&'a DynGraphicsPipeline> {
+        Ok(cast(self.inner.create_graphics_pipeline(
+            arena.inner::<B>(),
+            cast(root_signature),
+            root_signature_description,
+            &unwrap::graphics_pipeline_create_info::<DynBackend, B>(create_info),
+        )?))
+    }
+
+    unsafe fn create_compute_pipeline<'a>(
+        &self,
+        arena: &'a DynArena,
+        root_signature: &'a DynSignature,
+        root_signature_description: &SignatureDescription,
+        create_info: &ComputePipelineCreateInfo<'a, '_, DynBackend>,
+    ) -> Result<&'a DynComputePipeline> {
+        Ok(cast(self.inner.create_compute_pipeline(
+            arena.inner::<B>(),
+            cast(root_signature),
+            root_signature_description,
+            &unwrap::compute_pipeline_create_info::<DynBackend, B>(create_info),
+        )?))
+    }
+
+    unsafe fn create_signature<'a>(
+        &'a self,
+        arena: &'a DynArena,
+        inherited: &[&'a DynSignature],
+        description: &SignatureDescription,
+    ) -> Result<&'a DynSignature> {
+        let inherited: Vec<&B::Signature> = inherited.iter().map(|&s| cast(s)).collect();
+        Ok(cast(self.inner.create_signature(
+            arena.inner::<B>(),
+            &inherited,
+            description,
+        )?))
+    }
+
+    unsafe fn create_argument_block<'a>(
+        &self,
+        arena: &'a DynArena,
+        signature: &'a DynSignature,
+        arguments: &[BareArgumentBlock<'a, DynBackend>],
+        descriptors: &[Descriptor<'a, DynBackend>],
+        vertex_buffers: &[VertexBufferView<'a, DynBackend>],
+        index_buffer: Option<IndexBufferView<'a, DynBackend>>,
+        render_targets: &[RenderTargetView<'a, DynBackend>],
+        depth_stencil_target: Option<DepthStencilView<'a, DynBackend>>,
+        viewports: &[Viewport],
+        scissors: &[Scissor],
+    ) -> Result<&'a DynArgumentBlock> {
+        Ok(cast(
+            self.inner.create_argument_block(
+                arena.inner::<B>(),
+                cast(signature),
+                arguments
+                    .iter()
+                    .map(|b| unwrap::bare_argument_block::<DynBackend, B>(b)),
+                descriptors
+                    .iter()
+                    .map(|d| unwrap::descriptor::<DynBackend, B>(d)),
+                vertex_buffers
+                    .iter()
+                    .map(|vb| unwrap::vertex_buffer_view::<DynBackend, B>(vb)),
+                index_buffer.map(|ib| unwrap::index_buffer_view::<DynBackend, B>(&ib)),
+                render_targets
+                    .iter()
+                    .map(|rt| unwrap::render_target_view::<DynBackend, B>(rt)),
+                depth_stencil_target.map(|ds| unwrap::depth_stencil_view::<DynBackend, B>(&ds)),
+                viewports.iter().cloned(),
+                scissors.iter().cloned(),
+            )?,
+        ))
+    }
+
+    unsafe fn create_host_reference<'a>(
+        &self,
+        arena: &'a DynArena,
+        data: &'a [u8],
+    ) -> Result<&'a DynHostReference> {
+        Ok(cast(
+            self.inner.create_host_reference(arena.inner::<B>(), data)?,
+        ))
+    }
+
+    unsafe fn create_query_pool<'a>(
+        &self,
+        arena: &'a DynArena,
+        ty: QueryType,
+        count: u32,
+    ) -> Result<&'a DynQueryPool> {
+        Ok(cast(self.inner.create_query_pool(
+            arena.inner::<B>(),
+            ty,
+            count,
+        )?))
+    }
+
+    unsafe fn set_debug_name(&self, object: DebugObject<DynBackend>, name: &str) {
+        self.inner
+            .set_debug_name(unwrap::debug_object::<DynBackend, B>(object), name)
+    }
+
+    fn queues(&self) -> &[Queue] {
+        self.inner.queues()
+    }
+
+    unsafe fn submit_frame<'a>(
+        &self,
+        commands: &[Command<'a, DynBackend>],
+        batches: &[QueueBatch],
+    ) {
+        let commands: Vec<Command<'a, B>> = commands
+            .iter()
+            .map(|cmd| Command {
+                sortkey: cmd.sortkey,
+                queue: cmd.queue,
+                cmd: unwrap::command::<DynBackend, B>(&cmd.cmd),
+            })
+            .collect();
+        self.inner.submit_frame(&commands, batches)
+    }
+
+    fn uniform_buffer_offset_alignment(&self) -> usize {
+        self.inner.uniform_buffer_offset_alignment()
+    }
+
+    fn current_frame(&self) -> u64 {
+        self.inner.current_frame()
+    }
+
+    fn is_frame_finished(&self, frame: u64) -> bool {
+        self.inner.is_frame_finished(frame)
+    }
+
+    unsafe fn poll_readbacks(&self, timeout: Duration) -> bool {
+        self.inner.poll_readbacks(timeout)
+    }
+
+    fn frame_profile(&self) -> Option<FrameProfile> {
+        self.inner.frame_profile()
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+
+impl Instance<DynBackend> for Box<dyn DynInstance> {
+    unsafe fn create_arena(&self) -> Result<Box<DynArena>> {
+        (**self).create_arena()
+    }
+
+    unsafe fn drop_arena(&self, arena: Box<DynArena>) {
+        (**self).drop_arena(arena)
+    }
+
+    unsafe fn create_swapchain<'a>(
+        &self,
+        arena: &'a DynArena,
+        window: &DynWindow,
+    ) -> Result<&'a DynSwapchain> {
+        (**self).create_swapchain(arena, window)
+    }
+
+    unsafe fn default_swapchain<'a>(&'a self) -> Option<&'a DynSwapchain> {
+        (**self).default_swapchain()
+    }
+
+    unsafe fn create_image<'a>(
+        &self,
+        arena: &'a DynArena,
+        scope: AliasScope,
+        format: Format,
+        dimensions: Dimensions,
+        mipcount: MipmapsOption,
+        samples: u32,
+        usage: ImageUsageFlags,
+        initial_data: Option<&[u8]>,
+    ) -> Result<&'a DynImage> {
+        (**self).create_image(
+            arena,
+            scope,
+            format,
+            dimensions,
+            mipcount,
+            samples,
+            usage,
+            initial_data,
+        )
+    }
+
+    unsafe fn update_image(
+        &self,
+        image: &DynImage,
+        min_extent: (u32, u32, u32),
+        max_extent: (u32, u32, u32),
+        data: &[u8],
+    ) {
+        (**self).update_image(image, min_extent, max_extent, data)
+    }
+
+    unsafe fn create_immutable_buffer<'a>(
+        &self,
+        arena: &'a DynArena,
+        size: u64,
+        data: &[u8],
+    ) -> Result<&'a DynBuffer> {
+        (**self).create_immutable_buffer(arena, size, data)
+    }
+
+    unsafe fn create_buffer<'a>(
+        &self,
+        arena: &'a DynArena,
+        size: u64,
+        memory: MemoryType,
+    ) -> Result<&'a DynBuffer> {
+        (**self).create_buffer(arena, size, memory)
+    }
+
+    unsafe fn write_buffer(&self, buffer: &DynBuffer, offset: u64, data: &[u8]) {
+        (**self).write_buffer(buffer, offset, data)
+    }
+
+    unsafe fn create_shader_module<'a>(
+        &self,
+        arena: &'a DynArena,
+        spirv: &'_ [u8],
+        stage: ShaderStageFlags,
+    ) -> Result<&'a DynShaderModule> {
+        (**self).create_shader_module(arena, spirv, stage)
+    }
+
+    unsafe fn create_graphics_pipeline<'a>(
+        &self,
+        arena: &'a DynArena,
+        root_signature: &'a DynSignature,
+        root_signature_description: &SignatureDescription,
+        create_info: &GraphicsPipelineCreateInfo<'a, '_, DynBackend>,
+    ) -> Result<&'a DynGraphicsPipeline> {
+        (**self).create_graphics_pipeline(
+            arena,
+            root_signature,
+            root_signature_description,
+            create_info,
+        )
+    }
+
+    unsafe fn create_compute_pipeline<'a>(
+        &self,
+        arena: &'a DynArena,
+        root_signature: &'a DynSignature,
+        root_signature_description: &SignatureDescription,
+        create_info: &ComputePipelineCreateInfo<'a, '_, DynBackend>,
+    ) -> Result<&'a DynComputePipeline> {
+        (**self).create_compute_pipeline(
+            arena,
+            root_signature,
+            root_signature_description,
+            create_info,
+        )
+    }
+
+    unsafe fn create_signature<'a>(
+        &'a self,
+        arena: &'a DynArena,
+        inherited: &[&'a DynSignature],
+        description: &SignatureDescription,
+    ) -> Result<&'a DynSignature> {
+        (**self).create_signature(arena, inherited, description)
+    }
+
+    unsafe fn create_argument_block<'a>(
+        &self,
+        arena: &'a DynArena,
+        signature: &'a DynSignature,
+        arguments: impl IntoIterator<Item = BareArgumentBlock<'a, DynBackend>>,
+        descriptors: impl IntoIterator<Item = Descriptor<'a, DynBackend>>,
+        vertex_buffers: impl IntoIterator<Item = VertexBufferView<'a, DynBackend>>,
+        index_buffer: Option<IndexBufferView<'a, DynBackend>>,
+        render_targets: impl IntoIterator<Item = RenderTargetView<'a, DynBackend>>,
+        depth_stencil_target: Option<DepthStencilView<'a, DynBackend>>,
+        viewports: impl IntoIterator<Item = Viewport>,
+        scissors: impl IntoIterator<Item = Scissor>,
+    ) -> Result<&'a DynArgumentBlock> {
+        let arguments: Vec<_> = arguments.into_iter().collect();
+        let descriptors: Vec<_> = descriptors.into_iter().collect();
+        let vertex_buffers: Vec<_> = vertex_buffers.into_iter().collect();
+        let render_targets: Vec<_> = render_targets.into_iter().collect();
+        let viewports: Vec<_> = viewports.into_iter().collect();
+        let scissors: Vec<_> = scissors.into_iter().collect();
+        (**self).create_argument_block(
+            arena,
+            signature,
+            &arguments,
+            &descriptors,
+            &vertex_buffers,
+            index_buffer,
+            &render_targets,
+            depth_stencil_target,
+            &viewports,
+            &scissors,
+        )
+    }
+
+    unsafe fn create_host_reference<'a>(
+        &self,
+        arena: &'a DynArena,
+        data: &'a [u8],
+    ) -> Result<&'a DynHostReference> {
+        (**self).create_host_reference(arena, data)
+    }
+
+    unsafe fn create_query_pool<'a>(
+        &self,
+        arena: &'a DynArena,
+        ty: QueryType,
+        count: u32,
+    ) -> Result<&'a DynQueryPool> {
+        (**self).create_query_pool(arena, ty, count)
+    }
+
+    unsafe fn set_debug_name(&self, object: DebugObject<DynBackend>, name: &str) {
+        (**self).set_debug_name(object, name)
+    }
+
+    fn queues(&self) -> &[Queue] {
+        (**self).queues()
+    }
+
+    unsafe fn submit_frame<'a>(
+        &self,
+        commands: &[Command<'a, DynBackend>],
+        batches: &[QueueBatch],
+    ) {
+        (**self).submit_frame(commands, batches)
+    }
+
+    fn uniform_buffer_offset_alignment(&self) -> usize {
+        (**self).uniform_buffer_offset_alignment()
+    }
+
+    fn current_frame(&self) -> u64 {
+        (**self).current_frame()
+    }
+
+    fn is_frame_finished(&self, frame: u64) -> bool {
+        (**self).is_frame_finished(frame)
+    }
+
+    unsafe fn poll_readbacks(&self, timeout: Duration) -> bool {
+        (**self).poll_readbacks(timeout)
+    }
+
+    fn frame_profile(&self) -> Option<FrameProfile> {
+        (**self).frame_profile()
+    }
+}
@@ -4,19 +4,57 @@
 
 // TODO it's unclear what's best: a shared error enum like this, or smaller error types for each module
 
+use crate::{format::Format, pipeline::ShaderStageFlags};
 use std::{error, fmt};
 
+/// Errors returned by backend object creation functions.
+///
+/// Variants carry the message reported by the backend, if any.
 #[derive(Clone, Debug)]
 pub enum Error {
-    OutOfMemory,
-    InvalidRenderTarget,
-    InvalidSampledImage,
-    InvalidStorageImage,
+    /// A shader module failed to compile.
+    ShaderCompilation {
+        stage: ShaderStageFlags,
+        message: String,
+    },
+    /// The shaders of a pipeline failed to link.
+    ShaderLink { message: String },
+    /// The format is not supported by the backend, or not for the requested usage.
+    UnsupportedFormat { format: Format, message: String },
+    /// An implementation limit was exceeded (e.g. image size, or number of bindings).
+    LimitExceeded { message: String },
+    /// Out of host or device memory.
+    OutOfMemory { message: String },
+    /// Invalid parameters (e.g. a render target without `COLOR_ATTACHMENT` usage).
+    Validation { message: String },
 }
 
 impl fmt::Display for Error {
-    fn fmt(&self, _f: &mut fmt::Formatter) -> fmt::Result {
-        unimplemented!()
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::ShaderCompilation { stage, message } => {
+                write!(f, "shader compilation failed ({:?}): {}", stage, message)
+            }
+            Error::ShaderLink { message } => write!(f, "shader link failed: {}", message),
+            Error::UnsupportedFormat { format, message } => {
+                write!(f, "unsupported format {:?}", format)?;
+                if !message.is_empty() {
+                    write!(f, ": {}", message)?;
+                }
+                Ok(())
+            }
+            Error::LimitExceeded { message } => {
+                write!(f, "implementation limit exceeded: {}", message)
+            }
+            Error::OutOfMemory { message } => {
+                write!(f, "out of memory")?;
+                if !message.is_empty() {
+                    write!(f, ": {}", message)?;
+                }
+                Ok(())
+            }
+            Error::Validation { message } => write!(f, "validation failed: {}", message),
+        }
     }
 }
 
@@ -22,6 +22,8 @@ pub struct ImageCreateInfo<'a> {
     pub samples: u32,
     pub usage: ImageUsageFlags,
     pub data: Option<&'a [u8]>,
+    /// See [crate::debug].
+    pub debug_name: Option<&'a str>,
 }
 
 /// An image.
@@ -197,6 +199,49 @@ impl fmt::Debug for Dimensions {
     }
 }
 
+/// A region of an image, used in copy commands.
+///
+/// For array images, the array layers are selected by `base_array_layer` and `layer_count`,
+/// and `offset.2` and `extent.2` must be 0 and 1 respectively.
+///
+/// In copy commands, the mip level and array layers selected by the region take precedence over
+/// the subresource range of the image view.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ImageRegion {
+    pub mip_level: u32,
+    pub base_array_layer: u32,
+    pub layer_count: u32,
+    pub offset: (u32, u32, u32),
+    pub extent: (u32, u32, u32),
+}
+
+impl ImageRegion {
+    /// Region in the first mip level and array layer of the image.
+    pub fn new(offset: (u32, u32, u32), extent: (u32, u32, u32)) -> ImageRegion {
+        ImageRegion {
+            mip_level: 0,
+            base_array_layer: 0,
+            layer_count: 1,
+            offset,
+            extent,
+        }
+    }
+
+    /// Returns the same region in the specified mip level.
+    pub fn mip_level(self, mip_level: u32) -> ImageRegion {
+        ImageRegion { mip_level, ..self }
+    }
+
+    /// Returns the same region in the specified array layers.
+    pub fn array_layers(self, base_array_layer: u32, layer_count: u32) -> ImageRegion {
+        ImageRegion {
+            base_array_layer,
+            layer_count,
+            ..self
+        }
+    }
+}
+
 /*
 #[derive(Debug, Copy, Clone)]
 pub enum MipmapsCount {
@@ -448,7 +493,8 @@ macro_rules! impl_image_builder {
                 mipmaps: self.mipmaps,
                 samples: self.samples,
                 usage: self.usage,
-                data: None
+                data: None,
+                debug_name: self.debug_name.as_ref().map(|s| s.as_str()),
             };
             (self.builder)(&c)
             /*$rty {
@@ -475,7 +521,8 @@ macro_rules! impl_image_builder {
                 mipmaps: self.mipmaps,
                 samples: self.samples,
                 usage: self.usage,
-                data: Some(data)
+                data: Some(data),
+                debug_name: self.debug_name.as_ref().map(|s| s.as_str()),
             };
             (self.builder)(&c)
 
@@ -496,6 +543,7 @@ macro_rules! impl_image_builder {
             pub samples: u32,
             pub usage: ImageUsageFlags,
             pub aliasing: AliasScope,
+            pub debug_name: Option<String>,
         }
 
         impl<O, F: Fn(&ImageCreateInfo) -> O> $n<O,F> {
@@ -509,9 +557,16 @@ macro_rules! impl_image_builder {
                     samples: 1,
                     usage: impl_image_builder!(@E flags $mode),
                     aliasing: AliasScope::no_alias(),
+                    debug_name: None,
                 }
             }
 
+            /// Sets the name of the image in graphics debuggers. See [crate::debug].
+            pub fn debug_name(&mut self, name: &str) -> &mut Self {
+                self.debug_name = Some(name.to_string());
+                self
+            }
+
             impl_image_builder!(@M array_layers $shape);
             impl_image_builder!(@M dimensions $shape);
             impl_image_builder!(@M samples $multisample);
@@ -827,6 +882,12 @@ impl_single_mipmap_view!(default DepthStencilImage2d => Image2dView);
 impl_single_mipmap_view!(Image1dMipmap => Image1dView);
 impl_single_mipmap_view!(Image2dMipmap => Image2dView);
 impl_single_mipmap_view!(Image3dMipmap => Image3dView);
+// any image can be converted to an untyped view for copy commands
+impl_single_mipmap_view!(default Image1d => ImageView);
+impl_single_mipmap_view!(default Image2d => ImageView);
+impl_single_mipmap_view!(default Image3d => ImageView);
+impl_single_mipmap_view!(default RenderTargetImage2d => ImageView);
+impl_single_mipmap_view!(default DepthStencilImage2d => ImageView);
 
 impl_view_type!(RenderTargetView from RenderTarget2dView);
 impl_view_type!(DepthStencilView from DepthStencil2dView);
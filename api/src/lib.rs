@@ -29,18 +29,31 @@ extern crate log;
 #[cfg(feature = "glm")]
 pub use nalgebra_glm as glm;
 
+pub mod barrier;
 pub mod buffer;
+pub mod capture;
 pub mod command;
+pub mod debug;
 pub mod descriptor;
+pub mod dynamic;
 pub mod error;
 pub mod format;
 pub mod image;
+pub mod mock;
 pub mod pipeline;
 pub mod prelude;
+pub mod profile;
+pub mod query;
+pub mod readback;
+pub mod ring;
+pub mod schedule;
+pub mod sortkey;
 pub mod swapchain;
 pub mod traits;
 pub mod typedesc;
+mod unwrap;
 mod util;
+pub mod validation;
 pub mod vertex;
 
 pub use crate::{buffer::*, command::*, descriptor::*, format::*, image::*, util::*};
@@ -52,35 +65,59 @@ pub use autograph_shader_macros::{
 };
 
 use crate::{
+    capture::CaptureRecorder,
+    debug::DebugObject,
+    error::{Error, Result},
     pipeline::{
-        ArgumentBlock, Arguments, BareArgumentBlock, GraphicsPipeline, GraphicsPipelineCreateInfo,
+        cache::{bytecode_hash, DynamicSignatureCache, PipelineCache, PipelineCacheStats},
+        ArgumentBlock, Arguments, BareArgumentBlock, ComputePipeline, ComputePipelineCreateInfo,
+        DynamicSignature, DynamicSignatureBuilder, GraphicsPipeline, GraphicsPipelineCreateInfo,
         GraphicsShaderStages, ReflectedShader, Scissor, ShaderModule, ShaderStageFlags, Signature,
         SignatureDescription, TypedSignature, Viewport,
     },
+    profile::FrameProfile,
+    query::{QueryPool, QueryType},
+    readback::Readback,
+    schedule::QueueBatch,
     swapchain::Swapchain,
     vertex::{IndexBufferView, VertexBufferView},
 };
 use autograph_spirv::DroplessArena;
 use std::{
-    any::TypeId, collections::HashMap, fmt::Debug, hash::Hash, marker::PhantomData, mem,
-    sync::Mutex,
+    any::TypeId,
+    collections::HashMap,
+    fmt::Debug,
+    hash::Hash,
+    iter,
+    marker::PhantomData,
+    mem,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
 };
 
 //--------------------------------------------------------------------------------------------------
 
-/// Currently unused.
-#[derive(Copy, Clone, Debug)]
+/// Memory in which a mutable buffer is allocated. See [Instance::create_buffer].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub enum MemoryType {
+    /// Memory local to the GPU. Written from the CPU with an upload that is ordered before the
+    /// commands of the next submitted frame.
     DeviceLocal,
+    /// Host-visible memory, written directly by the CPU. Used for data that changes every frame.
     HostUpload,
+    /// Host-visible memory, for data read back by the CPU.
     HostReadback,
 }
 
-/// Currently unused.
-#[derive(Copy, Clone, Debug)]
+/// Queue on which a command is executed. See [schedule].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub enum Queue {
+    /// Draws, clears, blits and presentation. Can execute all commands.
     Graphics,
+    /// Compute dispatches.
     Compute,
+    /// Copies, uploads and readbacks.
     Transfer,
 }
 
@@ -90,6 +127,7 @@ pub enum Queue {
 ///
 /// An AliasScope is defined using a mask and a value (similarly to IP subnet masks, for example):
 /// a command with sortkey `s` is inside the range if `s & mask == value`.
+/// Use [SortKeyLayout](crate::sortkey::SortKeyLayout) to build scopes from named sortkey fields.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub struct AliasScope {
     pub value: u64,
@@ -102,6 +140,11 @@ impl AliasScope {
         AliasScope { value: 0, mask: 0 }
     }
 
+    /// Returns true if the command with the given sortkey is inside this scope.
+    pub fn contains(&self, sortkey: u64) -> bool {
+        sortkey & self.mask == self.value
+    }
+
     /// Returns true if this scope overlaps the other.
     pub fn overlaps(&self, other: &AliasScope) -> bool {
         let m = self.mask & other.mask;
@@ -111,15 +154,25 @@ impl AliasScope {
 
 //--------------------------------------------------------------------------------------------------
 
+/// Backend instance.
+///
+/// Object creation functions return an [Error](crate::error::Error) if the object could not
+/// be created.
 pub trait Instance<B: Backend> {
     /// Creates a new empty Arena.
-    unsafe fn create_arena(&self) -> Box<B::Arena>;
+    unsafe fn create_arena(&self) -> Result<Box<B::Arena>>;
 
     /// Drops an arena and all the objects it owns.
     unsafe fn drop_arena(&self, arena: Box<B::Arena>);
 
-    /// See [Renderer::create_swapchain](crate::Renderer::create_swapchain).
-    unsafe fn create_swapchain<'a>(&self, arena: &'a B::Arena) -> &'a B::Swapchain;
+    /// Creates a swapchain that presents to the specified window.
+    ///
+    /// See [Arena::create_swapchain](crate::Arena::create_swapchain).
+    unsafe fn create_swapchain<'a>(
+        &self,
+        arena: &'a B::Arena,
+        window: &B::Window,
+    ) -> Result<&'a B::Swapchain>;
 
     /// See [Renderer::default_swapchain](crate::Renderer::default_swapchain).
     unsafe fn default_swapchain<'a>(&'a self) -> Option<&'a B::Swapchain>;
@@ -135,7 +188,7 @@ pub trait Instance<B: Backend> {
         samples: u32,
         usage: ImageUsageFlags,
         initial_data: Option<&[u8]>,
-    ) -> &'a B::Image;
+    ) -> Result<&'a B::Image>;
 
     /// Updates a region of an image.
     ///
@@ -155,17 +208,28 @@ pub trait Instance<B: Backend> {
         arena: &'a B::Arena,
         size: u64,
         data: &[u8],
-    ) -> &'a B::Buffer;
+    ) -> Result<&'a B::Buffer>;
 
-    /// TODO
-    unsafe fn create_buffer<'a>(&self, arena: &'a B::Arena, size: u64) -> &'a B::Buffer;
+    /// Creates a mutable buffer of `size` bytes in the specified memory.
+    unsafe fn create_buffer<'a>(
+        &self,
+        arena: &'a B::Arena,
+        size: u64,
+        memory: MemoryType,
+    ) -> Result<&'a B::Buffer>;
+
+    /// Writes data to a buffer created with [create_buffer](Instance::create_buffer).
+    ///
+    /// For `HostUpload` buffers, the data is written directly to the memory of the buffer: the
+    /// caller must ensure that the range is not accessed by commands still executing on the GPU.
+    unsafe fn write_buffer(&self, buffer: &B::Buffer, offset: u64, data: &[u8]);
 
     unsafe fn create_shader_module<'a>(
         &self,
         arena: &'a B::Arena,
         spirv: &'_ [u8],
         stage: ShaderStageFlags,
-    ) -> &'a B::ShaderModule;
+    ) -> Result<&'a B::ShaderModule>;
 
     unsafe fn create_graphics_pipeline<'a>(
         &self,
@@ -173,14 +237,22 @@ pub trait Instance<B: Backend> {
         root_signature: &'a B::Signature,
         root_signature_description: &SignatureDescription,
         create_info: &GraphicsPipelineCreateInfo<'a, '_, B>,
-    ) -> &'a B::GraphicsPipeline;
+    ) -> Result<&'a B::GraphicsPipeline>;
+
+    unsafe fn create_compute_pipeline<'a>(
+        &self,
+        arena: &'a B::Arena,
+        root_signature: &'a B::Signature,
+        root_signature_description: &SignatureDescription,
+        create_info: &ComputePipelineCreateInfo<'a, '_, B>,
+    ) -> Result<&'a B::ComputePipeline>;
 
     unsafe fn create_signature<'a>(
         &'a self,
         arena: &'a B::Arena,
         inherited: &[&'a B::Signature],
         description: &SignatureDescription,
-    ) -> &'a B::Signature;
+    ) -> Result<&'a B::Signature>;
 
     unsafe fn create_argument_block<'a>(
         &self,
@@ -194,19 +266,62 @@ pub trait Instance<B: Backend> {
         depth_stencil_target: Option<DepthStencilView<'a, B>>,
         viewports: impl IntoIterator<Item = Viewport>,
         scissors: impl IntoIterator<Item = Scissor>,
-    ) -> &'a B::ArgumentBlock;
+    ) -> Result<&'a B::ArgumentBlock>;
 
     unsafe fn create_host_reference<'a>(
         &self,
         arena: &'a B::Arena,
         data: &'a [u8],
-    ) -> &'a B::HostReference;
+    ) -> Result<&'a B::HostReference>;
+
+    /// Creates a pool of `count` queries of the specified type.
+    unsafe fn create_query_pool<'a>(
+        &self,
+        arena: &'a B::Arena,
+        ty: QueryType,
+        count: u32,
+    ) -> Result<&'a B::QueryPool>;
+
+    /// Sets the name of an object in graphics debuggers. See [debug].
+    unsafe fn set_debug_name(&self, object: DebugObject<B>, name: &str);
+
+    /// Returns the queues on which the backend can execute commands.
+    ///
+    /// Commands tagged with other queues are executed on the graphics queue. The default
+    /// implementation only supports the graphics queue.
+    fn queues(&self) -> &[Queue] {
+        &[Queue::Graphics]
+    }
+
+    /// Returns the alignment required for the offsets of constant buffer bindings, in bytes.
+    fn uniform_buffer_offset_alignment(&self) -> usize;
+
+    /// Returns the number of the frame being recorded, which is the frame submitted by the next
+    /// call to [submit_frame](Instance::submit_frame). Frames are numbered from 1.
+    fn current_frame(&self) -> u64;
+
+    /// Returns whether the specified frame has finished executing on the GPU, without blocking.
+    ///
+    /// Frames that have not been submitted yet are not finished.
+    fn is_frame_finished(&self, frame: u64) -> bool;
 
     /// Sends commands to the GPU for execution, and ends the current frame.
     /// Uploads all referenced host data to the GPU and releases the borrows.
     ///
-    /// Precondition: the command list should be sorted by sortkey.
-    unsafe fn submit_frame<'a>(&self, commands: &[Command<'a, B>]);
+    /// Precondition: the command list should be sorted by sortkey. `batches` partition
+    /// the command list into runs of commands on the same queue (see [schedule]).
+    /// Backends with a single queue can ignore them and execute the commands in order.
+    unsafe fn submit_frame<'a>(&self, commands: &[Command<'a, B>], batches: &[QueueBatch]);
+
+    /// Completes the pending readbacks of the frames that have finished executing on the GPU.
+    ///
+    /// If there are pending readbacks but none of them can be completed yet, waits at most
+    /// `timeout` for the oldest one. Returns whether any readback was completed.
+    unsafe fn poll_readbacks(&self, timeout: Duration) -> bool;
+
+    /// Returns the timings of the profile scopes of the most recent frame that has finished
+    /// executing on the GPU, or `None` if no frame with profile scopes has finished yet.
+    fn frame_profile(&self) -> Option<FrameProfile>;
 }
 
 /// Trait implemented by renderer backends.
@@ -226,154 +341,18 @@ pub trait Backend:
     // (a.k.a. associated type constructors, or ATCs).
     type Instance: Instance<Self>;
     type Arena;
+    /// Window (or any presentation surface) from which swapchains are created.
+    type Window;
     type Swapchain: Sync + Debug + traits::Swapchain;
     type Image: Sync + Debug;
     type Buffer: Sync + Debug;
     type ShaderModule: Sync + Debug;
     type GraphicsPipeline: Sync + Debug;
+    type ComputePipeline: Sync + Debug;
     type Signature: Sync + Debug;
     type ArgumentBlock: Sync + Debug;
     type HostReference: Sync + Debug;
-}
-
-/// Dummy backend for testing purposes.
-///
-/// Should this be in render-test?
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
-pub struct DummyBackend;
-
-#[derive(Debug)]
-pub struct DummySwapchain;
-
-impl traits::Swapchain for DummySwapchain {
-    fn size(&self) -> (u32, u32) {
-        unimplemented!()
-    }
-}
-
-impl Backend for DummyBackend {
-    type Instance = DummyInstance;
-    type Arena = ();
-    type Swapchain = DummySwapchain;
-    type Image = ();
-    type Buffer = ();
-    type ShaderModule = ();
-    type GraphicsPipeline = ();
-    type Signature = ();
-    type ArgumentBlock = ();
-    type HostReference = ();
-}
-
-/// Dummy instance for testing purposes.
-///
-/// All functions panic when called.
-pub struct DummyInstance;
-
-impl Instance<DummyBackend> for DummyInstance {
-    unsafe fn create_arena(&self) -> Box<()> {
-        unimplemented!()
-    }
-
-    unsafe fn drop_arena(&self, _arena: Box<()>) {
-        unimplemented!()
-    }
-
-    unsafe fn create_swapchain<'a>(&self, _arena: &'a ()) -> &'a DummySwapchain {
-        unimplemented!()
-    }
-
-    unsafe fn default_swapchain<'a>(&'a self) -> Option<&DummySwapchain> {
-        unimplemented!()
-    }
-
-    unsafe fn create_image<'a>(
-        &self,
-        _arena: &'a (),
-        _scope: AliasScope,
-        _format: Format,
-        _dimensions: Dimensions,
-        _mipcount: MipmapsOption,
-        _samples: u32,
-        _usage: ImageUsageFlags,
-        _initial_data: Option<&[u8]>,
-    ) -> &'a () {
-        unimplemented!()
-    }
-
-    unsafe fn update_image(
-        &self,
-        _image: &(),
-        _min_extent: (u32, u32, u32),
-        _max_extent: (u32, u32, u32),
-        _data: &[u8],
-    ) {
-        unimplemented!()
-    }
-
-    unsafe fn create_immutable_buffer<'a>(
-        &self,
-        _arena: &'a (),
-        _size: u64,
-        _data: &[u8],
-    ) -> &'a () {
-        unimplemented!()
-    }
-
-    unsafe fn create_buffer<'a>(&self, _arena: &'a (), _size: u64) -> &'a () {
-        unimplemented!()
-    }
-
-    unsafe fn create_shader_module<'a>(
-        &self,
-        _arena: &'a (),
-        _spirv: &[u8],
-        _stage: ShaderStageFlags,
-    ) -> &'a () {
-        unimplemented!()
-    }
-
-    unsafe fn create_graphics_pipeline<'a>(
-        &self,
-        _arena: &'a (),
-        _root_signature: &'a (),
-        _root_signature_description: &SignatureDescription,
-        _create_info: &GraphicsPipelineCreateInfo<DummyBackend>,
-    ) -> &'a () {
-        unimplemented!()
-    }
-
-    unsafe fn create_signature<'a>(
-        &'a self,
-        _arena: &'a (),
-        _inherited: &[&()],
-        _description: &SignatureDescription,
-    ) -> &'a () {
-        unimplemented!()
-    }
-
-    unsafe fn create_argument_block<'a>(
-        &self,
-        _arena: &'a (),
-        _signature: &'a (),
-        _arguments: impl IntoIterator<Item = BareArgumentBlock<'a, DummyBackend>>,
-        _descriptors: impl IntoIterator<Item = Descriptor<'a, DummyBackend>>,
-        _vertex_buffers: impl IntoIterator<Item = VertexBufferView<'a, DummyBackend>>,
-        _index_buffer: Option<IndexBufferView<'a, DummyBackend>>,
-        _render_targets: impl IntoIterator<Item = RenderTargetView<'a, DummyBackend>>,
-        _depth_stencil_render_target: Option<DepthStencilView<'a, DummyBackend>>,
-        _viewports: impl IntoIterator<Item = Viewport>,
-        _scissors: impl IntoIterator<Item = Scissor>,
-    ) -> &'a () {
-        unimplemented!()
-    }
-
-    unsafe fn create_host_reference<'a>(&self, _arena: &'a (), _data: &'a [u8]) -> &'a () {
-        unimplemented!()
-    }
-
-    unsafe fn submit_frame<'a>(&self, _commands: &[Command<'a, DummyBackend>]) {
-        unimplemented!()
-    }
+    type QueryPool: Sync + Debug;
 }
 
 //--------------------------------------------------------------------------------------------------
@@ -390,7 +369,8 @@ impl Instance<DummyBackend> for DummyInstance {
 /// an application could have the following arenas, sorted from long-lived to short-lived:
 /// * an arena for long-lived resources, such as immutable textures,
 /// * an arena for hot-reloadable resources, destroyed and recreated on user input or filesystem events,
-/// * an arena for swapchain-related resources, destroyed and recreated when the swapchain is resized,
+/// * an arena for swapchain-related resources, destroyed and recreated when the swapchain is resized
+///   (see [Swapchain::generation](crate::swapchain::Swapchain::generation)),
 /// * an arena for resources that live for the current frame only.
 ///
 /// This type is a wrapper around [RendererBackend::Arena] that drops the arena
@@ -408,6 +388,13 @@ pub struct Arena<'r, B: Backend> {
 
 impl<'r, B: Backend> Drop for Arena<'r, B> {
     fn drop(&mut self) {
+        self.renderer
+            .record_capture(|c| c.forget_arena(self.inner.as_ref().unwrap()));
+        self.renderer
+            .pipeline_cache
+            .lock()
+            .unwrap()
+            .forget_arena(self.inner.as_ref().unwrap());
         unsafe { self.instance.drop_arena(self.inner.take().unwrap()) }
     }
 }
@@ -417,10 +404,16 @@ impl<'r, B: Backend> Arena<'r, B> {
         self.inner.as_ref().unwrap()
     }
 
-    /// Creates a swapchain.
+    /// Creates a swapchain for an additional window.
+    ///
+    /// The swapchains of all windows can be presented to in the same frame. The window must be
+    /// compatible with the instance: see the documentation of the backend.
     #[inline]
-    pub fn create_swapchain(&self) -> Swapchain<B> {
-        Swapchain(unsafe { self.instance.create_swapchain(self.inner()) })
+    pub fn create_swapchain(&self, window: &B::Window) -> Result<Swapchain<B>> {
+        let swapchain = unsafe { self.instance.create_swapchain(self.inner(), window)? };
+        self.renderer
+            .record_capture(|c| c.swapchain(self.inner(), swapchain));
+        Ok(Swapchain(swapchain))
     }
 
     /// Creates a shader module from SPIR-V bytecode.
@@ -428,17 +421,29 @@ impl<'r, B: Backend> Arena<'r, B> {
     pub fn create_shader_module<'a, 're>(
         &'a self,
         shader: ReflectedShader<'_, 're>,
-    ) -> ShaderModule<'a, 're, B> {
-        ShaderModule {
-            module: unsafe {
-                self.instance.create_shader_module(
-                    self.inner(),
-                    shader.bytecode,
-                    shader.reflection.stage,
-                )
-            },
+    ) -> Result<ShaderModule<'a, 're, B>> {
+        let module = unsafe {
+            self.instance.create_shader_module(
+                self.inner(),
+                shader.bytecode,
+                shader.reflection.stage,
+            )?
+        };
+        self.renderer.record_capture(|c| {
+            c.shader_module::<B>(
+                self.inner(),
+                module,
+                shader.reflection.stage,
+                shader.bytecode,
+                shader.reflection.fragment_outputs.len(),
+            )
+        });
+        Ok(ShaderModule {
+            module,
             reflection: shader.reflection,
-        }
+            bytecode: self.misc.alloc_extend(shader.bytecode.iter().cloned()),
+            hash: bytecode_hash(shader.bytecode),
+        })
     }
 
     /// Shorthand to create a `GraphicsShaderStages` object with a vertex and a fragment shader.
@@ -447,35 +452,41 @@ impl<'r, B: Backend> Arena<'r, B> {
         &'a self,
         vertex_shader: ReflectedShader<'_, 're>,
         fragment_shader: ReflectedShader<'_, 're>,
-    ) -> GraphicsShaderStages<'a, 're, B> {
-        assert_eq!(
-            vertex_shader.reflection.stage,
-            ShaderStageFlags::VERTEX,
-            "invalid shader stage"
-        );
-        assert_eq!(
-            fragment_shader.reflection.stage,
-            ShaderStageFlags::FRAGMENT,
-            "invalid shader stage"
-        );
-        let vert = self.create_shader_module(vertex_shader);
-        let frag = self.create_shader_module(fragment_shader);
-        GraphicsShaderStages {
+    ) -> Result<GraphicsShaderStages<'a, 're, B>> {
+        check_shader_stage(vertex_shader.reflection.stage, ShaderStageFlags::VERTEX)?;
+        check_shader_stage(fragment_shader.reflection.stage, ShaderStageFlags::FRAGMENT)?;
+        let vert = self.create_shader_module(vertex_shader)?;
+        let frag = self.create_shader_module(fragment_shader)?;
+        Ok(GraphicsShaderStages {
             vertex: vert,
             geometry: None,
             fragment: frag.into(),
             tess_eval: None,
             tess_control: None,
-        }
+        })
     }
 
     /// Creates a graphics pipeline given the pipeline description passed in create_info
     /// and information derived from the pipeline interface type.
+    ///
+    /// If an identical pipeline was already created in this arena, returns it instead
+    /// (see [pipeline::cache]).
     pub fn create_graphics_pipeline<'a, P: Arguments<'a, B>>(
         &'a self,
         create_info: &GraphicsPipelineCreateInfo<'a, '_, B>,
-    ) -> GraphicsPipeline<'a, B, TypedSignature<'a, B, P>> {
-        let root_signature = self.renderer.get_cached_signature::<P>();
+    ) -> Result<GraphicsPipeline<'a, B, TypedSignature<'a, B, P>>> {
+        let root_signature = self.renderer.get_cached_signature::<P>()?;
+
+        // hold the lock until the pipeline is in the cache, so that concurrent requests for the
+        // same pipeline don't both create it
+        let mut cache = self.renderer.pipeline_cache.lock().unwrap();
+        if let Some(inner) = cache.get(self.inner(), root_signature.0, create_info) {
+            return Ok(GraphicsPipeline {
+                // the pipeline is in this arena
+                inner: unsafe { &*inner },
+                signature: root_signature,
+            });
+        }
 
         // validate the pipeline
         /*let validation_result =
@@ -487,17 +498,53 @@ impl<'r, B: Backend> Arena<'r, B> {
             panic!("graphics pipeline validation failed");
         }*/
 
-        GraphicsPipeline {
-            inner: unsafe {
-                self.instance.create_graphics_pipeline(
-                    self.inner(),
-                    root_signature.0,
-                    P::SIGNATURE,
-                    &create_info,
-                )
-            },
+        let inner = unsafe {
+            self.instance.create_graphics_pipeline(
+                self.inner(),
+                root_signature.0,
+                P::SIGNATURE,
+                &create_info,
+            )?
+        };
+        self.renderer.record_capture(|c| {
+            c.graphics_pipeline(self.inner(), inner, root_signature.0, create_info)
+        });
+        cache.insert(self.inner(), root_signature.0, create_info, inner);
+
+        Ok(GraphicsPipeline {
+            inner,
             signature: root_signature,
-        }
+        })
+    }
+
+    /// Creates a compute pipeline from a compute shader module
+    /// and information derived from the pipeline interface type.
+    pub fn create_compute_pipeline<'a, P: Arguments<'a, B>>(
+        &'a self,
+        create_info: &ComputePipelineCreateInfo<'a, '_, B>,
+    ) -> Result<ComputePipeline<'a, B, TypedSignature<'a, B, P>>> {
+        check_shader_stage(
+            create_info.shader_stage.reflection.stage,
+            ShaderStageFlags::COMPUTE,
+        )?;
+        let root_signature = self.renderer.get_cached_signature::<P>()?;
+
+        let inner = unsafe {
+            self.instance.create_compute_pipeline(
+                self.inner(),
+                root_signature.0,
+                P::SIGNATURE,
+                &create_info,
+            )?
+        };
+        self.renderer.record_capture(|c| {
+            c.compute_pipeline(self.inner(), inner, root_signature.0, create_info)
+        });
+
+        Ok(ComputePipeline {
+            inner,
+            signature: root_signature,
+        })
     }
 
     /// Creates an image.
@@ -524,21 +571,53 @@ impl<'r, B: Backend> Arena<'r, B> {
         samples: u32,
         usage: ImageUsageFlags,
         initial_data: Option<&[u8]>,
-    ) -> UnsafeImage<B> {
-        UnsafeImage {
-            image: unsafe {
-                self.instance.create_image(
-                    self.inner(),
-                    scope,
-                    format,
-                    dimensions,
-                    mipcount,
-                    samples,
-                    usage,
-                    initial_data,
-                )
-            },
+    ) -> Result<UnsafeImage<B>> {
+        let image = unsafe {
+            self.instance.create_image(
+                self.inner(),
+                scope,
+                format,
+                dimensions,
+                mipcount,
+                samples,
+                usage,
+                initial_data,
+            )?
+        };
+        self.renderer.record_capture(|c| {
+            c.image::<B>(
+                self.inner(),
+                image,
+                scope,
+                format,
+                dimensions,
+                mipcount,
+                samples,
+                usage,
+                initial_data,
+            )
+        });
+        Ok(UnsafeImage { image })
+    }
+
+    /// Creates an image from the parameters given to an image builder.
+    fn build_image(&self, c: &ImageCreateInfo) -> Result<&B::Image> {
+        let image = self
+            .create_image(
+                c.scope,
+                c.format,
+                c.dimensions,
+                c.mipmaps,
+                c.samples,
+                c.usage,
+                c.data,
+            )?
+            .image;
+        if let Some(name) = c.debug_name {
+            self.renderer
+                .set_debug_name(DebugObject::Image(image), name);
         }
+        Ok(image)
     }
 
     #[inline]
@@ -546,19 +625,12 @@ impl<'r, B: Backend> Arena<'r, B> {
         &'a self,
         format: Format,
         size: u32,
-    ) -> Image1dBuilder<Image1d<'a, B>, impl Fn(&ImageCreateInfo) -> Image1d<'a, B>> {
-        Image1dBuilder::new(format, size, move |c| Image1d {
-            image: self
-                .create_image(
-                    c.scope,
-                    c.format,
-                    c.dimensions,
-                    c.mipmaps,
-                    c.samples,
-                    c.usage,
-                    c.data,
-                )
-                .image,
+    ) -> Image1dBuilder<Result<Image1d<'a, B>>, impl Fn(&ImageCreateInfo) -> Result<Image1d<'a, B>>>
+    {
+        Image1dBuilder::new(format, size, move |c| {
+            Ok(Image1d {
+                image: self.build_image(c)?,
+            })
         })
     }
 
@@ -568,19 +640,12 @@ impl<'r, B: Backend> Arena<'r, B> {
         format: Format,
         width: u32,
         height: u32,
-    ) -> Image2dBuilder<Image2d<'a, B>, impl Fn(&ImageCreateInfo) -> Image2d<'a, B>> {
-        Image2dBuilder::new(format, (width, height), move |c| Image2d {
-            image: self
-                .create_image(
-                    c.scope,
-                    c.format,
-                    c.dimensions,
-                    c.mipmaps,
-                    c.samples,
-                    c.usage,
-                    c.data,
-                )
-                .image,
+    ) -> Image2dBuilder<Result<Image2d<'a, B>>, impl Fn(&ImageCreateInfo) -> Result<Image2d<'a, B>>>
+    {
+        Image2dBuilder::new(format, (width, height), move |c| {
+            Ok(Image2d {
+                image: self.build_image(c)?,
+            })
         })
     }
 
@@ -591,91 +656,127 @@ impl<'r, B: Backend> Arena<'r, B> {
         width: u32,
         height: u32,
     ) -> RenderTargetBuilder<
-        RenderTargetImage2d<'a, B>,
-        impl Fn(&ImageCreateInfo) -> RenderTargetImage2d<'a, B>,
+        Result<RenderTargetImage2d<'a, B>>,
+        impl Fn(&ImageCreateInfo) -> Result<RenderTargetImage2d<'a, B>>,
     > {
-        RenderTargetBuilder::new(format, (width, height), move |c| RenderTargetImage2d {
-            image: self
-                .create_image(
-                    c.scope,
-                    c.format,
-                    c.dimensions,
-                    c.mipmaps,
-                    c.samples,
-                    c.usage,
-                    c.data,
-                )
-                .image,
+        RenderTargetBuilder::new(format, (width, height), move |c| {
+            Ok(RenderTargetImage2d {
+                image: self.build_image(c)?,
+            })
         })
     }
 
-    /// Creates a GPU (device local) buffer.
+    /// Creates a mutable GPU (device local) buffer.
+    #[inline]
+    pub fn create_buffer_typeless(&self, size: u64) -> Result<BufferTypeless<B>> {
+        self.create_buffer_typeless_in(size, MemoryType::DeviceLocal)
+    }
+
+    /// Creates a host-visible buffer that can be written from the CPU with
+    /// [Api::write_buffer].
     #[inline]
-    pub fn create_buffer_typeless(&self, size: u64) -> BufferTypeless<B> {
-        BufferTypeless(unsafe { self.instance.create_buffer(&self.inner(), size) })
+    pub fn create_host_buffer_typeless(&self, size: u64) -> Result<BufferTypeless<B>> {
+        self.create_buffer_typeless_in(size, MemoryType::HostUpload)
+    }
+
+    /// Creates a mutable GPU buffer in the specified memory.
+    pub fn create_buffer_typeless_in(
+        &self,
+        size: u64,
+        memory: MemoryType,
+    ) -> Result<BufferTypeless<B>> {
+        let buffer = unsafe { self.instance.create_buffer(&self.inner(), size, memory)? };
+        self.renderer
+            .record_capture(|c| c.buffer::<B>(self.inner(), buffer, size, None));
+        Ok(BufferTypeless(buffer))
+    }
+
+    /// Creates a pool of `count` queries of the specified type. See [query].
+    pub fn create_query_pool(&self, ty: QueryType, count: u32) -> Result<QueryPool<B>> {
+        let pool = unsafe { self.instance.create_query_pool(self.inner(), ty, count)? };
+        self.renderer
+            .record_capture(|c| c.query_pool::<B>(self.inner(), pool, ty, count));
+        Ok(QueryPool {
+            inner: pool,
+            ty,
+            count,
+        })
     }
 
     /// Creates a GPU (device local) buffer.
     #[inline]
-    pub fn create_immutable_buffer_typeless(&self, size: u64, data: &[u8]) -> BufferTypeless<B> {
-        BufferTypeless(unsafe {
+    pub fn create_immutable_buffer_typeless(
+        &self,
+        size: u64,
+        data: &[u8],
+    ) -> Result<BufferTypeless<B>> {
+        let buffer = unsafe {
             self.instance
-                .create_immutable_buffer(self.inner(), size, data)
-        })
+                .create_immutable_buffer(self.inner(), size, data)?
+        };
+        self.renderer
+            .record_capture(|c| c.buffer::<B>(self.inner(), buffer, size, Some(data)));
+        Ok(BufferTypeless(buffer))
     }
 
     /// Creates an immutable, device-local GPU buffer containing an object of type T.
     #[inline]
-    pub fn upload<T: Copy + 'static>(&self, data: &T) -> Buffer<B, T> {
+    pub fn upload<T: Copy + 'static>(&self, data: &T) -> Result<Buffer<B, T>> {
         let size = mem::size_of::<T>();
         let bytes = unsafe { ::std::slice::from_raw_parts(data as *const T as *const u8, size) };
 
-        Buffer(
-            unsafe {
-                self.instance
-                    .create_immutable_buffer(self.inner(), size as u64, bytes)
-            },
-            PhantomData,
-        )
+        let buffer = unsafe {
+            self.instance
+                .create_immutable_buffer(self.inner(), size as u64, bytes)?
+        };
+        self.renderer
+            .record_capture(|c| c.buffer::<B>(self.inner(), buffer, size as u64, Some(bytes)));
+        Ok(Buffer(buffer, PhantomData))
     }
 
     /// Creates an immutable, device-local GPU buffer containing an array of objects of type T.
     #[inline]
-    pub fn upload_slice<T: Copy + 'static>(&self, data: &[T]) -> Buffer<B, [T]> {
+    pub fn upload_slice<T: Copy + 'static>(&self, data: &[T]) -> Result<Buffer<B, [T]>> {
         let size = mem::size_of_val(data);
         let bytes = unsafe { ::std::slice::from_raw_parts(data.as_ptr() as *const u8, size) };
 
-        Buffer(
-            unsafe {
-                self.instance
-                    .create_immutable_buffer(&self.inner(), size as u64, bytes)
-            },
-            PhantomData,
-        )
+        let buffer = unsafe {
+            self.instance
+                .create_immutable_buffer(&self.inner(), size as u64, bytes)?
+        };
+        self.renderer
+            .record_capture(|c| c.buffer::<B>(self.inner(), buffer, size as u64, Some(bytes)));
+        Ok(Buffer(buffer, PhantomData))
     }
 
     /// Creates an immutable, device-local GPU buffer containing an array of objects of type T.
     #[inline]
-    pub fn host_reference<'a, T: Copy + 'static>(&'a self, data: &'a T) -> HostReference<'a, B, T> {
+    pub fn host_reference<'a, T: Copy + 'static>(
+        &'a self,
+        data: &'a T,
+    ) -> Result<HostReference<'a, B, T>> {
         let size = mem::size_of::<T>();
         let bytes = unsafe { ::std::slice::from_raw_parts(data as *const T as *const u8, size) };
 
-        HostReference(
-            unsafe { self.instance.create_host_reference(self.inner(), bytes) },
-            PhantomData,
-        )
+        let host_reference = unsafe { self.instance.create_host_reference(self.inner(), bytes)? };
+        self.renderer
+            .record_capture(|c| c.host_reference::<B>(self.inner(), host_reference, bytes));
+        Ok(HostReference(host_reference, PhantomData))
     }
 
     /// Creates an immutable, device-local GPU buffer containing an array of objects of type T.
     #[inline]
-    pub fn host_slice<'a, T: Copy + 'static>(&'a self, data: &'a [T]) -> HostReference<'a, B, T> {
+    pub fn host_slice<'a, T: Copy + 'static>(
+        &'a self,
+        data: &'a [T],
+    ) -> Result<HostReference<'a, B, T>> {
         let size = mem::size_of_val(data);
         let bytes = unsafe { ::std::slice::from_raw_parts(data.as_ptr() as *const u8, size) };
 
-        HostReference(
-            unsafe { self.instance.create_host_reference(self.inner(), bytes) },
-            PhantomData,
-        )
+        let host_reference = unsafe { self.instance.create_host_reference(self.inner(), bytes)? };
+        self.renderer
+            .record_capture(|c| c.host_reference::<B>(self.inner(), host_reference, bytes));
+        Ok(HostReference(host_reference, PhantomData))
     }
 
     /// Creates an _argument block_.
@@ -690,31 +791,72 @@ impl<'r, B: Backend> Arena<'r, B> {
         depth_stencil_target: Option<DepthStencilView<'a, B>>,
         viewports: impl IntoIterator<Item = Viewport>,
         scissors: impl IntoIterator<Item = Scissor>,
-    ) -> ArgumentBlock<'a, B, S> {
-        ArgumentBlock {
-            arguments: unsafe {
-                self.instance.create_argument_block(
-                    self.inner(),
-                    signature.inner(),
-                    inherited,
-                    descriptors,
-                    vertex_buffers,
-                    index_buffer,
-                    render_targets,
-                    depth_stencil_target,
-                    viewports,
-                    scissors,
-                )
-            },
+    ) -> Result<ArgumentBlock<'a, B, S>> {
+        let inherited: Vec<_> = inherited.into_iter().collect();
+        let descriptors: Vec<_> = descriptors.into_iter().collect();
+        let vertex_buffers: Vec<_> = vertex_buffers.into_iter().collect();
+        let render_targets: Vec<_> = render_targets.into_iter().collect();
+
+        // collect the resources accessed through this block, for hazard tracking
+        let accesses = inherited
+            .iter()
+            .flat_map(|b| b.1.iter().cloned())
+            .chain(descriptors.iter().filter_map(barrier::descriptor_access))
+            .chain(vertex_buffers.iter().map(barrier::vertex_buffer_access))
+            .chain(index_buffer.iter().map(barrier::index_buffer_access))
+            .chain(render_targets.iter().map(barrier::render_target_access))
+            .chain(
+                depth_stencil_target
+                    .iter()
+                    .map(barrier::depth_stencil_target_access),
+            );
+        let accesses = &*self.misc.alloc_extend(accesses);
+
+        let viewports: Vec<_> = viewports.into_iter().collect();
+        let scissors: Vec<_> = scissors.into_iter().collect();
+
+        let arguments = unsafe {
+            self.instance.create_argument_block(
+                self.inner(),
+                signature.inner(),
+                inherited.iter().cloned(),
+                descriptors.iter().cloned(),
+                vertex_buffers.iter().cloned(),
+                index_buffer,
+                render_targets.iter().cloned(),
+                depth_stencil_target,
+                viewports.iter().cloned(),
+                scissors.iter().cloned(),
+            )?
+        };
+        self.renderer.record_capture(|c| {
+            c.argument_block(
+                self.inner(),
+                arguments,
+                signature.inner(),
+                &inherited,
+                &descriptors,
+                &vertex_buffers,
+                index_buffer.as_ref(),
+                &render_targets,
+                depth_stencil_target.as_ref(),
+                &viewports,
+                &scissors,
+            )
+        });
+
+        Ok(ArgumentBlock {
+            arguments,
+            accesses,
             signature,
-        }
+        })
     }
 
     pub fn create_typed_argument_block<'a, T: Arguments<'a, B>>(
         &'a self,
         args: T,
-    ) -> ArgumentBlock<'a, B, TypedSignature<'a, B, T::IntoInterface>> {
-        let sig = self.renderer.get_cached_signature::<T::IntoInterface>();
+    ) -> Result<ArgumentBlock<'a, B, TypedSignature<'a, B, T::IntoInterface>>> {
+        let sig = self.renderer.get_cached_signature::<T::IntoInterface>()?;
         args.into_block(sig, self)
     }
 
@@ -722,14 +864,30 @@ impl<'r, B: Backend> Arena<'r, B> {
         &'a self,
         inherited: &[&'a B::Signature],
         description: &SignatureDescription,
-    ) -> &'a B::Signature {
-        unsafe {
+    ) -> Result<&'a B::Signature> {
+        let signature = unsafe {
             self.instance
-                .create_signature(self.inner(), inherited, description)
-        }
+                .create_signature(self.inner(), inherited, description)?
+        };
+        self.renderer
+            .record_capture(|c| c.signature::<B>(self.inner(), signature, inherited, description));
+        Ok(signature)
     }
 }
 
+/// Returns a validation error if a shader is used for the wrong pipeline stage.
+fn check_shader_stage(stage: ShaderStageFlags, expected: ShaderStageFlags) -> Result<()> {
+    if stage != expected {
+        return Err(Error::Validation {
+            message: format!(
+                "invalid shader stage: expected {:?}, got {:?}",
+                expected, stage
+            ),
+        });
+    }
+    Ok(())
+}
+
 //--------------------------------------------------------------------------------------------------
 
 /// Graphics API trait.
@@ -753,50 +911,146 @@ pub struct Api<B: Backend> {
     default_arena: Option<Box<B::Arena>>,
     /// Cache of pipeline signatures
     signature_cache: Mutex<HashMap<TypeId, *const B::Signature>>,
+    /// Cache of pipeline signatures defined at runtime
+    dynamic_signature_cache: Mutex<DynamicSignatureCache<B>>,
+    /// Cache of graphics pipelines
+    pipeline_cache: Mutex<PipelineCache<B>>,
+    /// Records object creation parameters for frame captures, if enabled
+    capture: Mutex<Option<CaptureRecorder>>,
 }
 
 impl<B: Backend> Api<B> {
     /// Creates a new renderer with the specified backend.
-    pub fn new(instance: B::Instance) -> Api<B> {
-        let default_arena = unsafe { instance.create_arena() };
-        Api {
+    pub fn new(instance: B::Instance) -> Result<Api<B>> {
+        let default_arena = unsafe { instance.create_arena()? };
+        Ok(Api {
             instance,
             default_arena: Some(default_arena),
             signature_cache: Mutex::new(HashMap::new()),
-        }
+            dynamic_signature_cache: Mutex::new(DynamicSignatureCache::new()),
+            pipeline_cache: Mutex::new(PipelineCache::new()),
+            capture: Mutex::new(None),
+        })
     }
 
-    pub fn create_arena(&self) -> Arena<B> {
-        Arena {
+    pub fn create_arena(&self) -> Result<Arena<B>> {
+        Ok(Arena {
             renderer: self,
             instance: &self.instance,
-            inner: Some(unsafe { self.instance.create_arena() }),
+            inner: Some(unsafe { self.instance.create_arena()? }),
             misc: DroplessArena::new(),
-        }
+        })
+    }
+
+    /// Returns the backend instance.
+    pub fn instance(&self) -> &B::Instance {
+        &self.instance
+    }
+
+    /// Returns the number of graphics pipelines that were found in the cache, and the number of
+    /// pipelines that were created. See [pipeline::cache].
+    pub fn pipeline_cache_stats(&self) -> PipelineCacheStats {
+        self.pipeline_cache.lock().unwrap().stats()
     }
 
     /// Returns or creates the pipeline signature associated to the pipeline interface type.
-    pub fn get_cached_signature<'r, P: Arguments<'r, B>>(&'r self) -> TypedSignature<'r, B, P> {
+    pub fn get_cached_signature<'r, P: Arguments<'r, B>>(
+        &'r self,
+    ) -> Result<TypedSignature<'r, B, P>> {
         let typeid = TypeId::of::<P::UniqueType>();
         let cached = self.signature_cache.lock().unwrap().get(&typeid).cloned();
         if let Some(cached) = cached {
-            unsafe { TypedSignature(&*cached, PhantomData) }
+            unsafe { Ok(TypedSignature(&*cached, PhantomData)) }
         } else {
             // signature not created yet
-            let inherited = P::get_inherited_signatures(self);
+            let inherited = P::get_inherited_signatures(self)?;
             let sig = unsafe {
                 self.instance.create_signature(
                     self.default_arena.as_ref().unwrap(),
                     &inherited,
                     P::SIGNATURE,
-                )
+                )?
             };
+            self.record_capture(|c| {
+                c.signature::<B>(
+                    self.default_arena.as_ref().unwrap(),
+                    sig,
+                    &inherited,
+                    P::SIGNATURE,
+                )
+            });
             self.signature_cache
                 .lock()
                 .unwrap()
                 .insert(typeid, sig as *const _);
-            TypedSignature(sig, PhantomData)
+            Ok(TypedSignature(sig, PhantomData))
+        }
+    }
+
+    /// Returns or creates a pipeline signature with the description of the builder.
+    ///
+    /// The signature is owned by the API, and is shared with all other signatures created by this
+    /// method that have a structurally identical description. Unlike the signatures returned by
+    /// [DynamicSignatureBuilder::build], it can be stored in long-lived objects.
+    ///
+    /// Inherited signatures must also be owned by the API (i.e. returned by this method or by
+    /// [get_cached_signature]).
+    pub fn get_cached_dynamic_signature<'r>(
+        &'r self,
+        builder: &DynamicSignatureBuilder<B>,
+    ) -> Result<DynamicSignature<'r, B>> {
+        let description = builder.description();
+        let inherited = builder.inherited_signatures();
+        for &s in inherited {
+            let typed = self
+                .signature_cache
+                .lock()
+                .unwrap()
+                .values()
+                .any(|&t| t == s as *const _);
+            if !typed && !self.dynamic_signature_cache.lock().unwrap().contains(s) {
+                return Err(Error::Validation {
+                    message: "inherited signature is not owned by the API".to_string(),
+                });
+            }
         }
+        // inherited signatures live as long as the API
+        let inherited: Vec<&'r B::Signature> = inherited
+            .iter()
+            .map(|&s| unsafe { &*(s as *const _) })
+            .collect();
+
+        let mut cache = self.dynamic_signature_cache.lock().unwrap();
+        if let Some((description, raw)) = cache.get(&inherited, &description) {
+            return unsafe {
+                Ok(DynamicSignature {
+                    description: &*description,
+                    raw: &*raw,
+                })
+            };
+        }
+
+        // signature not created yet
+        let raw = unsafe {
+            self.instance.create_signature(
+                self.default_arena.as_ref().unwrap(),
+                &inherited,
+                &description,
+            )?
+        };
+        self.record_capture(|c| {
+            c.signature::<B>(
+                self.default_arena.as_ref().unwrap(),
+                raw,
+                &inherited,
+                &description,
+            )
+        });
+        let description = cache.insert(&inherited, &description, raw);
+        Ok(DynamicSignature {
+            description: unsafe { &*description },
+            raw,
+        })
     }
 
     /// Returns the default swapchain if there is one.
@@ -817,7 +1071,138 @@ impl<B: Backend> Api<B> {
         &self,
         command_buffers: impl IntoIterator<Item = CommandBuffer<'a, B>>,
     ) {
+        self.submit_frame_with_recorded(command_buffers, iter::empty())
+    }
+
+    /// Submits command buffers and pre-recorded commands for the current frame.
+    ///
+    /// Each recorded command list is submitted with an offset added to its sortkeys. See
+    /// [RecordedCommands] and [submit_frame](Api::submit_frame).
+    pub fn submit_frame_with_recorded<'a, 'r>(
+        &self,
+        command_buffers: impl IntoIterator<Item = CommandBuffer<'a, B>>,
+        recorded: impl IntoIterator<Item = (&'r RecordedCommands<'a, B>, u64)>,
+    ) where
+        'a: 'r,
+    {
         let commands = sort_command_buffers(command_buffers);
-        unsafe { self.instance.submit_frame(&commands) }
+        let commands = merge_recorded_commands(commands, recorded);
+        let commands = barrier::insert_pipeline_barriers(commands);
+        self.capture_frame(&commands);
+        let batches = schedule::schedule_batches(&commands, self.instance.queues());
+        unsafe { self.instance.submit_frame(&commands, &batches) }
+    }
+
+    /// Writes data to a mutable buffer at the specified byte offset.
+    ///
+    /// Host-visible buffers are written immediately: the written range must not be in use by a
+    /// frame that is still executing on the GPU (see [UploadRing](crate::ring::UploadRing) for a
+    /// way to manage this). Writes to device-local buffers are ordered before the commands
+    /// of the next submitted frame.
+    pub fn write_buffer<'a>(
+        &self,
+        buffer: impl Into<BufferTypeless<'a, B>>,
+        offset: u64,
+        data: &[u8],
+    ) {
+        unsafe { self.instance.write_buffer(buffer.into().0, offset, data) }
+    }
+
+    /// Completes the readbacks of the frames that have finished executing, without blocking.
+    ///
+    /// Readbacks are also polled during [submit_frame](Api::submit_frame). See [readback].
+    pub fn poll_readbacks(&self) {
+        unsafe {
+            self.instance.poll_readbacks(Duration::from_secs(0));
+        }
+    }
+
+    /// Blocks until the data of the given readback is available, or until the timeout expires.
+    ///
+    /// Returns `None` if the timeout expired, or if the readback cannot complete because it was
+    /// not submitted with [submit_frame](Api::submit_frame).
+    pub fn wait_readback(&self, readback: &Readback, timeout: Duration) -> Option<Arc<[u8]>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(data) = readback.try_get() {
+                return Some(data);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return None;
+            }
+            // nothing completed: either the timeout expired, or there is nothing to wait for
+            if !unsafe { self.instance.poll_readbacks(deadline - now) } {
+                return readback.try_get();
+            }
+        }
+    }
+
+    /// Sets the name of an object in graphics debuggers. See [debug].
+    pub fn set_debug_name<'a>(&self, object: impl Into<DebugObject<'a, B>>, name: &str) {
+        unsafe { self.instance.set_debug_name(object.into(), name) }
+    }
+
+    /// Returns the GPU timings of the most recent frame that has finished executing.
+    ///
+    /// This is typically the frame submitted a few frames before the current one. See [profile].
+    pub fn frame_profile(&self) -> Option<FrameProfile> {
+        self.instance.frame_profile()
+    }
+
+    /// Starts recording the creation parameters of objects, so that frames can be captured.
+    ///
+    /// Only objects created after this call can appear in a capture. See [capture].
+    pub fn enable_capture(&self) {
+        let mut capture = self.capture.lock().unwrap();
+        if capture.is_none() {
+            *capture = Some(CaptureRecorder::new());
+        }
+    }
+
+    /// Returns whether object creation parameters are being recorded.
+    pub fn is_capture_enabled(&self) -> bool {
+        self.capture.lock().unwrap().is_some()
+    }
+
+    /// Captures the next submitted frame to the specified file.
+    ///
+    /// This also enables capture if it was not already enabled, but note that objects created
+    /// before that cannot be captured. See [capture].
+    pub fn capture_next_frame(&self, path: impl Into<PathBuf>) {
+        self.enable_capture();
+        self.capture.lock().unwrap().as_mut().unwrap().requested = Some(path.into());
+    }
+
+    fn record_capture(&self, f: impl FnOnce(&mut CaptureRecorder)) {
+        if let Some(recorder) = self.capture.lock().unwrap().as_mut() {
+            f(recorder)
+        }
+    }
+
+    /// Writes a capture of the sorted command stream, if one was requested.
+    fn capture_frame(&self, commands: &[Command<B>]) {
+        let mut capture = self.capture.lock().unwrap();
+        let recorder = match capture.as_mut() {
+            Some(recorder) => recorder,
+            None => return,
+        };
+        let path = match recorder.requested.take() {
+            Some(path) => path,
+            None => return,
+        };
+
+        // the default swapchain is not created through an arena
+        if let Some(swapchain) = unsafe { self.instance.default_swapchain() } {
+            recorder.swapchain(&self.instance, swapchain);
+        }
+
+        let result = recorder
+            .capture_frame(commands)
+            .and_then(|capture| capture.save(&path).map_err(|e| e.to_string()));
+        match result {
+            Ok(()) => log::info!("frame captured to {}", path.display()),
+            Err(e) => log::error!("frame capture to {} failed: {}", path.display(), e),
+        }
     }
 }
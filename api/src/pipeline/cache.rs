@@ -0,0 +1,351 @@
+//! Cache of graphics pipelines.
+//!
+//! [Arena::create_graphics_pipeline](crate::Arena::create_graphics_pipeline) returns an existing
+//! pipeline instead of creating a new one if a pipeline with the same signature, the same shader
+//! bytecode and the same states was already created in the arena. Pipelines are identified by a
+//! hash of these parameters, and compared on lookup.
+//!
+//! Pipelines are only shared within an arena, since a pipeline cannot outlive the arena that owns
+//! it: pipelines that are requested by long-lived objects (e.g. materials) should be created in a
+//! long-lived arena.
+//!
+//! This module also contains the cache of dynamic signatures
+//! (see [Api::get_cached_dynamic_signature](crate::Api::get_cached_dynamic_signature)), which
+//! identifies signatures by their description instead of their type.
+use crate::{
+    pipeline::{
+        ColorBlendAttachmentState, ColorBlendAttachments, DepthStencilState,
+        GraphicsPipelineCreateInfo, GraphicsShaderStages, InputAssemblyState, LogicOp,
+        MultisampleState, RasterisationState, Scissors, ScissorsOwned, SignatureDescription,
+        Viewports, ViewportsOwned,
+    },
+    Backend,
+};
+use autograph_spirv::DroplessArena;
+use ordered_float::NotNan;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+};
+
+/// Statistics of the pipeline cache. See [Api::pipeline_cache_stats](crate::Api::pipeline_cache_stats).
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct PipelineCacheStats {
+    /// Number of pipelines returned from the cache.
+    pub hits: u64,
+    /// Number of pipelines created by the backend.
+    pub misses: u64,
+    /// Number of pipelines currently in the cache, in all arenas.
+    pub pipelines: usize,
+}
+
+/// Hash of SPIR-V bytecode, computed when creating shader modules.
+pub(crate) fn bytecode_hash(bytecode: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    bytecode.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Bytecode of the shader stages of a pipeline.
+fn stage_bytecodes<'a, B: Backend>(
+    stages: &GraphicsShaderStages<'a, '_, B>,
+) -> [Option<&'a [u8]>; 5] {
+    [
+        Some(stages.vertex.bytecode),
+        stages.geometry.map(|s| s.bytecode),
+        stages.fragment.map(|s| s.bytecode),
+        stages.tess_eval.map(|s| s.bytecode),
+        stages.tess_control.map(|s| s.bytecode),
+    ]
+}
+
+/// Hash of the parameters of a graphics pipeline.
+fn graphics_pipeline_hash<B: Backend>(
+    signature: &B::Signature,
+    create_info: &GraphicsPipelineCreateInfo<B>,
+) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    // signatures are cached by type: same type, same signature object
+    (signature as *const B::Signature).hash(&mut hasher);
+    let stages = &create_info.shader_stages;
+    stages.vertex.hash.hash(&mut hasher);
+    for stage in &[
+        stages.geometry,
+        stages.fragment,
+        stages.tess_eval,
+        stages.tess_control,
+    ] {
+        stage.map(|s| s.hash).hash(&mut hasher);
+    }
+    create_info.viewport_state.hash(&mut hasher);
+    create_info.rasterization_state.hash(&mut hasher);
+    create_info.multisample_state.hash(&mut hasher);
+    create_info.depth_stencil_state.hash(&mut hasher);
+    create_info.input_assembly_state.hash(&mut hasher);
+    create_info.color_blend_state.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// See [ColorBlendAttachments].
+enum ColorBlendAttachmentsOwned {
+    All(ColorBlendAttachmentState),
+    Separate(Vec<ColorBlendAttachmentState>),
+}
+
+/// Copy of the parameters of a graphics pipeline.
+///
+/// Compared on lookup, so that a hash collision does not return a different pipeline.
+struct GraphicsPipelineKey<B: Backend> {
+    signature: *const B::Signature,
+    stages: [Option<Vec<u8>>; 5],
+    viewports: ViewportsOwned,
+    scissors: ScissorsOwned,
+    rasterization_state: RasterisationState,
+    multisample_state: MultisampleState,
+    depth_stencil_state: DepthStencilState,
+    input_assembly_state: InputAssemblyState,
+    logic_op: Option<LogicOp>,
+    color_blend_attachments: ColorBlendAttachmentsOwned,
+    blend_constants: [NotNan<f32>; 4],
+}
+
+impl<B: Backend> GraphicsPipelineKey<B> {
+    fn new(
+        signature: &B::Signature,
+        create_info: &GraphicsPipelineCreateInfo<B>,
+    ) -> GraphicsPipelineKey<B> {
+        let [vs, gs, fs, tes, tcs] = stage_bytecodes(&create_info.shader_stages);
+        let blend = &create_info.color_blend_state;
+        GraphicsPipelineKey {
+            signature: signature as *const _,
+            stages: [
+                vs.map(|b| b.to_vec()),
+                gs.map(|b| b.to_vec()),
+                fs.map(|b| b.to_vec()),
+                tes.map(|b| b.to_vec()),
+                tcs.map(|b| b.to_vec()),
+            ],
+            viewports: create_info.viewport_state.viewports.into(),
+            scissors: create_info.viewport_state.scissors.into(),
+            rasterization_state: create_info.rasterization_state,
+            multisample_state: create_info.multisample_state,
+            depth_stencil_state: create_info.depth_stencil_state,
+            input_assembly_state: create_info.input_assembly_state,
+            logic_op: blend.logic_op,
+            color_blend_attachments: match blend.attachments {
+                ColorBlendAttachments::All(a) => ColorBlendAttachmentsOwned::All(*a),
+                ColorBlendAttachments::Separate(a) => {
+                    ColorBlendAttachmentsOwned::Separate(a.to_vec())
+                }
+            },
+            blend_constants: blend.blend_constants,
+        }
+    }
+
+    /// Compares the key with the parameters of a pipeline, without allocating.
+    fn matches(
+        &self,
+        signature: &B::Signature,
+        create_info: &GraphicsPipelineCreateInfo<B>,
+    ) -> bool {
+        let stages = stage_bytecodes(&create_info.shader_stages);
+        let same_stages = self
+            .stages
+            .iter()
+            .zip(stages.iter())
+            .all(|(a, b)| a.as_ref().map(|a| &a[..]) == *b);
+        let same_viewports = match (&self.viewports, create_info.viewport_state.viewports) {
+            (ViewportsOwned::Static(a), Viewports::Static(b)) => &a[..] == b,
+            (ViewportsOwned::Dynamic, Viewports::Dynamic) => true,
+            _ => false,
+        };
+        let same_scissors = match (&self.scissors, create_info.viewport_state.scissors) {
+            (ScissorsOwned::Static(a), Scissors::Static(b)) => &a[..] == b,
+            (ScissorsOwned::Dynamic, Scissors::Dynamic) => true,
+            _ => false,
+        };
+        let blend = &create_info.color_blend_state;
+        let same_attachments = match (&self.color_blend_attachments, blend.attachments) {
+            (ColorBlendAttachmentsOwned::All(a), ColorBlendAttachments::All(b)) => a == b,
+            (ColorBlendAttachmentsOwned::Separate(a), ColorBlendAttachments::Separate(b)) => {
+                &a[..] == b
+            }
+            _ => false,
+        };
+
+        self.signature == signature as *const _
+            && same_stages
+            && same_viewports
+            && same_scissors
+            && self.rasterization_state == create_info.rasterization_state
+            && self.multisample_state == create_info.multisample_state
+            && self.depth_stencil_state == create_info.depth_stencil_state
+            && self.input_assembly_state == create_info.input_assembly_state
+            && self.logic_op == blend.logic_op
+            && same_attachments
+            && self.blend_constants == blend.blend_constants
+    }
+}
+
+/// Graphics pipelines by arena and by hash of their parameters.
+pub(crate) struct PipelineCache<B: Backend> {
+    pipelines:
+        HashMap<(*const B::Arena, u64), Vec<(GraphicsPipelineKey<B>, *const B::GraphicsPipeline)>>,
+    stats: PipelineCacheStats,
+}
+
+impl<B: Backend> PipelineCache<B> {
+    pub(crate) fn new() -> PipelineCache<B> {
+        PipelineCache {
+            pipelines: HashMap::new(),
+            stats: PipelineCacheStats::default(),
+        }
+    }
+
+    pub(crate) fn stats(&self) -> PipelineCacheStats {
+        PipelineCacheStats {
+            pipelines: self.pipelines.values().map(|v| v.len()).sum(),
+            ..self.stats
+        }
+    }
+
+    /// Looks up a pipeline with identical parameters, and counts a hit if found.
+    pub(crate) fn get(
+        &mut self,
+        arena: &B::Arena,
+        signature: &B::Signature,
+        create_info: &GraphicsPipelineCreateInfo<B>,
+    ) -> Option<*const B::GraphicsPipeline> {
+        let hash = graphics_pipeline_hash(signature, create_info);
+        let pipeline = self
+            .pipelines
+            .get(&(arena as *const B::Arena, hash))?
+            .iter()
+            .find(|(key, _)| key.matches(signature, create_info))
+            .map(|&(_, pipeline)| pipeline);
+        if pipeline.is_some() {
+            self.stats.hits += 1;
+        }
+        pipeline
+    }
+
+    /// Adds a pipeline that was just created by the backend, and counts a miss.
+    pub(crate) fn insert(
+        &mut self,
+        arena: &B::Arena,
+        signature: &B::Signature,
+        create_info: &GraphicsPipelineCreateInfo<B>,
+        pipeline: &B::GraphicsPipeline,
+    ) {
+        self.stats.misses += 1;
+        let hash = graphics_pipeline_hash(signature, create_info);
+        self.pipelines
+            .entry((arena as *const B::Arena, hash))
+            .or_insert_with(Vec::new)
+            .push((
+                GraphicsPipelineKey::new(signature, create_info),
+                pipeline as *const _,
+            ));
+    }
+
+    /// Removes the pipelines of an arena that is about to be dropped.
+    pub(crate) fn forget_arena(&mut self, arena: &B::Arena) {
+        let arena = arena as *const B::Arena;
+        self.pipelines.retain(|&(a, _), _| a != arena);
+    }
+}
+
+/// A dynamic signature owned by the API.
+struct CachedDynamicSignature<B: Backend> {
+    inherited: Vec<*const B::Signature>,
+    /// Allocated in the `descriptions` arena of the cache.
+    description: *const SignatureDescription<'static>,
+    signature: *const B::Signature,
+}
+
+/// Dynamic signatures by hash of their description and inherited signatures.
+///
+/// Signatures with structurally identical descriptions (and the same inherited signatures) are
+/// created only once.
+pub(crate) struct DynamicSignatureCache<B: Backend> {
+    /// Copies of the descriptions of the cached signatures.
+    descriptions: DroplessArena,
+    signatures: HashMap<u64, Vec<CachedDynamicSignature<B>>>,
+}
+
+fn dynamic_signature_hash<B: Backend>(
+    inherited: &[&B::Signature],
+    description: &SignatureDescription,
+) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for &s in inherited {
+        (s as *const B::Signature).hash(&mut hasher);
+    }
+    description.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl<B: Backend> DynamicSignatureCache<B> {
+    pub(crate) fn new() -> DynamicSignatureCache<B> {
+        DynamicSignatureCache {
+            descriptions: DroplessArena::new(),
+            signatures: HashMap::new(),
+        }
+    }
+
+    /// Returns whether the signature is owned by the cache.
+    pub(crate) fn contains(&self, signature: &B::Signature) -> bool {
+        let signature = signature as *const B::Signature;
+        self.signatures
+            .values()
+            .flat_map(|v| v.iter())
+            .any(|e| e.signature == signature)
+    }
+
+    /// Looks up a signature with the same inherited signatures and an identical description.
+    ///
+    /// Returns the copy of the description owned by the cache, and the signature.
+    pub(crate) fn get(
+        &self,
+        inherited: &[&B::Signature],
+        description: &SignatureDescription,
+    ) -> Option<(*const SignatureDescription<'static>, *const B::Signature)> {
+        let hash = dynamic_signature_hash::<B>(inherited, description);
+        self.signatures.get(&hash)?.iter().find_map(|e| {
+            let same_inherited = e.inherited.len() == inherited.len()
+                && e.inherited
+                    .iter()
+                    .zip(inherited.iter())
+                    .all(|(&a, &b)| a == b as *const B::Signature);
+            // the description is only freed when the cache is dropped
+            if same_inherited && unsafe { *e.description == *description } {
+                Some((e.description, e.signature))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Adds a signature that was just created by the backend.
+    ///
+    /// Returns the copy of the description owned by the cache.
+    pub(crate) fn insert(
+        &mut self,
+        inherited: &[&B::Signature],
+        description: &SignatureDescription,
+        signature: &B::Signature,
+    ) -> *const SignatureDescription<'static> {
+        let hash = dynamic_signature_hash::<B>(inherited, description);
+        let copy = description.copy_to(&self.descriptions) as *const SignatureDescription
+            as *const SignatureDescription<'static>;
+        self.signatures
+            .entry(hash)
+            .or_insert_with(Vec::new)
+            .push(CachedDynamicSignature {
+                inherited: inherited.iter().map(|&s| s as *const _).collect(),
+                description: copy,
+                signature: signature as *const _,
+            });
+        copy
+    }
+}
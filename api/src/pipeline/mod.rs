@@ -1,20 +1,24 @@
 use crate::{
+    barrier::ResourceAccess,
     buffer::Buffer,
     descriptor::{Descriptor, ResourceBinding},
+    error::Result,
     format::Format,
     image::{DepthStencilView, RenderTargetView},
     vertex::{
         IndexBufferView, IndexData, IndexFormat, Semantic, VertexBufferView, VertexData,
-        VertexInputRate, VertexLayout,
+        VertexInputRate, VertexLayout, VertexLayoutElement,
     },
     Arena, Backend, Api,
 };
 pub use autograph_api_macros::Arguments;
-use autograph_spirv::{TypeDesc};
+use autograph_spirv::{DroplessArena, TypeDesc};
 use bitflags::bitflags;
 use ordered_float::NotNan;
-use std::{fmt::Debug, marker::PhantomData, mem};
+use std::{fmt::Debug, marker::PhantomData, mem, str};
 
+pub mod cache;
+pub mod reflect;
 pub mod validate;
 
 bitflags! {
@@ -511,6 +515,12 @@ pub struct GraphicsPipelineCreateInfo<'a, 'b, B: Backend> {
     //pub dynamic_state: DynamicStateFlags,
 }
 
+#[derive(Copy, Clone)]
+pub struct ComputePipelineCreateInfo<'a, 'b, B: Backend> {
+    /// Compute shader
+    pub shader_stage: ShaderModule<'a, 'b, B>,
+}
+
 //--------------------------------------------------------------------------------------------------
 
 /// Shader module.
@@ -521,6 +531,10 @@ pub struct GraphicsPipelineCreateInfo<'a, 'b, B: Backend> {
 pub struct ShaderModule<'a, 're, B: Backend> {
     pub(crate) module: &'a B::ShaderModule,
     pub(crate) reflection: &'re ShaderStageReflection<'re>,
+    /// Copy of the bytecode, to find identical pipelines in the [cache].
+    pub(crate) bytecode: &'a [u8],
+    /// Hash of the bytecode.
+    pub(crate) hash: u64,
 }
 
 impl<'a, 're, B: Backend> ShaderModule<'a, 're, B> {
@@ -560,7 +574,7 @@ pub struct VertexInputBinding<'a> {
 /// Describes the contents (all arguments) of an argument block.
 ///
 /// This can be seen as the 'layout' or 'format' of an argument block.
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub struct SignatureDescription<'a> {
     /// Signatures of inherited argument blocks.
     ///
@@ -657,6 +671,68 @@ impl<'a> SignatureDescription<'a> {
                 .map(|&s| s.count_scissors())
                 .sum::<usize>()
     }
+
+    /// Copies this description, its inherited descriptions, and all the type information that
+    /// they reference into the specified arena.
+    pub(crate) fn copy_to<'b>(&self, a: &'b DroplessArena) -> &'b SignatureDescription<'b> {
+        // don't allocate in the arena while extending it: collect into vectors first
+        let inherited: Vec<_> = self.inherited.iter().map(|d| d.copy_to(a)).collect();
+        let descriptors: Vec<_> = self
+            .descriptors
+            .iter()
+            .map(|b| ResourceBinding {
+                set: b.set,
+                index: b.index,
+                ty: b.ty,
+                stage_flags: b.stage_flags,
+                count: b.count,
+                data_ty: b.data_ty.map(|ty| ty.copy_to(a)),
+                data_layout: b.data_layout.map(|l| l.copy_to(a)),
+                data_format: b.data_format,
+            })
+            .collect();
+        let vertex_inputs: Vec<_> = self
+            .vertex_inputs
+            .iter()
+            .map(|vi| {
+                let elements: Vec<_> = vi
+                    .layout
+                    .elements
+                    .iter()
+                    .map(|e| VertexLayoutElement {
+                        semantic: e.semantic.map(|s| Semantic {
+                            // the bytes come from a str
+                            name: str::from_utf8(a.alloc_extend(s.name.bytes())).unwrap(),
+                            index: s.index,
+                        }),
+                        format: e.format,
+                        offset: e.offset,
+                    })
+                    .collect();
+                VertexInputBinding {
+                    layout: VertexLayout {
+                        elements: a.alloc_extend(elements.into_iter()),
+                        stride: vi.layout.stride,
+                    },
+                    rate: vi.rate,
+                    base_location: vi.base_location,
+                }
+            })
+            .collect();
+
+        a.alloc(SignatureDescription {
+            inherited: a.alloc_extend(inherited.into_iter()),
+            descriptors: a.alloc_extend(descriptors.into_iter()),
+            vertex_inputs: a.alloc_extend(vertex_inputs.into_iter()),
+            fragment_outputs: a.alloc_extend(self.fragment_outputs.iter().cloned()),
+            depth_stencil_fragment_output: self.depth_stencil_fragment_output,
+            index_format: self.index_format,
+            num_viewports: self.num_viewports,
+            num_scissors: self.num_scissors,
+            is_root_fragment_output_signature: self.is_root_fragment_output_signature,
+            is_root_vertex_input_signature: self.is_root_vertex_input_signature,
+        })
+    }
 }
 
 pub trait Signature<'a, B: Backend>: Copy + Clone + Debug {
@@ -693,6 +769,8 @@ impl<'a, B: Backend, T: Arguments<'a, B>> Signature<'a, B> for TypedSignature<'a
 #[derivative(Copy(bound = ""), Clone(bound = ""), Debug(bound = ""))]
 pub struct ArgumentBlock<'a, B: Backend, S: Signature<'a, B>> {
     pub(crate) arguments: &'a B::ArgumentBlock,
+    /// Resources accessed through this block (including inherited blocks).
+    pub(crate) accesses: &'a [ResourceAccess<'a, B>],
     pub(crate) signature: S,
 }
 
@@ -702,13 +780,27 @@ pub type TypedArgumentBlock<'a, B, T> = ArgumentBlock<'a, B, TypedSignature<'a,
 /// Argument block without an associated signature.
 #[derive(derivative::Derivative)]
 #[derivative(Copy(bound = ""), Clone(bound = ""), Debug(bound = ""))]
-pub struct BareArgumentBlock<'a, B: Backend>(pub &'a B::ArgumentBlock);
+pub struct BareArgumentBlock<'a, B: Backend>(
+    pub &'a B::ArgumentBlock,
+    pub(crate) &'a [ResourceAccess<'a, B>],
+);
 
 impl<'a, B: Backend, S: Signature<'a, B>> From<ArgumentBlock<'a, B, S>>
     for BareArgumentBlock<'a, B>
 {
     fn from(b: ArgumentBlock<'a, B, S>) -> Self {
-        BareArgumentBlock(b.arguments)
+        BareArgumentBlock(b.arguments, b.accesses)
+    }
+}
+
+impl<'a, B: Backend, S: Signature<'a, B>> ArgumentBlock<'a, B, S> {
+    /// Re-associates a bare argument block with the signature it was created with.
+    pub unsafe fn from_bare(block: BareArgumentBlock<'a, B>, signature: S) -> Self {
+        ArgumentBlock {
+            arguments: block.0,
+            accesses: block.1,
+            signature,
+        }
     }
 }
 
@@ -720,6 +812,17 @@ pub struct GraphicsPipeline<'a, B: Backend, S: Signature<'a, B>> {
     pub(crate) signature: S,
 }
 
+impl<'a, B: Backend, S: Signature<'a, B>> GraphicsPipeline<'a, B, S> {
+    /// Re-associates a backend pipeline with the signature it was created with.
+    pub unsafe fn from_raw(inner: &'a B::GraphicsPipeline, signature: S) -> Self {
+        GraphicsPipeline { inner, signature }
+    }
+
+    pub fn inner(&self) -> &'a B::GraphicsPipeline {
+        self.inner
+    }
+}
+
 /// Graphics pipeline without an associated signature.
 #[derive(derivative::Derivative)]
 #[derivative(Copy(bound = ""), Clone(bound = ""), Debug(bound = ""))]
@@ -728,9 +831,20 @@ pub struct GraphicsPipelineTypeless<'a, B: Backend>(pub(crate) &'a B::GraphicsPi
 /// Type alias for argument blocks with a statically known signature.
 pub type TypedGraphicsPipeline<'a, B, T> = GraphicsPipeline<'a, B, TypedSignature<'a, B, T>>;
 
+/// Compute pipeline.
+#[derive(derivative::Derivative)]
+#[derivative(Copy(bound = ""), Clone(bound = ""), Debug(bound = ""))]
+pub struct ComputePipeline<'a, B: Backend, S: Signature<'a, B>> {
+    pub(crate) inner: &'a B::ComputePipeline,
+    pub(crate) signature: S,
+}
+
+/// Type alias for compute pipelines with a statically known signature.
+pub type TypedComputePipeline<'a, B, T> = ComputePipeline<'a, B, TypedSignature<'a, B, T>>;
+
 /// Trait for types that can be converted into an argument block.
 pub trait IntoArgumentBlock<'a, B: Backend, S: Signature<'a, B>> {
-    fn into_block(self, signature: S, arena: &'a Arena<B>) -> ArgumentBlock<'a, B, S>;
+    fn into_block(self, signature: S, arena: &'a Arena<B>) -> Result<ArgumentBlock<'a, B, S>>;
 }
 
 ///
@@ -779,6 +893,10 @@ pub trait IntoArgumentBlock<'a, B: Backend, S: Signature<'a, B>> {
 /// }
 /// ```
 ///
+/// Fields marked with `uniform_buffer` are bound as constant buffers. They can be constant buffer
+/// views, or [HostReference](crate::descriptor::HostReference)s to small host data, which is
+/// uploaded to the GPU when the frame is submitted.
+///
 /// In that case, if the render target is shared between different pipeline interfaces, it is better
 /// to put it in a separate argument block:
 ///
@@ -815,15 +933,15 @@ pub trait Arguments<'a, B: Backend>: Sized {
     type UniqueType: 'static;
     type IntoInterface: Arguments<'a, B> + 'a;
 
-    fn get_inherited_signatures(_renderer: &'a Api<B>) -> Vec<&'a B::Signature> {
-        vec![]
+    fn get_inherited_signatures(_renderer: &'a Api<B>) -> Result<Vec<&'a B::Signature>> {
+        Ok(vec![])
     }
 
     fn into_block(
         self,
         signature: TypedSignature<'a, B, Self::IntoInterface>,
         arena: &'a Arena<B>,
-    ) -> ArgumentBlock<'a, B, TypedSignature<'a, B, Self::IntoInterface>>;
+    ) -> Result<ArgumentBlock<'a, B, TypedSignature<'a, B, Self::IntoInterface>>>;
 }
 
 impl<'a, B: Backend, A: Arguments<'a, B>>
@@ -833,7 +951,7 @@ impl<'a, B: Backend, A: Arguments<'a, B>>
         self,
         signature: TypedSignature<'a, B, A::IntoInterface>,
         arena: &'a Arena<B>,
-    ) -> ArgumentBlock<'a, B, TypedSignature<'a, B, A::IntoInterface>> {
+    ) -> Result<ArgumentBlock<'a, B, TypedSignature<'a, B, A::IntoInterface>>> {
         A::into_block(self, signature, arena)
     }
 }
@@ -845,7 +963,7 @@ impl<'a, B: Backend, P: Arguments<'a, B>> Arguments<'a, B>
     type UniqueType = P::UniqueType;
     type IntoInterface = P;
 
-    fn get_inherited_signatures(renderer: &'a Api<B>) -> Vec<&'a B::Signature> {
+    fn get_inherited_signatures(renderer: &'a Api<B>) -> Result<Vec<&'a B::Signature>> {
         P::get_inherited_signatures(renderer)
     }
 
@@ -853,8 +971,8 @@ impl<'a, B: Backend, P: Arguments<'a, B>> Arguments<'a, B>
         self,
         _signature: TypedSignature<'a, B, P>,
         _arena: &'a Arena<B>,
-    ) -> ArgumentBlock<'a, B, TypedSignature<'a, B, P>> {
-        self.into()
+    ) -> Result<ArgumentBlock<'a, B, TypedSignature<'a, B, P>>> {
+        Ok(self)
     }
 }
 
@@ -867,7 +985,7 @@ pub struct VertexInputAttributeDescription<'tcx> {
     pub semantic: Option<Semantic<'tcx>>,
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub struct FragmentOutputDescription {
     // nothing yet, we just care about the count
 }
@@ -890,12 +1008,17 @@ pub struct ReflectedShader<'bc, 're> {
 
 //--------------------------------------------------------------------------------------------------
 
-// not good: this borrows the builder, cannot be stored in a struct
+/// Signature of argument blocks whose contents are only known at runtime.
+///
+/// Dynamic signatures are created with a [DynamicSignatureBuilder], either in an arena
+/// ([DynamicSignatureBuilder::build]) or in the API ([Api::get_cached_dynamic_signature]). In both
+/// cases, the signature owns a copy of its description, and does not borrow the builder: it can be
+/// stored in a struct next to typed signatures.
 #[derive(derivative::Derivative)]
 #[derivative(Copy(bound = ""), Clone(bound = ""), Debug(bound = ""))]
 pub struct DynamicSignature<'a, B: Backend> {
-    description: &'a SignatureDescription<'a>,
-    raw: &'a B::Signature,
+    pub(crate) description: &'a SignatureDescription<'a>,
+    pub(crate) raw: &'a B::Signature,
 }
 
 pub struct DynamicSignatureBuilder<'a, B: Backend> {
@@ -974,31 +1097,35 @@ impl<'a, B: Backend> DynamicSignatureBuilder<'a, B> {
         self
     }
 
-    // not good: borrows builder, result cannot be stored in a struct
-    // dynamicsignature should own stuff (box? Rc?)
-    pub fn build(&self, arena: &'a Arena<B>) -> DynamicSignature<'a, B> {
-        let inherited = arena.misc.alloc_extend(self.inherited.iter().cloned());
-        let descriptors = arena.misc.alloc_extend(self.descriptors.iter().cloned());
-        let vertex_inputs = arena.misc.alloc_extend(self.vertex_inputs.iter().cloned());
-        let fragment_outputs = arena
-            .misc
-            .alloc_extend(self.fragment_outputs.iter().cloned());
-
-        let description = arena.misc.alloc(SignatureDescription {
-            inherited,
-            descriptors,
-            vertex_inputs,
-            fragment_outputs,
+    /// Returns the description of the signature being built, borrowing the builder.
+    pub(crate) fn description(&self) -> SignatureDescription {
+        SignatureDescription {
+            inherited: &self.inherited,
+            descriptors: &self.descriptors,
+            vertex_inputs: &self.vertex_inputs,
+            fragment_outputs: &self.fragment_outputs,
             depth_stencil_fragment_output: self.depth_stencil_fragment_output,
             index_format: self.index_format,
             num_viewports: self.num_viewports,
             num_scissors: self.num_scissors,
             is_root_fragment_output_signature: self.is_root_fragment_output_signature,
             is_root_vertex_input_signature: self.is_root_vertex_input_signature,
-        });
-        let raw = arena.create_signature(&self.inherited_signatures, description);
+        }
+    }
+
+    /// Signatures of the inherited argument blocks.
+    pub(crate) fn inherited_signatures(&self) -> &[&'a B::Signature] {
+        &self.inherited_signatures
+    }
 
-        DynamicSignature { description, raw }
+    /// Creates a new signature in the specified arena.
+    ///
+    /// The description is copied into the arena. See also [Api::get_cached_dynamic_signature]
+    /// for signatures that should be shared between arenas.
+    pub fn build(&self, arena: &'a Arena<B>) -> Result<DynamicSignature<'a, B>> {
+        let description = self.description().copy_to(&arena.misc);
+        let raw = arena.create_signature(&self.inherited_signatures, description)?;
+        Ok(DynamicSignature { description, raw })
     }
 }
 
@@ -1087,7 +1214,7 @@ impl<'a, 'b, B: Backend> IntoArgumentBlock<'a, B, DynamicSignature<'a, B>>
         self,
         signature: DynamicSignature<'a, B>,
         arena: &'a Arena<B>,
-    ) -> ArgumentBlock<'a, B, DynamicSignature<'a, B>> {
+    ) -> Result<ArgumentBlock<'a, B, DynamicSignature<'a, B>>> {
         // comparing the signatures would also work, but this is faster
         assert_eq!(signature.raw as *const _, self.signature.raw as *const _);
         arena.create_argument_block(
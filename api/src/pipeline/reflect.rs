@@ -0,0 +1,243 @@
+//! Reflection of SPIR-V shaders at runtime.
+//!
+//! Produces the same information as the shader macros (`include_glsl!` and co.), for shaders
+//! that are compiled at runtime (e.g. when hot-reloading shaders).
+use crate::{
+    descriptor::{ResourceBinding, ResourceBindingType, ResourceShape},
+    error::{Error, Result},
+    format::Format,
+    pipeline::{
+        FragmentOutputDescription, ReflectedShader, ShaderStageFlags, ShaderStageReflection,
+        VertexInputAttributeDescription,
+    },
+};
+use autograph_spirv as spirv;
+use autograph_spirv::{
+    ast::Variable,
+    headers::{Dim, ImageFormat, StorageClass},
+    DroplessArena, ImageType, Layout, TypeDesc,
+};
+use log::warn;
+
+fn format_from_spirv(fmt: ImageFormat) -> Format {
+    match fmt {
+        ImageFormat::Unknown => Format::UNDEFINED,
+        ImageFormat::Rgba32f => Format::R32G32B32A32_SFLOAT,
+        ImageFormat::Rgba16f => Format::R16G16B16A16_SFLOAT,
+        ImageFormat::R32f => Format::R32_SFLOAT,
+        ImageFormat::Rgba8 => Format::R8G8B8A8_UNORM,
+        ImageFormat::Rgba8Snorm => Format::R8G8B8A8_SNORM,
+        ImageFormat::Rg32f => Format::R32G32_SFLOAT,
+        ImageFormat::Rg16f => Format::R16G16_SFLOAT,
+        ImageFormat::R11fG11fB10f => Format::B10G11R11_UFLOAT_PACK32,
+        ImageFormat::R16f => Format::R16_SFLOAT,
+        ImageFormat::Rgba16 => Format::R16G16B16A16_UNORM,
+        ImageFormat::Rgb10A2 => Format::A2B10G10R10_UNORM_PACK32,
+        ImageFormat::Rg16 => Format::R16G16_UNORM,
+        ImageFormat::Rg8 => Format::R8G8_UNORM,
+        ImageFormat::R16 => Format::R16_UNORM,
+        ImageFormat::R8 => Format::R8_UNORM,
+        ImageFormat::Rgba16Snorm => Format::R16G16B16A16_SNORM,
+        ImageFormat::Rg16Snorm => Format::R16G16_SNORM,
+        ImageFormat::Rg8Snorm => Format::R8G8_SNORM,
+        ImageFormat::R16Snorm => Format::R16_SNORM,
+        ImageFormat::R8Snorm => Format::R8_SNORM,
+        ImageFormat::Rgba32i => Format::R32G32B32A32_SINT,
+        ImageFormat::Rgba16i => Format::R16G16B16A16_SINT,
+        ImageFormat::Rgba8i => Format::R8G8B8A8_SINT,
+        ImageFormat::R32i => Format::R32_SINT,
+        ImageFormat::Rg32i => Format::R32G32_SINT,
+        ImageFormat::Rg16i => Format::R16G16_SINT,
+        ImageFormat::Rg8i => Format::R8G8_SINT,
+        ImageFormat::R16i => Format::R16_SINT,
+        ImageFormat::R8i => Format::R8_SINT,
+        ImageFormat::Rgba32ui => Format::R32G32B32A32_UINT,
+        ImageFormat::Rgba16ui => Format::R16G16B16A16_UINT,
+        ImageFormat::Rgba8ui => Format::R8G8B8A8_UINT,
+        ImageFormat::R32ui => Format::R32_UINT,
+        ImageFormat::Rgb10a2ui => Format::A2B10G10R10_UINT_PACK32,
+        ImageFormat::Rg32ui => Format::R32G32_UINT,
+        ImageFormat::Rg16ui => Format::R16G16_UINT,
+        ImageFormat::Rg8ui => Format::R8G8_UINT,
+        ImageFormat::R16ui => Format::R16_UINT,
+        ImageFormat::R8ui => Format::R8_UINT,
+    }
+}
+
+fn resource_shape(dim: Dim) -> Result<ResourceShape> {
+    match dim {
+        Dim::Dim1D => Ok(ResourceShape::R1d),
+        Dim::Dim2D => Ok(ResourceShape::R2d),
+        Dim::Dim3D => Ok(ResourceShape::R3d),
+        Dim::DimCube => Ok(ResourceShape::RCube),
+        _ => Err(Error::Validation {
+            message: format!("unsupported image dimensions: {:?}", dim),
+        }),
+    }
+}
+
+fn image_binding<'a>(
+    ty: ResourceBindingType,
+    stage: ShaderStageFlags,
+    set: u32,
+    binding: u32,
+    image_ty: &ImageType<'a>,
+) -> ResourceBinding<'a> {
+    ResourceBinding {
+        set: Some(set),
+        index: binding,
+        ty,
+        stage_flags: stage,
+        count: 1,
+        data_ty: Some(image_ty.sampled_ty),
+        data_layout: None,
+        data_format: format_from_spirv(image_ty.format),
+    }
+}
+
+/// Returns the type pointed to by a shader interface variable.
+fn pointee_type<'a>(v: &Variable<'a>) -> Result<&'a TypeDesc<'a>> {
+    v.ty.pointee_type().ok_or_else(|| Error::Validation {
+        message: format!("expected a variable of pointer type: {:?}", v),
+    })
+}
+
+fn buffer_binding<'a>(
+    a: &'a DroplessArena,
+    ty: ResourceBindingType,
+    stage: ShaderStageFlags,
+    set: u32,
+    binding: u32,
+    v: &Variable<'a>,
+) -> Result<ResourceBinding<'a>> {
+    let data_ty = pointee_type(v)?;
+    Ok(ResourceBinding {
+        set: Some(set),
+        index: binding,
+        ty,
+        stage_flags: stage,
+        count: 1,
+        data_ty: Some(data_ty),
+        // FIXME may be something other than std140
+        data_layout: Some(Layout::std140(a, data_ty)),
+        data_format: Format::UNDEFINED,
+    })
+}
+
+fn descriptor_reflection_info<'a>(
+    a: &'a DroplessArena,
+    v: &Variable<'a>,
+    stage: ShaderStageFlags,
+    set: u32,
+    binding: u32,
+) -> Result<Option<ResourceBinding<'a>>> {
+    let has_buffer_block_deco = v.has_buffer_block_decoration().is_some();
+
+    // same classification as the shader macros, so that shaders compiled at runtime have the
+    // same reflection information as the ones compiled with `include_glsl!`
+    let binding = if v.storage == StorageClass::Uniform
+    /*&& has_block_deco*/
+    {
+        buffer_binding(
+            a,
+            ResourceBindingType::ConstantBuffer,
+            stage,
+            set,
+            binding,
+            v,
+        )?
+    } else if (v.storage == StorageClass::Uniform && has_buffer_block_deco)
+        || (v.storage == StorageClass::StorageBuffer)
+    {
+        buffer_binding(a, ResourceBindingType::RwBuffer, stage, set, binding, v)?
+    } else if v.storage == StorageClass::UniformConstant {
+        match *v.ty {
+            TypeDesc::Pointer(&TypeDesc::Image(ref image_ty)) => {
+                let ty = ResourceBindingType::RwImage(resource_shape(image_ty.dimensions)?);
+                image_binding(ty, stage, set, binding, image_ty)
+            }
+            TypeDesc::Pointer(&TypeDesc::SampledImage(image_ty)) => {
+                let ty = ResourceBindingType::TextureSampler(resource_shape(image_ty.dimensions)?);
+                image_binding(ty, stage, set, binding, image_ty)
+            }
+            _ => {
+                warn!("unsupported uniform constant type: {:?}", v);
+                return Ok(None);
+            }
+        }
+    } else {
+        warn!("unsupported shader interface: {:?}", v);
+        return Ok(None);
+    };
+
+    Ok(Some(binding))
+}
+
+fn location(v: &Variable) -> Result<u32> {
+    v.location_decoration()
+        .map(|(_, loc)| loc)
+        .ok_or_else(|| Error::Validation {
+            message: "expected location decoration".to_string(),
+        })
+}
+
+/// Extracts the interface of a shader from its SPIR-V bytecode.
+///
+/// The reflection information is allocated in the specified arena.
+pub fn reflect_spirv<'a>(
+    a: &'a DroplessArena,
+    bytecode: &[u8],
+    stage: ShaderStageFlags,
+) -> Result<&'a ShaderStageReflection<'a>> {
+    let m = spirv::Module::from_bytes(bytecode).map_err(|e| Error::Validation {
+        message: format!("invalid SPIR-V bytecode: {:?}", e),
+    })?;
+    let ast = spirv::ast::Ast::new(a, &m);
+
+    let mut descriptors = Vec::new();
+    let mut vertex_input_attributes = Vec::new();
+    let mut fragment_outputs = Vec::new();
+
+    for (_, v) in ast.variables() {
+        if let Some((_, set)) = v.descriptor_set_decoration() {
+            let (_, binding) = v.binding_decoration().ok_or_else(|| Error::Validation {
+                message: "expected binding decoration".to_string(),
+            })?;
+            if let Some(d) = descriptor_reflection_info(a, v, stage, set, binding)? {
+                descriptors.push(d);
+            }
+        }
+
+        if stage == ShaderStageFlags::VERTEX && v.storage == StorageClass::Input {
+            vertex_input_attributes.push(VertexInputAttributeDescription {
+                location: Some(location(v)?),
+                ty: pointee_type(v)?,
+                semantic: None, // No match by semantic in GL or Vulkan
+            });
+        }
+
+        if stage == ShaderStageFlags::FRAGMENT && v.storage == StorageClass::Output {
+            location(v)?;
+            fragment_outputs.push(FragmentOutputDescription {});
+        }
+    }
+
+    Ok(a.alloc(ShaderStageReflection {
+        stage,
+        descriptors: a.alloc_extend(descriptors.into_iter()),
+        vertex_input_attributes: a.alloc_extend(vertex_input_attributes.into_iter()),
+        fragment_outputs: a.alloc_extend(fragment_outputs.into_iter()),
+    }))
+}
+
+/// Extracts the interface of a shader, and returns it along with the bytecode.
+pub fn reflect_shader<'a, 'bc>(
+    a: &'a DroplessArena,
+    bytecode: &'bc [u8],
+    stage: ShaderStageFlags,
+) -> Result<ReflectedShader<'bc, 'a>> {
+    Ok(ReflectedShader {
+        bytecode,
+        reflection: reflect_spirv(a, bytecode, stage)?,
+    })
+}
@@ -1,15 +1,165 @@
+//! Validation of the interface between shaders and pipeline signatures.
 use crate::{
     descriptor::{ResourceBinding, ResourceBindingType},
-    pipeline::{
-        FragmentOutputDescription, GraphicsPipelineCreateInfo, Scissors, SignatureDescription,
-        Viewports,
-    },
-    vertex::{IndexFormat, VertexLayout, VertexLayoutElement},
-    Backend,
+    pipeline::{ShaderStageFlags, ShaderStageReflection, SignatureDescription},
 };
-use autograph_spirv::{headers::StorageClass, TypeDesc};
-use log::warn;
 use std::{error, fmt};
+
+/// A mismatch between the interface of a shader and the signature of a pipeline.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum InterfaceError {
+    /// The signature has no descriptor at the binding used by the shader.
+    DescriptorNotFound {
+        stage: ShaderStageFlags,
+        set: u32,
+        binding: u32,
+    },
+    /// The type of a descriptor in the signature does not match the type expected by the shader.
+    DescriptorTypeMismatch {
+        stage: ShaderStageFlags,
+        set: u32,
+        binding: u32,
+        host: ResourceBindingType,
+        shader: ResourceBindingType,
+    },
+    /// The signature has no vertex attribute at the location of a vertex shader input.
+    VertexInputNotFound { location: u32 },
+    /// The fragment shader has more outputs than the signature has render targets.
+    FragmentOutputCountMismatch { shader: usize, host: usize },
+}
+
+impl fmt::Display for InterfaceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InterfaceError::DescriptorNotFound {
+                stage,
+                set,
+                binding,
+            } => write!(
+                f,
+                "descriptor (set={},binding={}) used in {:?} shader not found in signature",
+                set, binding, stage
+            ),
+            InterfaceError::DescriptorTypeMismatch {
+                stage,
+                set,
+                binding,
+                host,
+                shader,
+            } => write!(
+                f,
+                "descriptor type does not match: (set,binding)=({},{}), {:?} (host) vs. {:?} ({:?} shader)",
+                set, binding, host, shader, stage
+            ),
+            InterfaceError::VertexInputNotFound { location } => write!(
+                f,
+                "vertex input at location {} not found in signature",
+                location
+            ),
+            InterfaceError::FragmentOutputCountMismatch { shader, host } => write!(
+                f,
+                "the fragment shader has {} outputs, but the signature only has {} render targets",
+                shader, host
+            ),
+        }
+    }
+}
+
+impl error::Error for InterfaceError {}
+
+/// Descriptor sets, vertex attributes and render targets of a signature tree.
+struct FlatSignature<'a> {
+    descriptor_sets: Vec<&'a [ResourceBinding<'a>]>,
+    num_vertex_attributes: usize,
+    num_fragment_outputs: usize,
+}
+
+impl<'a> FlatSignature<'a> {
+    /// Inherited signatures come first, and a signature with descriptors defines a new
+    /// descriptor set.
+    fn add(&mut self, signature: &'a SignatureDescription<'a>) {
+        for &inherited in signature.inherited {
+            self.add(inherited);
+        }
+        if !signature.descriptors.is_empty() {
+            self.descriptor_sets.push(signature.descriptors);
+        }
+        self.num_vertex_attributes += signature
+            .vertex_inputs
+            .iter()
+            .map(|vi| vi.layout.elements.len())
+            .sum::<usize>();
+        self.num_fragment_outputs += signature.fragment_outputs.len();
+    }
+}
+
+/// Checks that the descriptors, vertex inputs and fragment outputs of a shader are present in
+/// the root signature of a pipeline.
+///
+/// Only the presence and the kind of descriptors are checked, not data types or image formats.
+///
+/// # TODO
+/// - compare data types and layouts of buffers
+/// - compare vertex attribute types
+pub fn validate_shader_interface<'a>(
+    signature: &'a SignatureDescription<'a>,
+    reflection: &ShaderStageReflection,
+) -> Result<(), Vec<InterfaceError>> {
+    let mut flat = FlatSignature {
+        descriptor_sets: Vec::new(),
+        num_vertex_attributes: 0,
+        num_fragment_outputs: 0,
+    };
+    flat.add(signature);
+
+    let stage = reflection.stage;
+    let mut errors = Vec::new();
+
+    for d in reflection.descriptors {
+        let set = d.set.unwrap_or(0);
+        let host = flat
+            .descriptor_sets
+            .get(set as usize)
+            .and_then(|descriptors| descriptors.iter().find(|h| h.index == d.index));
+        match host {
+            None => errors.push(InterfaceError::DescriptorNotFound {
+                stage,
+                set,
+                binding: d.index,
+            }),
+            Some(host) if host.ty != d.ty => errors.push(InterfaceError::DescriptorTypeMismatch {
+                stage,
+                set,
+                binding: d.index,
+                host: host.ty,
+                shader: d.ty,
+            }),
+            _ => {}
+        }
+    }
+
+    for attr in reflection.vertex_input_attributes {
+        let location = attr.location.unwrap_or(0);
+        if location as usize >= flat.num_vertex_attributes {
+            errors.push(InterfaceError::VertexInputNotFound { location });
+        }
+    }
+
+    if reflection.fragment_outputs.len() > flat.num_fragment_outputs {
+        errors.push(InterfaceError::FragmentOutputCountMismatch {
+            shader: reflection.fragment_outputs.len(),
+            host: flat.num_fragment_outputs,
+        });
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+// Previous SPIR-V based validation, kept for reference.
 /*
 #[derive(Copy, Clone, Debug)]
 pub enum Interface {
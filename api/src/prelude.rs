@@ -1,15 +1,16 @@
 pub use crate::{
     buffer::{BoolU32, StructuredBufferData},
-    command::DrawParams,
+    command::{DispatchParams, DrawIndexedParams, DrawParams},
     format::Format,
     image::{ImageUsageFlags, MipmapsOption, SamplerDescription},
     include_glsl,
     pipeline::{
         Arguments, ColorBlendAttachmentState, ColorBlendAttachments, ColorBlendState,
-        DepthStencilState, GraphicsPipelineCreateInfo, GraphicsShaderStages,
+        ComputePipelineCreateInfo, DepthStencilState, GraphicsPipelineCreateInfo, GraphicsShaderStages,
         InputAssemblyState, MultisampleState, PrimitiveTopology, RasterisationState,
         ReflectedShader, Viewport, ViewportState, Viewports,
     },
+    sortkey::SortKeyLayout,
     vertex::VertexData,
     AliasScope,
 };
@@ -0,0 +1,152 @@
+//! GPU profiling of frames.
+//!
+//! [CommandBuffer::begin_profile_scope](crate::command::CommandBuffer::begin_profile_scope) and
+//! [CommandBuffer::end_profile_scope](crate::command::CommandBuffer::end_profile_scope) delimit
+//! a range of the sorted command stream. Scopes nest according to their position in the sorted
+//! stream, not in the order in which they were recorded.
+//!
+//! The backend records a GPU timestamp at both ends of each scope. Since the GPU runs behind the
+//! CPU, the timings of a frame are only available once the frame has finished executing:
+//! [Api::frame_profile](crate::Api::frame_profile) returns the [FrameProfile] of the most recent
+//! frame that has completed (typically the frame submitted a few frames ago).
+//!
+//! ```ignore
+//! cmdbuf.begin_profile_scope(0x0000, "shadows");
+//! // ... shadow map passes, sortkeys 0x0001..0x0FFF ...
+//! cmdbuf.end_profile_scope(0x0FFF);
+//! api.submit_frame(vec![cmdbuf]);
+//! if let Some(profile) = api.frame_profile() {
+//!     println!("{}", profile);
+//! }
+//! ```
+use std::{fmt, time::Duration};
+
+/// Timings of a profile scope, and of the scopes nested inside it.
+#[derive(Clone, Debug)]
+pub struct ProfileScope {
+    pub name: String,
+    /// Start of the scope, relative to the start of the first scope in the frame.
+    pub start: Duration,
+    pub duration: Duration,
+    pub children: Vec<ProfileScope>,
+}
+
+/// Boundary of a profile scope, with the GPU timestamp recorded for it, in nanoseconds.
+///
+/// Used by backends to build a [FrameProfile].
+#[derive(Copy, Clone, Debug)]
+pub enum ProfileEvent<'a> {
+    Begin { name: &'a str, timestamp: u64 },
+    End { timestamp: u64 },
+}
+
+/// GPU timings of the profile scopes of a frame.
+#[derive(Clone, Debug, Default)]
+pub struct FrameProfile {
+    /// Number of the frame, as counted by the backend.
+    pub frame: u64,
+    /// Top-level scopes, in stream order.
+    pub scopes: Vec<ProfileScope>,
+}
+
+impl FrameProfile {
+    /// Builds the scope hierarchy from the scope boundaries of a frame, in stream order.
+    ///
+    /// Unmatched `End` events are ignored, and scopes that are still open at the end of the frame
+    /// end at the last timestamp.
+    pub fn from_events<'a>(
+        frame: u64,
+        events: impl IntoIterator<Item = ProfileEvent<'a>>,
+    ) -> FrameProfile {
+        let mut origin = None;
+        let mut last = 0;
+        // open scopes, with the timestamp at which they started
+        let mut stack: Vec<(ProfileScope, u64)> = Vec::new();
+        let mut scopes = Vec::new();
+
+        let close = |stack: &mut Vec<(ProfileScope, u64)>,
+                     scopes: &mut Vec<ProfileScope>,
+                     timestamp: u64| {
+            if let Some((mut scope, start)) = stack.pop() {
+                scope.duration = Duration::from_nanos(timestamp.saturating_sub(start));
+                match stack.last_mut() {
+                    Some((parent, _)) => parent.children.push(scope),
+                    None => scopes.push(scope),
+                }
+            }
+        };
+
+        for event in events {
+            match event {
+                ProfileEvent::Begin { name, timestamp } => {
+                    let origin = *origin.get_or_insert(timestamp);
+                    last = last.max(timestamp);
+                    stack.push((
+                        ProfileScope {
+                            name: name.to_string(),
+                            start: Duration::from_nanos(timestamp.saturating_sub(origin)),
+                            duration: Duration::from_secs(0),
+                            children: Vec::new(),
+                        },
+                        timestamp,
+                    ));
+                }
+                ProfileEvent::End { timestamp } => {
+                    last = last.max(timestamp);
+                    close(&mut stack, &mut scopes, timestamp);
+                }
+            }
+        }
+        while !stack.is_empty() {
+            close(&mut stack, &mut scopes, last);
+        }
+
+        FrameProfile { frame, scopes }
+    }
+
+    /// Returns the time spent in top-level scopes.
+    pub fn total(&self) -> Duration {
+        self.scopes.iter().map(|s| s.duration).sum()
+    }
+
+    /// Calls `f` on every scope, depth-first, with its nesting depth (0 for top-level scopes).
+    pub fn walk(&self, mut f: impl FnMut(usize, &ProfileScope)) {
+        fn walk_rec(
+            scopes: &[ProfileScope],
+            depth: usize,
+            f: &mut impl FnMut(usize, &ProfileScope),
+        ) {
+            for scope in scopes {
+                f(depth, scope);
+                walk_rec(&scope.children, depth + 1, f);
+            }
+        }
+        walk_rec(&self.scopes, 0, &mut f)
+    }
+}
+
+/// Prints the scopes as an indented list, with their durations in milliseconds.
+impl fmt::Display for FrameProfile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "frame {}: {:.3} ms", self.frame, millis(self.total()))?;
+        let mut result = Ok(());
+        self.walk(|depth, scope| {
+            if result.is_ok() {
+                result = writeln!(
+                    f,
+                    "{:indent$}{}: {:.3} ms",
+                    "",
+                    scope.name,
+                    millis(scope.duration),
+                    indent = 2 * (depth + 1)
+                );
+            }
+        });
+        result
+    }
+}
+
+/// Converts a duration to fractional milliseconds.
+pub fn millis(d: Duration) -> f64 {
+    d.as_secs() as f64 * 1e3 + f64::from(d.subsec_nanos()) * 1e-6
+}
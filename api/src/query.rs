@@ -0,0 +1,110 @@
+//! Occlusion and pipeline statistics queries.
+//!
+//! Queries are allocated in [QueryPool]s, created with
+//! [Arena::create_query_pool](crate::Arena::create_query_pool). A query measures the draws
+//! between a `begin_query` and an `end_query` command (see [CommandBuffer::begin_query]) in the
+//! sorted command stream. Its result can then be:
+//! * read back by the host once the frame has finished executing, with
+//! [CommandBuffer::readback_queries], which returns a [Readback](crate::readback::Readback)
+//! handle (see [query_results]),
+//! * used as a predicate for the conditional rendering of later draws in the stream, with
+//! [CommandBuffer::begin_conditional_rendering] (occlusion queries only).
+//!
+//! [CommandBuffer::begin_query]: crate::command::CommandBuffer::begin_query
+//! [CommandBuffer::readback_queries]: crate::command::CommandBuffer::readback_queries
+//! [CommandBuffer::begin_conditional_rendering]: crate::command::CommandBuffer::begin_conditional_rendering
+//!
+//! ```ignore
+//! let queries = arena.create_query_pool(QueryType::ConservativeOcclusion, 1)?;
+//! cmdbuf.begin_query(0x100, queries, 0);
+//! cmdbuf.draw(0x100, &arena, bounding_box_pipeline, ...);
+//! cmdbuf.end_query(0x100, queries, 0);
+//! cmdbuf.begin_conditional_rendering(0x200, queries, 0);
+//! cmdbuf.draw(0x200, &arena, mesh_pipeline, ...);
+//! cmdbuf.end_conditional_rendering(0x200);
+//! ```
+use crate::Backend;
+use bitflags::bitflags;
+use std::convert::TryInto;
+
+bitflags! {
+    /// Counters measured by a pipeline statistics query.
+    #[derive(Default)]
+    pub struct PipelineStatisticsFlags: u32 {
+        /// Number of vertices submitted to the input assembler.
+        const INPUT_ASSEMBLY_VERTICES = (1 << 0);
+        /// Number of primitives submitted to the input assembler.
+        const INPUT_ASSEMBLY_PRIMITIVES = (1 << 1);
+        const VERTEX_SHADER_INVOCATIONS = (1 << 2);
+        /// Number of primitives that reach the clipping stage.
+        const CLIPPING_INVOCATIONS = (1 << 3);
+        /// Number of primitives output by the clipping stage.
+        const CLIPPING_PRIMITIVES = (1 << 4);
+        const FRAGMENT_SHADER_INVOCATIONS = (1 << 5);
+        const COMPUTE_SHADER_INVOCATIONS = (1 << 6);
+    }
+}
+
+/// Type of the queries in a [QueryPool].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum QueryType {
+    /// Number of samples that passed the depth and stencil tests.
+    Occlusion,
+    /// Non-zero if any sample passed the depth and stencil tests. May be cheaper than `Occlusion`,
+    /// but can report false positives.
+    ConservativeOcclusion,
+    /// Pipeline statistics: one counter for each flag.
+    PipelineStatistics(PipelineStatisticsFlags),
+}
+
+impl QueryType {
+    /// Returns the number of 64-bit values in the result of one query.
+    pub fn values_per_query(&self) -> usize {
+        match *self {
+            QueryType::Occlusion | QueryType::ConservativeOcclusion => 1,
+            QueryType::PipelineStatistics(flags) => flags.bits().count_ones() as usize,
+        }
+    }
+
+    /// Returns whether the queries can be used as predicates for conditional rendering.
+    pub fn is_occlusion(&self) -> bool {
+        match *self {
+            QueryType::Occlusion | QueryType::ConservativeOcclusion => true,
+            QueryType::PipelineStatistics(_) => false,
+        }
+    }
+}
+
+/// A pool of queries of the same type.
+#[derive(derivative::Derivative)]
+#[derivative(Copy(bound = ""), Clone(bound = ""), Debug(bound = ""))]
+pub struct QueryPool<'a, B: Backend> {
+    pub(crate) inner: &'a B::QueryPool,
+    pub(crate) ty: QueryType,
+    pub(crate) count: u32,
+}
+
+impl<'a, B: Backend> QueryPool<'a, B> {
+    pub fn inner(&self) -> &'a B::QueryPool {
+        self.inner
+    }
+
+    pub fn ty(&self) -> QueryType {
+        self.ty
+    }
+
+    /// Returns the number of queries in the pool.
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+}
+
+/// Decodes the data of a query readback into 64-bit values.
+///
+/// The results of the queries are stored one after the other: for pipeline statistics, each
+/// query has one value per counter, in the order of the [PipelineStatisticsFlags] bits.
+pub fn query_results(data: &[u8]) -> Vec<u64> {
+    data.chunks_exact(8)
+        .map(|v| u64::from_le_bytes(v.try_into().unwrap()))
+        .collect()
+}
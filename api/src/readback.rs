@@ -0,0 +1,95 @@
+//! Reading back data from the GPU.
+//!
+//! [CommandBuffer::readback_image](crate::command::CommandBuffer::readback_image) and
+//! [CommandBuffer::readback_buffer](crate::command::CommandBuffer::readback_buffer) return a
+//! [Readback] handle, which resolves to the bytes read once the GPU has finished executing the
+//! frame in which the command was submitted. Image data is tightly packed, and in the format of
+//! the image.
+//!
+//! Readbacks are completed by the backend during [Api::submit_frame](crate::Api::submit_frame),
+//! [Api::poll_readbacks](crate::Api::poll_readbacks) and
+//! [Api::wait_readback](crate::Api::wait_readback). The result can then be retrieved either by
+//! polling the handle ([Readback::try_get]), by blocking with a timeout
+//! ([Api::wait_readback](crate::Api::wait_readback)), or through a callback
+//! ([Readback::on_complete]).
+//!
+//! ```ignore
+//! let readback = cmdbuf.readback_image(0x10, image, ImageRegion::new((0, 0, 0), (w, h, 1)));
+//! api.submit_frame(vec![cmdbuf]);
+//! let pixels = api.wait_readback(&readback, Duration::from_secs(1)).unwrap();
+//! ```
+use std::{
+    fmt, mem,
+    sync::{Arc, Mutex},
+};
+
+type Callback = Box<dyn FnOnce(&[u8]) + Send>;
+
+#[derive(Default)]
+struct ReadbackState {
+    data: Option<Arc<[u8]>>,
+    callbacks: Vec<Callback>,
+}
+
+/// Handle to the result of a readback command.
+///
+/// Handles are cheap to clone: all clones refer to the same result.
+#[derive(Clone, Default)]
+pub struct Readback(Arc<Mutex<ReadbackState>>);
+
+impl Readback {
+    /// Creates a pending readback.
+    pub fn new() -> Readback {
+        Readback::default()
+    }
+
+    /// Returns whether the data is available.
+    pub fn is_ready(&self) -> bool {
+        self.0.lock().unwrap().data.is_some()
+    }
+
+    /// Returns the data if it is available.
+    pub fn try_get(&self) -> Option<Arc<[u8]>> {
+        self.0.lock().unwrap().data.clone()
+    }
+
+    /// Registers a callback to be called with the data once it is available.
+    ///
+    /// If the data is already available, the callback is called immediately.
+    /// Otherwise, it is called on the thread that completes the readback.
+    pub fn on_complete(&self, callback: impl FnOnce(&[u8]) + Send + 'static) {
+        let mut state = self.0.lock().unwrap();
+        if let Some(data) = state.data.clone() {
+            drop(state);
+            callback(&data);
+        } else {
+            state.callbacks.push(Box::new(callback));
+        }
+    }
+
+    /// Resolves the readback with the data read from the GPU, and calls the registered callbacks.
+    ///
+    /// This is called by backends. Panics if the readback was already completed.
+    pub fn complete(&self, data: Vec<u8>) {
+        let data: Arc<[u8]> = data.into();
+        let callbacks = {
+            let mut state = self.0.lock().unwrap();
+            assert!(state.data.is_none(), "readback already completed");
+            state.data = Some(data.clone());
+            mem::replace(&mut state.callbacks, Vec::new())
+        };
+        for callback in callbacks {
+            callback(&data);
+        }
+    }
+}
+
+impl fmt::Debug for Readback {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let state = self.0.lock().unwrap();
+        f.debug_struct("Readback")
+            .field("size", &state.data.as_ref().map(|d| d.len()))
+            .field("callbacks", &state.callbacks.len())
+            .finish()
+    }
+}
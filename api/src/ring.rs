@@ -0,0 +1,143 @@
+//! Per-frame ring allocator for streaming data.
+//!
+//! An [UploadRing] is a host-visible buffer split into equal segments, one per frame in flight.
+//! During a frame, data is written linearly into the current segment, and the ring advances to
+//! the next segment with [UploadRing::next_frame]. This avoids creating a new buffer for every
+//! per-frame uniform block.
+//!
+//! ```ignore
+//! let ring = UploadRing::new(&arena, 64 * 1024, 3)?;
+//! loop {
+//!     let uniforms = ring.upload(&Uniforms { ... })?;
+//!     // ... create argument blocks with `uniforms`, submit the frame ...
+//!     api.submit_frame(cmdbufs);
+//!     ring.next_frame();
+//! }
+//! ```
+use crate::{
+    buffer::{BufferSlice, BufferTypeless, StructuredBufferData, TypedConstantBufferView},
+    error::{Error, Result},
+    Api, Arena, Backend, Instance,
+};
+use std::{marker::PhantomData, mem, slice, sync::Mutex};
+
+struct RingState {
+    segment: usize,
+    offset: usize,
+    /// Whether the current segment has been checked out for the current frame.
+    acquired: bool,
+    /// Last frame that used each segment (0 if not used yet).
+    frames: Vec<u64>,
+}
+
+/// Ring of per-frame segments in a host-visible buffer. See the [module-level documentation](self).
+pub struct UploadRing<'a, B: Backend> {
+    api: &'a Api<B>,
+    buffer: BufferTypeless<'a, B>,
+    alignment: usize,
+    segment_size: usize,
+    segment_count: usize,
+    state: Mutex<RingState>,
+}
+
+impl<'a, B: Backend> UploadRing<'a, B> {
+    /// Creates a ring with `segment_count` segments of `segment_size` bytes each.
+    ///
+    /// Allocations are aligned to the uniform buffer offset alignment of the backend.
+    /// The data of a segment is not overwritten while the GPU is still executing the frame
+    /// that uses it: `segment_count` should be greater than the number of frames that the
+    /// backend keeps in flight.
+    pub fn new(
+        arena: &'a Arena<B>,
+        segment_size: usize,
+        segment_count: usize,
+    ) -> Result<UploadRing<'a, B>> {
+        assert!(
+            segment_count > 0,
+            "an upload ring needs at least one segment"
+        );
+        let alignment = arena.renderer.instance().uniform_buffer_offset_alignment();
+        // keep the start of every segment aligned
+        let segment_size = align(segment_size, alignment);
+        let buffer = arena.create_host_buffer_typeless((segment_size * segment_count) as u64)?;
+        Ok(UploadRing {
+            api: arena.renderer,
+            buffer,
+            alignment,
+            segment_size,
+            segment_count,
+            state: Mutex::new(RingState {
+                segment: 0,
+                offset: 0,
+                acquired: false,
+                frames: vec![0; segment_count],
+            }),
+        })
+    }
+
+    /// Returns the buffer backing the ring.
+    pub fn buffer(&self) -> BufferTypeless<'a, B> {
+        self.buffer
+    }
+
+    /// Writes bytes into the current segment.
+    ///
+    /// Returns an [Error::OutOfMemory] error if the segment is full, or if it is still used by
+    /// a frame that has not finished executing on the GPU.
+    pub fn upload_bytes(&self, data: &[u8]) -> Result<BufferSlice<'a, B>> {
+        let mut state = self.state.lock().unwrap();
+        let instance = self.api.instance();
+        if !state.acquired {
+            let segment = state.segment;
+            let frame = state.frames[segment];
+            if frame != 0 && !instance.is_frame_finished(frame) {
+                return Err(Error::OutOfMemory {
+                    message: "upload ring segment is still in use by a frame in flight".into(),
+                });
+            }
+            state.frames[segment] = instance.current_frame();
+            state.acquired = true;
+        }
+        if state.offset + data.len() > self.segment_size {
+            return Err(Error::OutOfMemory {
+                message: "upload ring segment is full".into(),
+            });
+        }
+        let offset = state.segment * self.segment_size + state.offset;
+        state.offset = align(state.offset + data.len(), self.alignment);
+        self.api.write_buffer(self.buffer, offset as u64, data);
+        Ok(BufferSlice {
+            buffer: self.buffer,
+            offset,
+            size: data.len(),
+        })
+    }
+
+    /// Writes an object into the current segment, and returns a constant buffer view of it.
+    pub fn upload<T: StructuredBufferData + Copy>(
+        &self,
+        data: &T,
+    ) -> Result<TypedConstantBufferView<'a, B, T>> {
+        let size = mem::size_of::<T>();
+        let bytes = unsafe { slice::from_raw_parts(data as *const T as *const u8, size) };
+        let slice = self.upload_bytes(bytes)?;
+        Ok(TypedConstantBufferView {
+            buffer: slice.buffer.0,
+            offset: slice.offset,
+            size: Some(slice.size),
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Advances to the next segment. Call this once per frame, after submitting the frame.
+    pub fn next_frame(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.segment = (state.segment + 1) % self.segment_count;
+        state.offset = 0;
+        state.acquired = false;
+    }
+}
+
+fn align(size: usize, alignment: usize) -> usize {
+    (size + alignment - 1) / alignment * alignment
+}
@@ -0,0 +1,120 @@
+//! Multi-queue scheduling.
+//!
+//! Every command is tagged with the [Queue] on which it executes. The queue is either set
+//! explicitly per command buffer (see [CommandBuffer::set_queue](crate::CommandBuffer::set_queue)),
+//! or inferred from the kind of command: copies, uploads and readbacks go to the transfer queue,
+//! dispatches go to the compute queue, and everything else goes to the graphics queue.
+//!
+//! Once the command stream is sorted and barriers are inserted, [schedule_batches] splits it
+//! into [QueueBatch]es: runs of consecutive commands on the same queue. A batch waits for the
+//! batches on other queues that write resources it accesses, or that read resources it writes.
+//! Backends implement those waits with semaphores or an equivalent mechanism.
+//!
+//! Queues that the backend does not support ([Instance::queues](crate::Instance::queues)) are
+//! mapped to the graphics queue. Backends with a single queue, like OpenGL, receive a single batch
+//! and execute the command stream serially, in sorted order.
+use crate::{
+    barrier::{command_accesses, ResourceAccess},
+    command::{Command, CommandInner},
+    Backend, Queue,
+};
+use std::{collections::HashMap, ops::Range};
+
+/// A run of consecutive commands of the command stream that execute on the same queue.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct QueueBatch {
+    pub queue: Queue,
+    /// Range of the batch in the command stream.
+    pub commands: Range<usize>,
+    /// Indices of the batches on other queues that must finish before this one starts.
+    ///
+    /// There is at most one batch per queue: waiting for a batch implies waiting for the
+    /// previous batches on the same queue.
+    pub waits: Vec<usize>,
+}
+
+/// Last accesses to a resource, by batch.
+#[derive(Default)]
+struct ResourceBatches {
+    /// Batch that last wrote the resource.
+    last_write: Option<usize>,
+    /// Batches that read the resource since the last write.
+    reads: Vec<usize>,
+}
+
+/// Returns the queue on which a command tagged with `queue` is executed.
+fn effective_queue(queue: Queue, supported: &[Queue]) -> Queue {
+    if supported.contains(&queue) {
+        queue
+    } else {
+        Queue::Graphics
+    }
+}
+
+/// Splits a sorted command stream into per-queue batches.
+///
+/// `supported` is the list of queues supported by the backend: commands on other queues are
+/// executed on the graphics queue.
+pub fn schedule_batches<'a, B: Backend>(
+    commands: &[Command<'a, B>],
+    supported: &[Queue],
+) -> Vec<QueueBatch> {
+    let mut batches: Vec<QueueBatch> = Vec::new();
+    let mut resources: HashMap<usize, ResourceBatches> = HashMap::new();
+
+    for (i, cmd) in commands.iter().enumerate() {
+        let queue = effective_queue(cmd.queue, supported);
+        match batches.last_mut() {
+            Some(batch) if batch.queue == queue => batch.commands.end = i + 1,
+            _ => batches.push(QueueBatch {
+                queue,
+                commands: i..i + 1,
+                waits: Vec::new(),
+            }),
+        }
+        let current = batches.len() - 1;
+
+        // find the batches on other queues that the command depends on
+        let mut deps = Vec::new();
+        let mut track = |a: ResourceAccess<'a, B>| {
+            let state = resources.entry(a.resource.key()).or_default();
+            if a.access.is_write() {
+                deps.extend(state.last_write);
+                deps.extend(state.reads.drain(..));
+                state.last_write = Some(current);
+            } else {
+                deps.extend(state.last_write);
+                if !state.reads.contains(&current) {
+                    state.reads.push(current);
+                }
+            }
+        };
+        if let CommandInner::SetPipelineArguments { accesses, .. } = cmd.cmd {
+            for &a in accesses {
+                track(a);
+            }
+        }
+        command_accesses(&cmd.cmd, &mut track);
+
+        for dep in deps {
+            let dep_queue = batches[dep].queue;
+            if dep_queue == queue {
+                // ordered by the queue itself
+                continue;
+            }
+            let existing = batches[current]
+                .waits
+                .iter()
+                .position(|&w| batches[w].queue == dep_queue);
+            match existing {
+                Some(n) => {
+                    let w = &mut batches[current].waits[n];
+                    *w = (*w).max(dep);
+                }
+                None => batches[current].waits.push(dep),
+            }
+        }
+    }
+
+    batches
+}
@@ -0,0 +1,207 @@
+//! Structured sort keys.
+//!
+//! A [SortKeyLayout] splits the 64 bits of a sortkey into named fields (e.g. view, layer,
+//! pass, depth, material...). The first declared field occupies the most significant bits,
+//! and thus has the highest priority when sorting commands.
+//!
+//! Sort keys and [AliasScope]s should be built with the same layout, so that they can't
+//! disagree on the position of the fields. An alias scope must set all the fields above the least
+//! significant field it sets, since its commands would not be contiguous otherwise:
+//!
+//! ```
+//! use autograph_api::sortkey::SortKeyLayout;
+//!
+//! let layout = SortKeyLayout::new(&[("view", 4), ("pass", 8), ("depth", 24), ("material", 16)]);
+//! let key = layout.key().set("view", 1).set("pass", 2).set("depth", 1000).build();
+//! assert_eq!(layout.get(key, "pass"), 2);
+//! // all commands of pass 2 in view 1
+//! let scope = layout.key().set("view", 1).set("pass", 2).alias_scope();
+//! assert!(scope.contains(key));
+//! ```
+use crate::AliasScope;
+use std::fmt;
+
+/// Position of a named field inside a sortkey.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct SortKeyField {
+    pub name: &'static str,
+    /// Position of the least significant bit of the field.
+    pub shift: u32,
+    /// Width of the field in bits.
+    pub width: u32,
+}
+
+impl SortKeyField {
+    /// Mask of the bits of the field inside a sortkey.
+    pub fn mask(&self) -> u64 {
+        if self.width == 64 {
+            !0
+        } else {
+            ((1u64 << self.width) - 1) << self.shift
+        }
+    }
+
+    /// Maximum value of the field.
+    pub fn max_value(&self) -> u64 {
+        self.mask() >> self.shift
+    }
+
+    /// Returns the bits of a sortkey corresponding to the given value of the field.
+    ///
+    /// Panics if the value does not fit in the field.
+    pub fn encode(&self, value: u64) -> u64 {
+        assert!(
+            value <= self.max_value(),
+            "value {} does not fit in sort key field `{}` ({} bits)",
+            value,
+            self.name,
+            self.width
+        );
+        value << self.shift
+    }
+
+    /// Extracts the value of the field from a sortkey.
+    pub fn decode(&self, key: u64) -> u64 {
+        (key & self.mask()) >> self.shift
+    }
+}
+
+/// Describes how the bits of a sortkey are split into named fields.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct SortKeyLayout {
+    fields: Vec<SortKeyField>,
+}
+
+impl SortKeyLayout {
+    /// Creates a layout from a list of field names and widths (in bits),
+    /// from the most significant to the least significant.
+    ///
+    /// Panics if the total width exceeds 64 bits or if a field name appears more than once.
+    pub fn new(fields: &[(&'static str, u32)]) -> SortKeyLayout {
+        let total: u32 = fields.iter().map(|&(_, width)| width).sum();
+        assert!(total <= 64, "sort key layout is wider than 64 bits");
+
+        let mut shift = 64;
+        let mut out: Vec<SortKeyField> = Vec::with_capacity(fields.len());
+        for &(name, width) in fields.iter() {
+            assert!(width > 0, "sort key field `{}` has zero width", name);
+            assert!(
+                out.iter().all(|f| f.name != name),
+                "duplicate sort key field `{}`",
+                name
+            );
+            shift -= width;
+            out.push(SortKeyField { name, shift, width });
+        }
+
+        SortKeyLayout { fields: out }
+    }
+
+    /// Returns the fields of the layout, from the most significant to the least significant.
+    pub fn fields(&self) -> &[SortKeyField] {
+        &self.fields
+    }
+
+    /// Returns the field with the specified name.
+    ///
+    /// Panics if there is no such field.
+    pub fn field(&self, name: &str) -> SortKeyField {
+        *self
+            .fields
+            .iter()
+            .find(|f| f.name == name)
+            .unwrap_or_else(|| panic!("unknown sort key field `{}`", name))
+    }
+
+    /// Starts building a sortkey (or an alias scope). Unspecified fields are zero.
+    pub fn key(&self) -> SortKeyBuilder {
+        SortKeyBuilder {
+            layout: self,
+            value: 0,
+            mask: 0,
+        }
+    }
+
+    /// Returns the value of the named field in a sortkey.
+    pub fn get(&self, key: u64, name: &str) -> u64 {
+        self.field(name).decode(key)
+    }
+
+    /// Decodes a sortkey into its field values, from the most significant to the least significant.
+    pub fn decode(&self, key: u64) -> Vec<(&'static str, u64)> {
+        self.fields
+            .iter()
+            .map(|f| (f.name, f.decode(key)))
+            .collect()
+    }
+
+    /// Returns an object that displays a sortkey as its field values (e.g. `view=0 pass=2 ...`).
+    pub fn display(&self, key: u64) -> DisplaySortKey {
+        DisplaySortKey { layout: self, key }
+    }
+}
+
+/// Builds a sortkey or an alias scope from field values.
+///
+/// See [SortKeyLayout::key].
+#[derive(Copy, Clone, Debug)]
+pub struct SortKeyBuilder<'l> {
+    layout: &'l SortKeyLayout,
+    value: u64,
+    mask: u64,
+}
+
+impl<'l> SortKeyBuilder<'l> {
+    /// Sets the value of the named field.
+    pub fn set(mut self, name: &str, value: u64) -> Self {
+        let field = self.layout.field(name);
+        self.value = (self.value & !field.mask()) | field.encode(value);
+        self.mask |= field.mask();
+        self
+    }
+
+    /// Returns the sortkey.
+    pub fn build(self) -> u64 {
+        self.value
+    }
+
+    /// Returns the scope containing all commands whose sortkey has the same values
+    /// for the fields that were set.
+    ///
+    /// Panics if a field is not set while a less significant field is: the commands of the scope
+    /// would not form a contiguous range in the sorted command stream (e.g. "pass 2 in any
+    /// view", when the view is more significant than the pass).
+    pub fn alias_scope(self) -> AliasScope {
+        let fields = &self.layout.fields;
+        if let Some(lowest) = fields.iter().rposition(|f| self.mask & f.mask() != 0) {
+            if let Some(missing) = fields[..lowest].iter().find(|f| self.mask & f.mask() == 0) {
+                panic!(
+                    "alias scope sets sort key field `{}` but not the more significant field `{}`",
+                    fields[lowest].name, missing.name
+                );
+            }
+        }
+        AliasScope {
+            value: self.value,
+            mask: self.mask,
+        }
+    }
+}
+
+/// See [SortKeyLayout::display].
+pub struct DisplaySortKey<'l> {
+    layout: &'l SortKeyLayout,
+    key: u64,
+}
+
+impl<'l> fmt::Display for DisplaySortKey<'l> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, field) in self.layout.fields.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}={}", field.name, field.decode(self.key))?;
+        }
+        Ok(())
+    }
+}
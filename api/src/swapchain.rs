@@ -11,4 +11,12 @@ impl<'a, B: Backend> Swapchain<'a, B> {
     pub fn size(&self) -> (u32, u32) {
         crate::traits::Swapchain::size(self.0)
     }
+
+    /// Returns a counter that is incremented each time the swapchain is resized.
+    ///
+    /// Resources that depend on the size of the swapchain should be recreated when the
+    /// generation changes (see also [Arena](crate::Arena)).
+    pub fn generation(&self) -> u64 {
+        crate::traits::Swapchain::generation(self.0)
+    }
 }
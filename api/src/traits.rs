@@ -1,5 +1,7 @@
 pub trait Swapchain {
     fn size(&self) -> (u32, u32);
+    /// Returns a counter that is incremented each time the size of the swapchain changes.
+    fn generation(&self) -> u64;
 }
 
 /*
@@ -0,0 +1,421 @@
+//! Conversion of objects and commands of a backend that wraps another backend (such as
+//! [ValidatingBackend](crate::validation::ValidatingBackend)) to the objects and commands of the
+//! wrapped backend.
+use crate::{
+    command::CommandInner,
+    debug::DebugObject,
+    descriptor::Descriptor,
+    image::{DepthStencilView, RenderTargetView},
+    pipeline::{
+        BareArgumentBlock, ComputePipelineCreateInfo, GraphicsPipelineCreateInfo,
+        GraphicsShaderStages, ShaderModule,
+    },
+    vertex::{IndexBufferView, VertexBufferView},
+    Backend,
+};
+
+/// A backend whose objects wrap objects of the backend `B`.
+///
+/// The functions are unsafe because some backends cannot check that the objects were created
+/// by the wrapped backend.
+pub(crate) trait Unwrap<B: Backend>: Backend {
+    unsafe fn swapchain(swapchain: &Self::Swapchain) -> &B::Swapchain;
+    unsafe fn image(image: &Self::Image) -> &B::Image;
+    unsafe fn buffer(buffer: &Self::Buffer) -> &B::Buffer;
+    unsafe fn shader_module(module: &Self::ShaderModule) -> &B::ShaderModule;
+    unsafe fn graphics_pipeline(pipeline: &Self::GraphicsPipeline) -> &B::GraphicsPipeline;
+    unsafe fn compute_pipeline(pipeline: &Self::ComputePipeline) -> &B::ComputePipeline;
+    unsafe fn signature(signature: &Self::Signature) -> &B::Signature;
+    unsafe fn argument_block(block: &Self::ArgumentBlock) -> &B::ArgumentBlock;
+    unsafe fn host_reference(reference: &Self::HostReference) -> &B::HostReference;
+    unsafe fn query_pool(pool: &Self::QueryPool) -> &B::QueryPool;
+}
+
+pub(crate) unsafe fn descriptor<'a, W: Unwrap<B>, B: Backend>(
+    d: &Descriptor<'a, W>,
+) -> Descriptor<'a, B> {
+    match *d {
+        Descriptor::Sampler { desc } => Descriptor::Sampler { desc },
+        Descriptor::Texture { image, subresource } => Descriptor::Texture {
+            image: W::image(image),
+            subresource,
+        },
+        Descriptor::TextureSampler {
+            image,
+            subresource,
+            sampler,
+        } => Descriptor::TextureSampler {
+            image: W::image(image),
+            subresource,
+            sampler,
+        },
+        Descriptor::RwImage { image, subresource } => Descriptor::RwImage {
+            image: W::image(image),
+            subresource,
+        },
+        Descriptor::ConstantBuffer {
+            buffer,
+            offset,
+            size,
+        } => Descriptor::ConstantBuffer {
+            buffer: W::buffer(buffer),
+            offset,
+            size,
+        },
+        Descriptor::HostConstantBuffer { reference } => Descriptor::HostConstantBuffer {
+            reference: W::host_reference(reference),
+        },
+        Descriptor::RwBuffer {
+            buffer,
+            offset,
+            size,
+        } => Descriptor::RwBuffer {
+            buffer: W::buffer(buffer),
+            offset,
+            size,
+        },
+        Descriptor::TexelBuffer {
+            buffer,
+            offset,
+            size,
+        } => Descriptor::TexelBuffer {
+            buffer: W::buffer(buffer),
+            offset,
+            size,
+        },
+        Descriptor::RwTexelBuffer {
+            buffer,
+            offset,
+            size,
+        } => Descriptor::RwTexelBuffer {
+            buffer: W::buffer(buffer),
+            offset,
+            size,
+        },
+        Descriptor::Empty => Descriptor::Empty,
+    }
+}
+
+pub(crate) unsafe fn shader_module<'a, 're, W: Unwrap<B>, B: Backend>(
+    m: ShaderModule<'a, 're, W>,
+) -> ShaderModule<'a, 're, B> {
+    ShaderModule {
+        module: W::shader_module(m.module),
+        reflection: m.reflection,
+        bytecode: m.bytecode,
+        hash: m.hash,
+    }
+}
+
+pub(crate) unsafe fn command<'a, W: Unwrap<B>, B: Backend>(
+    cmd: &CommandInner<'a, W>,
+) -> CommandInner<'a, B> {
+    match *cmd {
+        CommandInner::PipelineBarrier {
+            src_access_mask,
+            dst_access_mask,
+        } => CommandInner::PipelineBarrier {
+            src_access_mask,
+            dst_access_mask,
+        },
+        CommandInner::CopyBuffer {
+            src,
+            src_offset,
+            dst,
+            dst_offset,
+            size,
+        } => CommandInner::CopyBuffer {
+            src: W::buffer(src),
+            src_offset,
+            dst: W::buffer(dst),
+            dst_offset,
+            size,
+        },
+        CommandInner::CopyBufferToImage {
+            src,
+            src_offset,
+            dst,
+            dst_region,
+        } => CommandInner::CopyBufferToImage {
+            src: W::buffer(src),
+            src_offset,
+            dst: W::image(dst),
+            dst_region,
+        },
+        CommandInner::CopyImageToBuffer {
+            src,
+            src_region,
+            dst,
+            dst_offset,
+        } => CommandInner::CopyImageToBuffer {
+            src: W::image(src),
+            src_region,
+            dst: W::buffer(dst),
+            dst_offset,
+        },
+        CommandInner::CopyImage {
+            src,
+            src_region,
+            dst,
+            dst_region,
+        } => CommandInner::CopyImage {
+            src: W::image(src),
+            src_region,
+            dst: W::image(dst),
+            dst_region,
+        },
+        CommandInner::FillBuffer {
+            dst,
+            offset,
+            size,
+            value,
+        } => CommandInner::FillBuffer {
+            dst: W::buffer(dst),
+            offset,
+            size,
+            value,
+        },
+        CommandInner::ReadbackImage {
+            src,
+            src_region,
+            ref readback,
+        } => CommandInner::ReadbackImage {
+            src: W::image(src),
+            src_region,
+            readback: readback.clone(),
+        },
+        CommandInner::ReadbackBuffer {
+            src,
+            offset,
+            size,
+            ref readback,
+        } => CommandInner::ReadbackBuffer {
+            src: W::buffer(src),
+            offset,
+            size,
+            readback: readback.clone(),
+        },
+        CommandInner::BlitImage {
+            src,
+            src_mip_level,
+            src_offsets,
+            dst,
+            dst_mip_level,
+            dst_offsets,
+            filter,
+        } => CommandInner::BlitImage {
+            src: W::image(src),
+            src_mip_level,
+            src_offsets,
+            dst: W::image(dst),
+            dst_mip_level,
+            dst_offsets,
+            filter,
+        },
+        CommandInner::ClearImageFloat { image, color } => CommandInner::ClearImageFloat {
+            image: W::image(image),
+            color,
+        },
+        CommandInner::ClearDepthStencilImage {
+            image,
+            depth,
+            stencil,
+        } => CommandInner::ClearDepthStencilImage {
+            image: W::image(image),
+            depth,
+            stencil,
+        },
+        CommandInner::Present { image, swapchain } => CommandInner::Present {
+            image: W::image(image),
+            swapchain: W::swapchain(swapchain),
+        },
+        CommandInner::BeginProfileScope { name } => CommandInner::BeginProfileScope { name },
+        CommandInner::EndProfileScope => CommandInner::EndProfileScope,
+        CommandInner::PushDebugGroup { name } => CommandInner::PushDebugGroup { name },
+        CommandInner::PopDebugGroup => CommandInner::PopDebugGroup,
+        CommandInner::InsertDebugMarker { name } => CommandInner::InsertDebugMarker { name },
+        CommandInner::BeginQuery { pool, index } => CommandInner::BeginQuery {
+            pool: W::query_pool(pool),
+            index,
+        },
+        CommandInner::EndQuery { pool, index } => CommandInner::EndQuery {
+            pool: W::query_pool(pool),
+            index,
+        },
+        CommandInner::ReadbackQueries {
+            pool,
+            first,
+            count,
+            ref readback,
+        } => CommandInner::ReadbackQueries {
+            pool: W::query_pool(pool),
+            first,
+            count,
+            readback: readback.clone(),
+        },
+        CommandInner::BeginConditionalRendering { pool, index } => {
+            CommandInner::BeginConditionalRendering {
+                pool: W::query_pool(pool),
+                index,
+            }
+        }
+        CommandInner::EndConditionalRendering => CommandInner::EndConditionalRendering,
+        CommandInner::DrawHeader { pipeline } => CommandInner::DrawHeader {
+            pipeline: W::graphics_pipeline(pipeline),
+        },
+        CommandInner::DispatchHeader { pipeline } => CommandInner::DispatchHeader {
+            pipeline: W::compute_pipeline(pipeline),
+        },
+        CommandInner::SetPipelineArguments { arguments, .. } => {
+            CommandInner::SetPipelineArguments {
+                arguments: W::argument_block(arguments),
+                // barriers are already in the command stream
+                accesses: &[],
+            }
+        }
+        CommandInner::Draw {
+            vertex_count,
+            instance_count,
+            first_vertex,
+            first_instance,
+        } => CommandInner::Draw {
+            vertex_count,
+            instance_count,
+            first_vertex,
+            first_instance,
+        },
+        CommandInner::DrawIndexed {
+            index_count,
+            instance_count,
+            first_index,
+            vertex_offset,
+            first_instance,
+        } => CommandInner::DrawIndexed {
+            index_count,
+            instance_count,
+            first_index,
+            vertex_offset,
+            first_instance,
+        },
+        CommandInner::DrawIndirect {
+            indirect_buffer,
+            offset,
+            draw_count,
+            count_buffer,
+        } => CommandInner::DrawIndirect {
+            indirect_buffer: W::buffer(indirect_buffer),
+            offset,
+            draw_count,
+            count_buffer: count_buffer.map(|b| W::buffer(b)),
+        },
+        CommandInner::DrawIndexedIndirect {
+            indirect_buffer,
+            offset,
+            draw_count,
+            count_buffer,
+        } => CommandInner::DrawIndexedIndirect {
+            indirect_buffer: W::buffer(indirect_buffer),
+            offset,
+            draw_count,
+            count_buffer: count_buffer.map(|b| W::buffer(b)),
+        },
+        CommandInner::Dispatch { x, y, z } => CommandInner::Dispatch { x, y, z },
+        CommandInner::DispatchIndirect {
+            indirect_buffer,
+            offset,
+        } => CommandInner::DispatchIndirect {
+            indirect_buffer: W::buffer(indirect_buffer),
+            offset,
+        },
+    }
+}
+
+pub(crate) unsafe fn graphics_pipeline_create_info<'a, 'b, W: Unwrap<B>, B: Backend>(
+    create_info: &GraphicsPipelineCreateInfo<'a, 'b, W>,
+) -> GraphicsPipelineCreateInfo<'a, 'b, B> {
+    let stages = &create_info.shader_stages;
+    GraphicsPipelineCreateInfo {
+        shader_stages: GraphicsShaderStages {
+            vertex: shader_module(stages.vertex),
+            geometry: stages.geometry.map(|s| shader_module(s)),
+            fragment: stages.fragment.map(|s| shader_module(s)),
+            tess_eval: stages.tess_eval.map(|s| shader_module(s)),
+            tess_control: stages.tess_control.map(|s| shader_module(s)),
+        },
+        viewport_state: create_info.viewport_state,
+        rasterization_state: create_info.rasterization_state,
+        multisample_state: create_info.multisample_state,
+        depth_stencil_state: create_info.depth_stencil_state,
+        input_assembly_state: create_info.input_assembly_state,
+        color_blend_state: create_info.color_blend_state,
+    }
+}
+
+pub(crate) unsafe fn compute_pipeline_create_info<'a, 'b, W: Unwrap<B>, B: Backend>(
+    create_info: &ComputePipelineCreateInfo<'a, 'b, W>,
+) -> ComputePipelineCreateInfo<'a, 'b, B> {
+    ComputePipelineCreateInfo {
+        shader_stage: shader_module(create_info.shader_stage),
+    }
+}
+
+/// The accesses of the block are not converted: they are only used by the frontend.
+pub(crate) unsafe fn bare_argument_block<'a, W: Unwrap<B>, B: Backend>(
+    block: &BareArgumentBlock<'a, W>,
+) -> BareArgumentBlock<'a, B> {
+    BareArgumentBlock(W::argument_block(block.0), &[])
+}
+
+pub(crate) unsafe fn vertex_buffer_view<'a, W: Unwrap<B>, B: Backend>(
+    vb: &VertexBufferView<'a, W>,
+) -> VertexBufferView<'a, B> {
+    VertexBufferView {
+        buffer: W::buffer(vb.buffer),
+        stride: vb.stride,
+        offset: vb.offset,
+    }
+}
+
+pub(crate) unsafe fn index_buffer_view<'a, W: Unwrap<B>, B: Backend>(
+    ib: &IndexBufferView<'a, W>,
+) -> IndexBufferView<'a, B> {
+    IndexBufferView {
+        buffer: W::buffer(ib.buffer),
+        format: ib.format,
+        offset: ib.offset,
+    }
+}
+
+pub(crate) unsafe fn render_target_view<'a, W: Unwrap<B>, B: Backend>(
+    rt: &RenderTargetView<'a, W>,
+) -> RenderTargetView<'a, B> {
+    RenderTargetView {
+        image: W::image(rt.image),
+        subresource: rt.subresource,
+    }
+}
+
+pub(crate) unsafe fn depth_stencil_view<'a, W: Unwrap<B>, B: Backend>(
+    ds: &DepthStencilView<'a, W>,
+) -> DepthStencilView<'a, B> {
+    DepthStencilView {
+        image: W::image(ds.image),
+        subresource: ds.subresource,
+    }
+}
+
+pub(crate) unsafe fn debug_object<'a, W: Unwrap<B>, B: Backend>(
+    object: DebugObject<'a, W>,
+) -> DebugObject<'a, B> {
+    match object {
+        DebugObject::Image(image) => DebugObject::Image(W::image(image)),
+        DebugObject::Buffer(buffer) => DebugObject::Buffer(W::buffer(buffer)),
+        DebugObject::GraphicsPipeline(pipeline) => {
+            DebugObject::GraphicsPipeline(W::graphics_pipeline(pipeline))
+        }
+        DebugObject::ComputePipeline(pipeline) => {
+            DebugObject::ComputePipeline(W::compute_pipeline(pipeline))
+        }
+        DebugObject::ArgumentBlock(block) => DebugObject::ArgumentBlock(W::argument_block(block)),
+    }
+}
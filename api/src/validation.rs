@@ -0,0 +1,1626 @@
+//! Validation layer.
+//!
+//! [ValidatingBackend] wraps another backend, similarly to the Vulkan validation layers:
+//! its instance checks the parameters of every call before forwarding it to the wrapped instance.
+//! Errors found in the sorted command stream are reported with the sortkey of the offending
+//! command.
+//!
+//! Validation errors are logged. Object creation calls then return [Error::Validation]; calls
+//! that cannot fail (`update_image`, `write_buffer` and `submit_frame`) panic once all errors have
+//! been reported.
+//!
+//! Instances created with [ValidatingInstance::with_use_after_drop_detection] also report commands
+//! that reference objects of dropped arenas.
+//!
+//! ```ignore
+//! let api = Api::<ValidatingBackend<OpenGlBackend>>::new(ValidatingInstance::new(instance));
+//! ```
+use crate::{
+    command::{Command, CommandInner, DispatchParams, DrawIndexedParams, DrawParams},
+    debug::DebugObject,
+    descriptor::{Descriptor, ResourceBindingType, SubresourceRange},
+    error::{Error, Result},
+    format::Format,
+    image::{
+        DepthStencilView, Dimensions, ImageRegion, ImageUsageFlags, MipmapsOption, RenderTargetView,
+    },
+    pipeline::{
+        BareArgumentBlock, ComputePipelineCreateInfo, GraphicsPipelineCreateInfo, Scissor,
+        ShaderModule, ShaderStageFlags, SignatureDescription, Viewport,
+    },
+    profile::FrameProfile,
+    query::QueryType,
+    schedule::QueueBatch,
+    traits,
+    unwrap::{self, Unwrap},
+    vertex::{IndexBufferView, VertexBufferView},
+    AliasScope, Backend, Instance, MemoryType, Queue,
+};
+use std::{
+    error, fmt,
+    marker::PhantomData,
+    mem,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+use typed_arena::Arena;
+
+/// A backend that validates all calls before forwarding them to the backend `B`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct ValidatingBackend<B: Backend>(PhantomData<B>);
+
+impl<B: Backend> Backend for ValidatingBackend<B> {
+    type Instance = ValidatingInstance<B>;
+    type Arena = ValidatingArena<B>;
+    type Window = B::Window;
+    type Swapchain = ValidatingSwapchain<B>;
+    type Image = ValidatingImage<B>;
+    type Buffer = ValidatingBuffer<B>;
+    type ShaderModule = ValidatingShaderModule<B>;
+    type GraphicsPipeline = ValidatingGraphicsPipeline<B>;
+    type ComputePipeline = ValidatingComputePipeline<B>;
+    type Signature = ValidatingSignature<B>;
+    type ArgumentBlock = ValidatingArgumentBlock<B>;
+    type HostReference = ValidatingHostReference<B>;
+    type QueryPool = ValidatingQueryPool<B>;
+}
+
+//--------------------------------------------------------------------------------------------------
+
+/// A validation error.
+#[derive(Clone, Debug)]
+pub struct ValidationError {
+    /// Sortkey of the offending command, if the error was found in the command stream.
+    pub sortkey: Option<u64>,
+    pub kind: ValidationErrorKind,
+}
+
+#[derive(Clone, Debug)]
+pub enum ValidationErrorKind {
+    /// An image is used in a way that is not allowed by its usage flags.
+    MissingImageUsage {
+        context: &'static str,
+        required: ImageUsageFlags,
+        usage: ImageUsageFlags,
+    },
+    /// An aliasable image is used by a command outside of its alias scope.
+    OutsideAliasScope { scope: AliasScope },
+    /// A subresource range or region is outside of the image.
+    ImageRangeOutOfBounds {
+        context: &'static str,
+        dimensions: Dimensions,
+        mip_levels: u32,
+    },
+    /// A range of bytes is outside of the buffer.
+    BufferRangeOutOfBounds {
+        context: &'static str,
+        offset: u64,
+        size: u64,
+        buffer_size: u64,
+    },
+    /// The number of arguments of some kind in an argument block does not match the signature.
+    ArgumentCountMismatch {
+        what: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+    /// A descriptor does not match the binding type in the signature.
+    DescriptorTypeMismatch {
+        index: usize,
+        expected: ResourceBindingType,
+        descriptor: &'static str,
+    },
+    /// The signature of an inherited argument block does not match the signature.
+    InheritedSignatureMismatch { index: usize },
+    /// A shader module is used for the wrong pipeline stage.
+    ShaderStageMismatch {
+        expected: ShaderStageFlags,
+        actual: ShaderStageFlags,
+    },
+    /// The signature of the argument block does not match the signature of the pipeline.
+    PipelineSignatureMismatch,
+    /// A command is not preceded by a pipeline of the correct type, or by arguments.
+    MissingPipeline { command: &'static str },
+    /// An indexed draw command is issued without an index buffer.
+    MissingIndexBuffer,
+    /// The presented image is smaller than the swapchain.
+    PresentOutOfRange {
+        image_size: (u32, u32),
+        swapchain_size: (u32, u32),
+    },
+    /// An immutable buffer is written from the host.
+    ImmutableBufferWrite,
+    /// A profile scope is ended without having been started.
+    UnmatchedEndProfileScope,
+    /// Profile scopes are still open at the end of the frame.
+    UnendedProfileScopes { count: usize },
+    /// `PopDebugGroup` without a matching `PushDebugGroup` before it in the sorted stream.
+    UnmatchedPopDebugGroup,
+    /// Debug groups are still open at the end of the frame.
+    UnendedDebugGroups { count: usize },
+    /// A query pool is empty, or a pipeline statistics pool has no counters.
+    EmptyQueryPool,
+    /// A query index is outside of the query pool.
+    QueryOutOfRange { index: u32, count: u32 },
+    /// A query that is not an occlusion query is used for conditional rendering.
+    InvalidPredicateQuery { ty: QueryType },
+    /// A command references an object of an arena that was dropped.
+    UseAfterDrop { object: &'static str },
+}
+
+impl fmt::Display for ValidationErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ValidationErrorKind::MissingImageUsage {
+                context,
+                required,
+                usage,
+            } => write!(
+                f,
+                "{}: image usage {:?} does not include {:?}",
+                context, usage, required
+            ),
+            ValidationErrorKind::OutsideAliasScope { scope } => write!(
+                f,
+                "aliasable image used outside of its scope (value={:016x}, mask={:016x})",
+                scope.value, scope.mask
+            ),
+            ValidationErrorKind::ImageRangeOutOfBounds {
+                context,
+                dimensions,
+                mip_levels,
+            } => write!(
+                f,
+                "{}: out of bounds of image {:?} ({} mip levels)",
+                context, dimensions, mip_levels
+            ),
+            ValidationErrorKind::BufferRangeOutOfBounds {
+                context,
+                offset,
+                size,
+                buffer_size,
+            } => match offset.checked_add(size) {
+                Some(end) => write!(
+                    f,
+                    "{}: range {}..{} out of bounds of buffer of size {}",
+                    context, offset, end, buffer_size
+                ),
+                None => write!(
+                    f,
+                    "{}: range of size {} at offset {} out of bounds of buffer of size {}",
+                    context, size, offset, buffer_size
+                ),
+            },
+            ValidationErrorKind::ArgumentCountMismatch {
+                what,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "argument block has {} {}, but the signature expects {}",
+                actual, what, expected
+            ),
+            ValidationErrorKind::DescriptorTypeMismatch {
+                index,
+                expected,
+                descriptor,
+            } => write!(
+                f,
+                "descriptor #{} is a {}, but the signature expects {:?}",
+                index, descriptor, expected
+            ),
+            ValidationErrorKind::InheritedSignatureMismatch { index } => write!(
+                f,
+                "signature of inherited argument block #{} does not match",
+                index
+            ),
+            ValidationErrorKind::ShaderStageMismatch { expected, actual } => write!(
+                f,
+                "shader module for stage {:?} used as {:?}",
+                actual, expected
+            ),
+            ValidationErrorKind::PipelineSignatureMismatch => write!(
+                f,
+                "signature of the argument block does not match the signature of the pipeline"
+            ),
+            ValidationErrorKind::MissingPipeline { command } => write!(
+                f,
+                "{} command without a matching pipeline or arguments",
+                command
+            ),
+            ValidationErrorKind::MissingIndexBuffer => {
+                write!(f, "indexed draw command without an index buffer")
+            }
+            ValidationErrorKind::PresentOutOfRange {
+                image_size,
+                swapchain_size,
+            } => write!(
+                f,
+                "presented image ({}x{}) is smaller than the swapchain ({}x{})",
+                image_size.0, image_size.1, swapchain_size.0, swapchain_size.1
+            ),
+            ValidationErrorKind::ImmutableBufferWrite => write!(f, "write to an immutable buffer"),
+            ValidationErrorKind::UnmatchedEndProfileScope => {
+                write!(f, "end of a profile scope that was not started")
+            }
+            ValidationErrorKind::UnendedProfileScopes { count } => write!(
+                f,
+                "{} profile scope(s) not ended at the end of the frame",
+                count
+            ),
+            ValidationErrorKind::UnmatchedPopDebugGroup => {
+                write!(f, "pop of a debug group that was not pushed")
+            }
+            ValidationErrorKind::UnendedDebugGroups { count } => write!(
+                f,
+                "{} debug group(s) not popped at the end of the frame",
+                count
+            ),
+            ValidationErrorKind::EmptyQueryPool => write!(f, "query pool without queries"),
+            ValidationErrorKind::QueryOutOfRange { index, count } => write!(
+                f,
+                "query #{} out of bounds of query pool of size {}",
+                index, count
+            ),
+            ValidationErrorKind::InvalidPredicateQuery { ty } => write!(
+                f,
+                "{:?} query used as a predicate for conditional rendering",
+                ty
+            ),
+            ValidationErrorKind::UseAfterDrop { object } => {
+                write!(f, "{} used after its arena was dropped", object)
+            }
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(sortkey) = self.sortkey {
+            write!(f, "[sortkey {:016x}] ", sortkey)?;
+        }
+        write!(f, "{}", self.kind)
+    }
+}
+
+impl error::Error for ValidationError {}
+
+/// Collects the errors of a single call or command.
+struct Reporter {
+    sortkey: Option<u64>,
+    errors: Vec<ValidationError>,
+}
+
+impl Reporter {
+    fn new() -> Reporter {
+        Reporter {
+            sortkey: None,
+            errors: Vec::new(),
+        }
+    }
+
+    fn error(&mut self, kind: ValidationErrorKind) {
+        self.errors.push(ValidationError {
+            sortkey: self.sortkey,
+            kind,
+        })
+    }
+
+    /// Logs all errors, and returns an error if there was any.
+    fn check(self, what: &str) -> Result<()> {
+        if self.errors.is_empty() {
+            return Ok(());
+        }
+        for e in self.errors.iter() {
+            log::error!("validation error: {}", e);
+        }
+        let errors: Vec<_> = self.errors.iter().map(|e| e.to_string()).collect();
+        Err(Error::Validation {
+            message: format!("{}: {}", what, errors.join("; ")),
+        })
+    }
+
+    /// Logs all errors, and panics with their description if there was any.
+    fn finish(self, what: &str) {
+        if let Err(e) = self.check(what) {
+            panic!("{}", e);
+        }
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+
+/// Swapchain of a [ValidatingBackend].
+#[derive(Debug)]
+pub struct ValidatingSwapchain<B: Backend> {
+    inner: *const B::Swapchain,
+    dropped: Arc<AtomicBool>,
+}
+
+/// Image of a [ValidatingBackend].
+#[derive(Debug)]
+pub struct ValidatingImage<B: Backend> {
+    inner: *const B::Image,
+    dropped: Arc<AtomicBool>,
+    scope: AliasScope,
+    format: Format,
+    dimensions: Dimensions,
+    mip_levels: u32,
+    usage: ImageUsageFlags,
+}
+
+/// Buffer of a [ValidatingBackend].
+#[derive(Debug)]
+pub struct ValidatingBuffer<B: Backend> {
+    inner: *const B::Buffer,
+    dropped: Arc<AtomicBool>,
+    size: u64,
+    /// Memory of mutable buffers, `None` for immutable buffers.
+    memory: Option<MemoryType>,
+}
+
+/// Shader module of a [ValidatingBackend].
+#[derive(Debug)]
+pub struct ValidatingShaderModule<B: Backend> {
+    inner: *const B::ShaderModule,
+    stage: ShaderStageFlags,
+}
+
+/// Graphics pipeline of a [ValidatingBackend].
+#[derive(Debug)]
+pub struct ValidatingGraphicsPipeline<B: Backend> {
+    inner: *const B::GraphicsPipeline,
+    dropped: Arc<AtomicBool>,
+    signature: *const ValidatingSignature<B>,
+}
+
+/// Compute pipeline of a [ValidatingBackend].
+#[derive(Debug)]
+pub struct ValidatingComputePipeline<B: Backend> {
+    inner: *const B::ComputePipeline,
+    dropped: Arc<AtomicBool>,
+    signature: *const ValidatingSignature<B>,
+}
+
+/// Signature of a [ValidatingBackend].
+#[derive(Debug)]
+pub struct ValidatingSignature<B: Backend> {
+    inner: *const B::Signature,
+    inherited: Vec<*const ValidatingSignature<B>>,
+    descriptors: Vec<ResourceBindingType>,
+    num_vertex_buffers: usize,
+    num_render_targets: usize,
+    has_index_buffer: bool,
+    has_depth_stencil_target: bool,
+    num_viewports: usize,
+    num_scissors: usize,
+}
+
+/// Argument block of a [ValidatingBackend].
+#[derive(Debug)]
+pub struct ValidatingArgumentBlock<B: Backend> {
+    inner: *const B::ArgumentBlock,
+    dropped: Arc<AtomicBool>,
+    signature: *const ValidatingSignature<B>,
+    /// All images referenced by the block (including inherited blocks).
+    images: Vec<*const ValidatingImage<B>>,
+    /// Whether the block or one of the inherited blocks contains an index buffer.
+    has_index_buffer: bool,
+}
+
+/// Host reference of a [ValidatingBackend].
+#[derive(Debug)]
+pub struct ValidatingHostReference<B: Backend> {
+    inner: *const B::HostReference,
+}
+
+/// Query pool of a [ValidatingBackend].
+#[derive(Debug)]
+pub struct ValidatingQueryPool<B: Backend> {
+    inner: *const B::QueryPool,
+    dropped: Arc<AtomicBool>,
+    ty: QueryType,
+    count: u32,
+}
+
+macro_rules! impl_wrapper {
+    ($($t:ident => $inner:ident),*) => {
+        $(
+        // The wrapped object is Sync, and the validation data is never modified.
+        unsafe impl<B: Backend> Sync for $t<B> {}
+
+        impl<B: Backend> $t<B> {
+            /// Returns the wrapped backend object.
+            pub fn inner(&self) -> &B::$inner {
+                unsafe { &*self.inner }
+            }
+        }
+        )*
+    };
+}
+
+impl_wrapper!(
+    ValidatingSwapchain => Swapchain,
+    ValidatingImage => Image,
+    ValidatingBuffer => Buffer,
+    ValidatingShaderModule => ShaderModule,
+    ValidatingGraphicsPipeline => GraphicsPipeline,
+    ValidatingComputePipeline => ComputePipeline,
+    ValidatingSignature => Signature,
+    ValidatingArgumentBlock => ArgumentBlock,
+    ValidatingHostReference => HostReference,
+    ValidatingQueryPool => QueryPool
+);
+
+impl<B: Backend> Unwrap<B> for ValidatingBackend<B> {
+    unsafe fn swapchain(swapchain: &ValidatingSwapchain<B>) -> &B::Swapchain {
+        swapchain.inner()
+    }
+    unsafe fn image(image: &ValidatingImage<B>) -> &B::Image {
+        image.inner()
+    }
+    unsafe fn buffer(buffer: &ValidatingBuffer<B>) -> &B::Buffer {
+        buffer.inner()
+    }
+    unsafe fn shader_module(module: &ValidatingShaderModule<B>) -> &B::ShaderModule {
+        module.inner()
+    }
+    unsafe fn graphics_pipeline(pipeline: &ValidatingGraphicsPipeline<B>) -> &B::GraphicsPipeline {
+        pipeline.inner()
+    }
+    unsafe fn compute_pipeline(pipeline: &ValidatingComputePipeline<B>) -> &B::ComputePipeline {
+        pipeline.inner()
+    }
+    unsafe fn signature(signature: &ValidatingSignature<B>) -> &B::Signature {
+        signature.inner()
+    }
+    unsafe fn argument_block(block: &ValidatingArgumentBlock<B>) -> &B::ArgumentBlock {
+        block.inner()
+    }
+    unsafe fn host_reference(reference: &ValidatingHostReference<B>) -> &B::HostReference {
+        reference.inner()
+    }
+    unsafe fn query_pool(pool: &ValidatingQueryPool<B>) -> &B::QueryPool {
+        pool.inner()
+    }
+}
+
+impl<B: Backend> traits::Swapchain for ValidatingSwapchain<B> {
+    fn size(&self) -> (u32, u32) {
+        self.inner().size()
+    }
+    fn generation(&self) -> u64 {
+        self.inner().generation()
+    }
+}
+
+impl<B: Backend> ValidatingImage<B> {
+    fn mip_size(&self, level: u32) -> (u32, u32, u32) {
+        let (w, h, d) = self.dimensions.width_height_depth();
+        (
+            (w >> level).max(1),
+            (h >> level).max(1),
+            (d >> level).max(1),
+        )
+    }
+
+    fn out_of_bounds(&self, context: &'static str) -> ValidationErrorKind {
+        ValidationErrorKind::ImageRangeOutOfBounds {
+            context,
+            dimensions: self.dimensions,
+            mip_levels: self.mip_levels,
+        }
+    }
+
+    fn check_usage(&self, r: &mut Reporter, required: ImageUsageFlags, context: &'static str) {
+        if !self.usage.contains(required) {
+            r.error(ValidationErrorKind::MissingImageUsage {
+                context,
+                required,
+                usage: self.usage,
+            })
+        }
+    }
+
+    fn check_subresource(
+        &self,
+        r: &mut Reporter,
+        subresource: &SubresourceRange,
+        context: &'static str,
+    ) {
+        let layers = self.dimensions.array_layers_with_cube();
+        let level_count = subresource
+            .level_count
+            .unwrap_or_else(|| self.mip_levels.saturating_sub(subresource.base_mip_level));
+        let layer_count = subresource
+            .layer_count
+            .unwrap_or_else(|| layers.saturating_sub(subresource.base_array_layer));
+        if subresource.base_mip_level >= self.mip_levels
+            || subresource.base_mip_level + level_count > self.mip_levels
+            || subresource.base_array_layer >= layers
+            || subresource.base_array_layer + layer_count > layers
+        {
+            r.error(self.out_of_bounds(context))
+        }
+    }
+
+    fn check_region(&self, r: &mut Reporter, region: &ImageRegion, context: &'static str) {
+        // overflowing ranges are out of bounds
+        let exceeds =
+            |start: u32, len: u32, max: u32| start.checked_add(len).map_or(true, |end| end > max);
+        let layers = self.dimensions.array_layers_with_cube();
+        if region.mip_level >= self.mip_levels
+            || exceeds(region.base_array_layer, region.layer_count, layers)
+        {
+            r.error(self.out_of_bounds(context));
+            return;
+        }
+        let (w, h, d) = self.mip_size(region.mip_level);
+        if exceeds(region.offset.0, region.extent.0, w)
+            || exceeds(region.offset.1, region.extent.1, h)
+            || exceeds(region.offset.2, region.extent.2, d)
+        {
+            r.error(self.out_of_bounds(context))
+        }
+    }
+
+    fn check_scope(&self, r: &mut Reporter, sortkey: u64) {
+        if !self.scope.contains(sortkey) {
+            r.error(ValidationErrorKind::OutsideAliasScope { scope: self.scope })
+        }
+    }
+
+    /// Size in bytes of the tightly-packed data of a region, if it can be determined.
+    fn region_byte_size(&self, region: &ImageRegion) -> Option<u64> {
+        let info = self.format.get_format_info();
+        if info.is_compressed() {
+            return None;
+        }
+        let (w, h, d) = region.extent;
+        Some(
+            u64::from(w)
+                * u64::from(h)
+                * u64::from(d)
+                * u64::from(region.layer_count)
+                * info.byte_size() as u64,
+        )
+    }
+}
+
+impl<B: Backend> ValidatingBuffer<B> {
+    fn check_range(&self, r: &mut Reporter, offset: u64, size: u64, context: &'static str) {
+        if offset.checked_add(size).map_or(true, |end| end > self.size) {
+            r.error(ValidationErrorKind::BufferRangeOutOfBounds {
+                context,
+                offset,
+                size,
+                buffer_size: self.size,
+            })
+        }
+    }
+
+    fn check_range_opt(
+        &self,
+        r: &mut Reporter,
+        offset: usize,
+        size: Option<usize>,
+        context: &'static str,
+    ) {
+        let offset = offset as u64;
+        let size = size.map(|s| s as u64).unwrap_or(0);
+        self.check_range(r, offset, size, context)
+    }
+}
+
+impl<B: Backend> ValidatingQueryPool<B> {
+    fn check_query(&self, r: &mut Reporter, index: u32) {
+        if index >= self.count {
+            r.error(ValidationErrorKind::QueryOutOfRange {
+                index,
+                count: self.count,
+            })
+        }
+    }
+}
+
+fn check_count(r: &mut Reporter, what: &'static str, expected: usize, actual: usize) {
+    if expected != actual {
+        r.error(ValidationErrorKind::ArgumentCountMismatch {
+            what,
+            expected,
+            actual,
+        })
+    }
+}
+
+fn check_stage<B: Backend>(
+    r: &mut Reporter,
+    module: &ShaderModule<ValidatingBackend<B>>,
+    expected: ShaderStageFlags,
+) {
+    if module.module.stage != expected {
+        r.error(ValidationErrorKind::ShaderStageMismatch {
+            expected,
+            actual: module.module.stage,
+        })
+    }
+}
+
+fn descriptor_name<B: Backend>(d: &Descriptor<B>) -> &'static str {
+    match d {
+        Descriptor::Sampler { .. } => "sampler",
+        Descriptor::Texture { .. } => "texture",
+        Descriptor::TextureSampler { .. } => "combined texture-sampler",
+        Descriptor::RwImage { .. } => "storage image",
+        Descriptor::ConstantBuffer { .. } => "constant buffer",
+        Descriptor::HostConstantBuffer { .. } => "host constant buffer",
+        Descriptor::RwBuffer { .. } => "storage buffer",
+        Descriptor::TexelBuffer { .. } => "texel buffer",
+        Descriptor::RwTexelBuffer { .. } => "storage texel buffer",
+        Descriptor::Empty => "empty descriptor",
+    }
+}
+
+fn descriptor_matches<B: Backend>(d: &Descriptor<B>, ty: ResourceBindingType) -> bool {
+    match (d, ty) {
+        (Descriptor::Empty, _) => true,
+        (Descriptor::Sampler { .. }, ResourceBindingType::Sampler) => true,
+        (Descriptor::Texture { .. }, ResourceBindingType::Texture(_)) => true,
+        (Descriptor::TextureSampler { .. }, ResourceBindingType::TextureSampler(_)) => true,
+        (Descriptor::RwImage { .. }, ResourceBindingType::RwImage(_)) => true,
+        (Descriptor::ConstantBuffer { .. }, ResourceBindingType::ConstantBuffer) => true,
+        (Descriptor::HostConstantBuffer { .. }, ResourceBindingType::ConstantBuffer) => true,
+        (Descriptor::RwBuffer { .. }, ResourceBindingType::RwBuffer) => true,
+        (Descriptor::TexelBuffer { .. }, ResourceBindingType::TexelBuffer) => true,
+        (Descriptor::RwTexelBuffer { .. }, ResourceBindingType::RwTexelBuffer) => true,
+        _ => false,
+    }
+}
+
+fn check_descriptor<B: Backend>(r: &mut Reporter, d: &Descriptor<ValidatingBackend<B>>) {
+    match *d {
+        Descriptor::Texture { image, subresource }
+        | Descriptor::TextureSampler {
+            image, subresource, ..
+        } => {
+            image.check_usage(r, ImageUsageFlags::SAMPLED, "sampled image");
+            image.check_subresource(r, &subresource, "sampled image");
+        }
+        Descriptor::RwImage { image, subresource } => {
+            image.check_usage(r, ImageUsageFlags::STORAGE, "storage image");
+            image.check_subresource(r, &subresource, "storage image");
+        }
+        Descriptor::ConstantBuffer {
+            buffer,
+            offset,
+            size,
+        }
+        | Descriptor::RwBuffer {
+            buffer,
+            offset,
+            size,
+        }
+        | Descriptor::TexelBuffer {
+            buffer,
+            offset,
+            size,
+        }
+        | Descriptor::RwTexelBuffer {
+            buffer,
+            offset,
+            size,
+        } => buffer.check_range_opt(r, offset, size, "buffer descriptor"),
+        Descriptor::HostConstantBuffer { .. } | Descriptor::Sampler { .. } | Descriptor::Empty => {}
+    }
+}
+
+/// Returns the type of the first object referenced by the command whose arena was dropped.
+///
+/// Only valid if the wrappers of dropped arenas are kept.
+fn dropped_object<B: Backend>(cmd: &CommandInner<ValidatingBackend<B>>) -> Option<&'static str> {
+    let check = |dropped: &AtomicBool, object| {
+        if dropped.load(Ordering::Relaxed) {
+            Some(object)
+        } else {
+            None
+        }
+    };
+    let image = |image: &ValidatingImage<B>| check(&image.dropped, "image");
+    let buffer = |buffer: &ValidatingBuffer<B>| check(&buffer.dropped, "buffer");
+    let query_pool = |pool: &ValidatingQueryPool<B>| check(&pool.dropped, "query pool");
+
+    match *cmd {
+        CommandInner::CopyBuffer { src, dst, .. } => buffer(src).or_else(|| buffer(dst)),
+        CommandInner::CopyBufferToImage { src, dst, .. } => buffer(src).or_else(|| image(dst)),
+        CommandInner::CopyImageToBuffer { src, dst, .. } => image(src).or_else(|| buffer(dst)),
+        CommandInner::CopyImage { src, dst, .. } | CommandInner::BlitImage { src, dst, .. } => {
+            image(src).or_else(|| image(dst))
+        }
+        CommandInner::FillBuffer { dst, .. } => buffer(dst),
+        CommandInner::ReadbackImage { src, .. } => image(src),
+        CommandInner::ReadbackBuffer { src, .. } => buffer(src),
+        CommandInner::ClearImageFloat { image: img, .. }
+        | CommandInner::ClearDepthStencilImage { image: img, .. } => image(img),
+        CommandInner::Present {
+            image: img,
+            swapchain,
+        } => image(img).or_else(|| check(&swapchain.dropped, "swapchain")),
+        CommandInner::BeginQuery { pool, .. }
+        | CommandInner::EndQuery { pool, .. }
+        | CommandInner::ReadbackQueries { pool, .. }
+        | CommandInner::BeginConditionalRendering { pool, .. } => query_pool(pool),
+        CommandInner::DrawHeader { pipeline } => check(&pipeline.dropped, "graphics pipeline"),
+        CommandInner::DispatchHeader { pipeline } => check(&pipeline.dropped, "compute pipeline"),
+        CommandInner::SetPipelineArguments { arguments, .. } => {
+            check(&arguments.dropped, "argument block").or_else(|| {
+                arguments
+                    .images
+                    .iter()
+                    .filter_map(|&img| image(unsafe { &*img }))
+                    .next()
+            })
+        }
+        CommandInner::DrawIndirect {
+            indirect_buffer,
+            count_buffer,
+            ..
+        }
+        | CommandInner::DrawIndexedIndirect {
+            indirect_buffer,
+            count_buffer,
+            ..
+        } => buffer(indirect_buffer).or_else(|| count_buffer.and_then(buffer)),
+        CommandInner::DispatchIndirect {
+            indirect_buffer, ..
+        } => buffer(indirect_buffer),
+        CommandInner::PipelineBarrier { .. }
+        | CommandInner::BeginProfileScope { .. }
+        | CommandInner::EndProfileScope
+        | CommandInner::PushDebugGroup { .. }
+        | CommandInner::PopDebugGroup
+        | CommandInner::InsertDebugMarker { .. }
+        | CommandInner::EndConditionalRendering
+        | CommandInner::Draw { .. }
+        | CommandInner::DrawIndexed { .. }
+        | CommandInner::Dispatch { .. } => None,
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+
+/// Tracks the current pipeline and arguments in the sorted command stream.
+struct FrameValidator<'a, B: Backend> {
+    /// Signature of the current pipeline, and whether it is a graphics pipeline.
+    pipeline: Option<(*const ValidatingSignature<B>, bool)>,
+    arguments: Option<&'a ValidatingArgumentBlock<B>>,
+    /// Number of open profile scopes.
+    profile_depth: usize,
+    /// Number of open debug groups.
+    debug_group_depth: usize,
+}
+
+impl<'a, B: Backend> FrameValidator<'a, B> {
+    /// Checks that a draw (or dispatch) command has a pipeline and arguments.
+    fn check_draw(&self, r: &mut Reporter, command: &'static str, graphics: bool) {
+        match (self.pipeline, self.arguments) {
+            (Some((_, is_graphics)), Some(_)) if is_graphics == graphics => {}
+            _ => r.error(ValidationErrorKind::MissingPipeline { command }),
+        }
+    }
+
+    fn check_indexed(&self, r: &mut Reporter) {
+        if let Some(arguments) = self.arguments {
+            if !arguments.has_index_buffer {
+                r.error(ValidationErrorKind::MissingIndexBuffer)
+            }
+        }
+    }
+
+    fn check_command(
+        &mut self,
+        r: &mut Reporter,
+        sortkey: u64,
+        cmd: &CommandInner<'a, ValidatingBackend<B>>,
+    ) {
+        match *cmd {
+            CommandInner::PipelineBarrier { .. } => {}
+            CommandInner::CopyBuffer {
+                src,
+                src_offset,
+                dst,
+                dst_offset,
+                size,
+            } => {
+                src.check_range(r, src_offset as u64, size as u64, "copy source");
+                dst.check_range(r, dst_offset as u64, size as u64, "copy destination");
+            }
+            CommandInner::CopyBufferToImage {
+                src,
+                src_offset,
+                dst,
+                ref dst_region,
+            } => {
+                dst.check_scope(r, sortkey);
+                dst.check_region(r, dst_region, "copy destination");
+                if let Some(size) = dst.region_byte_size(dst_region) {
+                    src.check_range(r, src_offset as u64, size, "copy source");
+                }
+            }
+            CommandInner::CopyImageToBuffer {
+                src,
+                ref src_region,
+                dst,
+                dst_offset,
+            } => {
+                src.check_scope(r, sortkey);
+                src.check_region(r, src_region, "copy source");
+                if let Some(size) = src.region_byte_size(src_region) {
+                    dst.check_range(r, dst_offset as u64, size, "copy destination");
+                }
+            }
+            CommandInner::CopyImage {
+                src,
+                ref src_region,
+                dst,
+                ref dst_region,
+            } => {
+                src.check_scope(r, sortkey);
+                src.check_region(r, src_region, "copy source");
+                dst.check_scope(r, sortkey);
+                dst.check_region(r, dst_region, "copy destination");
+            }
+            CommandInner::FillBuffer {
+                dst, offset, size, ..
+            } => dst.check_range(r, offset as u64, size as u64, "fill"),
+            CommandInner::ReadbackImage {
+                src,
+                ref src_region,
+                ..
+            } => {
+                src.check_scope(r, sortkey);
+                src.check_region(r, src_region, "readback source");
+            }
+            CommandInner::ReadbackBuffer {
+                src, offset, size, ..
+            } => src.check_range(r, offset as u64, size as u64, "readback source"),
+            CommandInner::BlitImage {
+                src,
+                src_mip_level,
+                src_offsets,
+                dst,
+                dst_mip_level,
+                dst_offsets,
+                ..
+            } => {
+                let region = |mip_level: u32, [(x0, y0), (x1, y1)]: [(u32, u32); 2]| ImageRegion {
+                    mip_level,
+                    base_array_layer: 0,
+                    layer_count: 1,
+                    offset: (x0.min(x1), y0.min(y1), 0),
+                    extent: (x0.max(x1) - x0.min(x1), y0.max(y1) - y0.min(y1), 1),
+                };
+                src.check_scope(r, sortkey);
+                src.check_region(r, &region(src_mip_level, src_offsets), "blit source");
+                dst.check_scope(r, sortkey);
+                dst.check_region(r, &region(dst_mip_level, dst_offsets), "blit destination");
+            }
+            CommandInner::ClearImageFloat { image, .. }
+            | CommandInner::ClearDepthStencilImage { image, .. } => {
+                image.check_scope(r, sortkey);
+            }
+            CommandInner::Present { image, swapchain } => {
+                image.check_scope(r, sortkey);
+                let image_size = image.dimensions.width_height();
+                let swapchain_size = traits::Swapchain::size(swapchain);
+                if image_size.0 < swapchain_size.0 || image_size.1 < swapchain_size.1 {
+                    r.error(ValidationErrorKind::PresentOutOfRange {
+                        image_size,
+                        swapchain_size,
+                    })
+                }
+            }
+            CommandInner::BeginQuery { pool, index } | CommandInner::EndQuery { pool, index } => {
+                pool.check_query(r, index)
+            }
+            CommandInner::ReadbackQueries {
+                pool, first, count, ..
+            } => {
+                if count != 0 {
+                    // the end of an overflowing range is past any pool
+                    let last = first.checked_add(count - 1).unwrap_or(u32::max_value());
+                    pool.check_query(r, last)
+                }
+            }
+            CommandInner::BeginConditionalRendering { pool, index } => {
+                pool.check_query(r, index);
+                if !pool.ty.is_occlusion() {
+                    r.error(ValidationErrorKind::InvalidPredicateQuery { ty: pool.ty })
+                }
+            }
+            CommandInner::EndConditionalRendering => {}
+            CommandInner::PushDebugGroup { .. } => self.debug_group_depth += 1,
+            CommandInner::PopDebugGroup => {
+                if self.debug_group_depth == 0 {
+                    r.error(ValidationErrorKind::UnmatchedPopDebugGroup)
+                } else {
+                    self.debug_group_depth -= 1;
+                }
+            }
+            CommandInner::InsertDebugMarker { .. } => {}
+            CommandInner::BeginProfileScope { .. } => self.profile_depth += 1,
+            CommandInner::EndProfileScope => {
+                if self.profile_depth == 0 {
+                    r.error(ValidationErrorKind::UnmatchedEndProfileScope)
+                } else {
+                    self.profile_depth -= 1;
+                }
+            }
+            CommandInner::DrawHeader { pipeline } => {
+                self.pipeline = Some((pipeline.signature, true));
+                self.arguments = None;
+            }
+            CommandInner::DispatchHeader { pipeline } => {
+                self.pipeline = Some((pipeline.signature, false));
+                self.arguments = None;
+            }
+            CommandInner::SetPipelineArguments { arguments, .. } => {
+                match self.pipeline {
+                    None => r.error(ValidationErrorKind::MissingPipeline {
+                        command: "SetPipelineArguments",
+                    }),
+                    Some((signature, _)) if signature != arguments.signature => {
+                        r.error(ValidationErrorKind::PipelineSignatureMismatch)
+                    }
+                    _ => {}
+                }
+                for &image in arguments.images.iter() {
+                    unsafe { &*image }.check_scope(r, sortkey);
+                }
+                self.arguments = Some(arguments);
+            }
+            CommandInner::Draw { .. } => self.check_draw(r, "Draw", true),
+            CommandInner::DrawIndexed { .. } => {
+                self.check_draw(r, "DrawIndexed", true);
+                self.check_indexed(r);
+            }
+            CommandInner::DrawIndirect {
+                indirect_buffer,
+                offset,
+                draw_count,
+                count_buffer,
+            } => {
+                self.check_draw(r, "DrawIndirect", true);
+                let size = mem::size_of::<DrawParams>() as u64 * u64::from(draw_count);
+                indirect_buffer.check_range(r, offset as u64, size, "indirect draw parameters");
+                if let Some(count_buffer) = count_buffer {
+                    count_buffer.check_range(r, 0, 4, "indirect draw count");
+                }
+            }
+            CommandInner::DrawIndexedIndirect {
+                indirect_buffer,
+                offset,
+                draw_count,
+                count_buffer,
+            } => {
+                self.check_draw(r, "DrawIndexedIndirect", true);
+                self.check_indexed(r);
+                let size = mem::size_of::<DrawIndexedParams>() as u64 * u64::from(draw_count);
+                indirect_buffer.check_range(r, offset as u64, size, "indirect draw parameters");
+                if let Some(count_buffer) = count_buffer {
+                    count_buffer.check_range(r, 0, 4, "indirect draw count");
+                }
+            }
+            CommandInner::Dispatch { .. } => self.check_draw(r, "Dispatch", false),
+            CommandInner::DispatchIndirect {
+                indirect_buffer,
+                offset,
+            } => {
+                self.check_draw(r, "DispatchIndirect", false);
+                let size = mem::size_of::<DispatchParams>() as u64;
+                indirect_buffer.check_range(r, offset as u64, size, "indirect dispatch parameters");
+            }
+        }
+    }
+}
+
+//--------------------------------------------------------------------------------------------------
+
+/// Arena of a [ValidatingBackend].
+pub struct ValidatingArena<B: Backend> {
+    inner: Box<B::Arena>,
+    /// Set when the arena is dropped.
+    dropped: Arc<AtomicBool>,
+    objects: ArenaObjects<B>,
+}
+
+/// Wrappers allocated in a [ValidatingArena].
+struct ArenaObjects<B: Backend> {
+    swapchains: Arena<ValidatingSwapchain<B>>,
+    images: Arena<ValidatingImage<B>>,
+    buffers: Arena<ValidatingBuffer<B>>,
+    shader_modules: Arena<ValidatingShaderModule<B>>,
+    graphics_pipelines: Arena<ValidatingGraphicsPipeline<B>>,
+    compute_pipelines: Arena<ValidatingComputePipeline<B>>,
+    signatures: Arena<ValidatingSignature<B>>,
+    argument_blocks: Arena<ValidatingArgumentBlock<B>>,
+    host_references: Arena<ValidatingHostReference<B>>,
+    query_pools: Arena<ValidatingQueryPool<B>>,
+}
+
+/// Instance of a [ValidatingBackend]: validates all calls before forwarding them
+/// to the wrapped instance.
+pub struct ValidatingInstance<B: Backend> {
+    inner: B::Instance,
+    /// Wrapper for the default swapchain of the wrapped instance, created on first use.
+    default_swapchain: Mutex<Option<Box<ValidatingSwapchain<B>>>>,
+    detect_use_after_drop: bool,
+    /// Wrappers of dropped arenas, kept when detecting use-after-drop.
+    dropped_arenas: Mutex<Vec<ArenaObjects<B>>>,
+}
+
+impl<B: Backend> ValidatingInstance<B> {
+    /// Wraps an instance.
+    pub fn new(inner: B::Instance) -> ValidatingInstance<B> {
+        ValidatingInstance {
+            inner,
+            default_swapchain: Mutex::new(None),
+            detect_use_after_drop: false,
+            dropped_arenas: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Wraps an instance, and reports submitted commands that reference objects of dropped
+    /// arenas.
+    ///
+    /// Safe code cannot reference objects after their arena is dropped, but objects recreated
+    /// from raw references (e.g. `Image2d::from_raw`) can. To detect this, the wrappers of
+    /// dropped arenas are kept until the instance is dropped, so this mode is intended for
+    /// debugging only.
+    pub fn with_use_after_drop_detection(inner: B::Instance) -> ValidatingInstance<B> {
+        ValidatingInstance {
+            detect_use_after_drop: true,
+            ..ValidatingInstance::new(inner)
+        }
+    }
+
+    /// Returns the wrapped instance.
+    pub fn inner(&self) -> &B::Instance {
+        &self.inner
+    }
+}
+
+impl<B: Backend> Instance<ValidatingBackend<B>> for ValidatingInstance<B> {
+    unsafe fn create_arena(&self) -> Result<Box<ValidatingArena<B>>> {
+        Ok(Box::new(ValidatingArena {
+            inner: self.inner.create_arena()?,
+            dropped: Arc::new(AtomicBool::new(false)),
+            objects: ArenaObjects {
+                swapchains: Arena::new(),
+                images: Arena::new(),
+                buffers: Arena::new(),
+                shader_modules: Arena::new(),
+                graphics_pipelines: Arena::new(),
+                compute_pipelines: Arena::new(),
+                signatures: Arena::new(),
+                argument_blocks: Arena::new(),
+                host_references: Arena::new(),
+                query_pools: Arena::new(),
+            },
+        }))
+    }
+
+    unsafe fn drop_arena(&self, arena: Box<ValidatingArena<B>>) {
+        // the wrappers don't own anything: they are dropped along with the arena, or kept
+        // (and marked as dropped) to detect use-after-drop
+        let arena = *arena;
+        arena.dropped.store(true, Ordering::Relaxed);
+        if self.detect_use_after_drop {
+            self.dropped_arenas.lock().unwrap().push(arena.objects);
+        }
+        self.inner.drop_arena(arena.inner)
+    }
+
+    unsafe fn create_swapchain<'a>(
+        &self,
+        arena: &'a ValidatingArena<B>,
+        window: &B::Window,
+    ) -> Result<&'a ValidatingSwapchain<B>> {
+        Ok(arena.objects.swapchains.alloc(ValidatingSwapchain {
+            dropped: arena.dropped.clone(),
+            inner: self.inner.create_swapchain(&arena.inner, window)?,
+        }))
+    }
+
+    unsafe fn default_swapchain<'a>(&'a self) -> Option<&'a ValidatingSwapchain<B>> {
+        let mut default_swapchain = self.default_swapchain.lock().unwrap();
+        if default_swapchain.is_none() {
+            *default_swapchain = self.inner.default_swapchain().map(|s| {
+                Box::new(ValidatingSwapchain {
+                    inner: s,
+                    dropped: Arc::new(AtomicBool::new(false)),
+                })
+            });
+        }
+        // the box is never replaced once created, so it lives as long as the instance
+        default_swapchain
+            .as_ref()
+            .map(|s| &*(s.as_ref() as *const ValidatingSwapchain<B>))
+    }
+
+    unsafe fn create_image<'a>(
+        &self,
+        arena: &'a ValidatingArena<B>,
+        scope: AliasScope,
+        format: Format,
+        dimensions: Dimensions,
+        mipcount: MipmapsOption,
+        samples: u32,
+        usage: ImageUsageFlags,
+        initial_data: Option<&[u8]>,
+    ) -> Result<&'a ValidatingImage<B>> {
+        let (w, h, d) = dimensions.width_height_depth();
+        Ok(arena.objects.images.alloc(ValidatingImage {
+            dropped: arena.dropped.clone(),
+            inner: self.inner.create_image(
+                &arena.inner,
+                scope,
+                format,
+                dimensions,
+                mipcount,
+                samples,
+                usage,
+                initial_data,
+            )?,
+            scope,
+            format,
+            dimensions,
+            mip_levels: mipcount.count(w, h, d),
+            usage,
+        }))
+    }
+
+    unsafe fn update_image(
+        &self,
+        image: &ValidatingImage<B>,
+        min_extent: (u32, u32, u32),
+        max_extent: (u32, u32, u32),
+        data: &[u8],
+    ) {
+        let mut r = Reporter::new();
+        image.check_region(
+            &mut r,
+            &ImageRegion::new(
+                min_extent,
+                (
+                    max_extent.0.saturating_sub(min_extent.0),
+                    max_extent.1.saturating_sub(min_extent.1),
+                    max_extent.2.saturating_sub(min_extent.2),
+                ),
+            ),
+            "image update",
+        );
+        r.finish("update_image");
+        self.inner
+            .update_image(image.inner(), min_extent, max_extent, data)
+    }
+
+    unsafe fn create_immutable_buffer<'a>(
+        &self,
+        arena: &'a ValidatingArena<B>,
+        size: u64,
+        data: &[u8],
+    ) -> Result<&'a ValidatingBuffer<B>> {
+        Ok(arena.objects.buffers.alloc(ValidatingBuffer {
+            dropped: arena.dropped.clone(),
+            inner: self
+                .inner
+                .create_immutable_buffer(&arena.inner, size, data)?,
+            size,
+            memory: None,
+        }))
+    }
+
+    unsafe fn create_buffer<'a>(
+        &self,
+        arena: &'a ValidatingArena<B>,
+        size: u64,
+        memory: MemoryType,
+    ) -> Result<&'a ValidatingBuffer<B>> {
+        Ok(arena.objects.buffers.alloc(ValidatingBuffer {
+            dropped: arena.dropped.clone(),
+            inner: self.inner.create_buffer(&arena.inner, size, memory)?,
+            size,
+            memory: Some(memory),
+        }))
+    }
+
+    unsafe fn write_buffer(&self, buffer: &ValidatingBuffer<B>, offset: u64, data: &[u8]) {
+        let mut r = Reporter::new();
+        if buffer.memory.is_none() {
+            r.error(ValidationErrorKind::ImmutableBufferWrite);
+        }
+        buffer.check_range(&mut r, offset, data.len() as u64, "buffer write");
+        r.finish("write_buffer");
+        self.inner.write_buffer(buffer.inner(), offset, data)
+    }
+
+    unsafe fn create_shader_module<'a>(
+        &self,
+        arena: &'a ValidatingArena<B>,
+        spirv: &'_ [u8],
+        stage: ShaderStageFlags,
+    ) -> Result<&'a ValidatingShaderModule<B>> {
+        Ok(arena.objects.shader_modules.alloc(ValidatingShaderModule {
+            inner: self
+                .inner
+                .create_shader_module(&arena.inner, spirv, stage)?,
+            stage,
+        }))
+    }
+
+    unsafe fn create_graphics_pipeline<'a>(
+        &self,
+        arena: &'a ValidatingArena<B>,
+        root_signature: &'a ValidatingSignature<B>,
+        root_signature_description: &SignatureDescription,
+        create_info: &GraphicsPipelineCreateInfo<'a, '_, ValidatingBackend<B>>,
+    ) -> Result<&'a ValidatingGraphicsPipeline<B>> {
+        let mut r = Reporter::new();
+        let stages = &create_info.shader_stages;
+        check_stage(&mut r, &stages.vertex, ShaderStageFlags::VERTEX);
+        if let Some(s) = &stages.geometry {
+            check_stage(&mut r, s, ShaderStageFlags::GEOMETRY);
+        }
+        if let Some(s) = &stages.fragment {
+            check_stage(&mut r, s, ShaderStageFlags::FRAGMENT);
+        }
+        if let Some(s) = &stages.tess_eval {
+            check_stage(&mut r, s, ShaderStageFlags::TESS_EVAL);
+        }
+        if let Some(s) = &stages.tess_control {
+            check_stage(&mut r, s, ShaderStageFlags::TESS_CONTROL);
+        }
+        r.check("create_graphics_pipeline")?;
+
+        let create_info = unwrap::graphics_pipeline_create_info(create_info);
+
+        Ok(arena
+            .objects
+            .graphics_pipelines
+            .alloc(ValidatingGraphicsPipeline {
+                dropped: arena.dropped.clone(),
+                inner: self.inner.create_graphics_pipeline(
+                    &arena.inner,
+                    root_signature.inner(),
+                    root_signature_description,
+                    &create_info,
+                )?,
+                signature: root_signature,
+            }))
+    }
+
+    unsafe fn create_compute_pipeline<'a>(
+        &self,
+        arena: &'a ValidatingArena<B>,
+        root_signature: &'a ValidatingSignature<B>,
+        root_signature_description: &SignatureDescription,
+        create_info: &ComputePipelineCreateInfo<'a, '_, ValidatingBackend<B>>,
+    ) -> Result<&'a ValidatingComputePipeline<B>> {
+        let mut r = Reporter::new();
+        check_stage(&mut r, &create_info.shader_stage, ShaderStageFlags::COMPUTE);
+        r.check("create_compute_pipeline")?;
+
+        let create_info = unwrap::compute_pipeline_create_info(create_info);
+
+        Ok(arena
+            .objects
+            .compute_pipelines
+            .alloc(ValidatingComputePipeline {
+                dropped: arena.dropped.clone(),
+                inner: self.inner.create_compute_pipeline(
+                    &arena.inner,
+                    root_signature.inner(),
+                    root_signature_description,
+                    &create_info,
+                )?,
+                signature: root_signature,
+            }))
+    }
+
+    unsafe fn create_signature<'a>(
+        &'a self,
+        arena: &'a ValidatingArena<B>,
+        inherited: &[&'a ValidatingSignature<B>],
+        description: &SignatureDescription,
+    ) -> Result<&'a ValidatingSignature<B>> {
+        let inherited_inner: Vec<_> = inherited.iter().map(|s| s.inner()).collect();
+        Ok(arena.objects.signatures.alloc(ValidatingSignature {
+            inner: self
+                .inner
+                .create_signature(&arena.inner, &inherited_inner, description)?,
+            inherited: inherited.iter().map(|&s| s as *const _).collect(),
+            descriptors: description.descriptors.iter().map(|d| d.ty).collect(),
+            num_vertex_buffers: description.vertex_inputs.len(),
+            num_render_targets: description.fragment_outputs.len(),
+            has_index_buffer: description.index_format.is_some(),
+            has_depth_stencil_target: description.depth_stencil_fragment_output.is_some(),
+            num_viewports: description.num_viewports,
+            num_scissors: description.num_scissors,
+        }))
+    }
+
+    unsafe fn create_argument_block<'a>(
+        &self,
+        arena: &'a ValidatingArena<B>,
+        signature: &'a ValidatingSignature<B>,
+        arguments: impl IntoIterator<Item = BareArgumentBlock<'a, ValidatingBackend<B>>>,
+        descriptors: impl IntoIterator<Item = Descriptor<'a, ValidatingBackend<B>>>,
+        vertex_buffers: impl IntoIterator<Item = VertexBufferView<'a, ValidatingBackend<B>>>,
+        index_buffer: Option<IndexBufferView<'a, ValidatingBackend<B>>>,
+        render_targets: impl IntoIterator<Item = RenderTargetView<'a, ValidatingBackend<B>>>,
+        depth_stencil_target: Option<DepthStencilView<'a, ValidatingBackend<B>>>,
+        viewports: impl IntoIterator<Item = Viewport>,
+        scissors: impl IntoIterator<Item = Scissor>,
+    ) -> Result<&'a ValidatingArgumentBlock<B>> {
+        let inherited: Vec<_> = arguments.into_iter().collect();
+        let descriptors: Vec<_> = descriptors.into_iter().collect();
+        let vertex_buffers: Vec<_> = vertex_buffers.into_iter().collect();
+        let render_targets: Vec<_> = render_targets.into_iter().collect();
+        let viewports: Vec<_> = viewports.into_iter().collect();
+        let scissors: Vec<_> = scissors.into_iter().collect();
+
+        let mut r = Reporter::new();
+
+        check_count(
+            &mut r,
+            "inherited argument blocks",
+            signature.inherited.len(),
+            inherited.len(),
+        );
+        for (index, (b, &s)) in inherited.iter().zip(signature.inherited.iter()).enumerate() {
+            if b.0.signature != s {
+                r.error(ValidationErrorKind::InheritedSignatureMismatch { index })
+            }
+        }
+
+        check_count(
+            &mut r,
+            "descriptors",
+            signature.descriptors.len(),
+            descriptors.len(),
+        );
+        for (index, (d, &ty)) in descriptors
+            .iter()
+            .zip(signature.descriptors.iter())
+            .enumerate()
+        {
+            if !descriptor_matches(d, ty) {
+                r.error(ValidationErrorKind::DescriptorTypeMismatch {
+                    index,
+                    expected: ty,
+                    descriptor: descriptor_name(d),
+                })
+            }
+            check_descriptor(&mut r, d);
+        }
+
+        check_count(
+            &mut r,
+            "vertex buffers",
+            signature.num_vertex_buffers,
+            vertex_buffers.len(),
+        );
+        for vb in vertex_buffers.iter() {
+            vb.buffer
+                .check_range_opt(&mut r, vb.offset, None, "vertex buffer");
+        }
+
+        check_count(
+            &mut r,
+            "index buffers",
+            signature.has_index_buffer as usize,
+            index_buffer.is_some() as usize,
+        );
+        if let Some(ib) = &index_buffer {
+            ib.buffer
+                .check_range_opt(&mut r, ib.offset, None, "index buffer");
+        }
+
+        check_count(
+            &mut r,
+            "render targets",
+            signature.num_render_targets,
+            render_targets.len(),
+        );
+        for rt in render_targets.iter() {
+            rt.image
+                .check_usage(&mut r, ImageUsageFlags::COLOR_ATTACHMENT, "render target");
+            rt.image
+                .check_subresource(&mut r, &rt.subresource, "render target");
+        }
+
+        check_count(
+            &mut r,
+            "depth-stencil targets",
+            signature.has_depth_stencil_target as usize,
+            depth_stencil_target.is_some() as usize,
+        );
+        if let Some(ds) = &depth_stencil_target {
+            ds.image.check_usage(
+                &mut r,
+                ImageUsageFlags::DEPTH_ATTACHMENT,
+                "depth-stencil target",
+            );
+            ds.image
+                .check_subresource(&mut r, &ds.subresource, "depth-stencil target");
+        }
+
+        check_count(
+            &mut r,
+            "viewports",
+            signature.num_viewports,
+            viewports.len(),
+        );
+        check_count(&mut r, "scissors", signature.num_scissors, scissors.len());
+
+        r.check("create_argument_block")?;
+
+        // collect images for alias scope checks in the command stream
+        let mut images: Vec<*const ValidatingImage<B>> = Vec::new();
+        for b in inherited.iter() {
+            images.extend(b.0.images.iter().cloned());
+        }
+        for d in descriptors.iter() {
+            match *d {
+                Descriptor::Texture { image, .. }
+                | Descriptor::TextureSampler { image, .. }
+                | Descriptor::RwImage { image, .. } => images.push(image),
+                _ => {}
+            }
+        }
+        images.extend(render_targets.iter().map(|rt| rt.image as *const _));
+        images.extend(depth_stencil_target.iter().map(|ds| ds.image as *const _));
+
+        let has_index_buffer =
+            index_buffer.is_some() || inherited.iter().any(|b| b.0.has_index_buffer);
+
+        let inner = self.inner.create_argument_block(
+            &arena.inner,
+            signature.inner(),
+            inherited.iter().map(|b| unwrap::bare_argument_block(b)),
+            descriptors.iter().map(|d| unwrap::descriptor(d)),
+            vertex_buffers
+                .iter()
+                .map(|vb| unwrap::vertex_buffer_view(vb)),
+            index_buffer.map(|ib| unwrap::index_buffer_view(&ib)),
+            render_targets
+                .iter()
+                .map(|rt| unwrap::render_target_view(rt)),
+            depth_stencil_target.map(|ds| unwrap::depth_stencil_view(&ds)),
+            viewports,
+            scissors,
+        )?;
+
+        Ok(arena
+            .objects
+            .argument_blocks
+            .alloc(ValidatingArgumentBlock {
+                dropped: arena.dropped.clone(),
+                inner,
+                signature,
+                images,
+                has_index_buffer,
+            }))
+    }
+
+    unsafe fn create_host_reference<'a>(
+        &self,
+        arena: &'a ValidatingArena<B>,
+        data: &'a [u8],
+    ) -> Result<&'a ValidatingHostReference<B>> {
+        Ok(arena
+            .objects
+            .host_references
+            .alloc(ValidatingHostReference {
+                inner: self.inner.create_host_reference(&arena.inner, data)?,
+            }))
+    }
+
+    unsafe fn create_query_pool<'a>(
+        &self,
+        arena: &'a ValidatingArena<B>,
+        ty: QueryType,
+        count: u32,
+    ) -> Result<&'a ValidatingQueryPool<B>> {
+        let mut r = Reporter::new();
+        if count == 0 || ty.values_per_query() == 0 {
+            r.error(ValidationErrorKind::EmptyQueryPool);
+        }
+        r.check("create_query_pool")?;
+        Ok(arena.objects.query_pools.alloc(ValidatingQueryPool {
+            dropped: arena.dropped.clone(),
+            inner: self.inner.create_query_pool(&arena.inner, ty, count)?,
+            ty,
+            count,
+        }))
+    }
+
+    unsafe fn set_debug_name(&self, object: DebugObject<ValidatingBackend<B>>, name: &str) {
+        self.inner
+            .set_debug_name(unwrap::debug_object(object), name)
+    }
+
+    fn queues(&self) -> &[Queue] {
+        self.inner.queues()
+    }
+
+    unsafe fn submit_frame<'a>(
+        &self,
+        commands: &[Command<'a, ValidatingBackend<B>>],
+        batches: &[QueueBatch],
+    ) {
+        let mut r = Reporter::new();
+        let mut validator = FrameValidator {
+            pipeline: None,
+            arguments: None,
+            profile_depth: 0,
+            debug_group_depth: 0,
+        };
+        for cmd in commands.iter() {
+            r.sortkey = Some(cmd.sortkey);
+            if self.detect_use_after_drop {
+                // don't look further into the objects: the wrapped objects are destroyed
+                if let Some(object) = dropped_object(&cmd.cmd) {
+                    r.error(ValidationErrorKind::UseAfterDrop { object });
+                    continue;
+                }
+            }
+            validator.check_command(&mut r, cmd.sortkey, &cmd.cmd);
+        }
+        r.sortkey = None;
+        if validator.profile_depth != 0 {
+            r.error(ValidationErrorKind::UnendedProfileScopes {
+                count: validator.profile_depth,
+            });
+        }
+        if validator.debug_group_depth != 0 {
+            r.error(ValidationErrorKind::UnendedDebugGroups {
+                count: validator.debug_group_depth,
+            });
+        }
+        r.finish("submit_frame");
+
+        let commands: Vec<_> = commands
+            .iter()
+            .map(|cmd| Command {
+                sortkey: cmd.sortkey,
+                queue: cmd.queue,
+                cmd: unwrap::command(&cmd.cmd),
+            })
+            .collect();
+        self.inner.submit_frame(&commands, batches)
+    }
+
+    fn uniform_buffer_offset_alignment(&self) -> usize {
+        self.inner.uniform_buffer_offset_alignment()
+    }
+
+    fn current_frame(&self) -> u64 {
+        self.inner.current_frame()
+    }
+
+    fn is_frame_finished(&self, frame: u64) -> bool {
+        self.inner.is_frame_finished(frame)
+    }
+
+    unsafe fn poll_readbacks(&self, timeout: Duration) -> bool {
+        self.inner.poll_readbacks(timeout)
+    }
+
+    fn frame_profile(&self) -> Option<FrameProfile> {
+        self.inner.frame_profile()
+    }
+}
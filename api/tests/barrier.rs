@@ -0,0 +1,199 @@
+//! barrier insertion tests
+use autograph_api::{
+    barrier::{insert_pipeline_barriers, AccessFlags, ResourceAccess},
+    command::{Command, CommandInner},
+    mock::{MockBackend, MockInstance, MockObject},
+    Api, Queue,
+};
+
+fn cmd(cmd: CommandInner<MockBackend>) -> Command<MockBackend> {
+    Command {
+        sortkey: 0,
+        queue: Queue::Graphics,
+        cmd,
+    }
+}
+
+fn fill<'a>(dst: &'a MockObject) -> Command<'a, MockBackend> {
+    cmd(CommandInner::FillBuffer {
+        dst,
+        offset: 0,
+        size: 256,
+        value: 0,
+    })
+}
+
+fn copy<'a>(src: &'a MockObject, dst: &'a MockObject) -> Command<'a, MockBackend> {
+    cmd(CommandInner::CopyBuffer {
+        src,
+        src_offset: 0,
+        dst,
+        dst_offset: 0,
+        size: 256,
+    })
+}
+
+fn barrier<'a>(src: AccessFlags, dst: AccessFlags) -> Command<'a, MockBackend> {
+    cmd(CommandInner::PipelineBarrier {
+        src_access_mask: src,
+        dst_access_mask: dst,
+    })
+}
+
+/// A draw (lead-in, arguments, lead-out) that accesses the specified resources.
+fn draw<'a>(
+    object: &'a MockObject,
+    accesses: &'a [ResourceAccess<'a, MockBackend>],
+) -> Vec<Command<'a, MockBackend>> {
+    vec![
+        cmd(CommandInner::DrawHeader { pipeline: object }),
+        cmd(CommandInner::SetPipelineArguments {
+            arguments: object,
+            accesses,
+        }),
+        cmd(CommandInner::Draw {
+            vertex_count: 3,
+            instance_count: 1,
+            first_vertex: 0,
+            first_instance: 0,
+        }),
+    ]
+}
+
+/// Returns the position and masks of the inserted barriers.
+fn barriers(commands: Vec<Command<MockBackend>>) -> Vec<(usize, AccessFlags, AccessFlags)> {
+    insert_pipeline_barriers(commands)
+        .iter()
+        .enumerate()
+        .filter_map(|(i, c)| match c.cmd {
+            CommandInner::PipelineBarrier {
+                src_access_mask,
+                dst_access_mask,
+            } => Some((i, src_access_mask, dst_access_mask)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_barrier_read_after_write() {
+    let api = Api::<MockBackend>::new(MockInstance::new()).unwrap();
+    let arena = api.create_arena().unwrap();
+    let a = arena.create_buffer_typeless(256).unwrap().0;
+    let b = arena.create_buffer_typeless(256).unwrap().0;
+    let c = arena.create_buffer_typeless(256).unwrap().0;
+
+    // the second read is already visible
+    assert_eq!(
+        barriers(vec![fill(a), copy(a, b), copy(a, c)]),
+        [(1, AccessFlags::TRANSFER_WRITE, AccessFlags::TRANSFER_READ)]
+    );
+    // independent resources
+    assert_eq!(barriers(vec![fill(a), fill(b), copy(c, b)]).len(), 1);
+}
+
+#[test]
+fn test_barrier_write_after_read() {
+    let api = Api::<MockBackend>::new(MockInstance::new()).unwrap();
+    let arena = api.create_arena().unwrap();
+    let a = arena.create_buffer_typeless(256).unwrap().0;
+    let b = arena.create_buffer_typeless(256).unwrap().0;
+
+    assert_eq!(
+        barriers(vec![copy(a, b), fill(a)]),
+        [(1, AccessFlags::TRANSFER_READ, AccessFlags::TRANSFER_WRITE)]
+    );
+}
+
+#[test]
+fn test_barrier_write_after_write() {
+    let api = Api::<MockBackend>::new(MockInstance::new()).unwrap();
+    let arena = api.create_arena().unwrap();
+    let a = arena.create_buffer_typeless(256).unwrap().0;
+
+    assert_eq!(
+        barriers(vec![fill(a), fill(a)]),
+        [(1, AccessFlags::TRANSFER_WRITE, AccessFlags::TRANSFER_WRITE)]
+    );
+}
+
+#[test]
+fn test_barrier_attachments() {
+    let api = Api::<MockBackend>::new(MockInstance::new()).unwrap();
+    let arena = api.create_arena().unwrap();
+    let object = arena.create_buffer_typeless(256).unwrap().0;
+    let rt = arena.create_buffer_typeless(256).unwrap().0;
+    let render = [ResourceAccess::image(
+        rt,
+        AccessFlags::COLOR_ATTACHMENT_READ | AccessFlags::COLOR_ATTACHMENT_WRITE,
+    )];
+    let sample = [ResourceAccess::image(rt, AccessFlags::SAMPLED_READ)];
+
+    let mut commands = Vec::new();
+    commands.extend(draw(object, &render));
+    commands.extend(draw(object, &render));
+    commands.extend(draw(object, &sample));
+    commands.extend(draw(object, &render));
+    // successive draws to the same attachment don't need barriers; barriers are inserted
+    // before the lead-in command of the draw
+    assert_eq!(
+        barriers(commands),
+        [
+            (
+                6,
+                AccessFlags::COLOR_ATTACHMENT_WRITE,
+                AccessFlags::SAMPLED_READ
+            ),
+            (
+                10,
+                AccessFlags::COLOR_ATTACHMENT_READ
+                    | AccessFlags::COLOR_ATTACHMENT_WRITE
+                    | AccessFlags::SAMPLED_READ,
+                AccessFlags::COLOR_ATTACHMENT_READ | AccessFlags::COLOR_ATTACHMENT_WRITE
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_barrier_explicit() {
+    let api = Api::<MockBackend>::new(MockInstance::new()).unwrap();
+    let arena = api.create_arena().unwrap();
+    let a = arena.create_buffer_typeless(256).unwrap().0;
+    let b = arena.create_buffer_typeless(256).unwrap().0;
+
+    // only the explicit barriers remain
+    assert_eq!(
+        barriers(vec![
+            fill(a),
+            barrier(AccessFlags::TRANSFER_WRITE, AccessFlags::TRANSFER_READ),
+            copy(a, b),
+            barrier(AccessFlags::TRANSFER_READ, AccessFlags::TRANSFER_WRITE),
+            fill(a),
+        ]),
+        [
+            (1, AccessFlags::TRANSFER_WRITE, AccessFlags::TRANSFER_READ),
+            (3, AccessFlags::TRANSFER_READ, AccessFlags::TRANSFER_WRITE),
+        ]
+    );
+
+    // a barrier that doesn't cover the previous accesses doesn't flush them
+    assert_eq!(
+        barriers(vec![
+            fill(a),
+            barrier(
+                AccessFlags::STORAGE_BUFFER_WRITE,
+                AccessFlags::TRANSFER_READ
+            ),
+            copy(a, b),
+        ]),
+        [
+            (
+                1,
+                AccessFlags::STORAGE_BUFFER_WRITE,
+                AccessFlags::TRANSFER_READ
+            ),
+            (2, AccessFlags::TRANSFER_WRITE, AccessFlags::TRANSFER_READ),
+        ]
+    );
+}
@@ -0,0 +1,383 @@
+//! frame capture tests
+use autograph_api::{
+    barrier::AccessFlags,
+    capture::{
+        replay, CapturedArgumentBlock, CapturedColorBlendAttachments, CapturedCommand,
+        CapturedCommandInner, CapturedGraphicsPipeline, CapturedObject,
+        CapturedSignatureDescription, FrameCapture, ObjectRef,
+    },
+    error::Error,
+    format::Format,
+    image::{Dimensions, Filter, ImageRegion, ImageUsageFlags, MipmapsOption},
+    mock::{MockBackend, MockInstance},
+    pipeline::{
+        ColorBlendAttachmentState, ColorBlendState, DepthStencilState, InputAssemblyState,
+        MultisampleState, RasterisationState, ScissorsOwned, ShaderStageFlags, ViewportsOwned,
+    },
+    query::QueryType,
+    AliasScope, Api, Queue,
+};
+
+fn image() -> CapturedObject {
+    CapturedObject::Image {
+        scope: AliasScope::no_alias(),
+        format: Format::R8G8B8A8_UNORM,
+        dimensions: Dimensions::Dim2d {
+            width: 64,
+            height: 64,
+            array_layers: 1,
+        },
+        mipmaps: MipmapsOption::NoMipmap,
+        samples: 1,
+        usage: ImageUsageFlags::SAMPLED | ImageUsageFlags::COLOR_ATTACHMENT,
+        initial_data: None,
+    }
+}
+
+fn shader_module(stage: ShaderStageFlags, bytecode: &[u8]) -> CapturedObject {
+    CapturedObject::ShaderModule {
+        stage,
+        bytecode: bytecode.to_vec(),
+        // not known by the mock backend
+        num_fragment_outputs: 0,
+    }
+}
+
+/// A capture with every kind of command.
+///
+/// Objects are listed in the order in which the commands first reference them (dependencies
+/// first), which is the order of the objects in the frames recorded by the mock backend.
+fn capture() -> FrameCapture {
+    let buffer = ObjectRef(0);
+    let initialized_buffer = ObjectRef(1);
+    let image_a = ObjectRef(2);
+    let image_b = ObjectRef(3);
+    let pool = ObjectRef(4);
+    let signature = ObjectRef(5);
+    let graphics_pipeline = ObjectRef(8);
+    let arguments = ObjectRef(9);
+    let compute_pipeline = ObjectRef(11);
+    let swapchain = ObjectRef(12);
+    let region = ImageRegion::new((0, 0, 0), (16, 16, 1));
+
+    let objects = vec![
+        CapturedObject::Buffer {
+            size: 256,
+            initial_data: None,
+        },
+        CapturedObject::Buffer {
+            size: 4,
+            initial_data: Some(vec![1, 2, 3, 4]),
+        },
+        image(),
+        image(),
+        CapturedObject::QueryPool {
+            ty: QueryType::Occlusion,
+            count: 4,
+        },
+        CapturedObject::Signature {
+            inherited: Vec::new(),
+            description: CapturedSignatureDescription {
+                descriptors: Vec::new(),
+                vertex_inputs: Vec::new(),
+                num_fragment_outputs: 0,
+                depth_stencil_fragment_output: false,
+                index_format: None,
+                num_viewports: 0,
+                num_scissors: 0,
+                is_root_fragment_output_signature: false,
+                is_root_vertex_input_signature: false,
+            },
+        },
+        shader_module(ShaderStageFlags::VERTEX, &[0, 1, 2, 3]),
+        shader_module(ShaderStageFlags::FRAGMENT, &[4, 5, 6, 7]),
+        CapturedObject::GraphicsPipeline(CapturedGraphicsPipeline {
+            signature,
+            vertex_shader: ObjectRef(6),
+            geometry_shader: None,
+            fragment_shader: Some(ObjectRef(7)),
+            tess_eval_shader: None,
+            tess_control_shader: None,
+            viewports: ViewportsOwned::Dynamic,
+            scissors: ScissorsOwned::Dynamic,
+            rasterization_state: RasterisationState::default(),
+            multisample_state: MultisampleState::default(),
+            depth_stencil_state: DepthStencilState::default(),
+            input_assembly_state: InputAssemblyState::default(),
+            logic_op: None,
+            color_blend_attachments: CapturedColorBlendAttachments::All(
+                ColorBlendAttachmentState::DISABLED,
+            ),
+            blend_constants: ColorBlendState::DISABLED.blend_constants,
+        }),
+        CapturedObject::ArgumentBlock(CapturedArgumentBlock {
+            signature,
+            inherited: Vec::new(),
+            descriptors: Vec::new(),
+            vertex_buffers: Vec::new(),
+            index_buffer: None,
+            render_targets: Vec::new(),
+            depth_stencil_target: None,
+            viewports: Vec::new(),
+            scissors: Vec::new(),
+        }),
+        shader_module(ShaderStageFlags::COMPUTE, &[8, 9, 10, 11]),
+        CapturedObject::ComputePipeline {
+            signature,
+            shader: ObjectRef(10),
+        },
+        CapturedObject::Swapchain,
+    ];
+
+    let commands = vec![
+        CapturedCommandInner::PipelineBarrier {
+            src_access_mask: AccessFlags::TRANSFER_WRITE,
+            dst_access_mask: AccessFlags::TRANSFER_READ,
+        },
+        CapturedCommandInner::FillBuffer {
+            dst: buffer,
+            offset: 0,
+            size: 256,
+            value: 42,
+        },
+        CapturedCommandInner::CopyBuffer {
+            src: initialized_buffer,
+            src_offset: 0,
+            dst: buffer,
+            dst_offset: 4,
+            size: 4,
+        },
+        CapturedCommandInner::CopyBufferToImage {
+            src: buffer,
+            src_offset: 8,
+            dst: image_a,
+            dst_region: region,
+        },
+        CapturedCommandInner::CopyImageToBuffer {
+            src: image_a,
+            src_region: region,
+            dst: buffer,
+            dst_offset: 16,
+        },
+        CapturedCommandInner::CopyImage {
+            src: image_a,
+            src_region: region,
+            dst: image_b,
+            dst_region: region.mip_level(0),
+        },
+        CapturedCommandInner::ReadbackImage {
+            src: image_a,
+            src_region: region,
+        },
+        CapturedCommandInner::ReadbackBuffer {
+            src: buffer,
+            offset: 32,
+            size: 64,
+        },
+        CapturedCommandInner::BeginProfileScope {
+            name: "scope".to_string(),
+        },
+        CapturedCommandInner::EndProfileScope,
+        CapturedCommandInner::PushDebugGroup {
+            name: "group".to_string(),
+        },
+        CapturedCommandInner::InsertDebugMarker {
+            name: "marker".to_string(),
+        },
+        CapturedCommandInner::PopDebugGroup,
+        CapturedCommandInner::BeginQuery { pool, index: 1 },
+        CapturedCommandInner::EndQuery { pool, index: 1 },
+        CapturedCommandInner::ReadbackQueries {
+            pool,
+            first: 0,
+            count: 2,
+        },
+        CapturedCommandInner::BeginConditionalRendering { pool, index: 1 },
+        CapturedCommandInner::EndConditionalRendering,
+        CapturedCommandInner::BlitImage {
+            src: image_a,
+            src_mip_level: 0,
+            src_offsets: [(0, 0), (64, 64)],
+            dst: image_b,
+            dst_mip_level: 0,
+            dst_offsets: [(0, 0), (32, 32)],
+            filter: Filter::Linear,
+        },
+        CapturedCommandInner::ClearImageFloat {
+            image: image_b,
+            color: [0.0, 0.25, 0.5, 1.0],
+        },
+        CapturedCommandInner::ClearDepthStencilImage {
+            image: image_b,
+            depth: 1.0,
+            stencil: Some(3),
+        },
+        CapturedCommandInner::DrawHeader {
+            pipeline: graphics_pipeline,
+        },
+        CapturedCommandInner::SetPipelineArguments { arguments },
+        CapturedCommandInner::Draw {
+            vertex_count: 3,
+            instance_count: 2,
+            first_vertex: 1,
+            first_instance: 4,
+        },
+        CapturedCommandInner::DrawIndexed {
+            index_count: 6,
+            instance_count: 1,
+            first_index: 2,
+            vertex_offset: -1,
+            first_instance: 0,
+        },
+        CapturedCommandInner::DrawIndirect {
+            indirect_buffer: buffer,
+            offset: 0,
+            draw_count: 2,
+            count_buffer: Some(initialized_buffer),
+        },
+        CapturedCommandInner::DrawIndexedIndirect {
+            indirect_buffer: buffer,
+            offset: 64,
+            draw_count: 1,
+            count_buffer: None,
+        },
+        CapturedCommandInner::DispatchHeader {
+            pipeline: compute_pipeline,
+        },
+        CapturedCommandInner::SetPipelineArguments { arguments },
+        CapturedCommandInner::Dispatch { x: 8, y: 4, z: 1 },
+        CapturedCommandInner::DispatchIndirect {
+            indirect_buffer: buffer,
+            offset: 128,
+        },
+        CapturedCommandInner::Present {
+            image: image_a,
+            swapchain,
+        },
+    ];
+
+    FrameCapture {
+        objects,
+        commands: commands
+            .into_iter()
+            .enumerate()
+            .map(|(i, cmd)| CapturedCommand {
+                sortkey: i as u64,
+                queue: Queue::Graphics,
+                cmd,
+            })
+            .collect(),
+    }
+}
+
+/// Does not compile if a command is added: add it to [capture] as well.
+#[allow(dead_code)]
+fn every_command_is_captured(cmd: &CapturedCommandInner) {
+    match cmd {
+        CapturedCommandInner::PipelineBarrier { .. }
+        | CapturedCommandInner::CopyBuffer { .. }
+        | CapturedCommandInner::CopyBufferToImage { .. }
+        | CapturedCommandInner::CopyImageToBuffer { .. }
+        | CapturedCommandInner::CopyImage { .. }
+        | CapturedCommandInner::FillBuffer { .. }
+        | CapturedCommandInner::ReadbackImage { .. }
+        | CapturedCommandInner::ReadbackBuffer { .. }
+        | CapturedCommandInner::BeginProfileScope { .. }
+        | CapturedCommandInner::EndProfileScope
+        | CapturedCommandInner::PushDebugGroup { .. }
+        | CapturedCommandInner::PopDebugGroup
+        | CapturedCommandInner::InsertDebugMarker { .. }
+        | CapturedCommandInner::BeginQuery { .. }
+        | CapturedCommandInner::EndQuery { .. }
+        | CapturedCommandInner::ReadbackQueries { .. }
+        | CapturedCommandInner::BeginConditionalRendering { .. }
+        | CapturedCommandInner::EndConditionalRendering
+        | CapturedCommandInner::BlitImage { .. }
+        | CapturedCommandInner::ClearImageFloat { .. }
+        | CapturedCommandInner::ClearDepthStencilImage { .. }
+        | CapturedCommandInner::Present { .. }
+        | CapturedCommandInner::DrawHeader { .. }
+        | CapturedCommandInner::DispatchHeader { .. }
+        | CapturedCommandInner::SetPipelineArguments { .. }
+        | CapturedCommandInner::Draw { .. }
+        | CapturedCommandInner::DrawIndexed { .. }
+        | CapturedCommandInner::DrawIndirect { .. }
+        | CapturedCommandInner::DrawIndexedIndirect { .. }
+        | CapturedCommandInner::Dispatch { .. }
+        | CapturedCommandInner::DispatchIndirect { .. } => {}
+    }
+}
+
+#[test]
+fn test_capture_round_trip() {
+    let capture = capture();
+
+    let mut bytes = Vec::new();
+    capture.write_to(&mut bytes).unwrap();
+    let read = FrameCapture::read_from(&mut &bytes[..]).unwrap();
+    assert_eq!(format!("{:?}", read), format!("{:?}", capture));
+
+    // the mock backend records the replayed frame as a capture
+    let api = Api::<MockBackend>::new(MockInstance::new()).unwrap();
+    let arena = api.create_arena().unwrap();
+    replay(&arena, &read).unwrap();
+    let replayed = api.instance().last_frame().unwrap();
+    assert_eq!(format!("{:?}", replayed), format!("{:?}", capture));
+}
+
+#[test]
+fn test_capture_invalid() {
+    let mut bytes = Vec::new();
+    capture().write_to(&mut bytes).unwrap();
+
+    // unknown version
+    let mut other_version = bytes.clone();
+    other_version[8] = other_version[8].wrapping_add(1);
+    assert!(FrameCapture::read_from(&mut &other_version[..]).is_err());
+
+    // truncated
+    assert!(FrameCapture::read_from(&mut &bytes[..bytes.len() - 1]).is_err());
+
+    // reference to an object that comes later
+    let mut capture = capture();
+    capture.objects.swap(5, 8);
+    let mut bytes = Vec::new();
+    capture.write_to(&mut bytes).unwrap();
+    assert!(FrameCapture::read_from(&mut &bytes[..]).is_err());
+}
+
+#[test]
+fn test_capture_wrong_object_kind() {
+    let api = Api::<MockBackend>::new(MockInstance::new()).unwrap();
+    let arena = api.create_arena().unwrap();
+
+    // a command that reads an image as a buffer
+    let mut wrong_command = capture();
+    wrong_command.commands.push(CapturedCommand {
+        sortkey: wrong_command.commands.len() as u64,
+        queue: Queue::Graphics,
+        cmd: CapturedCommandInner::ReadbackBuffer {
+            src: ObjectRef(2),
+            offset: 0,
+            size: 4,
+        },
+    });
+    match replay(&arena, &wrong_command) {
+        Err(Error::Validation { .. }) => {}
+        other => panic!("expected a validation error, got {:?}", other),
+    }
+
+    // a pipeline whose signature is a buffer
+    let mut wrong_object = capture();
+    wrong_object.objects.truncate(12);
+    if let CapturedObject::ComputePipeline { signature, .. } = &mut wrong_object.objects[11] {
+        *signature = ObjectRef(0);
+    } else {
+        panic!("object 11 is not a compute pipeline");
+    }
+    wrong_object.commands.clear();
+    match replay(&arena, &wrong_object) {
+        Err(Error::Validation { .. }) => {}
+        other => panic!("expected a validation error, got {:?}", other),
+    }
+}
@@ -0,0 +1,94 @@
+//! OpenGL backend tests
+use autograph_api::{
+    error::Error,
+    format::Format,
+    image::{Image2dView, ImageRegion},
+    pipeline::{
+        ComputePipelineCreateInfo, ReflectedShader, ShaderStageFlags, ShaderStageReflection,
+    },
+};
+use autograph_api_test::{with_test_fixture, NoArguments};
+use std::time::Duration;
+
+static COMPUTE_REFLECTION: ShaderStageReflection = ShaderStageReflection {
+    stage: ShaderStageFlags::COMPUTE,
+    descriptors: &[],
+    vertex_input_attributes: &[],
+    fragment_outputs: &[],
+};
+
+const GLSL_COMPUTE: &str = "#version 450\nlayout(local_size_x = 1) in;\nvoid main() {}\n";
+
+#[test]
+fn test_gl_glsl_pipeline_error() {
+    with_test_fixture("test_gl_glsl_pipeline_error", Some(1), |_, arena, _| {
+        let shader_stage = arena
+            .create_shader_module(ReflectedShader {
+                bytecode: GLSL_COMPUTE.as_bytes(),
+                reflection: &COMPUTE_REFLECTION,
+            })
+            .unwrap();
+        // GLSL modules compile, but there is no reflection to build the pipeline from
+        match arena
+            .create_compute_pipeline::<NoArguments>(&ComputePipelineCreateInfo { shader_stage })
+            .err()
+        {
+            Some(Error::ShaderLink { .. }) => {}
+            other => panic!("expected a link error, got {:?}", other),
+        }
+    })
+}
+
+#[test]
+fn test_gl_array_layer_copies() {
+    with_test_fixture(
+        "test_gl_array_layer_copies",
+        Some(1),
+        |renderer, arena, _| {
+            let image = arena
+                .image_2d(Format::R8G8B8A8_UNORM, 4, 4)
+                .array_layers(3)
+                .build()
+                .unwrap();
+            let view = Image2dView::from(image);
+            let layer_size = 4 * 4 * 4;
+            let data: Vec<u8> = (0..3 * layer_size)
+                .map(|i| (i / layer_size) as u8)
+                .collect();
+            let src = arena.upload_slice(&data).unwrap();
+            let dst = arena.create_buffer_typeless(2 * layer_size as u64).unwrap();
+            let region = ImageRegion::new((0, 0, 0), (4, 4, 1));
+
+            let mut cmdbuf = renderer.create_command_buffer();
+            cmdbuf.copy_buffer_to_image(0, src, 0, view, region.array_layers(0, 3));
+            let layer = cmdbuf.readback_image(1, view, region.array_layers(2, 1));
+            cmdbuf.copy_image_to_buffer(2, view, region.array_layers(1, 2), dst, 0);
+            let layers = cmdbuf.readback_buffer(3, dst, 0, 2 * layer_size);
+            renderer.submit_frame(vec![cmdbuf]);
+
+            let timeout = Duration::from_secs(5);
+            let layer = renderer.wait_readback(&layer, timeout).unwrap();
+            assert_eq!(&layer[..], &data[2 * layer_size..]);
+            let layers = renderer.wait_readback(&layers, timeout).unwrap();
+            assert_eq!(&layers[..], &data[layer_size..]);
+        },
+    )
+}
+
+#[test]
+fn test_gl_multisampled_array_image() {
+    with_test_fixture(
+        "test_gl_multisampled_array_image",
+        Some(1),
+        |_, arena, _| match arena
+            .image_2d(Format::R8G8B8A8_UNORM, 4, 4)
+            .array_layers(2)
+            .samples(4)
+            .build()
+            .err()
+        {
+            Some(Error::Validation { .. }) => {}
+            other => panic!("expected a validation error, got {:?}", other),
+        },
+    )
+}
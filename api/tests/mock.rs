@@ -0,0 +1,503 @@
+//! mock backend tests
+use autograph_api::{
+    capture::{CapturedCommandInner, CapturedObject},
+    dynamic::{into_dyn_instance, DynBackend, DynWindow},
+    error::Error,
+    format::Format,
+    image::{Image2d, Image2dView, ImageRegion},
+    mock::{MockBackend, MockInstance, MockObject, MockWindow},
+    pipeline::{
+        ColorBlendState, ComputePipelineCreateInfo, DepthStencilState, DynamicSignatureBuilder,
+        FragmentOutputDescription, GraphicsPipelineCreateInfo, InputAssemblyState,
+        MultisampleState, PrimitiveTopology, RasterisationState, ReflectedShader, ShaderStageFlags,
+        ShaderStageReflection, Signature, VertexInputBinding, ViewportState,
+    },
+    query::{query_results, PipelineStatisticsFlags, QueryType},
+    ring::UploadRing,
+    validation::{ValidatingBackend, ValidatingInstance},
+    vertex::{IndexFormat, Semantic, VertexInputRate, VertexLayout, VertexLayoutElement},
+    Api, Arena, Queue,
+};
+use autograph_api_test::NoArguments;
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+#[test]
+fn test_mock_submit_frame() {
+    let api = Api::<MockBackend>::new(MockInstance::new()).unwrap();
+    let arena = api.create_arena().unwrap();
+    let image = arena
+        .image_2d(Format::R8G8B8A8_UNORM, 640, 480)
+        .build()
+        .unwrap();
+    let buffer = arena.create_buffer_typeless(256).unwrap();
+
+    match api.instance().object(image.inner()) {
+        Some(CapturedObject::Image { format, .. }) => assert_eq!(format, Format::R8G8B8A8_UNORM),
+        other => panic!("unexpected object: {:?}", other),
+    }
+
+    let mut cmdbuf = api.create_command_buffer();
+    cmdbuf.present(2, image, api.default_swapchain().unwrap());
+    cmdbuf.fill_buffer(0, buffer, 0, 256, 0);
+    cmdbuf.clear_render_target(1, image.render_target_view(), &[0.0; 4]);
+    api.submit_frame(vec![cmdbuf]);
+
+    assert_eq!(api.instance().frame_count(), 1);
+    let frame = api.instance().last_frame().unwrap();
+    let commands: Vec<_> = frame
+        .commands
+        .iter()
+        .filter(|cmd| match cmd.cmd {
+            CapturedCommandInner::PipelineBarrier { .. } => false,
+            _ => true,
+        })
+        .map(|cmd| cmd.sortkey)
+        .collect();
+    assert_eq!(commands, [0, 1, 2]);
+    // swapchain, image and buffer
+    assert_eq!(frame.objects.len(), 3);
+}
+
+#[test]
+fn test_mock_readback() {
+    let api = Api::<MockBackend>::new(MockInstance::new()).unwrap();
+    let arena = api.create_arena().unwrap();
+    let image = arena
+        .image_2d(Format::R8G8B8A8_UNORM, 64, 32)
+        .build()
+        .unwrap();
+    let buffer = arena.create_buffer_typeless(256).unwrap();
+
+    let mut cmdbuf = api.create_command_buffer();
+    let image_readback = cmdbuf.readback_image(
+        0,
+        Image2dView::from(image),
+        ImageRegion::new((0, 0, 0), (16, 8, 1)),
+    );
+    let buffer_readback = cmdbuf.readback_buffer(1, buffer, 64, 128);
+    let called = Arc::new(Mutex::new(None));
+    let called2 = called.clone();
+    buffer_readback.on_complete(move |data| *called2.lock().unwrap() = Some(data.len()));
+    assert!(!image_readback.is_ready());
+    api.submit_frame(vec![cmdbuf]);
+
+    let data = api
+        .wait_readback(&image_readback, Duration::from_secs(0))
+        .unwrap();
+    assert_eq!(data.len(), 16 * 8 * 4);
+    assert_eq!(buffer_readback.try_get().unwrap().len(), 128);
+    assert_eq!(*called.lock().unwrap(), Some(128));
+
+    // never submitted: returns without waiting for the timeout
+    let mut cmdbuf = api.create_command_buffer();
+    let readback = cmdbuf.readback_buffer(0, buffer, 0, 64);
+    drop(cmdbuf);
+    let start = Instant::now();
+    assert!(api
+        .wait_readback(&readback, Duration::from_secs(60))
+        .is_none());
+    assert!(start.elapsed() < Duration::from_secs(60));
+}
+
+#[test]
+fn test_mock_upload_ring() {
+    let api = Api::<MockBackend>::new(MockInstance::new()).unwrap();
+    let arena = api.create_arena().unwrap();
+    let ring = UploadRing::new(&arena, 512, 2).unwrap();
+
+    ring.upload(&[1.0f32; 4]).unwrap();
+    ring.upload(&[2.0f32; 4]).unwrap();
+    assert!(ring.upload(&[3.0f32; 4]).is_err());
+    api.submit_frame(Vec::new());
+    ring.next_frame();
+    ring.upload(&[4.0f32; 4]).unwrap();
+    ring.next_frame();
+    ring.next_frame();
+    // the segment is used by the current frame, which is not submitted yet
+    assert!(ring.upload(&[5.0f32; 4]).is_err());
+    api.submit_frame(Vec::new());
+    ring.upload(&[6.0f32; 4]).unwrap();
+
+    let offsets: Vec<_> = api
+        .instance()
+        .buffer_writes()
+        .iter()
+        .map(|w| (w.offset, w.data.len()))
+        .collect();
+    assert_eq!(offsets, [(0, 16), (256, 16), (512, 16), (512, 16)]);
+}
+
+#[test]
+fn test_mock_queue_batches() {
+    let api = Api::<MockBackend>::new(MockInstance::new()).unwrap();
+    let arena = api.create_arena().unwrap();
+    let image = arena
+        .image_2d(Format::R8G8B8A8_UNORM, 64, 64)
+        .build()
+        .unwrap();
+
+    let mut cmdbuf = api.create_command_buffer();
+    cmdbuf.clear_render_target(0, image.render_target_view(), &[0.0; 4]);
+    cmdbuf.readback_image(
+        1,
+        Image2dView::from(image),
+        ImageRegion::new((0, 0, 0), (64, 64, 1)),
+    );
+    cmdbuf.present(2, image, api.default_swapchain().unwrap());
+    api.submit_frame(vec![cmdbuf]);
+
+    // clear | barrier, readback | present
+    let batches: Vec<_> = api
+        .instance()
+        .last_batches()
+        .into_iter()
+        .map(|b| (b.queue, b.commands, b.waits))
+        .collect();
+    assert_eq!(
+        batches,
+        [
+            (Queue::Graphics, 0..1, vec![]),
+            (Queue::Transfer, 1..3, vec![0]),
+            (Queue::Graphics, 3..4, vec![]),
+        ]
+    );
+}
+
+#[test]
+fn test_mock_profile_scopes() {
+    let api = Api::<MockBackend>::new(MockInstance::new()).unwrap();
+    let arena = api.create_arena().unwrap();
+    let buffer = arena.create_buffer_typeless(256).unwrap();
+    assert!(api.frame_profile().is_none());
+
+    // recorded out of order: scopes nest according to the sorted stream
+    let mut cmdbuf = api.create_command_buffer();
+    cmdbuf.begin_profile_scope(1, "inner");
+    cmdbuf.fill_buffer(1, buffer, 0, 256, 0);
+    cmdbuf.end_profile_scope(2);
+    cmdbuf.begin_profile_scope(0, "outer");
+    cmdbuf.end_profile_scope(3);
+    api.submit_frame(vec![cmdbuf]);
+
+    let profile = api.frame_profile().unwrap();
+    assert_eq!(profile.frame, 1);
+    assert_eq!(profile.scopes.len(), 1);
+    let outer = &profile.scopes[0];
+    assert_eq!(outer.name, "outer");
+    assert_eq!(outer.duration, Duration::from_nanos(4));
+    assert_eq!(outer.children.len(), 1);
+    assert_eq!(outer.children[0].name, "inner");
+    assert_eq!(outer.children[0].duration, Duration::from_nanos(2));
+}
+
+#[test]
+fn test_mock_query_readback() {
+    let api = Api::<MockBackend>::new(MockInstance::new()).unwrap();
+    let arena = api.create_arena().unwrap();
+    let flags = PipelineStatisticsFlags::VERTEX_SHADER_INVOCATIONS
+        | PipelineStatisticsFlags::FRAGMENT_SHADER_INVOCATIONS;
+    let queries = arena
+        .create_query_pool(QueryType::PipelineStatistics(flags), 2)
+        .unwrap();
+    assert_eq!(queries.count(), 2);
+
+    let mut cmdbuf = api.create_command_buffer();
+    cmdbuf.begin_query(0, queries, 1);
+    cmdbuf.end_query(1, queries, 1);
+    let readback = cmdbuf.readback_queries(2, queries, 0, 2);
+    api.submit_frame(vec![cmdbuf]);
+
+    // two queries, two counters each
+    let data = readback.try_get().unwrap();
+    assert_eq!(data.len(), 32);
+    assert_eq!(query_results(&data), [0; 4]);
+}
+
+#[test]
+fn test_mock_debug_labels() {
+    let api = Api::<MockBackend>::new(MockInstance::new()).unwrap();
+    let arena = api.create_arena().unwrap();
+    let image = arena
+        .image_2d(Format::R8G8B8A8_UNORM, 64, 64)
+        .debug_name("color")
+        .build()
+        .unwrap();
+    let buffer = arena.create_buffer_typeless(256).unwrap();
+    api.set_debug_name(buffer, "constants");
+    let instance = api.instance();
+    assert_eq!(instance.debug_name(image.inner()).unwrap(), "color");
+    assert_eq!(instance.debug_name(buffer.0).unwrap(), "constants");
+
+    let mut cmdbuf = api.create_command_buffer();
+    cmdbuf.pop_debug_group(2);
+    cmdbuf.fill_buffer(1, buffer, 0, 256, 0);
+    cmdbuf.push_debug_group(0, "fill");
+    api.submit_frame(vec![cmdbuf]);
+
+    let frame = api.instance().last_frame().unwrap();
+    match frame.commands[0].cmd {
+        CapturedCommandInner::PushDebugGroup { ref name } => assert_eq!(name, "fill"),
+        ref other => panic!("unexpected command: {:?}", other),
+    }
+}
+
+#[test]
+fn test_mock_multiple_swapchains() {
+    let api = Api::<MockBackend>::new(MockInstance::new()).unwrap();
+    let arena = api.create_arena().unwrap();
+    let swapchain = arena
+        .create_swapchain(&MockWindow { size: (320, 200) })
+        .unwrap();
+    assert_eq!(swapchain.size(), (320, 200));
+    let image = arena
+        .image_2d(Format::R8G8B8A8_UNORM, 640, 480)
+        .build()
+        .unwrap();
+
+    let mut cmdbuf = api.create_command_buffer();
+    cmdbuf.present(0, image, api.default_swapchain().unwrap());
+    cmdbuf.present(1, image, swapchain);
+    api.submit_frame(vec![cmdbuf]);
+
+    let frame = api.instance().last_frame().unwrap();
+    let presents = frame
+        .commands
+        .iter()
+        .filter(|cmd| match cmd.cmd {
+            CapturedCommandInner::Present { .. } => true,
+            _ => false,
+        })
+        .count();
+    assert_eq!(presents, 2);
+    // both swapchains and the image
+    assert_eq!(frame.objects.len(), 3);
+}
+
+#[test]
+fn test_mock_swapchain_resize() {
+    let api = Api::<MockBackend>::new(MockInstance::new()).unwrap();
+    let swapchain = api.default_swapchain().unwrap();
+    let generation = swapchain.generation();
+
+    swapchain.0.resize((640, 480));
+    assert_eq!(swapchain.generation(), generation);
+    swapchain.0.resize((800, 600));
+    assert_eq!(swapchain.generation(), generation + 1);
+    assert_eq!(swapchain.size(), (800, 600));
+}
+
+#[test]
+fn test_mock_dyn_backend() {
+    let api =
+        Api::<DynBackend>::new(into_dyn_instance::<MockBackend>(MockInstance::new())).unwrap();
+    let arena = api.create_arena().unwrap();
+    let swapchain = arena
+        .create_swapchain(&DynWindow::new(MockWindow { size: (320, 200) }))
+        .unwrap();
+    assert_eq!(swapchain.size(), (320, 200));
+    assert!(arena.create_swapchain(&DynWindow::new(())).is_err());
+    let image = arena
+        .image_2d(Format::R8G8B8A8_UNORM, 640, 480)
+        .build()
+        .unwrap();
+
+    let mut cmdbuf = api.create_command_buffer();
+    cmdbuf.present(0, image, api.default_swapchain().unwrap());
+    cmdbuf.present(1, image, swapchain);
+    api.submit_frame(vec![cmdbuf]);
+}
+
+#[test]
+fn test_mock_dynamic_signature_cache() {
+    let api = Api::<MockBackend>::new(MockInstance::new()).unwrap();
+    let create = || {
+        // the layout only lives as long as the builder
+        let name = String::from("POSITION");
+        let elements = [VertexLayoutElement {
+            semantic: Some(Semantic {
+                name: &name,
+                index: 0,
+            }),
+            format: Format::R32G32_SFLOAT,
+            offset: 0,
+        }];
+        api.get_cached_dynamic_signature(DynamicSignatureBuilder::new().vertex_input(
+            VertexInputBinding {
+                layout: VertexLayout {
+                    elements: &elements,
+                    stride: 8,
+                },
+                rate: VertexInputRate::Vertex,
+                base_location: None,
+            },
+        ))
+        .unwrap()
+    };
+    let a = create();
+    let b = create();
+    assert_eq!(a.inner() as *const _, b.inner() as *const _);
+    assert_eq!(a.description().vertex_inputs[0].layout.elements.len(), 1);
+
+    let c = api
+        .get_cached_dynamic_signature(DynamicSignatureBuilder::new().inherited(&a))
+        .unwrap();
+    assert_ne!(a.inner() as *const _, c.inner() as *const _);
+
+    // inherited signatures must be owned by the API
+    let arena = api.create_arena().unwrap();
+    let d = DynamicSignatureBuilder::new()
+        .index_format(IndexFormat::U16)
+        .build(&arena)
+        .unwrap();
+    assert!(api
+        .get_cached_dynamic_signature(DynamicSignatureBuilder::new().inherited(&d))
+        .is_err());
+}
+
+#[test]
+#[should_panic(expected = "submit_frame")]
+fn test_mock_use_after_drop() {
+    let api = Api::<ValidatingBackend<MockBackend>>::new(
+        ValidatingInstance::with_use_after_drop_detection(MockInstance::new()),
+    )
+    .unwrap();
+    let image = {
+        let arena = api.create_arena().unwrap();
+        let image = arena
+            .image_2d(Format::R8G8B8A8_UNORM, 640, 480)
+            .build()
+            .unwrap();
+        image.inner() as *const _
+    };
+
+    // recreated from a raw reference after the arena was dropped
+    let image = unsafe { Image2d::from_raw(&*image) };
+    let mut cmdbuf = api.create_command_buffer();
+    cmdbuf.present(0, image, api.default_swapchain().unwrap());
+    api.submit_frame(vec![cmdbuf]);
+}
+
+static VERTEX_REFLECTION: ShaderStageReflection = ShaderStageReflection {
+    stage: ShaderStageFlags::VERTEX,
+    descriptors: &[],
+    vertex_input_attributes: &[],
+    fragment_outputs: &[],
+};
+
+static FRAGMENT_REFLECTION: ShaderStageReflection = ShaderStageReflection {
+    stage: ShaderStageFlags::FRAGMENT,
+    descriptors: &[],
+    vertex_input_attributes: &[],
+    fragment_outputs: &[FragmentOutputDescription {}],
+};
+
+/// Creates a pipeline with new shader modules, returns its address.
+fn create_pipeline(
+    arena: &Arena<MockBackend>,
+    fragment_bytecode: &[u8],
+    primitive_restart_enable: bool,
+) -> *const MockObject {
+    let create_info = GraphicsPipelineCreateInfo {
+        shader_stages: arena
+            .create_vertex_fragment_shader_stages(
+                ReflectedShader {
+                    bytecode: &[0, 1, 2, 3],
+                    reflection: &VERTEX_REFLECTION,
+                },
+                ReflectedShader {
+                    bytecode: fragment_bytecode,
+                    reflection: &FRAGMENT_REFLECTION,
+                },
+            )
+            .unwrap(),
+        viewport_state: ViewportState::default(),
+        rasterization_state: RasterisationState::default(),
+        multisample_state: MultisampleState::default(),
+        depth_stencil_state: DepthStencilState::default(),
+        input_assembly_state: InputAssemblyState {
+            topology: PrimitiveTopology::TriangleList,
+            primitive_restart_enable,
+        },
+        color_blend_state: ColorBlendState::DISABLED,
+    };
+    arena
+        .create_graphics_pipeline::<NoArguments>(&create_info)
+        .unwrap()
+        .inner() as *const _
+}
+
+#[test]
+fn test_mock_pipeline_cache() {
+    let api = Api::<MockBackend>::new(MockInstance::new()).unwrap();
+    let arena = api.create_arena().unwrap();
+
+    // identical parameters, in different shader modules
+    let a = create_pipeline(&arena, &[4, 5, 6, 7], false);
+    let b = create_pipeline(&arena, &[4, 5, 6, 7], false);
+    assert_eq!(a, b);
+    let stats = api.pipeline_cache_stats();
+    assert_eq!((stats.hits, stats.misses), (1, 1));
+
+    // different bytecode or states
+    assert_ne!(create_pipeline(&arena, &[4, 5, 6, 8], false), a);
+    assert_ne!(create_pipeline(&arena, &[4, 5, 6, 7], true), a);
+    let stats = api.pipeline_cache_stats();
+    assert_eq!((stats.hits, stats.misses), (1, 3));
+
+    // pipelines are not shared between arenas
+    let other_arena = api.create_arena().unwrap();
+    assert_ne!(create_pipeline(&other_arena, &[4, 5, 6, 7], false), a);
+    let stats = api.pipeline_cache_stats();
+    assert_eq!((stats.hits, stats.misses, stats.pipelines), (1, 4, 4));
+
+    drop(other_arena);
+    assert_eq!(api.pipeline_cache_stats().pipelines, 3);
+    drop(arena);
+    assert_eq!(api.pipeline_cache_stats().pipelines, 0);
+}
+
+#[test]
+fn test_mock_invalid_shader_stage() {
+    let api = Api::<MockBackend>::new(MockInstance::new()).unwrap();
+    let arena = api.create_arena().unwrap();
+
+    // vertex and fragment shaders swapped
+    match arena
+        .create_vertex_fragment_shader_stages(
+            ReflectedShader {
+                bytecode: &[4, 5, 6, 7],
+                reflection: &FRAGMENT_REFLECTION,
+            },
+            ReflectedShader {
+                bytecode: &[0, 1, 2, 3],
+                reflection: &VERTEX_REFLECTION,
+            },
+        )
+        .err()
+    {
+        Some(Error::Validation { .. }) => {}
+        other => panic!("expected a validation error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_mock_compute_pipeline_stage() {
+    let api = Api::<MockBackend>::new(MockInstance::new()).unwrap();
+    let arena = api.create_arena().unwrap();
+    let shader_stage = arena
+        .create_shader_module(ReflectedShader {
+            bytecode: &[0, 1, 2, 3],
+            reflection: &VERTEX_REFLECTION,
+        })
+        .unwrap();
+    match arena
+        .create_compute_pipeline::<NoArguments>(&ComputePipelineCreateInfo { shader_stage })
+        .err()
+    {
+        Some(Error::Validation { .. }) => {}
+        other => panic!("expected a validation error, got {:?}", other),
+    }
+}
@@ -0,0 +1,66 @@
+//! command sorting tests
+use autograph_api::{
+    command::{
+        merge_recorded_commands, sort_command_buffers, Command, CommandInner, RecordedCommands,
+    },
+    mock::{MockBackend, MockInstance},
+    Api,
+};
+
+/// Returns the name of a debug marker.
+fn marker<'a>(cmd: &Command<'a, MockBackend>) -> &'a str {
+    match cmd.cmd {
+        CommandInner::InsertDebugMarker { name } => name,
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn test_sort_stable() {
+    let api = Api::<MockBackend>::new(MockInstance::new()).unwrap();
+    let mut a = api.create_command_buffer();
+    let mut b = api.create_command_buffer();
+    a.insert_debug_marker(1 << 40, "0");
+    a.insert_debug_marker(0, "1");
+    a.insert_debug_marker(1 << 40, "2");
+    b.insert_debug_marker(0, "3");
+    b.insert_debug_marker(1 << 40, "4");
+    b.insert_debug_marker(0x100, "5");
+
+    let sorted: Vec<_> = sort_command_buffers(vec![a, b])
+        .iter()
+        .map(marker)
+        .collect();
+    assert_eq!(sorted, ["1", "3", "5", "0", "2", "4"]);
+}
+
+#[test]
+fn test_merge_recorded() {
+    let api = Api::<MockBackend>::new(MockInstance::new()).unwrap();
+    let mut a = api.create_command_buffer();
+    a.insert_debug_marker(2, "0");
+    a.insert_debug_marker(0, "1");
+    let recorded = RecordedCommands::new(a);
+
+    let mut b = api.create_command_buffer();
+    b.insert_debug_marker(0x102, "2");
+    b.insert_debug_marker(1, "3");
+    let commands = sort_command_buffers(vec![b]);
+
+    // the same commands submitted twice, at different offsets
+    let merged: Vec<_> =
+        merge_recorded_commands(commands, vec![(&recorded, 0x100), (&recorded, 0)])
+            .map(|cmd| (cmd.sortkey, marker(&cmd)))
+            .collect();
+    assert_eq!(
+        merged,
+        [
+            (0, "1"),
+            (1, "3"),
+            (2, "0"),
+            (0x100, "1"),
+            (0x102, "2"),
+            (0x102, "0")
+        ]
+    );
+}
@@ -0,0 +1,71 @@
+//! structured sortkey tests
+use autograph_api::{sortkey::SortKeyLayout, AliasScope};
+
+fn layout() -> SortKeyLayout {
+    SortKeyLayout::new(&[("view", 4), ("pass", 8), ("depth", 24), ("material", 16)])
+}
+
+#[test]
+fn test_sortkey_set_get() {
+    let layout = layout();
+    let key = layout
+        .key()
+        .set("view", 15)
+        .set("pass", 2)
+        .set("material", 0xffff)
+        .build();
+    assert_eq!(key, (15 << 60) | (2 << 52) | (0xffff << 12));
+    assert_eq!(layout.get(key, "view"), 15);
+    assert_eq!(layout.get(key, "pass"), 2);
+    assert_eq!(layout.get(key, "depth"), 0);
+    assert_eq!(layout.get(key, "material"), 0xffff);
+
+    // setting a field twice replaces the value
+    let key = layout.key().set("pass", 0xff).set("pass", 1).build();
+    assert_eq!(layout.get(key, "pass"), 1);
+}
+
+#[test]
+#[should_panic(expected = "does not fit")]
+fn test_sortkey_value_too_wide() {
+    layout().key().set("view", 16);
+}
+
+#[test]
+fn test_sortkey_display() {
+    let layout = layout();
+    let key = layout.key().set("view", 1).set("depth", 1000).build();
+    assert_eq!(
+        layout.display(key).to_string(),
+        "view=1 pass=0 depth=1000 material=0"
+    );
+}
+
+#[test]
+fn test_sortkey_alias_scope() {
+    let layout = layout();
+    let scope = layout.key().set("view", 1).set("pass", 2).alias_scope();
+    assert!(scope.contains(layout.key().set("view", 1).set("pass", 2).build()));
+    assert!(scope.contains(
+        layout
+            .key()
+            .set("view", 1)
+            .set("pass", 2)
+            .set("depth", 1000)
+            .set("material", 3)
+            .build()
+    ));
+    assert!(!scope.contains(layout.key().set("view", 0).set("pass", 2).build()));
+    assert!(!scope.contains(layout.key().set("view", 1).set("pass", 3).build()));
+
+    let view = layout.key().set("view", 1).alias_scope();
+    assert!(view.overlaps(&scope));
+    assert!(!view.overlaps(&layout.key().set("view", 2).alias_scope()));
+    assert_eq!(layout.key().alias_scope(), AliasScope::no_alias());
+}
+
+#[test]
+#[should_panic(expected = "more significant field `view`")]
+fn test_sortkey_alias_scope_not_contiguous() {
+    layout().key().set("pass", 2).alias_scope();
+}
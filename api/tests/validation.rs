@@ -1,7 +1,267 @@
 //! validation tests
+use autograph_api::{
+    command::{Command, CommandInner},
+    descriptor::{Descriptor, ResourceBinding, ResourceBindingType},
+    error::{Error, Result},
+    format::Format,
+    image::{Image2d, Image2dView, ImageRegion, ImageUsageFlags, RenderTargetView},
+    mock::{MockBackend, MockInstance},
+    pipeline::{DynamicSignatureBuilder, FragmentOutputDescription, ShaderStageFlags},
+    query::QueryType,
+    readback::Readback,
+    validation::{ValidatingBackend, ValidatingInstance},
+    Api, Instance, Queue,
+};
 use autograph_api_test::with_test_fixture;
+use std::{
+    iter,
+    panic::{self, AssertUnwindSafe},
+};
+
+type Backend = ValidatingBackend<MockBackend>;
+
+fn cmd(cmd: CommandInner<Backend>) -> Command<Backend> {
+    Command {
+        sortkey: 0,
+        queue: Queue::Graphics,
+        cmd,
+    }
+}
+
+/// Runs `f`, and returns the description of the validation errors that made it panic (empty if
+/// it did not panic).
+fn validation_errors(f: impl FnOnce()) -> String {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(()) => String::new(),
+        Err(e) => *e.downcast::<String>().unwrap(),
+    }
+}
+
+/// Submits the commands directly to the instance, and returns the description of the validation
+/// errors.
+fn submit_errors(api: &Api<Backend>, commands: &[Command<Backend>]) -> String {
+    validation_errors(|| unsafe { api.instance().submit_frame(commands, &[]) })
+}
+
+/// Returns the description of the validation errors of an object creation call.
+fn creation_errors<T>(result: Result<T>) -> String {
+    match result {
+        Ok(_) => String::new(),
+        Err(Error::Validation { message }) => message,
+        Err(other) => panic!("unexpected error: {}", other),
+    }
+}
 
 #[test]
 fn test_simple() {
     with_test_fixture("test_simple", Some(60), |r, a, run| {})
 }
+
+#[test]
+fn test_validation_indirect_bounds() {
+    let api = Api::<Backend>::new(ValidatingInstance::new(MockInstance::new())).unwrap();
+    let arena = api.create_arena().unwrap();
+    // 3 `DrawParams`, 2 `DrawIndexedParams`, 2 `DispatchParams`
+    let draws = arena.create_buffer_typeless(48).unwrap().0;
+    let indexed_draws = arena.create_buffer_typeless(40).unwrap().0;
+    let dispatches = arena.create_buffer_typeless(24).unwrap().0;
+    let count = arena.create_buffer_typeless(2).unwrap().0;
+
+    let draw = |offset, draw_count, count_buffer| {
+        let errors = submit_errors(
+            &api,
+            &[cmd(CommandInner::DrawIndirect {
+                indirect_buffer: draws,
+                offset,
+                draw_count,
+                count_buffer,
+            })],
+        );
+        errors.contains("indirect draw")
+    };
+    assert!(!draw(16, 2, None));
+    assert!(draw(16, 3, None));
+    assert!(draw(0, 1, Some(count)));
+
+    let draw_indexed = |offset, draw_count| {
+        let errors = submit_errors(
+            &api,
+            &[cmd(CommandInner::DrawIndexedIndirect {
+                indirect_buffer: indexed_draws,
+                offset,
+                draw_count,
+                count_buffer: None,
+            })],
+        );
+        errors.contains("indirect draw parameters: range 20..")
+    };
+    assert!(!draw_indexed(20, 1));
+    assert!(draw_indexed(20, 2));
+
+    let dispatch = |offset| {
+        let errors = submit_errors(
+            &api,
+            &[cmd(CommandInner::DispatchIndirect {
+                indirect_buffer: dispatches,
+                offset,
+            })],
+        );
+        errors.contains("indirect dispatch parameters")
+    };
+    assert!(!dispatch(12));
+    assert!(dispatch(16));
+    // the end of the range overflows
+    assert!(dispatch(u64::max_value() - 3));
+}
+
+#[test]
+fn test_validation_query_range() {
+    let api = Api::<Backend>::new(ValidatingInstance::new(MockInstance::new())).unwrap();
+    let arena = api.create_arena().unwrap();
+    let pool = arena.create_query_pool(QueryType::Occlusion, 4).unwrap();
+
+    let readback = |first, count| {
+        let errors = submit_errors(
+            &api,
+            &[cmd(CommandInner::ReadbackQueries {
+                pool: pool.inner(),
+                first,
+                count,
+                readback: Readback::new(),
+            })],
+        );
+        errors.contains("out of bounds of query pool of size 4")
+    };
+    assert!(!readback(1, 3));
+    assert!(readback(1, 4));
+    // the end of the range overflows
+    assert!(readback(1, u32::max_value()));
+}
+
+#[test]
+fn test_validation_image_region() {
+    let api = Api::<Backend>::new(ValidatingInstance::new(MockInstance::new())).unwrap();
+    let arena = api.create_arena().unwrap();
+    let image = arena
+        .image_2d(Format::R8G8B8A8_UNORM, 64, 64)
+        .build()
+        .unwrap();
+    let buffer = arena.create_buffer_typeless(64 * 64 * 4).unwrap();
+
+    let copy = |offset| {
+        let mut cmdbuf = api.create_command_buffer();
+        cmdbuf.copy_image_to_buffer(
+            0,
+            Image2dView::from(image),
+            ImageRegion::new(offset, (32, 32, 1)),
+            buffer,
+            0,
+        );
+        validation_errors(|| api.submit_frame(vec![cmdbuf]))
+    };
+    assert_eq!(copy((32, 32, 0)), "");
+    assert!(copy((48, 0, 0)).contains("copy source: out of bounds of image"));
+    assert!(copy((u32::max_value(), 0, 0)).contains("copy source: out of bounds of image"));
+}
+
+#[test]
+fn test_validation_image_usage() {
+    let api = Api::<Backend>::new(ValidatingInstance::new(MockInstance::new())).unwrap();
+    let arena = api.create_arena().unwrap();
+    let mut builder = arena.image_2d(Format::R8G8B8A8_UNORM, 64, 64);
+    builder.usage = ImageUsageFlags::SAMPLED;
+    let texture = builder.build().unwrap();
+    let render_target = arena
+        .image_2d(Format::R8G8B8A8_UNORM, 64, 64)
+        .build()
+        .unwrap();
+    let signature = DynamicSignatureBuilder::new()
+        .fragment_output(FragmentOutputDescription {})
+        .viewport_count(0)
+        .build(&arena)
+        .unwrap();
+
+    let block = |image: Image2d<Backend>| {
+        creation_errors(arena.create_argument_block(
+            signature,
+            iter::empty(),
+            iter::empty(),
+            iter::empty(),
+            None,
+            iter::once(RenderTargetView::from(image)),
+            None,
+            iter::empty(),
+            iter::empty(),
+        ))
+    };
+    assert_eq!(block(render_target), "");
+    assert!(block(texture).contains("render target: image usage"));
+}
+
+#[test]
+fn test_validation_descriptor() {
+    let api = Api::<Backend>::new(ValidatingInstance::new(MockInstance::new())).unwrap();
+    let arena = api.create_arena().unwrap();
+    let buffer = arena.create_buffer_typeless(256).unwrap();
+    let signature = DynamicSignatureBuilder::new()
+        .descriptor(ResourceBinding {
+            set: None,
+            index: 0,
+            ty: ResourceBindingType::ConstantBuffer,
+            stage_flags: ShaderStageFlags::ALL_GRAPHICS,
+            count: 1,
+            data_ty: None,
+            data_layout: None,
+            data_format: Format::UNDEFINED,
+        })
+        .viewport_count(0)
+        .build(&arena)
+        .unwrap();
+
+    let block = |descriptor: Descriptor<Backend>| {
+        creation_errors(arena.create_argument_block(
+            signature,
+            iter::empty(),
+            iter::once(descriptor),
+            iter::empty(),
+            None,
+            iter::empty(),
+            None,
+            iter::empty(),
+            iter::empty(),
+        ))
+    };
+    assert_eq!(
+        block(Descriptor::ConstantBuffer {
+            buffer: buffer.0,
+            offset: 0,
+            size: None,
+        }),
+        ""
+    );
+    assert!(block(Descriptor::RwBuffer {
+        buffer: buffer.0,
+        offset: 0,
+        size: None,
+    })
+    .contains("descriptor #0 is a storage buffer"));
+}
+
+#[test]
+fn test_validation_present() {
+    let api = Api::<Backend>::new(ValidatingInstance::new(MockInstance::new())).unwrap();
+    let arena = api.create_arena().unwrap();
+    let swapchain = api.default_swapchain().unwrap();
+
+    let present = |width, height| {
+        let image = arena
+            .image_2d(Format::R8G8B8A8_UNORM, width, height)
+            .build()
+            .unwrap();
+        let mut cmdbuf = api.create_command_buffer();
+        cmdbuf.present(0, image, swapchain);
+        validation_errors(|| api.submit_frame(vec![cmdbuf]))
+    };
+    assert_eq!(present(640, 480), "");
+    assert!(present(320, 480).contains("presented image (320x480) is smaller than the swapchain"));
+}
@@ -3,6 +3,7 @@
 use autograph_api::{
     buffer::{Buffer, StructuredBufferData, TypedConstantBufferView},
     command::{CommandBuffer, DrawIndexedParams},
+    error::Result,
     format::Format,
     glm,
     image::{
@@ -15,10 +16,11 @@ use autograph_api::{
         InputAssemblyState, MultisampleState, RasterisationState, ReflectedShader, Scissor,
         ScissorRect, TypedArgumentBlock, TypedGraphicsPipeline, Viewport, ViewportState,
     },
+    profile::{millis, FrameProfile},
     vertex::{IndexBufferView, VertexBufferView, VertexData},
     Arena, Backend,
 };
-use imgui::ImGui;
+use imgui::{im_str, ImGui};
 use std::{mem, slice};
 
 /// ImGui vertex shader
@@ -67,9 +69,9 @@ struct ImArguments<'a, B: Backend> {
 
 fn create_pipeline<'a, B: Backend>(
     arena: &'a Arena<B>,
-) -> TypedGraphicsPipeline<'a, B, ImArguments<'a, B>> {
+) -> Result<TypedGraphicsPipeline<'a, B, ImArguments<'a, B>>> {
     let create_info = GraphicsPipelineCreateInfo {
-        shader_stages: arena.create_vertex_fragment_shader_stages(IMGUI_VERT, IMGUI_FRAG),
+        shader_stages: arena.create_vertex_fragment_shader_stages(IMGUI_VERT, IMGUI_FRAG)?,
         viewport_state: ViewportState::DYNAMIC_VIEWPORT_SCISSOR,
         rasterization_state: RasterisationState::default(),
         multisample_state: MultisampleState::default(),
@@ -100,29 +102,29 @@ impl<'a, B: Backend> ImGuiRenderer<'a, B> {
         ui: &mut ImGui,
         target: RenderTarget2dView<'a, B>,
         viewport: Viewport,
-    ) -> ImGuiRenderer<'a, B> {
+    ) -> Result<ImGuiRenderer<'a, B>> {
         // sanity check
         assert_eq!(
             mem::size_of::<imgui::ImDrawVert>(),
             mem::size_of::<ImDrawVert>()
         );
 
-        let pipeline = create_pipeline(arena);
+        let pipeline = create_pipeline(arena)?;
 
         let font_tex = ui.prepare_texture(|handle| {
-            let texture = arena
+            arena
                 .image_2d(Format::R8G8B8A8_SRGB, handle.width, handle.height)
-                .with_data(handle.pixels);
-            texture
-        });
+                .with_data(handle.pixels)
+        })?;
 
-        let render_target = arena.create_typed_argument_block(ImRenderTarget { target, viewport });
+        let render_target =
+            arena.create_typed_argument_block(ImRenderTarget { target, viewport })?;
 
-        ImGuiRenderer {
+        Ok(ImGuiRenderer {
             pipeline,
             font_tex: font_tex.sampled(SamplerDescription::NEAREST_MIPMAP_NEAREST),
             render_target,
-        }
+        })
     }
 
     fn render_draw_list<'b>(
@@ -132,7 +134,8 @@ impl<'a, B: Backend> ImGuiRenderer<'a, B> {
         cmdbuf: &mut CommandBuffer<'b, B>,
         sortkey: u64,
         draw_list: &imgui::DrawList,
-    ) where
+    ) -> Result<()>
+    where
         'a: 'b,
     {
         let vertices = unsafe {
@@ -141,13 +144,13 @@ impl<'a, B: Backend> ImGuiRenderer<'a, B> {
                 draw_list.vtx_buffer.len(),
             )
         };
-        let vertices = frame_arena.upload_slice(vertices);
-        let indices = frame_arena.upload_slice(draw_list.idx_buffer);
+        let vertices = frame_arena.upload_slice(vertices)?;
+        let indices = frame_arena.upload_slice(draw_list.idx_buffer)?;
         let (width, height) = ui.imgui().display_size();
         let (scale_width, scale_height) = ui.imgui().display_framebuffer_scale();
 
         if width == 0.0 || height == 0.0 {
-            return;
+            return Ok(());
         }
 
         let mat = glm::transpose(&glm::mat4(
@@ -181,12 +184,12 @@ impl<'a, B: Backend> ImGuiRenderer<'a, B> {
 
             let args = frame_arena.create_typed_argument_block(ImArguments {
                 rt: self.render_target,
-                uniforms: frame_arena.upload(&ImUniforms { mat }).into(),
+                uniforms: frame_arena.upload(&ImUniforms { mat })?.into(),
                 vertices,
                 tex: self.font_tex,
                 indices,
                 scissor: Scissor::Enabled(scissor),
-            });
+            })?;
 
             cmdbuf.draw_indexed(
                 sortkey,
@@ -204,6 +207,8 @@ impl<'a, B: Backend> ImGuiRenderer<'a, B> {
 
             idx_start += cmd.elem_count;
         }
+
+        Ok(())
     }
 
     /// Renders the specified imgui frame into a command buffer.
@@ -215,21 +220,46 @@ impl<'a, B: Backend> ImGuiRenderer<'a, B> {
     /// - `frame_arena`: the arena that should be used to allocate the temporary resources
     ///  (vertex buffers, index buffers, etc.) necessary for the rendering commands.
     /// - `ui`: the imgui frame to render
+    ///
+    /// Returns an error if the temporary resources could not be allocated.
     pub fn render<'b>(
         &mut self,
         cmdbuf: &mut CommandBuffer<'b, B>,
         sortkey: u64,
         frame_arena: &'b Arena<'b, B>,
         ui: imgui::Ui,
-    ) where
+    ) -> Result<()>
+    where
         'a: 'b,
     {
-        ui.render(move |ui, draw_data| -> Result<(), String> {
+        ui.render(move |ui, draw_data| -> Result<()> {
             for draw_list in &draw_data {
-                self.render_draw_list(ui, frame_arena, cmdbuf, sortkey, &draw_list);
+                self.render_draw_list(ui, frame_arena, cmdbuf, sortkey, &draw_list)?;
             }
             Ok(())
         })
-        .unwrap();
     }
 }
+
+/// Displays the GPU timings of a frame (see [autograph_api::profile]) in an imgui window.
+///
+/// Each profile scope is shown on its own line, indented according to its nesting depth.
+pub fn profile_window(ui: &imgui::Ui, profile: &FrameProfile) {
+    ui.window(im_str!("GPU profile")).build(|| {
+        ui.text(im_str!(
+            "frame {}: {:.3} ms",
+            profile.frame,
+            millis(profile.total())
+        ));
+        ui.separator();
+        profile.walk(|depth, scope| {
+            ui.text(im_str!(
+                "{:indent$}{}: {:.3} ms",
+                "",
+                scope.name,
+                millis(scope.duration),
+                indent = 2 * depth
+            ));
+        });
+    });
+}
@@ -88,11 +88,11 @@ fn test_imgui() {
     'outer: loop {
         let default_swapchain = r.default_swapchain().unwrap();
         let (w, h) = default_swapchain.size();
-        let arena_1 = r.create_arena();
+        let arena_1 = r.create_arena().unwrap();
         let color_buffer =
             arena_1.create_unaliasable_render_target(Format::R8G8B8A8_SRGB, (w, h), 1);
         let mut imgui_renderer =
-            ImGuiRenderer::new(&arena_1, imgui.imgui(), color_buffer, (w, h).into());
+            ImGuiRenderer::new(&arena_1, imgui.imgui(), color_buffer, (w, h).into()).unwrap();
 
         'inner: loop {
             //----------------------------------------------------------------------------------
@@ -122,10 +122,12 @@ fn test_imgui() {
             let mut ui = imgui.frame(app.window());
             test_ui(&mut ui);
 
-            let arena_frame = r.create_arena();
+            let arena_frame = r.create_arena().unwrap();
             let mut cmdbuf = r.create_command_buffer();
             cmdbuf.clear_image(0x0, color_buffer, &[0.0, 0.2, 0.8, 1.0]);
-            imgui_renderer.render(&mut cmdbuf, 0x0, &arena_frame, ui);
+            imgui_renderer
+                .render(&mut cmdbuf, 0x0, &arena_frame, ui)
+                .unwrap();
             cmdbuf.present(0x0, color_buffer, default_swapchain);
             r.submit_frame(vec![cmdbuf]);
 
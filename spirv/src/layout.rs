@@ -39,6 +39,33 @@ impl<'tcx> Layout<'tcx> {
             details: LayoutDetails::None,
         }
     }
+
+    /// Copies this layout and all the layouts it references into the specified arena.
+    pub fn copy_to<'a>(&self, a: &'a DroplessArena) -> &'a Layout<'a> {
+        let details = match self.details {
+            LayoutDetails::None => LayoutDetails::None,
+            LayoutDetails::Array(ArrayLayout {
+                elem_layout,
+                stride,
+            }) => LayoutDetails::Array(ArrayLayout {
+                elem_layout: elem_layout.copy_to(a),
+                stride,
+            }),
+            LayoutDetails::Struct(FieldsLayout { offsets, layouts }) => {
+                // don't allocate in the arena while extending it: collect into a vector first
+                let layouts: Vec<_> = layouts.iter().map(|l| l.copy_to(a)).collect();
+                LayoutDetails::Struct(FieldsLayout {
+                    offsets: a.alloc_extend(offsets.iter().cloned()),
+                    layouts: a.alloc_extend(layouts.into_iter()),
+                })
+            }
+        };
+        a.alloc(Layout {
+            align: self.align,
+            size: self.size,
+            details,
+        })
+    }
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
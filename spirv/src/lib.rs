@@ -198,27 +198,57 @@ impl<'tcx> TypeDesc<'tcx> {
     }
 }
 
-/*
-/// Owned typedesc
+impl<'tcx> ImageType<'tcx> {
+    /// Copies this image type and the types it references into the specified arena.
+    pub fn copy_to<'a>(&self, a: &'a DroplessArena) -> ImageType<'a> {
+        ImageType {
+            sampled_ty: self.sampled_ty.copy_to(a),
+            format: self.format,
+            dimensions: self.dimensions,
+        }
+    }
+}
 
 impl<'tcx> TypeDesc<'tcx> {
-    pub fn copy_from(other: &TypeDesc,
-                     alloc: &mut impl FnMut(TypeDesc<'tcx>) -> &'tcx TypeDesc<'tcx>,
-                     alloc_layouts: &mut impl) -> &'tcx TypeDesc<'tcx> {
-        match other {
-            &TypeDesc::Primitive(primty) => alloc(TypeDesc::Primitive(primty)),
-            &TypeDesc::Array(elemty, len, stride) => alloc(TypeDesc::Array(TypeDesc::copy_from(elemty, alloc), len, stride)),
-            &TypeDesc::Vector(primty, len) => alloc(TypeDesc::Vector(primty, len)),
-            &TypeDesc::Matrix(primty, rows, cols) => alloc(TypeDesc::Matrix(primty, rows, cols)),
-            &TypeDesc::Struct(StructLayout<'tcx>) => alloc(TypeDesc),
-            &TypeDesc::Image(ImageDataType, Option<ImageFormat>),
-            &TypeDesc::SampledImage(ImageDataType, Option<ImageFormat>),
-            &TypeDesc::Void,
-            &TypeDesc::Pointer(&'tcx TypeDesc<'tcx>),
-            &TypeDesc::Unknown,
-        }
+    /// Copies this type and all the types it references into the specified arena.
+    ///
+    /// Used to make type information outlive the arena it was allocated in
+    /// (e.g. the reflection data of a shader module).
+    pub fn copy_to<'a>(&self, a: &'a DroplessArena) -> &'a TypeDesc<'a> {
+        let ty = match *self {
+            TypeDesc::Primitive(ty) => TypeDesc::Primitive(ty),
+            TypeDesc::Array { elem_ty, len } => TypeDesc::Array {
+                elem_ty: elem_ty.copy_to(a),
+                len,
+            },
+            TypeDesc::Vector { elem_ty, len } => TypeDesc::Vector { elem_ty, len },
+            TypeDesc::Matrix {
+                elem_ty,
+                rows,
+                columns,
+            } => TypeDesc::Matrix {
+                elem_ty,
+                rows,
+                columns,
+            },
+            TypeDesc::Struct { fields } => {
+                // don't allocate in the arena while extending it: collect into a vector first
+                let fields: Vec<_> = fields.iter().map(|f| f.copy_to(a)).collect();
+                TypeDesc::Struct {
+                    fields: a.alloc_extend(fields.into_iter()),
+                }
+            }
+            TypeDesc::Image(ref image_ty) => TypeDesc::Image(image_ty.copy_to(a)),
+            TypeDesc::SampledImage(image_ty) => {
+                TypeDesc::SampledImage(a.alloc(image_ty.copy_to(a)))
+            }
+            TypeDesc::Void => TypeDesc::Void,
+            TypeDesc::Pointer(ty) => TypeDesc::Pointer(ty.copy_to(a)),
+            TypeDesc::Unknown => TypeDesc::Unknown,
+        };
+        a.alloc(ty)
     }
-}*/
+}
 
 /*
 pub const TYPE_FLOAT: TypeDesc = TypeDesc::Primitive(PrimitiveType::Float);
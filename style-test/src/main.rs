@@ -10,6 +10,7 @@ use openimageio as oiio;
 use std::{env, iter, mem, path::Path, slice, time};
 use autograph_api::pipeline::{DynamicSignatureBuilder, VertexInputBinding};
 use autograph_api::vertex::VertexInputRate;
+use autograph_api::error::Result;
 
 // Shaders -----------------------------------------------------------------------------------------
 static QUAD_VERT: ReflectedShader = include_glsl!("quad.vert");
@@ -335,12 +336,12 @@ struct Pipelines<'a> {
 }
 
 impl<'a> Pipelines<'a> {
-    pub fn create(arena: &'a Arena) -> Pipelines<'a> {
+    pub fn create(api: &'a autograph_api::Api<Backend>, arena: &'a Arena) -> Result<Pipelines<'a>> {
         let edge_detection_dog_rgbd = GraphicsPipelineCreateInfo {
             shader_stages: arena.create_vertex_fragment_shader_stages(
                 QUAD_SAMPLER_VERT,
                 EDGE_DETECTION_DOG_RGBD_FRAG,
-            ),
+            )?,
             viewport_state: ViewportState::default(),
             rasterization_state: RasterisationState::default(),
             multisample_state: MultisampleState::default(),
@@ -353,7 +354,7 @@ impl<'a> Pipelines<'a> {
             shader_stages: arena.create_vertex_fragment_shader_stages(
                 QUAD_SAMPLER_VERT,
                 EDGE_DETECTION_SOBEL_RGBD_FRAG,
-            ),
+            )?,
             viewport_state: ViewportState::default(),
             rasterization_state: RasterisationState::default(),
             multisample_state: MultisampleState::default(),
@@ -366,7 +367,7 @@ impl<'a> Pipelines<'a> {
             shader_stages: arena.create_vertex_fragment_shader_stages(
                 QUAD_SAMPLER_VERT,
                 SUBSTRATE_DEFERRED_LIGHTING_FRAG,
-            ),
+            )?,
             viewport_state: ViewportState::default(),
             rasterization_state: RasterisationState::default(),
             multisample_state: MultisampleState::default(),
@@ -379,7 +380,7 @@ impl<'a> Pipelines<'a> {
             shader_stages: arena.create_vertex_fragment_shader_stages(
                 WATERCOLOR_SHADING_VERT,
                 WATERCOLOR_SHADING_FRAG,
-            ),
+            )?,
             viewport_state: ViewportState::default(),
             rasterization_state: RasterisationState::default(),
             multisample_state: MultisampleState::default(),
@@ -389,8 +390,10 @@ impl<'a> Pipelines<'a> {
         };
 
         let substrate_distortion = GraphicsPipelineCreateInfo {
-            shader_stages: arena
-                .create_vertex_fragment_shader_stages(QUAD_SAMPLER_VERT, SUBSTRATE_DISTORTION_FRAG),
+            shader_stages: arena.create_vertex_fragment_shader_stages(
+                QUAD_SAMPLER_VERT,
+                SUBSTRATE_DISTORTION_FRAG,
+            )?,
             viewport_state: ViewportState::default(),
             rasterization_state: RasterisationState::default(),
             multisample_state: MultisampleState::default(),
@@ -399,21 +402,22 @@ impl<'a> Pipelines<'a> {
             color_blend_state: ColorBlendState::DISABLED,
         };
 
-        let watercolor_shading_signature = DynamicSignatureBuilder::new().vertex_input(VertexInputBinding {
+        let watercolor_shading_signature = api.get_cached_dynamic_signature(DynamicSignatureBuilder::new().vertex_input(VertexInputBinding {
             layout: <Vertex2D as VertexData>::LAYOUT,
             rate: VertexInputRate::Vertex,
             base_location: None
-        }).build(arena);
+        }))?;
 
-        Pipelines {
-            edge_detection_dog_rgbd: arena.create_graphics_pipeline(&edge_detection_dog_rgbd),
-            edge_detection_sobel_rgbd: arena.create_graphics_pipeline(&edge_detection_sobel_rgbd),
+        Ok(Pipelines {
+            edge_detection_dog_rgbd: arena.create_graphics_pipeline(&edge_detection_dog_rgbd)?,
+            edge_detection_sobel_rgbd: arena
+                .create_graphics_pipeline(&edge_detection_sobel_rgbd)?,
             substrate_deferred_lighting: arena
-                .create_graphics_pipeline(&substrate_deferred_lighting),
-            watercolor_shading: arena.create_graphics_pipeline(&watercolor_shading),
-            substrate_distortion: arena.create_graphics_pipeline(&substrate_distortion),
+                .create_graphics_pipeline(&substrate_deferred_lighting)?,
+            watercolor_shading: arena.create_graphics_pipeline(&watercolor_shading)?,
+            substrate_distortion: arena.create_graphics_pipeline(&substrate_distortion)?,
             watercolor_shading_signature
-        }
+        })
     }
 }
 
@@ -463,7 +467,7 @@ fn load_image_data<'a, T: oiio::ImageData>(
 ) -> Image2d<'a> {
     let (w, h, _) = input.spec().size();
     let data: oiio::ImageBuffer<T> = input.channels_by_name(chans).unwrap().read().unwrap();
-    a.image_2d(fmt, w, h).with_data(data.as_bytes())
+    a.image_2d(fmt, w, h).with_data(data.as_bytes()).unwrap()
 }
 
 const FONT: &[u8] = include_bytes!("../../imgui/tests/ChiKareGo2.ttf");
@@ -520,8 +524,8 @@ fn main() {
     let app = App::new();
     let r = app.renderer();
 
-    let arena_0 = r.create_arena();
-    let pipelines = Pipelines::create(&arena_0);
+    let arena_0 = r.create_arena().unwrap();
+    let pipelines = Pipelines::create(r, &arena_0).expect("failed to create pipelines");
 
     // load test image
     let mut img = oiio::ImageInput::open("data/output0013.exr").unwrap();
@@ -571,11 +575,13 @@ fn main() {
             substrate_data.width() as u32,
             substrate_data.height() as u32,
         )
-        .with_data(substrate_data.as_bytes());
+        .with_data(substrate_data.as_bytes())
+        .unwrap();
 
     let control = arena_0
         .image_2d(Format::R8G8B8A8_UNORM, frame_width, frame_height)
-        .build();
+        .build()
+        .unwrap();
 
     // clear control map
     let mut cmdbuf = r.create_command_buffer();
@@ -595,25 +601,29 @@ fn main() {
     'outer: loop {
         let default_swapchain = r.default_swapchain().unwrap();
         let (w, h) = default_swapchain.size();
-        let outer_arena = r.create_arena();
+        let outer_arena = r.create_arena().unwrap();
 
         // common arguments
-        let common = outer_arena.create_typed_argument_block(CommonArguments {
-            uniforms: outer_arena
-                .upload(&CommonUniforms {
-                    wvp: glm::identity(),
-                    screen_size: glm::vec2(w as f32, h as f32),
-                    _padding: [0.0; 2],
-                    luminance_coeff: glm::vec3(1.0, 1.0, 1.0),
-                })
-                .into(),
-            viewport: (frame_width, frame_height).into(),
-        });
+        let common = outer_arena
+            .create_typed_argument_block(CommonArguments {
+                uniforms: outer_arena
+                    .upload(&CommonUniforms {
+                        wvp: glm::identity(),
+                        screen_size: glm::vec2(w as f32, h as f32),
+                        _padding: [0.0; 2],
+                        luminance_coeff: glm::vec3(1.0, 1.0, 1.0),
+                    })
+                    .unwrap()
+                    .into(),
+                viewport: (frame_width, frame_height).into(),
+            })
+            .unwrap();
 
         let color_buffer = outer_arena
             .render_target(Format::R16G16B16A16_SFLOAT, w, h)
             .samples(8)
-            .build();
+            .build()
+            .unwrap();
 
         // UI renderer
         let mut imgui_renderer = ImGuiRenderer::new(
@@ -621,10 +631,11 @@ fn main() {
             imguictx.imgui(),
             color_buffer.render_target_view(),
             (w, h).into(),
-        );
+        )
+        .unwrap();
 
         'swapchain: loop {
-            let bb = Blackboard::new(r);
+            let bb = Blackboard::new(r).unwrap();
 
             let mut cmdbuf = r.create_command_buffer();
 
@@ -636,21 +647,24 @@ fn main() {
             );
 
             bb.image_2d("edge_map", Format::R32_SFLOAT, frame_width, frame_height)
-                .build();
+                .build()
+                .unwrap();
             bb.image_2d(
                 "lighting",
                 Format::R16G16B16_UNORM,
                 frame_width,
                 frame_height,
             )
-            .build();
+            .build()
+            .unwrap();
             bb.image_2d(
                 "distortion",
                 Format::R16G16B16_UNORM,
                 frame_width,
                 frame_height,
             )
-            .build();
+            .build()
+            .unwrap();
 
             //----------------------------------------------------------------------------------
             // Run shading
@@ -664,7 +678,11 @@ fn main() {
                         .unwrap()
                         .render_target_view(),
                     viewport: (frame_width, frame_height).into(),
-                    params: bb.arena().upload(&watercolor_shading_params).into(),
+                    params: bb
+                        .arena()
+                        .upload(&watercolor_shading_params)
+                        .unwrap()
+                        .into(),
                     diffuse_color: diffuse_color.sampled_linear(),
                     diffuse_direct_lighting: diffuse_direct_lighting.sampled_linear(),
                     specular_color: specular_color.sampled_linear(),
@@ -685,7 +703,7 @@ fn main() {
                         .image_2d_by_name("distortion")
                         .unwrap()
                         .render_target_view(),
-                    params: bb.arena().upload(&substrate_params).into(),
+                    params: bb.arena().upload(&substrate_params).unwrap().into(),
                     color_tex: bb.image_2d_by_name("lighting").unwrap().sampled_linear(),
                     substrate_tex: substrate.sampled_linear(),
                     edge_tex: bb.image_2d_by_name("edge_map").unwrap().sampled_linear(),
@@ -703,7 +721,7 @@ fn main() {
                 SubstrateCommon {
                     common,
                     color_target: color_buffer.render_target_view(),
-                    params: bb.arena().upload(&substrate_params).into(),
+                    params: bb.arena().upload(&substrate_params).unwrap().into(),
                     color_tex: bb.image_2d_by_name("distortion").unwrap().sampled_linear(),
                     substrate_tex: substrate.sampled_linear(),
                     edge_tex: bb.image_2d_by_name("edge_map").unwrap().sampled_linear(),
@@ -716,7 +734,7 @@ fn main() {
 
             'events: loop {
                 let mut cmdbuf = r.create_command_buffer();
-                let inner_arena = r.create_arena();
+                let inner_arena = r.create_arena().unwrap();
                 //----------------------------------------------------------------------------------
                 // handle events
                 let should_close =
@@ -789,7 +807,9 @@ fn main() {
                     watercolor_shading_params.paper_color.as_mut(),
                 )
                 .build();
-                imgui_renderer.render(&mut cmdbuf, 0x0, &inner_arena, ui);
+                imgui_renderer
+                    .render(&mut cmdbuf, 0x0, &inner_arena, ui)
+                    .unwrap();
 
                 // Present
                 cmdbuf.present(0x0, color_buffer, default_swapchain);